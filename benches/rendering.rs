@@ -0,0 +1,82 @@
+#[macro_use]
+extern crate criterion;
+extern crate unsegen;
+extern crate unsegen_jsonviewer;
+
+use criterion::Criterion;
+use unsegen::base::{Height, Width, WindowBuffer};
+use unsegen::widget::{RenderingHints, Widget};
+
+use unsegen_jsonviewer::json_ext::{JsonValue, Object};
+use unsegen_jsonviewer::{JsonPath, JsonViewer};
+
+const NUM_ELEMENTS: usize = 10_000;
+
+/// An array of objects with ~100k nodes in total.
+fn document() -> JsonValue {
+    let elements = (0..NUM_ELEMENTS)
+        .map(|i| {
+            let mut object = Object::new();
+            object.insert("id", i.into());
+            object.insert("name", format!("element {}", i).into());
+            object.insert("active", (i % 2 == 0).into());
+            object.insert("tags", vec!["a", "b", "c"].into());
+            object.insert("score", (i as f64 / 3.0).into());
+            object
+        })
+        .map(JsonValue::Object)
+        .collect();
+    JsonValue::Array(elements)
+}
+
+/// A viewer with all elements of the top level array shown.
+fn expanded_viewer() -> JsonViewer {
+    let mut viewer = JsonViewer::new(document());
+    viewer
+        .select_path(&JsonPath::root().index(NUM_ELEMENTS - 1))
+        .unwrap();
+    viewer
+}
+
+fn draw(viewer: &JsonViewer, buffer: &mut WindowBuffer) {
+    viewer
+        .as_widget()
+        .draw(buffer.as_window(), RenderingHints::default());
+}
+
+fn bench_rendering(c: &mut Criterion) {
+    let mut buffer = WindowBuffer::new(Width::new(80).unwrap(), Height::new(50).unwrap());
+
+    let viewer = expanded_viewer();
+    c.bench_function("draw expanded", |b| b.iter(|| draw(&viewer, &mut buffer)));
+    c.bench_function("draw expanded scrolled to the end", |b| {
+        b.iter(|| {
+            let widget = viewer.as_widget();
+            let offset = widget.line_of(&JsonPath::root().index(NUM_ELEMENTS - 1));
+            widget
+                .scroll_offset(offset.unwrap())
+                .draw(buffer.as_window(), RenderingHints::default())
+        })
+    });
+    c.bench_function("space_demand expanded", |b| {
+        b.iter(|| viewer.as_widget().space_demand())
+    });
+
+    let mut viewer = expanded_viewer();
+    let path = JsonPath::root().index(NUM_ELEMENTS / 2);
+    viewer.select_path(&path).unwrap();
+    c.bench_function("toggle and space_demand expanded", |b| {
+        b.iter(|| {
+            viewer.toggle_active_element().unwrap();
+            viewer.as_widget().space_demand()
+        })
+    });
+
+    let mut viewer = expanded_viewer();
+    viewer.select_path(&JsonPath::root()).unwrap();
+    viewer.toggle_active_element().unwrap();
+    c.bench_function("draw folded", |b| b.iter(|| draw(&viewer, &mut buffer)));
+}
+
+criterion_group!(benches, bench_rendering);
+criterion_main!(benches);
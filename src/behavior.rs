@@ -0,0 +1,127 @@
+//! Input handling for `JsonViewer` as part of an `unsegen` input chain.
+use std::borrow::Cow;
+
+use unsegen::input::{Behavior, Input, ToEvent};
+
+use super::keymap::{Action, KeyMap};
+use super::JsonViewer;
+
+/// Triggers the operations of a `JsonViewer` according to a `KeyMap` (the default one unless
+/// specified otherwise), e.g.:
+///
+/// ```no_run
+/// # extern crate unsegen;
+/// # extern crate unsegen_jsonviewer;
+/// # use unsegen::input::{Input, Key};
+/// # use unsegen_jsonviewer::{Action, JsonViewer, JsonViewerBehavior};
+/// # fn handle(input: Input, viewer: &mut JsonViewer) {
+/// input.chain(JsonViewerBehavior::new(viewer).bind(Key::Char('o'), Action::Toggle));
+/// # }
+/// # fn main() {}
+/// ```
+///
+/// If the triggered operation fails, the input is passed on.
+pub struct JsonViewerBehavior<'a> {
+    viewer: &'a mut JsonViewer,
+    keymap: Cow<'a, KeyMap>,
+}
+
+impl<'a> JsonViewerBehavior<'a> {
+    /// Create the behavior with the default keymap.
+    pub fn new(viewer: &'a mut JsonViewer) -> Self {
+        Self::with_keymap(viewer, KeyMap::shared_default())
+    }
+
+    /// Create the behavior with a custom keymap (which is usually stored by the application
+    /// rather than being built for every input).
+    pub fn with_keymap(viewer: &'a mut JsonViewer, keymap: &'a KeyMap) -> Self {
+        JsonViewerBehavior {
+            viewer,
+            keymap: Cow::Borrowed(keymap),
+        }
+    }
+
+    /// Additionally trigger `action` on `event`.
+    pub fn bind<E: ToEvent>(mut self, event: E, action: Action) -> Self {
+        let keymap = self.keymap.into_owned().bind(event, action);
+        self.keymap = Cow::Owned(keymap);
+        self
+    }
+}
+
+impl<'a> Behavior for JsonViewerBehavior<'a> {
+    fn input(self, input: Input) -> Option<Input> {
+        match self.keymap.action(&input.event) {
+            Some(action) if action.apply(self.viewer).is_ok() => None,
+            _ => Some(input),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::JsonPath;
+    use unsegen::input::{Event, Key};
+
+    fn key(c: char) -> Input {
+        Input {
+            event: Event::Key(Key::Char(c)),
+            raw: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_behavior() {
+        let mut viewer = JsonViewer::new(&object! { "a" => array! { 1, 2 }, "b" => 3 });
+        assert!(key('j')
+            .chain(JsonViewerBehavior::new(&mut viewer))
+            .finish()
+            .is_none());
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a"));
+        assert!(key('-')
+            .chain(JsonViewerBehavior::new(&mut viewer))
+            .finish()
+            .is_none());
+        assert_eq!(viewer.stats().rendered_lines, 6);
+        assert!(key('_')
+            .chain(JsonViewerBehavior::new(&mut viewer))
+            .finish()
+            .is_none());
+        assert_eq!(viewer.stats().rendered_lines, 4);
+
+        // Unhandled and failing operations are passed on.
+        assert!(key('x')
+            .chain(JsonViewerBehavior::new(&mut viewer))
+            .finish()
+            .is_some());
+        assert!(key('n')
+            .chain(JsonViewerBehavior::new(&mut viewer))
+            .finish()
+            .is_some());
+        let keymap = KeyMap::empty();
+        assert!(key('j')
+            .chain(JsonViewerBehavior::with_keymap(&mut viewer, &keymap))
+            .finish()
+            .is_some());
+        assert!(key('x')
+            .chain(
+                JsonViewerBehavior::with_keymap(&mut viewer, &keymap)
+                    .bind(Key::Char('x'), Action::ExpandAll)
+            )
+            .finish()
+            .is_none());
+        assert_eq!(viewer.stats().rendered_lines, 6);
+    }
+
+    #[test]
+    fn test_default_keymap_is_shared() {
+        let mut viewer = JsonViewer::new(&array! { 1 });
+        let keymap = |behavior: &JsonViewerBehavior| match &behavior.keymap {
+            Cow::Borrowed(keymap) => *keymap as *const KeyMap,
+            Cow::Owned(_) => panic!("the default keymap is copied"),
+        };
+        let first = keymap(&JsonViewerBehavior::new(&mut viewer));
+        assert_eq!(keymap(&JsonViewerBehavior::new(&mut viewer)), first);
+    }
+}
@@ -0,0 +1,177 @@
+//! Detection and preview of binary data that is encoded as a hex or base64 string.
+
+/// How a string encodes binary data.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum BinaryEncoding {
+    Hex,
+    /// Standard or URL-safe base64, with or without padding.
+    Base64,
+}
+
+/// Shorter strings are never considered to be binary data.
+const MIN_LENGTH: usize = 24;
+/// The number of bytes shown in the hexdump of an expanded value.
+const PREVIEW_BYTES: usize = 64;
+const BYTES_PER_LINE: usize = 16;
+
+/// Guess whether `text` is binary data. Only long strings consisting entirely of hex digits or
+/// base64 characters that are not plain numbers or words are considered, so this is a heuristic.
+pub fn detect(text: &str) -> Option<BinaryEncoding> {
+    if text.len() < MIN_LENGTH || text.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let bytes = text.as_bytes();
+    if bytes.chunks_exact(2).remainder().is_empty() && bytes.iter().all(u8::is_ascii_hexdigit) {
+        return Some(BinaryEncoding::Hex);
+    }
+    let data = text.trim_end_matches('=');
+    let padding = text.len() - data.len();
+    let mixed = data.bytes().any(|b| b.is_ascii_uppercase())
+        && data
+            .bytes()
+            .any(|b| b.is_ascii_lowercase() || b.is_ascii_digit());
+    if padding <= 2
+        && (padding == 0 || bytes.chunks_exact(4).remainder().is_empty())
+        && data.len() % 4 != 1
+        && data.bytes().all(|b| base64_digit(b).is_some())
+        && (mixed || data.bytes().any(|b| !b.is_ascii_alphanumeric()))
+    {
+        return Some(BinaryEncoding::Base64);
+    }
+    None
+}
+
+fn base64_digit(b: u8) -> Option<u8> {
+    match b {
+        b'A'..=b'Z' => Some(b - b'A'),
+        b'a'..=b'z' => Some(b - b'a' + 26),
+        b'0'..=b'9' => Some(b - b'0' + 52),
+        b'+' | b'-' => Some(62),
+        b'/' | b'_' => Some(63),
+        _ => None,
+    }
+}
+
+/// The number of bytes encoded by `text`.
+pub fn decoded_len(text: &str, encoding: BinaryEncoding) -> usize {
+    match encoding {
+        BinaryEncoding::Hex => text.len() / 2,
+        BinaryEncoding::Base64 => text.trim_end_matches('=').len() * 3 / 4,
+    }
+}
+
+/// Decode (at most) the first `max` bytes of `text`.
+fn decode_prefix(text: &str, encoding: BinaryEncoding, max: usize) -> Vec<u8> {
+    match encoding {
+        BinaryEncoding::Hex => text
+            .as_bytes()
+            .chunks(2)
+            .take(max)
+            .filter_map(|pair| u8::from_str_radix(::std::str::from_utf8(pair).ok()?, 16).ok())
+            .collect(),
+        BinaryEncoding::Base64 => {
+            let mut bytes = Vec::new();
+            let (mut buffer, mut bits) = (0u32, 0);
+            for digit in text.bytes().filter_map(base64_digit) {
+                buffer = (buffer << 6) | u32::from(digit);
+                bits += 6;
+                if bits >= 8 {
+                    bits -= 8;
+                    bytes.push((buffer >> bits) as u8);
+                    buffer &= (1 << bits) - 1;
+                    if bytes.len() == max {
+                        break;
+                    }
+                }
+            }
+            bytes
+        }
+    }
+}
+
+fn format_size(bytes: usize) -> String {
+    if bytes < 1024 {
+        format!("{} B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    }
+}
+
+/// A short description of the data, e.g., `[binary, 4.2 KB]`.
+pub fn summary(text: &str, encoding: BinaryEncoding) -> String {
+    format!("[binary, {}]", format_size(decoded_len(text, encoding)))
+}
+
+/// A hexdump of the beginning of the data, one line per 16 bytes, e.g.,
+/// `0000: 48 65 6c 6c 6f                                   |Hello|`.
+pub fn hexdump(text: &str, encoding: BinaryEncoding) -> String {
+    let bytes = decode_prefix(text, encoding, PREVIEW_BYTES);
+    let mut lines = bytes
+        .chunks(BYTES_PER_LINE)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex = chunk
+                .iter()
+                .map(|b| format!("{:02x}", b))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let ascii = chunk
+                .iter()
+                .map(|&b| {
+                    if b.is_ascii_graphic() || b == b' ' {
+                        b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect::<String>();
+            format!(
+                "{:04x}: {:width$} |{}|",
+                i * BYTES_PER_LINE,
+                hex,
+                ascii,
+                width = BYTES_PER_LINE * 3 - 1
+            )
+        })
+        .collect::<Vec<_>>();
+    let len = decoded_len(text, encoding);
+    if len > bytes.len() {
+        lines.push(format!("… {} more", format_size(len - bytes.len())));
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_detect_and_decode() {
+        assert_eq!(detect("short"), None);
+        assert_eq!(detect("123456789012345678901234567890"), None);
+        assert_eq!(detect("this is not binary data at all"), None);
+        assert_eq!(detect("abcdefghijklmnopqrstuvwxyz"), None);
+        let hex = "48656c6c6f2c20776f726c6421";
+        assert_eq!(detect(&hex.repeat(2)), Some(BinaryEncoding::Hex));
+        let base64 = "SGVsbG8sIHdvcmxkISBIZWxsbyE=";
+        assert_eq!(detect(base64), Some(BinaryEncoding::Base64));
+
+        assert_eq!(summary(base64, BinaryEncoding::Base64), "[binary, 20 B]");
+        assert_eq!(
+            decode_prefix(base64, BinaryEncoding::Base64, 100),
+            b"Hello, world! Hello!".to_vec()
+        );
+        assert_eq!(
+            decode_prefix(hex, BinaryEncoding::Hex, 5),
+            b"Hello".to_vec()
+        );
+        assert_eq!(
+            hexdump(base64, BinaryEncoding::Base64),
+            "0000: 48 65 6c 6c 6f 2c 20 77 6f 72 6c 64 21 20 48 65 |Hello, world! He|\n\
+             0010: 6c 6c 6f 21                                     |llo!|"
+        );
+        assert_eq!(format_size(4300), "4.2 KB");
+    }
+}
@@ -0,0 +1,181 @@
+use json::JsonValue;
+
+use super::displayvalue::{DisplayScalar, DisplayValue};
+use super::members::MemberId;
+use super::path::{JsonPath, JsonPathSegment};
+use super::selection::Selection;
+use crate::ScalarKind;
+
+/// How `JsonViewer::copy_active` formats the active subtree.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum CopyFormat {
+    /// Pretty-printed json. Numbers, booleans and `null` are copied as such, all other scalars as
+    /// strings. All members with duplicate keys are copied in order.
+    Json,
+    /// One `path = value` line per scalar (or empty object or array), as in the flat view, but
+    /// regardless of the fold state.
+    Flat,
+}
+
+/// `value` (located at `location`) in `format`.
+pub(crate) fn format(value: &DisplayValue, location: &JsonPath, format: CopyFormat) -> String {
+    match format {
+        CopyFormat::Json => {
+            let mut json = String::new();
+            write_json(value, 0, &mut json);
+            json
+        }
+        CopyFormat::Flat => {
+            let mut lines = Vec::new();
+            flat_lines(value, &mut location.clone(), &mut lines);
+            lines.join("\n")
+        }
+    }
+}
+
+/// The selected children of `parent` (located at `location`) in `format`. In json, they are
+/// copied as an object (or array) containing only these members (or elements).
+pub(crate) fn format_selection(
+    parent: &DisplayValue,
+    location: &JsonPath,
+    selection: &Selection,
+    format: CopyFormat,
+) -> String {
+    // The selected children with their keys (None for array elements).
+    let children: Vec<(Option<MemberId>, &DisplayValue)> = match parent {
+        DisplayValue::Scalar(_) => Vec::new(),
+        DisplayValue::Object(obj) => obj
+            .members
+            .iter()
+            .map(|member| (Some(member.id()), &member.value))
+            .collect(),
+        DisplayValue::Array(array) => array.values.iter().map(|value| (None, value)).collect(),
+    };
+    let mut location = location.clone();
+    let mut selected = Vec::new();
+    for (i, (key, value)) in children.into_iter().enumerate() {
+        location.push(match &key {
+            Some(key) => JsonPathSegment::Key(key.clone()),
+            None => JsonPathSegment::Index(i),
+        });
+        if selection.contains(&location) {
+            selected.push((key, value, location.clone()));
+        }
+        location.pop();
+    }
+    match format {
+        CopyFormat::Json => {
+            let brackets = match parent {
+                DisplayValue::Object(_) => ('{', '}'),
+                _ => ('[', ']'),
+            };
+            let children = selected
+                .into_iter()
+                .map(|(key, value, _)| (key.map(|id| id.key.to_string()), value));
+            let mut json = String::new();
+            write_container(children, brackets, 0, &mut json);
+            json
+        }
+        CopyFormat::Flat => {
+            let mut lines = Vec::new();
+            for (_, value, mut location) in selected {
+                flat_lines(value, &mut location, &mut lines);
+            }
+            lines.join("\n")
+        }
+    }
+}
+
+/// Append `value` as pretty-printed json (as `JsonValue::pretty(2)`) to `out`, assuming that the
+/// current line is indented by `indent` levels.
+fn write_json(value: &DisplayValue, indent: usize, out: &mut String) {
+    match value {
+        DisplayValue::Scalar(scalar) => out.push_str(&scalar_json(scalar)),
+        DisplayValue::Object(obj) => {
+            let members = obj
+                .members
+                .iter()
+                .map(|member| (Some(member.key.to_string()), &member.value));
+            write_container(members, ('{', '}'), indent, out)
+        }
+        DisplayValue::Array(array) => {
+            let values = array.values.iter().map(|value| (None, value));
+            write_container(values, ('[', ']'), indent, out)
+        }
+    }
+}
+
+/// Like `write_json`, for an object (or array) with the `children` and their keys (None for array
+/// elements). Unlike a `JsonValue` object, members with the same key are all kept.
+fn write_container<'a>(
+    children: impl Iterator<Item = (Option<String>, &'a DisplayValue)>,
+    (open, close): (char, char),
+    indent: usize,
+    out: &mut String,
+) {
+    out.push(open);
+    let mut empty = true;
+    for (key, value) in children {
+        out.push_str(if empty { "\n" } else { ",\n" });
+        empty = false;
+        out.push_str(&"  ".repeat(indent + 1));
+        if let Some(key) = key {
+            out.push_str(&JsonValue::from(key).dump());
+            out.push_str(": ");
+        }
+        write_json(value, indent + 1, out);
+    }
+    if !empty {
+        out.push('\n');
+        out.push_str(&"  ".repeat(indent));
+    }
+    out.push(close);
+}
+
+/// The json of a scalar according to its kind. Masked values and numbers that are not valid json
+/// (e.g., `NaN`) are copied as strings.
+fn scalar_json(scalar: &DisplayScalar) -> String {
+    let text = scalar.text();
+    let literal = !scalar.redacted
+        && match scalar.kind {
+            ScalarKind::Number => matches!(json::parse(text), Ok(JsonValue::Number(_))),
+            ScalarKind::Boolean => text == "true" || text == "false",
+            ScalarKind::Null => text == "null",
+            ScalarKind::String | ScalarKind::Other => false,
+        };
+    if literal {
+        text.to_owned()
+    } else {
+        JsonValue::from(text).dump()
+    }
+}
+
+fn flat_lines(value: &DisplayValue, location: &mut JsonPath, lines: &mut Vec<String>) {
+    match value {
+        DisplayValue::Scalar(scalar) => lines.push(format!(
+            "{} = {}",
+            location,
+            scalar.text().replace('\n', " ")
+        )),
+        DisplayValue::Object(obj) if obj.members.len() == 0 => {
+            lines.push(format!("{} = {{}}", location))
+        }
+        DisplayValue::Object(obj) => {
+            for member in obj.members.iter() {
+                location.push(JsonPathSegment::Key(member.id()));
+                flat_lines(&member.value, location, lines);
+                location.pop();
+            }
+        }
+        DisplayValue::Array(array) if array.values.is_empty() => {
+            lines.push(format!("{} = []", location))
+        }
+        DisplayValue::Array(array) => {
+            for (i, value) in array.values.iter().enumerate() {
+                location.push(JsonPathSegment::Index(i));
+                flat_lines(value, location, lines);
+                location.pop();
+            }
+        }
+    }
+}
@@ -0,0 +1,138 @@
+//! CSV data with a header row, shown as an array of objects keyed by the header.
+use std::io::Read;
+
+use csv;
+
+use super::{Key, Value, ValueVariant};
+
+/// The records of CSV data, e.g., to preview a file:
+///
+/// ```
+/// # extern crate unsegen_jsonviewer;
+/// # use unsegen_jsonviewer::{CsvTable, JsonViewer};
+/// # fn main() {
+/// let table = CsvTable::parse("name,age\nAda,36\n").unwrap();
+/// // [ { name: Ada, age: 36 } ]
+/// let viewer = JsonViewer::new(table.value());
+/// # }
+/// ```
+///
+/// Records may have a different number of fields than the header. Fields without a header are
+/// keyed by their (zero based) column.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CsvTable {
+    headers: Vec<String>,
+    records: Vec<Vec<String>>,
+}
+
+impl CsvTable {
+    /// Parse comma separated data with a header row.
+    pub fn parse(text: &str) -> Result<Self, csv::Error> {
+        Self::from_reader(text.as_bytes())
+    }
+
+    /// Read comma separated data with a header row.
+    pub fn from_reader(reader: impl Read) -> Result<Self, csv::Error> {
+        let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(reader);
+        Self::from_csv(&mut reader)
+    }
+
+    /// Read all records of `reader`, e.g., to use another delimiter. If `reader` is not configured
+    /// to read a header row, the columns are keyed by their index.
+    pub fn from_csv<R: Read>(reader: &mut csv::Reader<R>) -> Result<Self, csv::Error> {
+        let headers = if reader.has_headers() {
+            reader.headers()?.iter().map(str::to_owned).collect()
+        } else {
+            Vec::new()
+        };
+        let records = reader
+            .records()
+            .map(|record| Ok(record?.iter().map(str::to_owned).collect()))
+            .collect::<Result<_, csv::Error>>()?;
+        Ok(CsvTable { headers, records })
+    }
+
+    pub fn headers(&self) -> &[String] {
+        &self.headers
+    }
+
+    pub fn records(&self) -> &[Vec<String>] {
+        &self.records
+    }
+
+    /// The records as an array of objects.
+    pub fn value(&self) -> CsvValue<'_> {
+        CsvValue(Repr::Table(self))
+    }
+}
+
+/// A table (see `CsvTable::value`), record or field.
+#[derive(Clone, Debug)]
+pub struct CsvValue<'a>(Repr<'a>);
+
+#[derive(Clone, Debug)]
+enum Repr<'a> {
+    Table(&'a CsvTable),
+    Record(&'a [String], &'a [String]),
+    Field(&'a str),
+}
+
+impl<'a> Value for CsvValue<'a> {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        match self.0 {
+            Repr::Table(table) => ValueVariant::Array(
+                None,
+                Box::new(
+                    table
+                        .records
+                        .iter()
+                        .map(move |record| CsvValue(Repr::Record(&table.headers, record))),
+                ),
+            ),
+            Repr::Record(headers, record) => ValueVariant::Map(
+                None,
+                Box::new(record.iter().enumerate().map(move |(column, field)| {
+                    let key = match headers.get(column) {
+                        Some(header) => Key::from(header.as_str()),
+                        None => Key::Integer(column as i64),
+                    };
+                    (key, CsvValue(Repr::Field(field)))
+                })),
+            ),
+            Repr::Field(field) => ValueVariant::Scalar(None, field.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{JsonPath, JsonViewer};
+
+    #[test]
+    fn test_csv_table() {
+        let table = CsvTable::parse("name,age\nAda,36\n\"Lovelace, A.\",36,extra\n").unwrap();
+        assert_eq!(table.headers(), ["name", "age"]);
+        let viewer = JsonViewer::new(table.value());
+        let field = |path: JsonPath| viewer.node(&path).unwrap().scalar().map(str::to_owned);
+        assert_eq!(
+            field(JsonPath::root().index(0).key("age")),
+            Some("36".into())
+        );
+        assert_eq!(
+            field(JsonPath::root().index(1).key("name")),
+            Some("Lovelace, A.".into())
+        );
+        assert_eq!(
+            field(JsonPath::root().index(1).key(Key::Integer(2))),
+            Some("extra".into())
+        );
+
+        let mut reader = csv::ReaderBuilder::new()
+            .delimiter(b';')
+            .has_headers(false)
+            .from_reader(&b"a;b\n"[..]);
+        let table = CsvTable::from_csv(&mut reader).unwrap();
+        assert_eq!(table.records(), [vec!["a".to_owned(), "b".to_owned()]]);
+    }
+}
@@ -0,0 +1,361 @@
+//! Rust values in their `Debug` format (e.g., `{:#?}` output), for values without a serializer.
+use super::{Value, ValueVariant};
+
+/// A value parsed from its `Debug` format: Structs are shown as objects and tuple structs as arrays,
+/// both with their type as description. `Vec`s, slices, tuples and sets are shown as arrays, maps
+/// as objects. Tuple structs with a single field (e.g., `Some(3)`) are shown as the field with the
+/// type as description.
+///
+/// ```
+/// # extern crate unsegen_jsonviewer;
+/// # use unsegen_jsonviewer::{DebugValue, JsonViewer};
+/// # fn main() {
+/// let value = DebugValue::parse(&format!("{:#?}", vec![Some((1, "a"))]));
+/// let viewer = JsonViewer::new(&value);
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Debug)]
+pub struct DebugValue {
+    pub description: Option<String>,
+    pub node: DebugNode,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum DebugNode {
+    /// A string (without quotes and escapes), number, unit struct, or anything else the parser
+    /// did not understand.
+    Scalar(String),
+    List(Vec<DebugValue>),
+    Map(Vec<(String, DebugValue)>),
+}
+
+impl DebugValue {
+    /// Parse `text` as far as possible. If the format is not understood (e.g., because of a custom
+    /// `Debug` impl), the whole text is shown as a string.
+    pub fn parse(text: &str) -> Self {
+        let mut parser = Parser { text, pos: 0 };
+        match parser.value() {
+            Ok(value) if parser.at_end() => value,
+            _ => DebugValue::scalar(text.trim()),
+        }
+    }
+
+    fn scalar(text: &str) -> Self {
+        DebugValue {
+            description: None,
+            node: DebugNode::Scalar(text.to_owned()),
+        }
+    }
+
+    fn node(node: DebugNode) -> Self {
+        DebugValue {
+            description: None,
+            node,
+        }
+    }
+}
+
+impl Value for &DebugValue {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        let description = self.description.as_ref().map(|d| d.as_str().into());
+        match &self.node {
+            DebugNode::Scalar(text) => ValueVariant::Scalar(description, text.as_str().into()),
+            DebugNode::List(values) => ValueVariant::Array(description, Box::new(values.iter())),
+            DebugNode::Map(members) => ValueVariant::Map(
+                description,
+                Box::new(
+                    members
+                        .iter()
+                        .map(|(key, value)| (key.as_str().into(), value)),
+                ),
+            ),
+        }
+    }
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    pos: usize,
+}
+
+impl<'t> Parser<'t> {
+    fn rest(&self) -> &'t str {
+        &self.text[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn at_end(&mut self) -> bool {
+        self.skip_whitespace();
+        self.pos == self.text.len()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        if self.rest().starts_with(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Result<DebugValue, ()> {
+        self.skip_whitespace();
+        if self.eat('"') {
+            return self.string().map(|s| DebugValue::scalar(&s));
+        }
+        if self.eat('[') {
+            return self
+                .list(']')
+                .map(|values| DebugValue::node(DebugNode::List(values)));
+        }
+        if self.eat('(') {
+            return self
+                .list(')')
+                .map(|values| DebugValue::node(DebugNode::List(values)));
+        }
+        if self.eat('{') {
+            return self.map_or_set();
+        }
+        let path_len = self.path_len();
+        if path_len > 0 {
+            let name = self.rest()[..path_len].to_owned();
+            self.pos += path_len;
+            let after_name = self.pos;
+            if self.eat('{') {
+                let fields = self.members('}')?;
+                return Ok(DebugValue {
+                    description: Some(name),
+                    node: DebugNode::Map(fields),
+                });
+            }
+            if self.eat('(') {
+                let mut values = self.list(')')?;
+                if values.len() == 1 {
+                    let mut value = values.remove(0);
+                    value.description = Some(match value.description {
+                        Some(inner) => format!("{} {}", name, inner),
+                        None => name,
+                    });
+                    return Ok(value);
+                }
+                return Ok(DebugValue {
+                    description: Some(name),
+                    node: DebugNode::List(values),
+                });
+            }
+            self.pos = after_name - path_len;
+        }
+        self.atom().map(|atom| DebugValue::scalar(&atom))
+    }
+
+    // The length of the (possibly qualified) type name at the start of the rest, e.g.,
+    // `std::path::PathBuf`, or 0.
+    fn path_len(&self) -> usize {
+        let rest = self.rest();
+        if !rest.starts_with(|c: char| c.is_alphabetic() || c == '_') {
+            return 0;
+        }
+        let mut len = 0;
+        loop {
+            len += rest[len..]
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(rest.len() - len);
+            if rest[len..].starts_with("::") {
+                len += 2;
+            } else {
+                return len;
+            }
+        }
+    }
+
+    // Anything up to the next delimiter, e.g., a number or `<opaque>`.
+    fn atom(&mut self) -> Result<String, ()> {
+        let rest = self.rest();
+        let len = rest
+            .find(|c: char| ",:)]}\n".contains(c))
+            .unwrap_or(rest.len());
+        let atom = rest[..len].trim();
+        if atom.is_empty() {
+            return Err(());
+        }
+        self.pos += len;
+        Ok(atom.to_owned())
+    }
+
+    // The rest of a string after the opening quote.
+    fn string(&mut self) -> Result<String, ()> {
+        let mut result = String::new();
+        let mut chars = self.rest().char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(result);
+                }
+                '\\' => match chars.next().ok_or(())?.1 {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    'r' => result.push('\r'),
+                    '0' => result.push('\0'),
+                    'u' => {
+                        let (start, _) = chars.next().filter(|&(_, c)| c == '{').ok_or(())?;
+                        let len = self.rest()[start..].find('}').ok_or(())?;
+                        let hex = &self.rest()[start + 1..start + len];
+                        let code = u32::from_str_radix(hex, 16).map_err(|_| ())?;
+                        result.push(std::char::from_u32(code).ok_or(())?);
+                        for _ in 0..len {
+                            chars.next();
+                        }
+                    }
+                    other => result.push(other),
+                },
+                c => result.push(c),
+            }
+        }
+        Err(())
+    }
+
+    fn list(&mut self, close: char) -> Result<Vec<DebugValue>, ()> {
+        let mut values = Vec::new();
+        loop {
+            if self.eat(close) {
+                return Ok(values);
+            }
+            values.push(self.value()?);
+            if !self.eat(',') && !self.rest().trim_start().starts_with(close) {
+                return Err(());
+            }
+        }
+    }
+
+    // The rest of the members of a struct or map after the opening brace. A trailing `..` (of
+    // non-exhaustive structs) is skipped.
+    fn members(&mut self, close: char) -> Result<Vec<(String, DebugValue)>, ()> {
+        let mut members = Vec::new();
+        loop {
+            if self.eat(close) {
+                return Ok(members);
+            }
+            self.skip_whitespace();
+            if self.rest().starts_with("..") {
+                self.pos += 2;
+                continue;
+            }
+            let key = self.key()?;
+            if !self.eat(':') {
+                return Err(());
+            }
+            members.push((key, self.value()?));
+            if !self.eat(',') && !self.rest().trim_start().starts_with(close) {
+                return Err(());
+            }
+        }
+    }
+
+    // The key of a member, shown as in the `Debug` format, except for the quotes of strings.
+    fn key(&mut self) -> Result<String, ()> {
+        self.skip_whitespace();
+        let start = self.pos;
+        let key = self.value()?;
+        match key.node {
+            DebugNode::Scalar(text) if key.description.is_none() => Ok(text),
+            _ => Ok(self.text[start..self.pos].trim().to_owned()),
+        }
+    }
+
+    // The rest of a map or set after the opening brace.
+    fn map_or_set(&mut self) -> Result<DebugValue, ()> {
+        let start = self.pos;
+        if self.eat('}') {
+            return Ok(DebugValue::node(DebugNode::Map(Vec::new())));
+        }
+        self.value()?;
+        let is_map = self.eat(':');
+        self.pos = start;
+        if is_map {
+            self.members('}')
+                .map(|members| DebugValue::node(DebugNode::Map(members)))
+        } else {
+            self.list('}')
+                .map(|values| DebugValue::node(DebugNode::List(values)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Point {
+        x: i32,
+        label: &'static str,
+    }
+
+    #[allow(dead_code)]
+    #[derive(Debug)]
+    struct Wrapper(Vec<Point>, f64);
+
+    fn scalar(text: &str) -> DebugValue {
+        DebugValue::scalar(text)
+    }
+
+    #[test]
+    fn test_parse() {
+        let value = Wrapper(
+            vec![Point {
+                x: -1,
+                label: "a \"b\"\n",
+            }],
+            0.5,
+        );
+        let point = DebugValue {
+            description: Some("Point".to_owned()),
+            node: DebugNode::Map(vec![
+                ("x".to_owned(), scalar("-1")),
+                ("label".to_owned(), scalar("a \"b\"\n")),
+            ]),
+        };
+        let expected = DebugValue {
+            description: Some("Wrapper".to_owned()),
+            node: DebugNode::List(vec![
+                DebugValue::node(DebugNode::List(vec![point])),
+                scalar("0.5"),
+            ]),
+        };
+        assert_eq!(DebugValue::parse(&format!("{:?}", value)), expected);
+        assert_eq!(DebugValue::parse(&format!("{:#?}", value)), expected);
+
+        let map = vec![("k", Some(1)), ("l", None)]
+            .into_iter()
+            .collect::<BTreeMap<_, _>>();
+        let some = DebugValue {
+            description: Some("Some".to_owned()),
+            node: DebugNode::Scalar("1".to_owned()),
+        };
+        assert_eq!(
+            DebugValue::parse(&format!("{:#?}", (map, ()))),
+            DebugValue::node(DebugNode::List(vec![
+                DebugValue::node(DebugNode::Map(vec![
+                    ("k".to_owned(), some),
+                    ("l".to_owned(), scalar("None")),
+                ])),
+                DebugValue::node(DebugNode::List(vec![])),
+            ]))
+        );
+
+        assert_eq!(DebugValue::parse("'\\u{e9}'"), scalar("'\\u{e9}'"));
+        assert_eq!(DebugValue::parse("\"\\u{e9}\""), scalar("é"));
+        assert_eq!(DebugValue::parse("S { a: 1, .. }").node, {
+            DebugNode::Map(vec![("a".to_owned(), scalar("1"))])
+        });
+        assert_eq!(DebugValue::parse("[1, 2"), scalar("[1, 2"));
+    }
+}
@@ -0,0 +1,1063 @@
+//! Comparison of two documents, shown side by side or as a unified diff.
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeSet, HashMap};
+use std::ops::Range;
+
+use unsegen::base::basic_types::*;
+use unsegen::base::{BoolModifyMode, Color, Cursor, StyleModifier, Window};
+use unsegen::input::{OperationResult, Scrollable};
+use unsegen::widget::{Demand, Demand2D, RenderingHints, Widget};
+
+use super::displayvalue::{common_subsequence_by, DisplayScalar, DisplayValue};
+use super::layout::Extent;
+use super::path::JsonPath;
+use super::StyledString;
+use crate::Value;
+
+const PANE_SEPARATOR: &str = " │ ";
+
+/// How a row of a `JsonDiffViewer` differs between the old and the new document.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiffKind {
+    Unchanged,
+    /// The node only exists in the new document.
+    Added,
+    /// The node only exists in the old document.
+    Removed,
+    /// The node exists in both documents, but its content differs.
+    Changed,
+}
+
+/// How a `JsonDiffViewer` presents the two documents.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DiffLayout {
+    /// The old document on the left and the new one on the right, with matching nodes on the
+    /// same line.
+    SideBySide,
+    /// A single column in which removed and added lines are marked with `-` and `+`.
+    Unified,
+}
+
+// The location of a node in the old and the new document.
+type NodeId = (Option<JsonPath>, Option<JsonPath>);
+
+// The text of a row in one of the documents. Continuation lines of multi-line scalars are
+// aligned with the start of the value.
+struct Text {
+    text: String,
+    height: usize,
+    width: usize,
+}
+
+impl Text {
+    fn new(text: String) -> Self {
+        let extent = Extent::text(&text);
+        Text {
+            text,
+            height: extent.height,
+            width: extent.first.max(extent.rest),
+        }
+    }
+
+    // `label` followed by `value`.
+    fn labeled(label: &str, value: &str) -> Self {
+        let indentation = format!("\n{}", " ".repeat(Extent::text(label).first));
+        Text::new(format!("{}{}", label, value.replace('\n', &indentation)))
+    }
+
+    fn line(&self, index: usize) -> Option<&str> {
+        self.text.split('\n').nth(index)
+    }
+}
+
+fn height(text: &Option<Text>) -> usize {
+    text.as_ref().map(|text| text.height).unwrap_or(0)
+}
+
+struct Row {
+    depth: usize,
+    old: Option<Text>,
+    new: Option<Text>,
+    kind: DiffKind,
+    // The node that starts in this row (None for closing brackets).
+    node: Option<NodeId>,
+    container: bool,
+    folded: bool,
+    // Whether this is the first row of a difference (see `JsonDiffViewer::select_next_change`).
+    change_start: bool,
+}
+
+impl Row {
+    // Whether the row is highlighted. Unfolded containers only differ in their content, which is
+    // highlighted itself.
+    fn differs(&self) -> bool {
+        self.kind != DiffKind::Unchanged && (!self.container || self.folded)
+    }
+
+    // The texts drawn for the row in `layout`, one per pane, and their prefixes in the unified
+    // layout.
+    fn texts(&self, layout: DiffLayout) -> Vec<(&'static str, &Option<Text>)> {
+        match layout {
+            DiffLayout::SideBySide => vec![("", &self.old), ("", &self.new)],
+            DiffLayout::Unified => {
+                let differs = self.differs();
+                let (old, new) = (self.old.is_some(), self.new.is_some());
+                let mut texts = Vec::new();
+                if old && (differs || !new) {
+                    texts.push(("- ", &self.old));
+                }
+                if new && (differs || !old) {
+                    texts.push(("+ ", &self.new));
+                }
+                if !differs && old && new {
+                    texts.push(("  ", &self.new));
+                }
+                texts
+            }
+        }
+    }
+
+    // The number of lines of the row in `layout`.
+    fn height(&self, layout: DiffLayout) -> usize {
+        match layout {
+            DiffLayout::SideBySide => height(&self.old).max(height(&self.new)),
+            DiffLayout::Unified => self
+                .texts(layout)
+                .iter()
+                .map(|(_, text)| height(text))
+                .sum(),
+        }
+    }
+}
+
+fn description_prefix(description: &Option<StyledString>) -> String {
+    match description {
+        Some(description) => format!("{} ", description.as_str()),
+        None => String::new(),
+    }
+}
+
+fn scalar_text(scalar: &DisplayScalar) -> String {
+    description_prefix(&scalar.description) + scalar.value.as_str()
+}
+
+// The opening and closing bracket of a container (or None for scalars).
+fn brackets(value: &DisplayValue) -> Option<(String, &'static str)> {
+    match value {
+        DisplayValue::Scalar(_) => None,
+        DisplayValue::Object(obj) => Some((description_prefix(&obj.description) + "{", "}")),
+        DisplayValue::Array(array) => Some((description_prefix(&array.description) + "[", "]")),
+    }
+}
+
+// Whether two nodes are equal, apart from their children.
+fn shallow_same(old: &DisplayValue, new: &DisplayValue) -> bool {
+    match (old, new) {
+        (DisplayValue::Scalar(o), DisplayValue::Scalar(n)) => {
+            o.description == n.description && o.value == n.value && o.kind == n.kind
+        }
+        (DisplayValue::Object(o), DisplayValue::Object(n)) => {
+            o.description == n.description
+                && o.members.len() == n.members.len()
+                && o.members.keys().eq(n.members.keys())
+        }
+        (DisplayValue::Array(o), DisplayValue::Array(n)) => {
+            o.description == n.description && o.values.len() == n.values.len()
+        }
+        _ => false,
+    }
+}
+
+// The children at `index` of two nodes that are the same apart from their children.
+fn child_pair<'v>(
+    old: &'v DisplayValue,
+    new: &'v DisplayValue,
+    index: usize,
+) -> Option<(&'v DisplayValue, &'v DisplayValue)> {
+    match (old, new) {
+        (DisplayValue::Object(o), DisplayValue::Object(n)) => Some((
+            &o.members.get_index(index)?.value,
+            &n.members.get_index(index)?.value,
+        )),
+        (DisplayValue::Array(o), DisplayValue::Array(n)) => {
+            Some((o.values.get(index)?, n.values.get(index)?))
+        }
+        _ => None,
+    }
+}
+
+struct RowBuilder<'a> {
+    folded: &'a BTreeSet<NodeId>,
+    rows: Vec<Row>,
+    // Whether pairs of nodes (by address) have the same content, so that nested nodes are not
+    // compared again for each of their ancestors.
+    same: RefCell<HashMap<(usize, usize), bool>>,
+}
+
+impl<'a> RowBuilder<'a> {
+    // Whether `old` and `new` have the same content (see `DisplayValue::same_content`).
+    fn same(&self, old: &DisplayValue, new: &DisplayValue) -> bool {
+        let id = |o: &DisplayValue, n: &DisplayValue| {
+            (
+                o as *const DisplayValue as usize,
+                n as *const DisplayValue as usize,
+            )
+        };
+        let mut same = self.same.borrow_mut();
+        if let Some(&result) = same.get(&id(old, new)) {
+            return result;
+        }
+        // Pairs of nodes being compared and the index of their next pair of children
+        let mut stack = vec![(old, new, 0)];
+        // The result for the pair compared last
+        let mut last = true;
+        while let Some(&(o, n, next)) = stack.last() {
+            // A pair differs if it differs itself or if the pair of children compared last does
+            let differs = if next == 0 {
+                !shallow_same(o, n)
+            } else {
+                !last
+            };
+            let result = if differs {
+                Some(false)
+            } else {
+                match child_pair(o, n, next) {
+                    Some((child_o, child_n)) => {
+                        stack.last_mut().expect("not empty").2 += 1;
+                        match same.get(&id(child_o, child_n)) {
+                            Some(&result) => last = result,
+                            None => stack.push((child_o, child_n, 0)),
+                        }
+                        None
+                    }
+                    None => Some(true),
+                }
+            };
+            if let Some(result) = result {
+                same.insert(id(o, n), result);
+                stack.pop();
+                last = result;
+            }
+        }
+        last
+    }
+
+    // Add the rows of a node that exists in only one of the documents.
+    fn one_sided(
+        &mut self,
+        value: &DisplayValue,
+        kind: DiffKind,
+        path: &JsonPath,
+        label: &str,
+        depth: usize,
+    ) {
+        let start = self.rows.len();
+        self.one_sided_rows(value, kind, path, label, depth);
+        self.rows[start].change_start = true;
+    }
+
+    fn one_sided_rows(
+        &mut self,
+        value: &DisplayValue,
+        kind: DiffKind,
+        path: &JsonPath,
+        label: &str,
+        depth: usize,
+    ) {
+        let node = if kind == DiffKind::Removed {
+            (Some(path.clone()), None)
+        } else {
+            (None, Some(path.clone()))
+        };
+        let sided = |text: Text| {
+            if kind == DiffKind::Removed {
+                (Some(text), None)
+            } else {
+                (None, Some(text))
+            }
+        };
+        let (open, close) = match value {
+            DisplayValue::Scalar(scalar) => {
+                let (old, new) = sided(Text::labeled(label, &scalar_text(scalar)));
+                self.rows.push(Row {
+                    depth,
+                    old,
+                    new,
+                    kind,
+                    node: Some(node),
+                    container: false,
+                    folded: false,
+                    change_start: false,
+                });
+                return;
+            }
+            _ => brackets(value).unwrap(),
+        };
+        let folded = self.folded.contains(&node);
+        let header = if folded {
+            format!("{}{} ... {}", label, open, close)
+        } else {
+            format!("{}{}", label, open)
+        };
+        let (old, new) = sided(Text::new(header));
+        self.rows.push(Row {
+            depth,
+            old,
+            new,
+            kind,
+            node: Some(node),
+            container: true,
+            folded,
+            change_start: false,
+        });
+        if folded {
+            return;
+        }
+        match value {
+            DisplayValue::Object(obj) => {
+                for member in obj.members.iter() {
+                    let path = path.clone().member(member.id());
+                    let label = format!("{}: ", member.key);
+                    self.one_sided_rows(&member.value, kind, &path, &label, depth + 1);
+                }
+            }
+            DisplayValue::Array(array) => {
+                for (i, element) in array.values.iter().enumerate() {
+                    self.one_sided_rows(element, kind, &path.clone().index(i), "", depth + 1);
+                }
+            }
+            DisplayValue::Scalar(_) => {}
+        }
+        let (old, new) = sided(Text::new(close.to_owned()));
+        self.rows.push(Row {
+            depth,
+            old,
+            new,
+            kind,
+            node: None,
+            container: true,
+            folded: false,
+            change_start: false,
+        });
+    }
+
+    // Add the rows of a node that exists in both documents.
+    fn both(
+        &mut self,
+        old: &DisplayValue,
+        new: &DisplayValue,
+        old_path: &JsonPath,
+        new_path: &JsonPath,
+        label: &str,
+        depth: usize,
+    ) {
+        let kind = if self.same(old, new) {
+            DiffKind::Unchanged
+        } else {
+            DiffKind::Changed
+        };
+        let node = (Some(old_path.clone()), Some(new_path.clone()));
+        let ((old_open, close), (new_open, _)) = match (old, new) {
+            (DisplayValue::Scalar(o), DisplayValue::Scalar(n)) => {
+                self.rows.push(Row {
+                    depth,
+                    old: Some(Text::labeled(label, &scalar_text(o))),
+                    new: Some(Text::labeled(label, &scalar_text(n))),
+                    kind,
+                    node: Some(node),
+                    container: false,
+                    folded: false,
+                    change_start: kind == DiffKind::Changed,
+                });
+                return;
+            }
+            (DisplayValue::Object(_), DisplayValue::Object(_))
+            | (DisplayValue::Array(_), DisplayValue::Array(_)) => {
+                (brackets(old).unwrap(), brackets(new).unwrap())
+            }
+            _ => {
+                // The type has changed: Show the old node as removed and the new one as added
+                self.one_sided(old, DiffKind::Removed, old_path, label, depth);
+                self.one_sided(new, DiffKind::Added, new_path, label, depth);
+                return;
+            }
+        };
+        let folded = self.folded.contains(&node);
+        let header = |open: &str| {
+            Text::new(if folded {
+                format!("{}{} ... {}", label, open, close)
+            } else {
+                format!("{}{}", label, open)
+            })
+        };
+        self.rows.push(Row {
+            depth,
+            old: Some(header(&old_open)),
+            new: Some(header(&new_open)),
+            kind,
+            node: Some(node),
+            container: true,
+            folded,
+            change_start: folded && kind == DiffKind::Changed,
+        });
+        if folded {
+            return;
+        }
+        match (old, new) {
+            (DisplayValue::Object(o), DisplayValue::Object(n)) => {
+                for member in o.members.iter() {
+                    let id = member.id();
+                    let label = format!("{}: ", member.key);
+                    let old_path = old_path.clone().member(id.clone());
+                    match n.members.get_by_id(&id) {
+                        Some(new_value) => {
+                            let new_path = new_path.clone().member(id);
+                            self.both(
+                                &member.value,
+                                new_value,
+                                &old_path,
+                                &new_path,
+                                &label,
+                                depth + 1,
+                            );
+                        }
+                        None => self.one_sided(
+                            &member.value,
+                            DiffKind::Removed,
+                            &old_path,
+                            &label,
+                            depth + 1,
+                        ),
+                    }
+                }
+                for member in n.members.iter() {
+                    let id = member.id();
+                    if o.members.position(&id).is_none() {
+                        let label = format!("{}: ", member.key);
+                        let new_path = new_path.clone().member(id);
+                        self.one_sided(
+                            &member.value,
+                            DiffKind::Added,
+                            &new_path,
+                            &label,
+                            depth + 1,
+                        );
+                    }
+                }
+            }
+            (DisplayValue::Array(o), DisplayValue::Array(n)) => {
+                let mut anchors =
+                    common_subsequence_by(&o.values, &n.values, |o, n| self.same(o, n));
+                anchors.push((o.values.len(), n.values.len()));
+                let (mut old_begin, mut new_begin) = (0, 0);
+                for (old_end, new_end) in anchors {
+                    // Elements between matches are (probably) modified versions of each other
+                    let paired = (old_end - old_begin).min(new_end - new_begin);
+                    for k in 0..paired {
+                        let (i, j) = (old_begin + k, new_begin + k);
+                        self.both(
+                            &o.values[i],
+                            &n.values[j],
+                            &old_path.clone().index(i),
+                            &new_path.clone().index(j),
+                            "",
+                            depth + 1,
+                        );
+                    }
+                    for i in old_begin + paired..old_end {
+                        let path = old_path.clone().index(i);
+                        self.one_sided(&o.values[i], DiffKind::Removed, &path, "", depth + 1);
+                    }
+                    for j in new_begin + paired..new_end {
+                        let path = new_path.clone().index(j);
+                        self.one_sided(&n.values[j], DiffKind::Added, &path, "", depth + 1);
+                    }
+                    if old_end < o.values.len() && new_end < n.values.len() {
+                        self.both(
+                            &o.values[old_end],
+                            &n.values[new_end],
+                            &old_path.clone().index(old_end),
+                            &new_path.clone().index(new_end),
+                            "",
+                            depth + 1,
+                        );
+                    }
+                    old_begin = old_end + 1;
+                    new_begin = new_end + 1;
+                }
+            }
+            _ => {}
+        }
+        self.rows.push(Row {
+            depth,
+            old: Some(Text::new(close.to_owned())),
+            new: Some(Text::new(close.to_owned())),
+            kind,
+            node: None,
+            container: true,
+            folded: false,
+            change_start: false,
+        });
+    }
+}
+
+/// Shows the differences between two documents. Nodes at matching paths are aligned, array
+/// elements are matched using a sequence diff. Both documents share a single selection, so
+/// scrolling is always synchronized.
+///
+/// Objects and arrays can be folded using `toggle_active_element`.
+pub struct JsonDiffViewer {
+    old: DisplayValue,
+    new: DisplayValue,
+    folded: BTreeSet<NodeId>,
+    rows: Vec<Row>,
+    // The line at which each row starts in the current layout, followed by the number of lines.
+    line_starts: Vec<usize>,
+    active: usize,
+    layout: DiffLayout,
+    // The first line drawn last time (if no scroll offset was passed to the widget), which is
+    // only moved as far as necessary to keep the selected row in view.
+    scroll: Cell<usize>,
+}
+
+impl JsonDiffViewer {
+    /// Compare `old` with `new`. The documents are shown side by side by default.
+    pub fn new(old: impl Value, new: impl Value) -> Self {
+        let mut res = JsonDiffViewer {
+            old: DisplayValue::new(old),
+            new: DisplayValue::new(new),
+            folded: BTreeSet::new(),
+            rows: Vec::new(),
+            line_starts: Vec::new(),
+            active: 0,
+            layout: DiffLayout::SideBySide,
+            scroll: Cell::new(0),
+        };
+        res.rebuild_rows();
+        res
+    }
+
+    /// Compare two other documents. Folded nodes that still exist stay folded and the selection
+    /// stays on the same node if possible.
+    pub fn update(&mut self, old: impl Value, new: impl Value) {
+        self.old = DisplayValue::new(old);
+        self.new = DisplayValue::new(new);
+        self.rebuild_rows();
+    }
+
+    pub fn set_layout(&mut self, layout: DiffLayout) {
+        self.layout = layout;
+        self.count_lines();
+    }
+
+    fn rebuild_rows(&mut self) {
+        let active_node = self.rows.get(self.active).and_then(|row| row.node.clone());
+        let mut builder = RowBuilder {
+            folded: &self.folded,
+            rows: Vec::new(),
+            same: RefCell::new(HashMap::new()),
+        };
+        let root = JsonPath::root();
+        builder.both(&self.old, &self.new, &root, &root, "", 0);
+        self.rows = builder.rows;
+        self.active = match active_node {
+            Some(node) => self
+                .rows
+                .iter()
+                .position(|row| row.node.as_ref() == Some(&node))
+                .unwrap_or(self.active),
+            None => self.active,
+        }
+        .min(self.rows.len() - 1);
+        self.count_lines();
+    }
+
+    fn count_lines(&mut self) {
+        let layout = self.layout;
+        let mut line = 0;
+        self.line_starts = Vec::with_capacity(self.rows.len() + 1);
+        self.line_starts.push(0);
+        for row in &self.rows {
+            line += row.height(layout);
+            self.line_starts.push(line);
+        }
+    }
+
+    // The number of lines of all rows.
+    fn num_lines(&self) -> usize {
+        *self.line_starts.last().expect("contains the total")
+    }
+
+    /// Select the next row.
+    pub fn select_next(&mut self) -> Result<(), ()> {
+        if self.active + 1 < self.rows.len() {
+            self.active += 1;
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Select the previous row.
+    pub fn select_previous(&mut self) -> Result<(), ()> {
+        if self.active > 0 {
+            self.active -= 1;
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Select the next row that differs between the documents (skipping rows within the same
+    /// changed node).
+    pub fn select_next_change(&mut self) -> Result<(), ()> {
+        let next = (self.active + 1..self.rows.len())
+            .find(|&i| self.rows[i].change_start)
+            .ok_or(())?;
+        self.active = next;
+        Ok(())
+    }
+
+    /// Select the previous row that differs between the documents.
+    pub fn select_previous_change(&mut self) -> Result<(), ()> {
+        let previous = (0..self.active)
+            .rev()
+            .find(|&i| self.rows[i].change_start)
+            .ok_or(())?;
+        self.active = previous;
+        Ok(())
+    }
+
+    /// Fold or unfold the selected object or array.
+    pub fn toggle_active_element(&mut self) -> Result<(), ()> {
+        let row = &self.rows[self.active];
+        let node = match (&row.node, row.container) {
+            (Some(node), true) => node.clone(),
+            _ => return Err(()),
+        };
+        if !self.folded.remove(&node) {
+            self.folded.insert(node);
+        }
+        self.rebuild_rows();
+        Ok(())
+    }
+
+    /// The location of the selected node in the new document (or in the old document if the node
+    /// has been removed). Closing brackets belong to their object or array.
+    pub fn active_path(&self) -> JsonPath {
+        let depth = self.rows[self.active].depth;
+        // The header of a container is the closest preceding row on the same level
+        let header = self.rows[..=self.active]
+            .iter()
+            .rev()
+            .find(|row| row.node.is_some() && row.depth == depth);
+        match header.and_then(|row| row.node.as_ref()) {
+            Some((old, new)) => new.clone().or_else(|| old.clone()).unwrap_or_default(),
+            None => JsonPath::root(),
+        }
+    }
+
+    /// The kind of difference of the selected row.
+    pub fn active_kind(&self) -> DiffKind {
+        self.rows[self.active].kind
+    }
+
+    /// The lines of the selected row.
+    fn active_lines(&self) -> Range<usize> {
+        self.line_starts[self.active]..self.line_starts[self.active + 1]
+    }
+
+    pub fn as_widget<'a>(&'a self) -> JsonDiffViewerWidget<'a> {
+        JsonDiffViewerWidget {
+            inner: self,
+            indentation: Width::new(2).unwrap(),
+            scroll_offset: None,
+            active_focused_style: StyleModifier::new()
+                .invert(BoolModifyMode::Toggle)
+                .bold(true),
+            inactive_focused_style: StyleModifier::new().bold(true),
+            added_style: StyleModifier::new().fg_color(Color::Green),
+            removed_style: StyleModifier::new().fg_color(Color::Red),
+            changed_style: StyleModifier::new().fg_color(Color::Yellow),
+        }
+    }
+}
+
+impl Scrollable for JsonDiffViewer {
+    fn scroll_forwards(&mut self) -> OperationResult {
+        self.select_next()
+    }
+    fn scroll_backwards(&mut self) -> OperationResult {
+        self.select_previous()
+    }
+}
+
+pub struct JsonDiffViewerWidget<'a> {
+    inner: &'a JsonDiffViewer,
+    indentation: Width,
+    scroll_offset: Option<usize>,
+    active_focused_style: StyleModifier,
+    inactive_focused_style: StyleModifier,
+    added_style: StyleModifier,
+    removed_style: StyleModifier,
+    changed_style: StyleModifier,
+}
+
+// A line of output: The text of one or two panes and their styles.
+struct Line {
+    row: usize,
+    panes: Vec<(String, StyleModifier)>,
+}
+
+impl<'a> JsonDiffViewerWidget<'a> {
+    pub fn indentation(mut self, w: Width) -> Self {
+        self.indentation = w;
+        self
+    }
+    /// Draw the diff starting at `line` instead of the line the viewer scrolled to last to keep
+    /// the selected row in view.
+    pub fn scroll_offset(mut self, line: usize) -> Self {
+        self.scroll_offset = Some(line);
+        self
+    }
+    pub fn active_focused(mut self, style: StyleModifier) -> Self {
+        self.active_focused_style = style;
+        self
+    }
+    pub fn inactive_focused(mut self, style: StyleModifier) -> Self {
+        self.inactive_focused_style = style;
+        self
+    }
+    pub fn added(mut self, style: StyleModifier) -> Self {
+        self.added_style = style;
+        self
+    }
+    pub fn removed(mut self, style: StyleModifier) -> Self {
+        self.removed_style = style;
+        self
+    }
+    pub fn changed(mut self, style: StyleModifier) -> Self {
+        self.changed_style = style;
+        self
+    }
+
+    fn style(&self, row: &Row) -> StyleModifier {
+        if !row.differs() {
+            return StyleModifier::new();
+        }
+        match row.kind {
+            DiffKind::Unchanged => StyleModifier::new(),
+            DiffKind::Added => self.added_style,
+            DiffKind::Removed => self.removed_style,
+            DiffKind::Changed => self.changed_style,
+        }
+    }
+
+    // The lines of the row at `index`.
+    fn row_lines(&self, index: usize) -> Vec<Line> {
+        let row = &self.inner.rows[index];
+        let style = self.style(row);
+        let indentation = " ".repeat(row.depth * self.indentation.raw_value() as usize);
+        let pane = |prefix: &str, text: &Option<Text>, line: usize| match text
+            .as_ref()
+            .and_then(|text| text.line(line))
+        {
+            Some(text) => (format!("{}{}{}", prefix, indentation, text), style),
+            None => (String::new(), style),
+        };
+        let texts = row.texts(self.inner.layout);
+        match self.inner.layout {
+            DiffLayout::SideBySide => (0..row.height(DiffLayout::SideBySide))
+                .map(|line| Line {
+                    row: index,
+                    panes: texts
+                        .iter()
+                        .map(|(prefix, text)| pane(prefix, text, line))
+                        .collect(),
+                })
+                .collect(),
+            DiffLayout::Unified => texts
+                .iter()
+                .flat_map(|(prefix, text)| (0..height(text)).map(move |line| (prefix, text, line)))
+                .map(|(prefix, text, line)| Line {
+                    row: index,
+                    panes: vec![pane(prefix, text, line)],
+                })
+                .collect(),
+        }
+    }
+
+    // The (at most) `height` lines starting at line `first`.
+    fn lines(&self, first: usize, height: usize) -> Vec<Line> {
+        let starts = &self.inner.line_starts;
+        let mut row = starts
+            .partition_point(|&start| start <= first)
+            .saturating_sub(1);
+        let mut lines = Vec::with_capacity(height);
+        let mut skip = first.saturating_sub(starts[row]);
+        while lines.len() < height && row < self.inner.rows.len() {
+            let row_lines = self.row_lines(row);
+            lines.extend(row_lines.into_iter().skip(skip).take(height - lines.len()));
+            skip = 0;
+            row += 1;
+        }
+        lines
+    }
+
+    // The first line to draw in a window of `height` lines.
+    fn first_line(&self, height: usize) -> usize {
+        if let Some(offset) = self.scroll_offset {
+            return offset;
+        }
+        let active = self.inner.active_lines();
+        let mut first = self.inner.scroll.get();
+        if active.end > first + height {
+            first = active.end.saturating_sub(height);
+        }
+        if active.start < first {
+            first = active.start;
+        }
+        // Do not leave empty lines at the bottom if the diff has become shorter
+        first = first.min(self.inner.num_lines().saturating_sub(height));
+        self.inner.scroll.set(first);
+        first
+    }
+
+    fn draw_pane(&self, mut window: Window, lines: &[Line], pane: usize, focused: StyleModifier) {
+        let mut cursor = Cursor::new(&mut window);
+        for line in lines {
+            if let Some((text, style)) = line.panes.get(pane) {
+                let mut cursor = cursor.save().style_modifier();
+                cursor.apply_style_modifier(*style);
+                if line.row == self.inner.active {
+                    cursor.apply_style_modifier(focused);
+                }
+                cursor.write(text);
+            }
+            cursor.wrap_line();
+        }
+    }
+}
+
+impl<'a> Widget for JsonDiffViewerWidget<'a> {
+    fn space_demand(&self) -> Demand2D {
+        let indentation = self.indentation.raw_value() as usize;
+        let layout = self.inner.layout;
+        // The widths of the texts are known, so no lines have to be built
+        let pane_width = self
+            .inner
+            .rows
+            .iter()
+            .flat_map(|row| {
+                row.texts(layout).into_iter().map(move |(prefix, text)| {
+                    text.as_ref().map_or(0, |text| {
+                        prefix.len() + row.depth * indentation + text.width
+                    })
+                })
+            })
+            .max()
+            .unwrap_or(0);
+        let width = match layout {
+            DiffLayout::SideBySide => 2 * pane_width + Extent::text(PANE_SEPARATOR).first,
+            DiffLayout::Unified => pane_width,
+        };
+        Demand2D {
+            width: Demand::at_least(Width::new(width as i32).unwrap()),
+            height: Demand::exact(Height::new(self.inner.num_lines() as i32).unwrap()),
+        }
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        let height = window.get_height().raw_value() as usize;
+        let lines = self.lines(self.first_line(height), height);
+        let lines = &lines[..];
+        let focused_style = if hints.active {
+            self.active_focused_style
+        } else {
+            self.inactive_focused_style
+        };
+        if self.inner.layout == DiffLayout::Unified {
+            self.draw_pane(window, lines, 0, focused_style);
+            return;
+        }
+        let separator_width = Extent::text(PANE_SEPARATOR).first as i32;
+        let pane_width = (window.get_width().raw_value() - separator_width).max(0) / 2;
+        let (left, rest) = match window.split(ColIndex::new(pane_width)) {
+            Ok(split) => split,
+            Err(_) => return,
+        };
+        let (mut separator, right) = match rest.split(ColIndex::new(separator_width)) {
+            Ok(split) => split,
+            Err(_) => return,
+        };
+        self.draw_pane(left, lines, 0, focused_style);
+        {
+            let mut cursor = Cursor::new(&mut separator);
+            for _ in lines {
+                cursor.write(PANE_SEPARATOR);
+                cursor.wrap_line();
+            }
+        }
+        self.draw_pane(right, lines, 1, focused_style);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use json::JsonValue;
+    use unsegen::base::WindowBuffer;
+
+    fn draw(viewer: &JsonDiffViewer, width: i32, height: i32) -> Vec<String> {
+        let mut buffer =
+            WindowBuffer::new(Width::new(width).unwrap(), Height::new(height).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        lines(&mut buffer)
+    }
+
+    #[test]
+    fn test_side_by_side() {
+        let old = object! { "a" => 1, "b" => array! { 1, 2, 3 }, "c" => "x" };
+        let new = object! { "a" => 2, "b" => array! { 1, 3 }, "d" => true };
+        let mut viewer = JsonDiffViewer::new(&old, &new);
+        assert_eq!(
+            draw(&viewer, 23, 9),
+            vec![
+                "{          │ {",
+                "  a: 1     │   a: 2",
+                "  b: [     │   b: [",
+                "    1      │     1",
+                "    2      │",
+                "    3      │     3",
+                "  ]        │   ]",
+                "  c: x     │",
+                "           │   d: true",
+            ]
+        );
+
+        viewer.select_next_change().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a"));
+        assert_eq!(viewer.active_kind(), DiffKind::Changed);
+        viewer.select_next_change().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("b").index(1));
+        assert_eq!(viewer.active_kind(), DiffKind::Removed);
+        viewer.select_next_change().unwrap();
+        viewer.select_next_change().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("d"));
+        assert!(viewer.select_next_change().is_err());
+
+        // Folding keeps the selection on the folded node
+        viewer.select_previous_change().unwrap();
+        viewer.select_previous_change().unwrap();
+        viewer.select_previous().unwrap();
+        viewer.select_previous().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("b"));
+        viewer.toggle_active_element().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("b"));
+        assert_eq!(draw(&viewer, 31, 3)[2], "  b: [ ... ]   │   b: [ ... ]");
+    }
+
+    #[test]
+    fn test_unified() {
+        let old = array! { 1, object! { "x" => 1 } };
+        let new = array! { 1, "foo" };
+        let mut viewer = JsonDiffViewer::new(&old, &new);
+        viewer.set_layout(DiffLayout::Unified);
+        assert_eq!(
+            draw(&viewer, 20, 7),
+            vec![
+                "  [",
+                "    1",
+                "-   {",
+                "-     x: 1",
+                "-   }",
+                "+   foo",
+                "  ]"
+            ]
+        );
+        assert_eq!(
+            viewer.as_widget().space_demand().height,
+            Demand::exact(Height::new(7).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_multi_line_scalars() {
+        let old = object! { "a" => "x\ny\nz", "b" => 1 };
+        let new = object! { "a" => "x\nw", "b" => 1 };
+        let mut viewer = JsonDiffViewer::new(&old, &new);
+        assert_eq!(
+            draw(&viewer, 23, 6),
+            vec![
+                "{          │ {",
+                "  a: x     │   a: x",
+                "     y     │      w",
+                "     z     │",
+                "  b: 1     │   b: 1",
+                "}          │ }",
+            ]
+        );
+        viewer.set_layout(DiffLayout::Unified);
+        assert_eq!(
+            draw(&viewer, 12, 8),
+            vec![
+                "  {", "-   a: x", "-      y", "-      z", "+   a: x", "+      w", "    b: 1",
+                "  }"
+            ]
+        );
+        assert_eq!(
+            viewer.as_widget().space_demand().height,
+            Demand::exact(Height::new(8).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_scroll() {
+        let value = (0..10).map(JsonValue::from).collect::<Vec<_>>();
+        let mut viewer =
+            JsonDiffViewer::new(JsonValue::from(value.clone()), JsonValue::from(value));
+        viewer.set_layout(DiffLayout::Unified);
+        for _ in 0..6 {
+            viewer.select_next().unwrap();
+        }
+        assert_eq!(draw(&viewer, 10, 3), vec!["    3", "    4", "    5"]);
+        // Moving the selection within the window does not scroll
+        viewer.select_previous().unwrap();
+        assert_eq!(draw(&viewer, 10, 3), vec!["    3", "    4", "    5"]);
+        viewer.select_previous().unwrap();
+        viewer.select_previous().unwrap();
+        assert_eq!(draw(&viewer, 10, 3), vec!["    2", "    3", "    4"]);
+
+        let mut buffer = WindowBuffer::new(Width::new(10).unwrap(), Height::new(2).unwrap());
+        viewer
+            .as_widget()
+            .scroll_offset(10)
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(lines(&mut buffer), vec!["    9", "  ]"]);
+    }
+
+    #[test]
+    fn test_same() {
+        let folded = BTreeSet::new();
+        let builder = RowBuilder {
+            folded: &folded,
+            rows: Vec::new(),
+            same: RefCell::new(HashMap::new()),
+        };
+        let a = DisplayValue::new(&object! { "x" => array! { 1, array! { 2 } }, "y" => 1 });
+        let b = DisplayValue::new(&object! { "x" => array! { 1, array! { 2 } }, "y" => 1 });
+        let c = DisplayValue::new(&object! { "x" => array! { 1, array! { 3 } }, "y" => 1 });
+        let d = DisplayValue::new(&object! { "y" => 1, "x" => array! { 1, array! { 2 } } });
+        assert!(builder.same(&a, &b));
+        assert!(!builder.same(&a, &c));
+        assert!(!builder.same(&a, &d));
+        // Nested pairs are remembered
+        let x = |value: &DisplayValue| {
+            value.unwrap_object_ref().members.get("x").unwrap() as *const DisplayValue as usize
+        };
+        assert_eq!(builder.same.borrow().get(&(x(&a), x(&b))), Some(&true));
+        assert_eq!(builder.same.borrow().get(&(x(&a), x(&c))), Some(&false));
+    }
+}
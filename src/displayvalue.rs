@@ -1,413 +1,3381 @@
-use std::collections::BTreeMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use unsegen::base::basic_types::*;
-use unsegen::base::{Cursor, CursorTarget, StyleModifier};
+use unsegen::base::{Cursor, CursorTarget, StyleModifier, WrappingMode};
 use unsegen::widget::RenderingHints;
 
-use crate::{Value, ValueVariant};
+use crate::{Key, ScalarKind, StyledString, Value, ValueVariant};
 
-use std::cmp::min;
+use std::cmp::{max, min};
+use std::ops::Range;
 
+use super::binary::{self, BinaryEncoding};
+use super::layers::{StyleLayer, StyleLayers, StylePrecedence};
+use super::layout::*;
+use super::members::*;
+use super::node::{Node, NodeKind};
+use super::number::{self, NumberBase};
 use super::path::*;
+use super::renderer::{NodeCursor, NodeRenderer};
+use super::search::Pattern;
+use super::selection::Selection;
+use super::sort::ArraySort;
+use super::stats::*;
+use super::styled::write_highlighted;
+#[cfg(feature = "chrono")]
+use super::timestamp::{self, TimestampOptions};
+use super::worddiff;
 
-pub struct RenderingInfo {
+pub struct RenderingInfo<'a> {
     pub hints: RenderingHints,
-    pub active_focused_style: StyleModifier,
-    pub inactive_focused_style: StyleModifier,
+    pub active_focused_style: FocusStyles,
+    pub inactive_focused_style: FocusStyles,
+    /// The order in which the styles that apply to the same part of a node are combined.
+    pub precedence: StylePrecedence,
     pub item_changed_style: StyleModifier,
+    pub link_style: StyleModifier,
+    pub error_style: StyleModifier,
+    pub duplicate_key_style: StyleModifier,
+    /// Style of the elements marked by `JsonViewer::mark_duplicates`.
+    pub duplicate_style: StyleModifier,
+    pub compact_width: Option<usize>,
+    pub inline_width: Option<usize>,
+    pub separators: Separators,
+    pub closing_brackets: ClosingBrackets,
+    pub errors: &'a BTreeMap<JsonPath, String>,
+    /// Notes drawn next to nodes (see `JsonViewer::annotate`).
+    pub annotations: &'a BTreeMap<JsonPath, Annotation>,
+    /// Nodes that are drawn as a placeholder (see `JsonViewer::hide_path`).
+    pub hidden: &'a BTreeSet<JsonPath>,
+    pub duplicates: &'a BTreeSet<JsonPath>,
+    /// Objects and arrays below the maximum render depth, drawn as `{ … }` (see
+    /// `JsonViewer::set_max_render_depth`).
+    pub cut: &'a BTreeSet<JsonPath>,
+    pub renderer: Option<&'a dyn NodeRenderer>,
+    /// Collects the locations of the nodes drawn by `renderer`, if set.
+    pub overridden: Option<&'a RefCell<BTreeSet<JsonPath>>>,
+    /// Style of the humanized form of timestamps.
+    pub timestamp_style: StyleModifier,
+    /// Style of the previous values of scalars.
+    pub history_style: StyleModifier,
+    /// Parts of keys and scalars that match this pattern are drawn in `highlight_style`.
+    pub highlight: Option<&'a Pattern>,
+    pub highlight_style: StyleModifier,
+    /// Nodes of the selected range of siblings (see `JsonViewer::extend_selection_next`) are
+    /// drawn in `selected_style`.
+    pub selection: Option<&'a Selection>,
+    pub selected_style: StyleModifier,
+    pub node_style: Option<&'a NodeStyle<'a>>,
+    pub badge: Option<&'a Badge<'a>>,
+    /// Style of the vertical lines drawn next to the members of unfolded objects and arrays, if
+    /// they are shown at all.
+    pub indentation_guide: Option<StyleModifier>,
+    /// Styles of the brackets and keys of nodes by depth, repeated for deeper nodes.
+    pub rainbow: &'a [StyleModifier],
+    /// Computes the text shown within folded objects and arrays.
+    pub summary: Option<&'a Summary<'a>>,
+    pub summary_style: StyleModifier,
+    /// The layout parameters if all nodes are drawn exactly as described by their extents, which
+    /// allows skipping the children of objects and arrays above the target.
+    pub layout: Option<LayoutParams>,
+    /// The row in which the active element was drawn, set while drawing.
+    pub active_row: Cell<Option<RowIndex>>,
+    /// The rows in which the members of the top-level value start (with their index) and the row
+    /// of its closing bracket (None), set while drawing.
+    pub member_rows: RefCell<Vec<(RowIndex, Option<usize>)>>,
+    /// Wrap scalars that do not fit into the window, continuing under their start.
+    pub wrap_values: bool,
+    /// Style of the indices drawn in front of the elements of arrays (on multiple lines), if they
+    /// are shown at all.
+    pub index_label: Option<StyleModifier>,
 }
 
-impl RenderingInfo {
-    fn get_focused_style(&self) -> StyleModifier {
+/// A note attached to a node using `JsonViewer::annotate`.
+#[derive(Clone, Debug)]
+pub struct Annotation {
+    pub text: String,
+    pub style: StyleModifier,
+}
+
+/// Computes the identity of an element of the array at the specified path (see
+/// `JsonViewer::set_array_identity`).
+pub type ArrayIdentity = dyn Fn(&JsonPath, Node) -> Option<String> + Send + Sync;
+
+/// The style of the node at the specified path, if it deviates from the default (see
+/// `JsonViewerWidget::node_style`).
+pub type NodeStyle<'a> = dyn Fn(&JsonPath, NodeKind) -> Option<StyleModifier> + 'a;
+
+/// The badge drawn in front of the node at the specified path (and its key), if any (see
+/// `JsonViewerWidget::badge`).
+pub type Badge<'a> = dyn Fn(&JsonPath, Node) -> Option<StyledString> + 'a;
+
+/// The text shown within the folded object or array at the specified path, if any (see
+/// `JsonViewerWidget::collapsed_summary`).
+pub type Summary<'a> = dyn Fn(&JsonPath, Node) -> Option<String> + 'a;
+
+/// Decides whether the scalar at the specified path (with the specified key if it is an object
+/// member) is redacted (see `JsonViewer::set_redaction`).
+pub type Redaction = dyn Fn(&JsonPath, Option<&Key>) -> bool + Send + Sync;
+
+/// Determines how the nodes of the old and new value are matched in `DisplayValue::update_with`.
+#[derive(Clone, Copy, Default)]
+pub struct UpdateOptions<'a> {
+    pub array_identity: Option<&'a ArrayIdentity>,
+    /// Match array elements using a sequence diff (if no `array_identity` is set).
+    pub sequence_diff: bool,
+    /// Show how much changed numbers changed (see `JsonViewer::set_numeric_deltas`).
+    pub numeric_deltas: bool,
+    /// The number of previous values kept per scalar (see `JsonViewer::set_history_limit`).
+    pub history_limit: usize,
+}
+
+impl<'a> RenderingInfo<'a> {
+    /// The rainbow style of the brackets and keys of nodes at `depth`.
+    fn depth_style(&self, depth: usize) -> StyleModifier {
+        if self.rainbow.is_empty() {
+            StyleModifier::new()
+        } else {
+            self.rainbow[depth % self.rainbow.len()]
+        }
+    }
+
+    fn get_focused_style(&self, kind: NodeKind) -> StyleModifier {
         if self.hints.active {
-            self.active_focused_style
+            self.active_focused_style.get(kind)
         } else {
-            self.inactive_focused_style
+            self.inactive_focused_style.get(kind)
+        }
+    }
+
+    /// `layers` with the focused style of `kind` if `active`.
+    fn focus_if(&self, active: bool, kind: NodeKind, layers: StyleLayers) -> StyleLayers {
+        layers.with_if(active, StyleLayer::Focused, self.get_focused_style(kind))
+    }
+
+    /// The style of a part of a node to which `layers` apply.
+    fn style(&self, layers: StyleLayers) -> StyleModifier {
+        layers.resolve(&self.precedence)
+    }
+
+    /// Remember the row of the cursor as the one in which the active element is drawn.
+    fn mark_active_row<T: CursorTarget>(&self, cursor: &Cursor<T>) {
+        self.active_row.set(Some(cursor.get_row()));
+    }
+}
+
+/// The styles of the active element by the kind of node it belongs to, i.e., of scalars, of the
+/// knobs of objects and arrays, and of the keys of object members (by the kind of their value).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct FocusStyles {
+    scalar: StyleModifier,
+    object: StyleModifier,
+    array: StyleModifier,
+}
+
+impl FocusStyles {
+    /// The same style for all kinds of nodes.
+    pub fn uniform(style: StyleModifier) -> Self {
+        FocusStyles {
+            scalar: style,
+            object: style,
+            array: style,
+        }
+    }
+
+    pub fn get(&self, kind: NodeKind) -> StyleModifier {
+        match kind {
+            NodeKind::Scalar => self.scalar,
+            NodeKind::Object => self.object,
+            NodeKind::Array => self.array,
+        }
+    }
+
+    pub fn set(&mut self, kind: NodeKind, style: StyleModifier) {
+        match kind {
+            NodeKind::Scalar => self.scalar = style,
+            NodeKind::Object => self.object = style,
+            NodeKind::Array => self.array = style,
         }
     }
 }
 
+#[derive(Clone)]
 pub struct DisplayObject {
-    description: Option<String>,
-    pub members: BTreeMap<String, DisplayValue>,
+    pub description: Option<StyledString>,
+    pub members: Members,
     pub extended: bool,
     description_changed: bool,
+    extent: ExtentCache,
+    stats: StatsCache,
+}
+
+const OPEN_SYMBOL: &str = "[+]";
+const CLOSE_SYMBOL: &str = "[-]";
+const HIDDEN_PLACEHOLDER: &str = "(hidden)";
+const REDACTED: &str = "•••";
+const INDENTATION_GUIDE: &str = "│";
+/// Drawn at the end of arrays of scalars on a single line if not all elements are shown.
+const ELLIPSIS: &str = "…";
+/// The maximum width of the elements of arrays drawn on a single line according to
+/// `LayoutParams::inline_width`.
+const SHORT_SCALAR_WIDTH: usize = 20;
+
+/// The minimum number of columns right of the start of a wrapped scalar for its continuation lines
+/// to be indented up to there (rather than just like the line of the scalar).
+const MIN_WRAP_WIDTH: i32 = 16;
+
+/// The extent of what is drawn before the `i`th member of a container.
+fn item_separator(compact: bool, i: usize) -> Extent {
+    if !compact {
+        Extent::line_break()
+    } else if i > 0 {
+        Extent::text(", ")
+    } else {
+        Extent::text(" ")
+    }
+}
+
+/// Draw the opening bracket of a container followed by the (un)folding knob.
+fn draw_toggle<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    kind: NodeKind,
+    symbol: &str,
+    active: bool,
+    info: &RenderingInfo,
+    layers: StyleLayers,
+) {
+    let bracket = if kind == NodeKind::Object { "{ " } else { "[ " };
+    write_styled(cursor, bracket, info.style(layers));
+    write_styled(
+        cursor,
+        symbol,
+        info.style(info.focus_if(active, kind, layers)),
+    );
+}
+
+/// Write `text` in `style` (on top of the style of the cursor).
+fn write_styled<T: CursorTarget>(cursor: &mut Cursor<T>, text: &str, style: StyleModifier) {
+    let mut cursor = cursor.save().style_modifier();
+    cursor.apply_style_modifier(style);
+    cursor.write(text);
+}
+
+/// Draw an indentation guide in `column` for all rows from `first` to the row of the cursor. Only
+/// empty cells are drawn over.
+fn draw_indentation_guide<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    column: ColIndex,
+    first: RowIndex,
+    style: StyleModifier,
+) {
+    let last = cursor.get_row();
+    let mut cursor = cursor.save().col().row().style_modifier();
+    cursor.apply_style_modifier(style);
+    for row in first.raw_value()..=last.raw_value() {
+        cursor.move_to(column, RowIndex::new(row));
+        let empty = match cursor.get_current_cell() {
+            Some(cell) => cell.grapheme_cluster.as_str() == " ",
+            None => false,
+        };
+        if empty {
+            cursor.write(INDENTATION_GUIDE);
+        }
+    }
+}
+
+/// How an object or array is decorated, in addition to the styles of `RenderingInfo`.
+#[derive(Clone, Copy)]
+struct ContainerStyle<'s> {
+    layers: StyleLayers,
+    /// Shown within the brackets if the node is folded.
+    summary: Option<&'s str>,
+}
+
+/// Draw the summary of a folded container (if there is one) after the knob.
+fn draw_summary<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    summary: Option<&str>,
+    info: &RenderingInfo,
+) {
+    if let Some(summary) = summary {
+        cursor.write(" ");
+        let mut cursor = cursor.save().style_modifier();
+        cursor.apply_style_modifier(info.summary_style);
+        cursor.write(summary);
+    }
+}
+
+/// Draw `separator` followed by the closing bracket of a container.
+fn draw_closing_bracket<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    separator: &str,
+    bracket: &str,
+    node_style: StyleModifier,
+) {
+    cursor.write(separator);
+    let mut cursor = cursor.save().style_modifier();
+    cursor.apply_style_modifier(node_style);
+    cursor.write(bracket);
+}
+
+/// Draw the (optional) description of a node, followed by a space.
+fn draw_description<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    description: &Option<StyledString>,
+    changed: bool,
+    info: &RenderingInfo,
+) {
+    if let Some(description) = description {
+        let mut cursor = cursor.save().style_modifier();
+        if changed {
+            cursor.apply_style_modifier(info.item_changed_style);
+        }
+        description.draw(&mut cursor);
+        cursor.write(" ");
+    }
+}
+
+fn description_extent(description: &Option<StyledString>) -> Extent {
+    match description {
+        Some(description) => Extent::text(description.as_str()).then(Extent::text(" ")),
+        None => Extent::text(""),
+    }
+}
+
+/// Whether the cursor has left the bottom of the target, i.e., nothing drawn from now on will be
+/// visible.
+fn is_below_target<T: CursorTarget>(cursor: &Cursor<T>) -> bool {
+    cursor.get_row() >= cursor.target().get_height().from_origin()
 }
 
-const OPEN_SYMBOL: &'static str = "[+]";
-const CLOSE_SYMBOL: &'static str = "[-]";
+/// The lines (relative to the first line of a container) at which its shown children with the
+/// specified extents start, followed by the line of the closing bracket. `first` is the line of
+/// the opening bracket.
+fn child_lines(first: usize, compact: bool, children: impl Iterator<Item = Extent>) -> Vec<usize> {
+    let mut line = first;
+    let mut lines = Vec::new();
+    for child in children {
+        if !compact {
+            line += 1;
+        }
+        lines.push(line);
+        line += child.height - 1;
+    }
+    if !compact {
+        line += 1;
+    }
+    lines.push(line);
+    lines
+}
+
+/// The number of children (starting at `lines`, see `child_lines`) of a container whose first
+/// line is drawn in row `top` that are completely above the target, and the line at which the
+/// next child (or the closing bracket) starts.
+fn children_above_target(lines: &[usize], top: RowIndex) -> (usize, usize) {
+    let hidden_lines = max(-top.raw_value(), 0) as usize;
+    let above = lines[1..].partition_point(|&next| next <= hidden_lines);
+    (above, lines[above])
+}
+
+/// Skip drawing the children of a container that are completely above the target: Returns the
+/// number of skipped children and moves the cursor to the line before the first other one.
+fn skip_children_above_target<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    top: RowIndex,
+    lines: &[usize],
+) -> usize {
+    let (above, next) = children_above_target(lines, top);
+    if above > 0 {
+        cursor.move_to_y(RowIndex::new(top.raw_value() + next as i32 - 1));
+    }
+    above
+}
+
+fn text_width(text: &str) -> Option<usize> {
+    if text.contains('\n') {
+        None
+    } else {
+        Some(text.chars().count())
+    }
+}
+
+/// A custom renderer and the location of the node being measured, to measure nodes as they are
+/// drawn by the renderer (see `DisplayValue::single_line_width`).
+struct Rendered<'a> {
+    renderer: &'a dyn NodeRenderer,
+    location: JsonPath,
+}
+
+impl<'a> Rendered<'a> {
+    fn new(renderer: &'a dyn NodeRenderer, location: &JsonPath) -> Self {
+        Rendered {
+            renderer,
+            location: location.clone(),
+        }
+    }
+
+    /// The width of `node` (at `location`) if the renderer draws it on a single line, or None if
+    /// it spans multiple lines. None (at the top level) if the node is not drawn by the renderer.
+    fn measure(&self, node: &DisplayValue) -> Option<Option<usize>> {
+        let mut measure = WidthMeasure::default();
+        if !self
+            .renderer
+            .draw(&self.location, Node::new(node), false, &mut measure)
+        {
+            return None;
+        }
+        Some(Some(measure.width).filter(|_| !measure.wrapped))
+    }
+}
+
+/// A `NodeCursor` that only measures the text written to it.
+#[derive(Default)]
+struct WidthMeasure {
+    width: usize,
+    wrapped: bool,
+}
+
+impl NodeCursor for WidthMeasure {
+    fn write(&mut self, text: &str) {
+        match text_width(text) {
+            Some(width) => self.width += width,
+            None => self.wrapped = true,
+        }
+    }
+    fn write_styled(&mut self, text: &str, _: StyleModifier) {
+        self.write(text);
+    }
+    fn wrap_line(&mut self) {
+        self.wrapped = true;
+    }
+}
+
+fn description_width(description: &Option<StyledString>) -> Option<usize> {
+    match description {
+        Some(description) => text_width(description.as_str()).map(|w| w + 1),
+        None => Some(0),
+    }
+}
 
 impl DisplayObject {
     pub fn toggle_visibility(&mut self) {
         self.extended ^= true;
+        self.extent.invalidate();
     }
 
-    fn update<'s, V: Value>(
-        &self,
-        description: Option<String>,
-        obj: Box<dyn Iterator<Item = (String, V)> + 's>,
-    ) -> Self {
+    /// The updated node (without members) and its members to build (see `DisplayValue::build`).
+    fn update<'o, 's, V: Value>(
+        &'o self,
+        description: Option<StyledString>,
+        obj: Box<dyn Iterator<Item = (Key, V)> + 's>,
+        keys: &KeyPool,
+    ) -> (Self, Vec<Child<'o, V>>) {
         let description_changed = self.description != description;
-        let mut result = DisplayObject {
+        let result = DisplayObject {
             description,
-            members: BTreeMap::new(),
+            members: Members::new(),
             extended: self.extended,
             description_changed,
+            extent: ExtentCache::default(),
+            stats: StatsCache::default(),
         };
+        // Members with duplicate keys are matched in order of their occurrence
+        let mut occurrences = BTreeMap::new();
+        let mut children = Vec::new();
         for (key, value) in obj.into_iter() {
-            let new_value = if let Some(old_val) = self.members.get(&key) {
-                old_val.update(value)
-            } else {
-                DisplayValue::new(value)
+            let occurrence = {
+                let occurrence = occurrences.entry(key.clone()).or_insert(0);
+                *occurrence += 1;
+                *occurrence - 1
             };
-            result.members.insert(key.to_string(), new_value);
+            let id = MemberId {
+                key: key.clone(),
+                occurrence,
+            };
+            children.push(match self.members.get_by_id(&id) {
+                Some(old_val) => Child {
+                    segment: Some(JsonPathSegment::Key(id)),
+                    key: Some(keys.intern(key)),
+                    build: Build::Update(old_val, value),
+                },
+                None => Child {
+                    key: Some(keys.intern(key)),
+                    segment: None,
+                    build: Build::New(value),
+                },
+            });
         }
-        result
+        (result, children)
     }
 
-    fn new<'s, V: Value>(
-        description: Option<String>,
-        obj: Box<dyn Iterator<Item = (String, V)> + 's>,
-    ) -> Self {
-        let mut result = DisplayObject {
+    /// The node (without members) and its members to build (see `DisplayValue::build`).
+    fn new<'o, 's, V: Value>(
+        description: Option<StyledString>,
+        obj: Box<dyn Iterator<Item = (Key, V)> + 's>,
+        keys: &KeyPool,
+    ) -> (Self, Vec<Child<'o, V>>) {
+        let result = DisplayObject {
             description,
-            members: BTreeMap::new(),
+            members: Members::new(),
             extended: true,
             description_changed: false,
+            extent: ExtentCache::default(),
+            stats: StatsCache::default(),
         };
-        for (key, value) in obj.into_iter() {
-            result
-                .members
-                .insert(key.to_string(), DisplayValue::new(value));
+        let children = obj
+            .map(|(key, value)| Child {
+                key: Some(keys.intern(key)),
+                segment: None,
+                build: Build::New(value),
+            })
+            .collect();
+        (result, children)
+    }
+
+    /// Width of the single line form `{ [-] a: 1, b: 2 }` or None if it would exceed `max`.
+    fn single_line_width(&self, max: usize, mut rendered: Option<&mut Rendered>) -> Option<usize> {
+        let mut width = description_width(&self.description)? + "{ ".len() + CLOSE_SYMBOL.len();
+        if self.extended {
+            for (i, member) in self.members.iter().enumerate() {
+                if i > 0 {
+                    width += ",".len();
+                }
+                width += " ".len() + text_width(&member.key.to_string())? + ": ".len();
+                if width > max {
+                    return None;
+                }
+                width += member.value.child_single_line_width(
+                    || JsonPathSegment::Key(member.id()),
+                    max - width,
+                    rendered.as_deref_mut(),
+                )?;
+            }
+        }
+        width += " }".len();
+        if width <= max {
+            Some(width)
+        } else {
+            None
         }
-        result
     }
 
-    fn draw<T: CursorTarget>(
+    /// The extent of the node as drawn by `draw`.
+    fn extent(&self, params: LayoutParams) -> Extent {
+        self.extent.get_or_compute(params, || {
+            let extent = description_extent(&self.description).then(Extent::text("{ "));
+            if !self.extended {
+                return extent
+                    .then(Extent::text(OPEN_SYMBOL))
+                    .then(Extent::text(" }"));
+            }
+            let compact = params
+                .compact_width
+                .and_then(|w| self.single_line_width(w, None))
+                .is_some();
+            let mut body = Extent::text("");
+            let n = self.members.len();
+            for (i, member) in self.members.iter().enumerate() {
+                body = body
+                    .then(item_separator(compact, i))
+                    .then(Extent::text(&member.key.to_string()))
+                    .then(Extent::text(": "))
+                    .then(member.value.extent(params.nested()));
+                if !compact {
+                    body = body.then(Extent::text(params.separators.after(i, n)));
+                }
+            }
+            extent
+                .then(Extent::text(CLOSE_SYMBOL))
+                .then(body.indent(params.indentation))
+                .then(Extent::text(if compact {
+                    " "
+                } else {
+                    params.closing_brackets.separator()
+                }))
+                .then(Extent::text("}"))
+        })
+    }
+
+    /// Call `f` with the lines at which the shown members start (see `child_lines`).
+    fn with_child_lines<R>(&self, params: LayoutParams, f: impl FnOnce(&[usize]) -> R) -> R {
+        let compute = || {
+            let first = description_extent(&self.description).height - 1;
+            let compact = !self.extended
+                || params
+                    .compact_width
+                    .and_then(|w| self.single_line_width(w, None))
+                    .is_some();
+            let shown = if self.extended { self.members.len() } else { 0 };
+            let members = self.members.iter().take(shown).map(|member| {
+                Extent::text(&member.key.to_string())
+                    .then(Extent::text(": "))
+                    .then(member.value.extent(params.nested()))
+            });
+            child_lines(first, compact, members)
+        };
+        self.extent.with_child_lines(params, compute, f)
+    }
+
+    fn draw_key<T: CursorTarget>(
         &self,
         cursor: &mut Cursor<T>,
-        path: Option<&ObjectPath>,
+        member: &Member,
+        active: bool,
         info: &RenderingInfo,
-        indentation: Width,
+        depth: usize,
     ) {
-        use std::fmt::Write;
-        {
-            let mut cursor = cursor.save().style_modifier();
-            if self.description_changed {
-                cursor.apply_style_modifier(info.item_changed_style);
-            }
-            if let Some(description) = &self.description {
-                write!(cursor, "{} ", description).unwrap();
-            }
+        let node_style = if member.duplicate {
+            info.duplicate_key_style.on_top_of(info.depth_style(depth))
+        } else {
+            info.depth_style(depth)
+        };
+        let kind = Node::new(&member.value).kind();
+        let layers = StyleLayers::default().with(StyleLayer::Node, node_style);
+        let layers = info.focus_if(active, kind, layers);
+        let key = member.key.to_string();
+        match info.highlight {
+            Some(pattern) => write_highlighted(
+                cursor,
+                &key,
+                0,
+                &pattern.find_ranges(&key),
+                info.style(layers),
+                info.style(layers.with(StyleLayer::Match, info.highlight_style)),
+            ),
+            None => write_styled(cursor, &key, info.style(layers)),
         }
+        cursor.write(": ");
+    }
+
+    /// Draw the node up to its first member. Returns `None` if it is folded (and thus drawn
+    /// completely), otherwise whether the members are drawn on a single line (see
+    /// `DisplayArray::single_line`).
+    fn draw_open<T: CursorTarget>(
+        &self,
+        cursor: &mut Cursor<T>,
+        path: Option<&ObjectPath>,
+        info: &RenderingInfo,
+        style: ContainerStyle,
+        compact_width: Option<usize>,
+        location: &JsonPath,
+    ) -> Option<Option<bool>> {
+        draw_description(cursor, &self.description, self.description_changed, info);
+        let toggle_active = path == Some(&ObjectPath::Toggle);
         if self.extended {
+            draw_toggle(
+                cursor,
+                NodeKind::Object,
+                CLOSE_SYMBOL,
+                toggle_active,
+                info,
+                style.layers,
+            );
+            let mut rendered = info.renderer.map(|r| Rendered::new(r, location));
+            let compact = compact_width
+                .and_then(|w| self.single_line_width(w, rendered.as_mut()))
+                .is_some();
+            Some(Some(false).filter(|_| compact))
+        } else {
+            draw_toggle(
+                cursor,
+                NodeKind::Object,
+                OPEN_SYMBOL,
+                toggle_active,
+                info,
+                style.layers,
+            );
+            draw_summary(cursor, style.summary, info);
+            draw_closing_bracket(cursor, " ", "}", info.style(style.layers));
+            None
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DisplayArray {
+    pub description: Option<StyledString>,
+    pub values: Vec<DisplayValue>,
+    pub extended: bool,
+    pub num_extended: usize,
+    /// The number of elements shown at the end of the array in addition to the first
+    /// `num_extended` ones (see `JsonViewer::set_array_tail_count`).
+    pub num_tail: usize,
+    /// The number of elements per page if the array is paged (see
+    /// `JsonViewer::set_array_page_size`). Then, the `num_extended` elements from `page_start` on
+    /// are shown instead of the first ones.
+    pub page_size: Option<usize>,
+    pub page_start: usize,
+    /// The order of the shown elements (see `JsonViewer::set_array_sort`). Heads, tails and pages
+    /// refer to the sorted elements.
+    pub sort: Option<ArraySort>,
+    // The indices of the elements in sorted order and the sorted position of each element (empty
+    // if the array is not sorted).
+    order: Vec<usize>,
+    rank: Vec<usize>,
+    pub length_changed: bool,
+    description_changed: bool,
+    extent: ExtentCache,
+    stats: StatsCache,
+}
+/// Whether a node is unfolded and, for arrays, how many of its elements are shown.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Fold {
+    pub extended: bool,
+    pub num_extended: usize,
+    pub num_tail: usize,
+    pub page_size: Option<usize>,
+    pub page_start: usize,
+}
+
+impl Fold {
+    fn new(extended: bool, num_extended: usize, num_tail: usize) -> Self {
+        Fold {
+            extended,
+            num_extended,
+            num_tail,
+            page_size: None,
+            page_start: 0,
+        }
+    }
+}
+
+/// The start and length of the page of `size` elements of an array of `len` elements that contains
+/// `index` (or the last page if `index` is beyond the end).
+fn page_bounds(size: usize, index: usize, len: usize) -> (usize, usize) {
+    let start = min(index, len.saturating_sub(1)) / size * size;
+    (start, min(size, len - start))
+}
+
+/// The indices of the values that have the same content (see `DisplayValue::same_content`) as
+/// another one of `values`.
+pub fn duplicate_indices(values: &[DisplayValue]) -> Vec<usize> {
+    // Only values of the same size (and scalars with the same text) can have the same content.
+    let mut buckets: BTreeMap<_, Vec<usize>> = BTreeMap::new();
+    for (i, value) in values.iter().enumerate() {
+        let stats = value.stats();
+        let text = match value {
+            DisplayValue::Scalar(scalar) => scalar.value.as_str(),
+            _ => "",
+        };
+        buckets
+            .entry((stats.nodes, stats.bytes, text))
+            .or_default()
+            .push(i);
+    }
+    let mut duplicates = Vec::new();
+    for bucket in buckets.values() {
+        for &i in bucket {
+            if bucket
+                .iter()
+                .any(|&j| j != i && values[i].same_content(&values[j]))
             {
-                write!(cursor, "{{ ").unwrap();
-                let mut cursor = cursor.save().style_modifier();
-                if let Some(&ObjectPath::Toggle) = path {
-                    cursor.apply_style_modifier(info.get_focused_style());
-                }
-                write!(cursor, "{}", CLOSE_SYMBOL).unwrap();
+                duplicates.push(i);
             }
-            {
-                let mut cursor = cursor.save().line_start_column();
-                cursor.move_line_start_column(indentation.into());
-                for (key, value) in self.members.iter() {
-                    cursor.wrap_line();
-                    write!(cursor, "{}: ", key).unwrap();
-                    let subpath = if let Some(&ObjectPath::Item(ref active_key, ref subpath)) = path
-                    {
-                        if active_key == key {
-                            Some(subpath.as_ref())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    };
-                    value.draw(&mut cursor, subpath, info, indentation);
-                    write!(cursor, ",").unwrap();
-                }
+        }
+    }
+    duplicates.sort_unstable();
+    duplicates
+}
+
+/// The maximum number of insertions and removals between `old` and `new` for which
+/// `common_subsequence_by` looks for common elements, which bounds its time and memory.
+const MAX_EDIT_DISTANCE: usize = 1000;
+
+/// The indices of the longest common subsequence of `old` and `new` in terms of `same`.
+///
+/// Only the common prefix and suffix are returned if the sequences differ by more than
+/// `MAX_EDIT_DISTANCE` insertions and removals, i.e., the elements in between are matched by their
+/// position.
+pub fn common_subsequence_by<A, B>(
+    old: &[A],
+    new: &[B],
+    same: impl Fn(&A, &B) -> bool,
+) -> Vec<(usize, usize)> {
+    // Skip the common prefix and suffix, which (usually) leaves a short edit script
+    let prefix = old.iter().zip(new).take_while(|(o, n)| same(o, n)).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(o, n)| same(o, n))
+        .count();
+    let old_mid = &old[prefix..old.len() - suffix];
+    let new_mid = &new[prefix..new.len() - suffix];
+
+    let mut result = (0..prefix).map(|i| (i, i)).collect::<Vec<_>>();
+    if let Some(common) = shortest_edit_common(old_mid, new_mid, &same) {
+        result.extend(common.into_iter().map(|(i, j)| (prefix + i, prefix + j)));
+    }
+    let old_suffix = old.len() - suffix;
+    let new_suffix = new.len() - suffix;
+    result.extend((0..suffix).map(|k| (old_suffix + k, new_suffix + k)));
+    result
+}
+
+/// The elements `old` and `new` have in common according to Myers' shortest edit script, or None
+/// if it has more than `MAX_EDIT_DISTANCE` insertions and removals.
+fn shortest_edit_common<A, B>(
+    old: &[A],
+    new: &[B],
+    same: &impl Fn(&A, &B) -> bool,
+) -> Option<Vec<(usize, usize)>> {
+    let (n, m) = (old.len() as isize, new.len() as isize);
+    let max_d = min(old.len() + new.len(), MAX_EDIT_DISTANCE) as isize;
+    // furthest[k + offset]: The furthest x reached on diagonal k = x - y.
+    let offset = max_d + 1;
+    let mut furthest = vec![0isize; 2 * max_d as usize + 3];
+    // trace[d][k + d]: `furthest` after d edits, for backtracking.
+    let mut trace: Vec<Vec<isize>> = Vec::new();
+    for d in 0..=max_d {
+        for k in (-d..=d).step_by(2) {
+            let i = (k + offset) as usize;
+            let mut x = if k == -d || (k != d && furthest[i - 1] < furthest[i + 1]) {
+                furthest[i + 1]
+            } else {
+                furthest[i - 1] + 1
+            };
+            let mut y = x - k;
+            while x < n && y < m && same(&old[x as usize], &new[y as usize]) {
+                x += 1;
+                y += 1;
+            }
+            furthest[i] = x;
+            if x >= n && y >= m {
+                return Some(backtrack_common(&trace, d, x, y));
             }
-            write!(cursor, "\n}}").unwrap();
+        }
+        trace.push(furthest[(offset - d) as usize..=(offset + d) as usize].to_vec());
+    }
+    None
+}
+
+/// The diagonal moves (i.e., common elements) of the edit script ending at `(x, y)` after `d`
+/// edits, in ascending order.
+fn backtrack_common(
+    trace: &[Vec<isize>],
+    d: isize,
+    mut x: isize,
+    mut y: isize,
+) -> Vec<(usize, usize)> {
+    let mut result = Vec::new();
+    for d in (1..=d).rev() {
+        let previous = &trace[d as usize - 1];
+        let at = |k: isize| previous[(k + d - 1) as usize];
+        let k = x - y;
+        let previous_k = if k == -d || (k != d && at(k - 1) < at(k + 1)) {
+            k + 1
         } else {
-            write!(cursor, "{{ ").unwrap();
-            {
-                let mut cursor = cursor.save().style_modifier();
-                if let Some(&ObjectPath::Toggle) = path {
-                    cursor.apply_style_modifier(info.get_focused_style());
+            k - 1
+        };
+        let previous_x = at(previous_k);
+        let previous_y = previous_x - previous_k;
+        while x > previous_x && y > previous_y {
+            x -= 1;
+            y -= 1;
+            result.push((x as usize, y as usize));
+        }
+        x = previous_x;
+        y = previous_y;
+    }
+    while x > 0 && y > 0 {
+        x -= 1;
+        y -= 1;
+        result.push((x as usize, y as usize));
+    }
+    result.reverse();
+    result
+}
+
+impl DisplayArray {
+    pub fn toggle_visibility(&mut self) {
+        self.extended ^= true;
+        self.extent.invalidate();
+    }
+    /// Show one more element or, if the array is paged, the next page.
+    pub fn grow(&mut self) {
+        if let Some(size) = self.page_size {
+            let next = self.page_start + size;
+            self.show_page(next);
+            return;
+        }
+        self.num_extended += 1;
+        assert!(self.num_shown() <= self.values.len());
+        self.extent.invalidate();
+    }
+    /// Show one element less or, if the array is paged, the previous page.
+    pub fn shrink(&mut self) {
+        if let Some(size) = self.page_size {
+            let previous = self.page_start.saturating_sub(size);
+            self.show_page(previous);
+            return;
+        }
+        self.num_extended -= 1;
+        self.extent.invalidate();
+    }
+    pub fn grow_tail(&mut self) {
+        self.num_tail += 1;
+        assert!(self.num_shown() <= self.values.len());
+        self.extent.invalidate();
+    }
+    pub fn shrink_tail(&mut self) {
+        self.num_tail -= 1;
+        self.extent.invalidate();
+    }
+
+    /// Whether there are elements that are not shown (at either end) or, if the array is paged,
+    /// whether there is a next page.
+    pub fn can_grow(&self) -> bool {
+        self.page_start + self.num_shown() < self.values.len()
+    }
+
+    pub fn can_shrink(&self) -> bool {
+        match self.page_size {
+            Some(_) => self.page_start > 0,
+            None => self.num_extended > 0,
+        }
+    }
+
+    pub fn can_grow_tail(&self) -> bool {
+        self.page_size.is_none() && self.can_grow()
+    }
+
+    pub fn can_shrink_tail(&self) -> bool {
+        self.num_tail > 0
+    }
+
+    /// Show the first `count` elements (or all of them if there are fewer), fewer at the end if
+    /// they would overlap. Ends paging.
+    pub fn set_num_extended(&mut self, count: usize) {
+        self.page_size = None;
+        self.page_start = 0;
+        self.num_extended = min(count, self.values.len());
+        self.num_tail = min(self.num_tail, self.values.len() - self.num_extended);
+        self.extent.invalidate();
+    }
+
+    /// Show the last `count` elements (or as many as are not shown at the start already).
+    pub fn set_num_tail(&mut self, count: usize) {
+        assert!(self.page_size.is_none());
+        self.num_tail = min(count, self.values.len() - self.num_extended);
+        self.extent.invalidate();
+    }
+
+    /// Show `size` elements per page, starting with the first page (or the one containing the
+    /// current page if the array is paged already), or end paging (None) and show all elements up
+    /// to the end of the current page.
+    pub fn set_page_size(&mut self, size: Option<usize>) {
+        match size {
+            Some(size) => {
+                assert!(size > 0);
+                self.page_size = Some(size);
+                self.num_tail = 0;
+                let start = self.page_start;
+                self.show_page(start);
+            }
+            None => {
+                let end = self.page_start + self.num_extended;
+                self.set_num_extended(end);
+            }
+        }
+    }
+
+    /// Show the page that contains the element at `index` (or the last page) of a paged array.
+    pub fn jump_to_index(&mut self, index: usize) {
+        let position = self.sorted_position(index);
+        self.show_page(position);
+    }
+
+    /// Show the page that contains the `position`th (sorted) element.
+    fn show_page(&mut self, position: usize) {
+        let size = self.page_size.expect("array is paged");
+        let (start, count) = page_bounds(size, position, self.values.len());
+        self.page_start = start;
+        self.num_extended = count;
+        self.extent.invalidate();
+    }
+
+    /// Show the current page (if the array is paged) again after elements were added or removed.
+    pub fn fix_page(&mut self) {
+        if self.page_size.is_some() {
+            let start = self.page_start;
+            self.show_page(start);
+        }
+    }
+
+    /// Make sure that the element at `index` is shown by showing more elements or, if the array is
+    /// paged, its page.
+    pub fn reveal(&mut self, index: usize) {
+        if self.page_size.is_some() {
+            self.jump_to_index(index);
+        } else if self.shown_position(index).is_none() {
+            let position = self.sorted_position(index);
+            self.set_num_extended(position + 1);
+        }
+    }
+
+    /// Show the elements in the order of `sort` (or in document order, None).
+    pub fn set_sort(&mut self, sort: Option<ArraySort>) {
+        self.sort = sort;
+        self.update_order();
+    }
+
+    /// Sort the elements again after they were modified.
+    pub fn update_order(&mut self) {
+        self.order = match &self.sort {
+            Some(sort) => sort.order(&self.values),
+            None => Vec::new(),
+        };
+        self.rank = vec![0; self.order.len()];
+        for (position, &i) in self.order.iter().enumerate() {
+            self.rank[i] = position;
+        }
+        self.extent.invalidate();
+    }
+
+    /// The position of the element at `index` among all (sorted) elements.
+    pub fn sorted_position(&self, index: usize) -> usize {
+        self.rank.get(index).cloned().unwrap_or(index)
+    }
+
+    /// The index of the element at `position` among all (sorted) elements.
+    fn sorted_index(&self, position: usize) -> usize {
+        self.order.get(position).cloned().unwrap_or(position)
+    }
+
+    /// The (1-based) number of the current page and the number of pages if the array is paged.
+    pub fn page(&self) -> Option<(usize, usize)> {
+        let size = self.page_size?;
+        let pages = max(1, self.values.len().div_ceil(size));
+        Some((self.page_start / size + 1, pages))
+    }
+
+    /// The aggregates of the numbers among the elements or, with `field`, among the members
+    /// `field` of the elements. Cached until the array is modified.
+    pub fn aggregates(&self, field: Option<&str>) -> Option<Aggregates> {
+        self.stats.aggregates_or_compute(field, || {
+            let numbers = self.values.iter().filter_map(|value| {
+                let value = match (field, value) {
+                    (None, value) => value,
+                    (Some(field), DisplayValue::Object(obj)) => obj.members.get(field)?,
+                    (Some(_), _) => return None,
+                };
+                match value {
+                    DisplayValue::Scalar(scalar) => number::parse_float(scalar.text()),
+                    _ => None,
+                }
+            });
+            Aggregates::of(numbers)
+        })
+    }
+
+    /// The text of the length indicator without the knobs, e.g., `3/5`, `page 2/7` or
+    /// `3/5, sorted by id ↑`.
+    pub fn length_text(&self) -> String {
+        let length = match self.page() {
+            Some((page, pages)) => format!("page {}/{}", page, pages),
+            None => format!("{}/{}", self.num_shown(), self.values.len()),
+        };
+        match &self.sort {
+            Some(sort) => format!("{}, {}", length, sort.indicator()),
+            None => length,
+        }
+    }
+
+    /// The number of shown elements at the start and the end.
+    pub fn num_shown(&self) -> usize {
+        self.num_extended + self.num_tail
+    }
+
+    /// The index of the `position`th shown element.
+    pub fn shown_index(&self, position: usize) -> usize {
+        self.sorted_index(if position < self.num_extended {
+            self.page_start + position
+        } else {
+            self.values.len() - self.num_shown() + position
+        })
+    }
+
+    /// The position of the element at `index` among the shown ones or None if it is not shown.
+    pub fn shown_position(&self, index: usize) -> Option<usize> {
+        let index = self.sorted_position(index);
+        let tail_start = self.values.len() - self.num_tail;
+        if index >= self.page_start && index < self.page_start + self.num_extended {
+            Some(index - self.page_start)
+        } else if index >= tail_start && index < self.values.len() {
+            Some(self.num_extended + index - tail_start)
+        } else {
+            None
+        }
+    }
+
+    /// The indices of the shown elements in order.
+    pub fn shown_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.num_shown()).map(move |position| self.shown_index(position))
+    }
+
+    /// The marker drawn between the first and last shown elements if elements are left out there,
+    /// e.g., `… 994 elided …`.
+    fn elided_marker(&self) -> Option<String> {
+        let elided = self.values.len() - self.num_shown();
+        if self.num_tail > 0 && elided > 0 {
+            Some(format!("{} {} elided {}", ELLIPSIS, elided, ELLIPSIS))
+        } else {
+            None
+        }
+    }
+
+    /// Add `values` to the end of the array. If `follow_tail` is set and all elements were shown
+    /// before, the new elements are shown as well.
+    pub fn append<V: Value>(
+        &mut self,
+        values: impl IntoIterator<Item = V>,
+        follow_tail: bool,
+        keys: &KeyPool,
+    ) {
+        let old_len = self.values.len();
+        let all_visible = !self.can_grow();
+        self.values.extend(
+            values
+                .into_iter()
+                .map(|value| DisplayValue::new_with_keys(value, keys)),
+        );
+        if self.values.len() != old_len {
+            self.length_changed = true;
+        }
+        self.update_order();
+        if self.page_size.is_some() {
+            let position = if follow_tail && all_visible {
+                self.values.len()
+            } else {
+                self.page_start
+            };
+            self.show_page(position);
+        } else if follow_tail && all_visible {
+            self.num_extended = self.values.len() - self.num_tail;
+        }
+        self.extent.invalidate();
+        self.stats.invalidate();
+    }
+
+    /// The updated node (without elements) and its elements to build (see
+    /// `DisplayValue::build`).
+    fn update<'o, 's, V: Value>(
+        &'o self,
+        description: Option<StyledString>,
+        values: Box<dyn Iterator<Item = V> + 's>,
+        options: UpdateOptions,
+        location: &mut JsonPath,
+        keys: &KeyPool,
+    ) -> (Self, Vec<Child<'o, V>>) {
+        let children = if let Some(identity) = options.array_identity {
+            self.update_values_by_identity(values, identity, location, keys)
+        } else if options.sequence_diff {
+            self.update_values_by_sequence(values, keys)
+        } else {
+            let mut old_vals = self.values.iter();
+            values
+                .into_iter()
+                .enumerate()
+                .map(|(i, value)| match old_vals.next() {
+                    Some(old_val) => Child {
+                        key: None,
+                        segment: Some(JsonPathSegment::Index(i)),
+                        build: Build::Update(old_val, value),
+                    },
+                    None => Child {
+                        key: None,
+                        segment: None,
+                        build: Build::New(value),
+                    },
+                })
+                .collect::<Vec<_>>()
+        };
+        let (page_start, num_extended) = match self.page_size {
+            Some(size) => page_bounds(size, self.page_start, children.len()),
+            None => (0, min(self.num_extended, children.len())),
+        };
+        let num_tail = min(self.num_tail, children.len() - num_extended);
+        let length_changed = self.values.len() != children.len();
+        let description_changed = self.description != description;
+        let result = DisplayArray {
+            description,
+            values: Vec::with_capacity(children.len()),
+            extended: self.extended,
+            num_extended,
+            num_tail,
+            page_size: self.page_size,
+            page_start,
+            sort: self.sort.clone(),
+            order: Vec::new(),
+            rank: Vec::new(),
+            length_changed,
+            description_changed,
+            extent: ExtentCache::default(),
+            stats: StatsCache::default(),
+        };
+        (result, children)
+    }
+
+    /// Match new and old elements by their identity (or their index if they do not have one). The
+    /// identities are computed from the unmasked content of the nodes, and the nodes built for
+    /// new elements are merged into the matching old ones rather than built again.
+    fn update_values_by_identity<'o, 's, V: Value>(
+        &'o self,
+        values: Box<dyn Iterator<Item = V> + 's>,
+        identity: &ArrayIdentity,
+        location: &JsonPath,
+        keys: &KeyPool,
+    ) -> Vec<Child<'o, V>> {
+        let old_ids = self
+            .values
+            .iter()
+            .map(|value| identity(location, Node::unmasked(value)))
+            .collect::<Vec<_>>();
+        let mut old_by_id = BTreeMap::new();
+        for (i, id) in old_ids.iter().enumerate() {
+            if let Some(id) = id {
+                old_by_id
+                    .entry(id.as_str())
+                    .or_insert_with(VecDeque::new)
+                    .push_back(i);
+            }
+        }
+        let mut result = Vec::new();
+        for (i, value) in values.enumerate() {
+            let mut new_value = DisplayValue::new_with_keys(value, keys);
+            let old_i = match identity(location, Node::unmasked(&new_value)) {
+                Some(id) => old_by_id.get_mut(id.as_str()).and_then(|i| i.pop_front()),
+                None if old_ids.get(i) == Some(&None) => Some(i),
+                None => None,
+            };
+            result.push(match old_i {
+                Some(old_i) => Child {
+                    key: None,
+                    segment: Some(JsonPathSegment::Index(i)),
+                    build: Build::Merge(&self.values[old_i], Box::new(new_value)),
+                },
+                None => {
+                    new_value.set_changed(true);
+                    Child {
+                        key: None,
+                        segment: None,
+                        build: Build::Done(Box::new(new_value)),
+                    }
+                }
+            });
+        }
+        result
+    }
+
+    /// Match new and old elements using the longest common subsequence of (unchanged) elements.
+    /// Elements between matched ones are compared pairwise, the remaining new elements are
+    /// considered inserted.
+    fn update_values_by_sequence<'o, 's, V: Value>(
+        &'o self,
+        values: Box<dyn Iterator<Item = V> + 's>,
+        keys: &KeyPool,
+    ) -> Vec<Child<'o, V>> {
+        let values = values.collect::<Vec<_>>();
+        let mut anchors = common_subsequence_by(&self.values, &values, |old, new| {
+            old.same_value(new.clone())
+        });
+        anchors.push((self.values.len(), values.len()));
+
+        let mut old_for_new = vec![None; values.len()];
+        let (mut old_begin, mut new_begin) = (0, 0);
+        for (old_end, new_end) in anchors {
+            // Elements between matches are (probably) modified versions of each other
+            for (old_i, new_i) in (old_begin..old_end).zip(new_begin..new_end) {
+                old_for_new[new_i] = Some(old_i);
+            }
+            if new_end < old_for_new.len() {
+                old_for_new[new_end] = Some(old_end);
+            }
+            old_begin = old_end + 1;
+            new_begin = new_end + 1;
+        }
+
+        values
+            .into_iter()
+            .zip(old_for_new)
+            .enumerate()
+            .map(|(i, (value, old_i))| match old_i {
+                Some(old_i) => Child {
+                    key: None,
+                    segment: Some(JsonPathSegment::Index(i)),
+                    build: Build::Update(&self.values[old_i], value),
+                },
+                None => {
+                    let mut fresh = DisplayValue::new_with_keys(value, keys);
+                    fresh.set_changed(true);
+                    Child {
+                        key: None,
+                        segment: None,
+                        build: Build::Done(Box::new(fresh)),
+                    }
+                }
+            })
+            .collect()
+    }
+
+    /// The node (without elements) and its elements to build (see `DisplayValue::build`).
+    fn new<'o, 's, V: Value>(
+        description: Option<StyledString>,
+        values: Box<dyn Iterator<Item = V> + 's>,
+    ) -> (Self, Vec<Child<'o, V>>) {
+        let children = values
+            .map(|value| Child {
+                key: None,
+                segment: None,
+                build: Build::New(value),
+            })
+            .collect::<Vec<_>>();
+        let result = DisplayArray {
+            description,
+            values: Vec::with_capacity(children.len()),
+            extended: true,
+            num_extended: min(3, children.len()),
+            num_tail: 0,
+            page_size: None,
+            page_start: 0,
+            sort: None,
+            order: Vec::new(),
+            rank: Vec::new(),
+            length_changed: false,
+            description_changed: false,
+            extent: ExtentCache::default(),
+            stats: StatsCache::default(),
+        };
+        (result, children)
+    }
+
+    /// Width of the single line form `[ [-] 1, 2 ] <-2/5+>` or None if it would exceed `max`.
+    fn single_line_width(&self, max: usize, mut rendered: Option<&mut Rendered>) -> Option<usize> {
+        let mut width = description_width(&self.description)? + "[ ".len() + CLOSE_SYMBOL.len();
+        if self.extended {
+            for (position, i) in self.shown_indices().take(self.num_extended).enumerate() {
+                let value = &self.values[i];
+                if position > 0 {
+                    width += ",".len();
+                }
+                width += " ".len();
+                if width > max {
+                    return None;
+                }
+                width += value.child_single_line_width(
+                    || JsonPathSegment::Index(i),
+                    max - width,
+                    rendered.as_deref_mut(),
+                )?;
+            }
+            width += " ] <".len() + 1 + self.length_text().chars().count() + 1 + ">".len();
+        } else {
+            width += " ]".len();
+        }
+        if width <= max {
+            Some(width)
+        } else {
+            None
+        }
+    }
+
+    /// Whether all shown elements are scalars that are at most `SHORT_SCALAR_WIDTH` wide.
+    fn shows_short_scalars(&self) -> bool {
+        self.shown_indices()
+            .take(self.num_extended)
+            .all(|i| match &self.values[i] {
+                DisplayValue::Scalar(scalar) => {
+                    scalar.single_line_width(SHORT_SCALAR_WIDTH).is_some()
+                }
+                _ => false,
+            })
+    }
+
+    /// Whether the unfolded array is drawn on a single line and, if so, whether that line ends
+    /// with an ellipsis because it is an array of short scalars of which not all are shown.
+    /// If `rendered` is set, nodes drawn by its custom renderer are measured as drawn.
+    fn single_line(
+        &self,
+        compact_width: Option<usize>,
+        inline_width: Option<usize>,
+        mut rendered: Option<&mut Rendered>,
+    ) -> Option<bool> {
+        // The marker of elided elements is drawn on a line of its own.
+        if self.num_tail > 0 {
+            return None;
+        }
+        if compact_width
+            .and_then(|w| self.single_line_width(w, rendered.as_deref_mut()))
+            .is_some()
+        {
+            return Some(false);
+        }
+        let truncated = self.num_extended < self.values.len();
+        let ellipsis = if truncated {
+            item_separator(true, self.num_extended)
+                .then(Extent::text(ELLIPSIS))
+                .first
+        } else {
+            0
+        };
+        inline_width
+            .filter(|_| self.shows_short_scalars())
+            .and_then(|w| self.single_line_width(w.checked_sub(ellipsis)?, rendered))
+            .map(|_| truncated)
+    }
+
+    /// The extent of the node as drawn by `draw`.
+    fn extent(&self, params: LayoutParams) -> Extent {
+        self.extent.get_or_compute(params, || {
+            let extent = description_extent(&self.description).then(Extent::text("[ "));
+            if !self.extended {
+                return extent
+                    .then(Extent::text(OPEN_SYMBOL))
+                    .then(Extent::text(" ]"));
+            }
+            let single_line = self.single_line(params.compact_width, params.inline_width, None);
+            let compact = single_line.is_some();
+            let mut body = Extent::text("");
+            let marker = self.elided_marker();
+            for (position, i) in self.shown_indices().enumerate() {
+                if let (Some(marker), true) = (&marker, position == self.num_extended) {
+                    body = body.then(Extent::line_break()).then(Extent::text(marker));
+                }
+                body = body
+                    .then(item_separator(compact, position))
+                    .then(self.values[i].extent(params.nested()));
+                if !compact {
+                    let separator = params.separators.after(position, self.num_shown());
+                    body = body.then(Extent::text(separator));
+                }
+            }
+            if single_line == Some(true) {
+                body = body
+                    .then(item_separator(true, self.num_extended))
+                    .then(Extent::text(ELLIPSIS));
+            }
+            // The length indicator, e.g., `<-3/5+>`
+            let length_indicator = Extent::text(&format!("<-{}+>", self.length_text()));
+            extent
+                .then(Extent::text(CLOSE_SYMBOL))
+                .then(body.indent(params.indentation))
+                .then(Extent::text(if compact {
+                    " "
+                } else {
+                    params.closing_brackets.separator()
+                }))
+                .then(Extent::text("] "))
+                .then(length_indicator)
+        })
+    }
+
+    /// Call `f` with the lines at which the shown elements start (see `child_lines`). The marker
+    /// of elided elements counts as part of the last element before it (or the opening bracket).
+    fn with_child_lines<R>(&self, params: LayoutParams, f: impl FnOnce(&[usize]) -> R) -> R {
+        let compute = || {
+            let mut first = description_extent(&self.description).height - 1;
+            let compact = !self.extended
+                || self
+                    .single_line(params.compact_width, params.inline_width, None)
+                    .is_some();
+            let shown = if self.extended { self.num_shown() } else { 0 };
+            let marker = self.elided_marker().filter(|_| self.extended);
+            if marker.is_some() && self.num_extended == 0 {
+                first += 1;
+            }
+            let values = self
+                .shown_indices()
+                .take(shown)
+                .enumerate()
+                .map(|(position, i)| {
+                    let extent = self.values[i].extent(params.nested());
+                    match &marker {
+                        Some(marker) if position + 1 == self.num_extended => {
+                            extent.then(Extent::line_break()).then(Extent::text(marker))
+                        }
+                        _ => extent,
+                    }
+                });
+            child_lines(first, compact, values)
+        };
+        self.extent.with_child_lines(params, compute, f)
+    }
+
+    fn draw_length_indicator<T: CursorTarget>(
+        &self,
+        cursor: &mut Cursor<T>,
+        path: Option<&ArrayPath>,
+        info: &RenderingInfo,
+    ) {
+        let layers = StyleLayers::default().with_if(
+            self.length_changed,
+            StyleLayer::Changed,
+            info.item_changed_style,
+        );
+        let knob_style = |active: bool| info.style(info.focus_if(active, NodeKind::Array, layers));
+        write_styled(cursor, "<", info.style(layers));
+        if self.can_shrink() {
+            write_styled(cursor, "-", knob_style(path == Some(&ArrayPath::Shrink)));
+        } else {
+            write_styled(cursor, " ", info.style(layers));
+        }
+        let length = self.length_text();
+        write_styled(cursor, &length, info.style(layers));
+        if self.can_grow() {
+            write_styled(cursor, "+", knob_style(path == Some(&ArrayPath::Grow)));
+        } else {
+            write_styled(cursor, " ", info.style(layers));
+        }
+        write_styled(cursor, ">", info.style(layers));
+    }
+
+    /// Draw the node up to its first element. Returns `None` if it is folded (and thus drawn
+    /// completely), otherwise whether the elements are drawn on a single line (see
+    /// `single_line`).
+    fn draw_open<T: CursorTarget>(
+        &self,
+        cursor: &mut Cursor<T>,
+        path: Option<&ArrayPath>,
+        info: &RenderingInfo,
+        style: ContainerStyle,
+        compact_width: Option<usize>,
+        location: &JsonPath,
+    ) -> Option<Option<bool>> {
+        draw_description(cursor, &self.description, self.description_changed, info);
+        let toggle_active = path == Some(&ArrayPath::Toggle);
+        if self.extended {
+            draw_toggle(
+                cursor,
+                NodeKind::Array,
+                CLOSE_SYMBOL,
+                toggle_active,
+                info,
+                style.layers,
+            );
+            let mut rendered = info.renderer.map(|r| Rendered::new(r, location));
+            Some(self.single_line(compact_width, info.inline_width, rendered.as_mut()))
+        } else {
+            draw_toggle(
+                cursor,
+                NodeKind::Array,
+                OPEN_SYMBOL,
+                toggle_active,
+                info,
+                style.layers,
+            );
+            draw_summary(cursor, style.summary, info);
+            draw_closing_bracket(cursor, " ", "]", info.style(style.layers));
+            None
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct DisplayScalar {
+    pub description: Option<StyledString>,
+    pub value: StyledString,
+    pub kind: ScalarKind,
+    pub changed: bool,
+    /// Whether the value is masked (see `JsonViewer::set_redaction`).
+    pub redacted: bool,
+    /// The encoding if the value looks like binary data (see `JsonViewer::set_binary_detection`).
+    /// Such values are shown as a summary that can be expanded to a hexdump.
+    pub binary: Option<BinaryEncoding>,
+    pub expanded: bool,
+    /// A human readable form of the value if it is a timestamp (see
+    /// `JsonViewer::set_humanize_timestamps`), which is shown after the value.
+    pub humanized: Option<String>,
+    /// The base the value is shown in if it is an integer.
+    pub number_base: NumberBase,
+    description_changed: bool,
+    // The byte ranges of the value that differ from the previous one if only parts of it changed.
+    changed_ranges: Option<Vec<Range<usize>>>,
+    // The difference to the previous value if both are numbers, e.g., `+5`.
+    delta: Option<String>,
+    /// The previous values, oldest first (see `JsonViewer::set_history_limit`).
+    pub history: VecDeque<String>,
+    /// Whether the previous values are shown below the value.
+    pub history_expanded: bool,
+}
+
+impl DisplayScalar {
+    /// The value as it is shown, i.e., masked if it is redacted.
+    pub fn text(&self) -> &str {
+        if self.redacted {
+            REDACTED
+        } else {
+            self.value.as_str()
+        }
+    }
+
+    /// The value as a URL if it looks like one (i.e., an `http(s)://` link without whitespace).
+    /// Only values that are a URL as a whole count: Activating a node yields a single link, and
+    /// URLs within longer texts are often not delimited unambiguously (e.g., by punctuation).
+    pub fn link(&self) -> Option<&str> {
+        if self.redacted {
+            return None;
+        }
+        let value = self.value.as_str();
+        let is_link = (value.starts_with("http://") || value.starts_with("https://"))
+            && !value.contains(char::is_whitespace);
+        if is_link {
+            Some(value)
+        } else {
+            None
+        }
+    }
+
+    fn update(
+        &self,
+        description: Option<StyledString>,
+        new_value: StyledString,
+        kind: ScalarKind,
+        options: UpdateOptions,
+    ) -> Self {
+        let changed = self.value != new_value || self.kind != kind;
+        let description_changed = self.description != description;
+        let changed_ranges = if changed {
+            worddiff::changed_ranges(self.value.as_str(), new_value.as_str())
+        } else {
+            None
+        };
+        let delta = if changed && options.numeric_deltas {
+            number::format_delta(self.value.as_str(), new_value.as_str())
+        } else {
+            None
+        };
+        let mut history = self.history.clone();
+        if changed && options.history_limit > 0 {
+            history.push_back(self.value.as_str().to_owned());
+        }
+        while history.len() > options.history_limit {
+            history.pop_front();
+        }
+        DisplayScalar {
+            description,
+            value: new_value,
+            kind,
+            changed,
+            redacted: self.redacted,
+            binary: self.binary,
+            expanded: self.expanded,
+            humanized: self.humanized.clone(),
+            number_base: self.number_base,
+            description_changed,
+            changed_ranges,
+            delta,
+            history,
+            history_expanded: self.history_expanded,
+        }
+    }
+
+    fn new(description: Option<StyledString>, value: StyledString, kind: ScalarKind) -> Self {
+        DisplayScalar {
+            description,
+            value,
+            kind,
+            changed: false,
+            redacted: false,
+            binary: None,
+            expanded: false,
+            humanized: None,
+            number_base: NumberBase::Decimal,
+            description_changed: false,
+            changed_ranges: None,
+            delta: None,
+            history: VecDeque::new(),
+            history_expanded: false,
+        }
+    }
+
+    /// The encoding of the value if it is shown as binary data.
+    fn shown_binary(&self) -> Option<BinaryEncoding> {
+        self.binary.filter(|_| !self.redacted)
+    }
+
+    /// The summary and toggle that are drawn instead of binary data, followed by the hexdump if
+    /// the value is expanded.
+    fn binary_parts(&self, encoding: BinaryEncoding) -> (String, Option<String>) {
+        let value = self.value.as_str();
+        let symbol = if self.expanded {
+            CLOSE_SYMBOL
+        } else {
+            OPEN_SYMBOL
+        };
+        let summary = format!("{} {}", binary::summary(value, encoding), symbol);
+        let dump = if self.expanded {
+            Some(binary::hexdump(value, encoding))
+        } else {
+            None
+        };
+        (summary, dump)
+    }
+
+    /// The humanized form of the value that is shown after it, e.g., ` (2023-11-14 22:13 UTC)`.
+    fn shown_humanized(&self) -> Option<String> {
+        match self.humanized {
+            Some(ref humanized) if !self.redacted => Some(format!(" ({})", humanized)),
+            _ => None,
+        }
+    }
+
+    /// The previous values as they are shown below the value if expanded, e.g., `was: 3, 5, 8`.
+    fn shown_history(&self) -> Option<String> {
+        if self.redacted || !self.history_expanded || self.history.is_empty() {
+            return None;
+        }
+        let values = self.history.iter().map(|v| v.replace('\n', " "));
+        Some(format!("\nwas: {}", values.collect::<Vec<_>>().join(", ")))
+    }
+
+    fn shown_delta(&self) -> Option<String> {
+        match self.delta {
+            Some(ref delta) if !self.redacted && self.changed => Some(format!(" ({})", delta)),
+            _ => None,
+        }
+    }
+
+    /// The value in `number_base` if it is not shown in decimal.
+    fn shown_number(&self) -> Option<String> {
+        if self.redacted || self.number_base == NumberBase::Decimal {
+            return None;
+        }
+        number::format_integer(self.value.as_str(), self.number_base)
+    }
+
+    /// Show the value in `base`. The original value (e.g., in `Node::scalar`) is not affected.
+    /// Fails if the value is not an integer.
+    pub fn set_number_base(&mut self, base: NumberBase) -> Result<(), ()> {
+        number::parse_integer(self.value.as_str()).ok_or(())?;
+        self.number_base = base;
+        Ok(())
+    }
+
+    /// The text that is drawn for the value.
+    fn shown_text(&self) -> String {
+        match self.shown_binary() {
+            Some(encoding) => match self.binary_parts(encoding) {
+                (summary, Some(dump)) => format!("{}\n{}", summary, dump),
+                (summary, None) => summary,
+            },
+            None => {
+                let value = self
+                    .shown_number()
+                    .unwrap_or_else(|| self.text().to_owned());
+                value
+                    + &self.shown_delta().unwrap_or_default()
+                    + &self.shown_humanized().unwrap_or_default()
+                    + &self.shown_history().unwrap_or_default()
+            }
+        }
+    }
+
+    /// Expand or collapse the hexdump of binary data and return whether it is expanded now. Fails
+    /// for other values.
+    pub fn toggle_binary(&mut self) -> Result<bool, ()> {
+        self.shown_binary().ok_or(())?;
+        self.expanded = !self.expanded;
+        Ok(self.expanded)
+    }
+
+    /// Width of the description and value or None if it would exceed `max`.
+    fn single_line_width(&self, max: usize) -> Option<usize> {
+        Some(description_width(&self.description)? + text_width(&self.shown_text())?)
+            .filter(|&w| w <= max)
+    }
+
+    fn extent(&self) -> Extent {
+        description_extent(&self.description).then(Extent::text(&self.shown_text()))
+    }
+
+    fn draw<T: CursorTarget>(
+        &self,
+        cursor: &mut Cursor<T>,
+        active: bool,
+        info: &RenderingInfo,
+        layers: StyleLayers,
+    ) {
+        draw_description(cursor, &self.description, self.description_changed, info);
+        if !info.wrap_values {
+            return self.draw_text(cursor, active, info, layers);
+        }
+        let mut cursor = cursor.save().line_start_column();
+        let start = cursor.get_col();
+        if start.raw_value() + MIN_WRAP_WIDTH < cursor.target().get_width().raw_value() {
+            cursor.set_line_start_column(start);
+        }
+        cursor.set_wrapping_mode(WrappingMode::Wrap);
+        self.draw_text(&mut cursor, active, info, layers);
+        cursor.set_wrapping_mode(WrappingMode::NoWrap);
+    }
+
+    /// Draw everything but the description.
+    fn draw_text<T: CursorTarget>(
+        &self,
+        cursor: &mut Cursor<T>,
+        active: bool,
+        info: &RenderingInfo,
+        layers: StyleLayers,
+    ) {
+        let changed = layers.with(StyleLayer::Changed, info.item_changed_style);
+        if let Some(encoding) = self.shown_binary() {
+            let layers = if self.changed { changed } else { layers };
+            let (summary, dump) = self.binary_parts(encoding);
+            let (text, symbol) = summary.split_at(summary.len() - OPEN_SYMBOL.len());
+            write_styled(cursor, text, info.style(layers));
+            let focused = info.focus_if(active, NodeKind::Scalar, layers);
+            write_styled(cursor, symbol, info.style(focused));
+            if let Some(dump) = dump {
+                write_styled(cursor, &format!("\n{}", dump), info.style(layers));
+            }
+            return;
+        }
+        {
+            let value_layers = match self.link() {
+                Some(_) => {
+                    let node_style = layers.get(StyleLayer::Node);
+                    layers.with(StyleLayer::Node, info.link_style.on_top_of(node_style))
+                }
+                None => layers,
+            };
+            let value_layers = info.focus_if(active, NodeKind::Scalar, value_layers);
+            // Only mark the parts of a string that changed if it is drawn as it is.
+            let changed_ranges = self.changed_ranges.as_ref().filter(|_| {
+                self.changed
+                    && !self.redacted
+                    && info.highlight.is_none()
+                    && self.shown_number().is_none()
+            });
+            let value_layers = value_layers.with_if(
+                self.changed && changed_ranges.is_none(),
+                StyleLayer::Changed,
+                info.item_changed_style,
+            );
+            let style = info.style(value_layers);
+            match (info.highlight, self.shown_number()) {
+                _ if self.redacted => write_styled(cursor, REDACTED, style),
+                (_, Some(number)) => write_styled(cursor, &number, style),
+                (Some(pattern), None) => self.value.draw_highlighted(
+                    cursor,
+                    &pattern.find_ranges(self.value.as_str()),
+                    style,
+                    info.style(value_layers.with(StyleLayer::Match, info.highlight_style)),
+                ),
+                (None, None) => {
+                    let changed = value_layers.with(StyleLayer::Changed, info.item_changed_style);
+                    let ranges = changed_ranges.map_or(&[][..], |ranges| &ranges[..]);
+                    self.value
+                        .draw_highlighted(cursor, ranges, style, info.style(changed))
+                }
+            }
+        }
+        if let Some(delta) = self.shown_delta() {
+            write_styled(cursor, &delta, info.style(changed));
+        }
+        if let Some(humanized) = self.shown_humanized() {
+            let style = info.timestamp_style.on_top_of(info.style(layers));
+            write_styled(cursor, &humanized, style);
+        }
+        if let Some(history) = self.shown_history() {
+            let style = info.history_style.on_top_of(info.style(layers));
+            write_styled(cursor, &history, style);
+        }
+    }
+}
+
+pub enum DisplayValue {
+    Scalar(DisplayScalar),
+    Object(DisplayObject),
+    Array(DisplayArray),
+}
+
+impl Clone for DisplayValue {
+    fn clone(&self) -> Self {
+        // Copy the nodes one by one, deepest first, rather than recursively (see `drop`).
+        let mut stack = vec![(self, self.children(), Vec::new())];
+        loop {
+            let (_, children, _) = stack.last_mut().expect("not empty");
+            match children.next() {
+                Some(child) => stack.push((child, child.children(), Vec::new())),
+                None => {
+                    let (node, _, children) = stack.pop().expect("not empty");
+                    let copy = node.with_children(children);
+                    match stack.last_mut() {
+                        Some((_, _, siblings)) => siblings.push(copy),
+                        None => return copy,
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Drop for DisplayValue {
+    fn drop(&mut self) {
+        // Drop the descendants one by one rather than recursively so that arbitrarily deep values
+        // do not overflow the stack.
+        let mut descendants = match self {
+            DisplayValue::Scalar(_) => return,
+            DisplayValue::Object(obj) => obj.members.take_values(),
+            DisplayValue::Array(array) => ::std::mem::take(&mut array.values),
+        };
+        while let Some(mut node) = descendants.pop() {
+            match &mut node {
+                DisplayValue::Scalar(_) => {}
+                DisplayValue::Object(obj) => descendants.extend(obj.members.take_values()),
+                DisplayValue::Array(array) => descendants.append(&mut array.values),
+            }
+        }
+    }
+}
+
+/// The key of the node at `location` if it is an object member.
+fn member_key(location: &JsonPath) -> Option<&Key> {
+    match location.segments().last() {
+        Some(JsonPathSegment::Key(id)) => Some(&id.key),
+        _ => None,
+    }
+}
+
+/// The children of a node with their path segment and their key if they are object members (see
+/// `DisplayValue::walk`).
+enum Children<'a> {
+    Members(::std::slice::Iter<'a, Member>),
+    Values(::std::iter::Enumerate<::std::slice::Iter<'a, DisplayValue>>),
+}
+
+impl<'a> Children<'a> {
+    fn of(node: &'a DisplayValue) -> Self {
+        match node {
+            DisplayValue::Scalar(_) => Children::Values(Default::default()),
+            DisplayValue::Object(obj) => Children::Members(obj.members.iter()),
+            DisplayValue::Array(array) => Children::Values(array.values.iter().enumerate()),
+        }
+    }
+}
+
+impl<'a> Iterator for Children<'a> {
+    type Item = (JsonPathSegment, Option<&'a Key>, &'a DisplayValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Children::Members(members) => members.next().map(|member| {
+                let segment = JsonPathSegment::Key(member.id());
+                (segment, Some(&member.key), &member.value)
+            }),
+            Children::Values(values) => values
+                .next()
+                .map(|(i, value)| (JsonPathSegment::Index(i), None, value)),
+        }
+    }
+}
+
+/// A node whose children are being visited by `DisplayValue::update_nodes`.
+struct NodeUpdate<'a> {
+    /// The caches of the node, which are invalidated if it or any of its descendants changed.
+    caches: Option<(&'a mut ExtentCache, &'a mut StatsCache)>,
+    members: ::std::slice::IterMut<'a, Member>,
+    values: ::std::iter::Enumerate<::std::slice::IterMut<'a, DisplayValue>>,
+    changed: bool,
+}
+
+impl<'a> NodeUpdate<'a> {
+    fn new(node: &'a mut DisplayValue, changed: bool) -> Self {
+        let mut update = NodeUpdate {
+            caches: None,
+            members: Default::default(),
+            values: Default::default(),
+            changed,
+        };
+        match node {
+            DisplayValue::Scalar(_) => {}
+            DisplayValue::Object(obj) => {
+                update.caches = Some((&mut obj.extent, &mut obj.stats));
+                update.members = obj.members.iter_mut();
+            }
+            DisplayValue::Array(array) => {
+                update.caches = Some((&mut array.extent, &mut array.stats));
+                update.values = array.values.iter_mut().enumerate();
+            }
+        }
+        update
+    }
+
+    /// The next child with its path segment and its key if it is an object member.
+    fn next_child(&mut self) -> Option<(JsonPathSegment, Option<&'a Key>, &'a mut DisplayValue)> {
+        if let Some(member) = self.members.next() {
+            let segment = JsonPathSegment::Key(member.id());
+            let Member { key, value, .. } = member;
+            return Some((segment, Some(&*key), value));
+        }
+        self.values
+            .next()
+            .map(|(i, value)| (JsonPathSegment::Index(i), None, value))
+    }
+}
+
+/// How a node is built by `DisplayValue::build`.
+enum Build<'o, V> {
+    New(V),
+    /// Update the (old) node with the value.
+    Update(&'o DisplayValue, V),
+    /// The node has been built already.
+    Done(Box<DisplayValue>),
+    /// Update the (old) node with a node that has been built from the new value already.
+    Merge(&'o DisplayValue, Box<DisplayValue>),
+}
+
+/// A built node as a `Value`, e.g., to update another node with it (see `Build::Merge`). Redacted
+/// scalars are not masked.
+#[derive(Clone, Copy)]
+struct Built<'a>(&'a DisplayValue);
+
+impl<'a> Value for Built<'a> {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        match self.0 {
+            DisplayValue::Scalar(scalar) => {
+                ValueVariant::Scalar(scalar.description.clone(), scalar.value.clone())
+            }
+            DisplayValue::Object(obj) => ValueVariant::Map(
+                obj.description.clone(),
+                Box::new(
+                    obj.members
+                        .iter()
+                        .map(|member| (member.key.clone(), Built(&member.value))),
+                ),
+            ),
+            DisplayValue::Array(array) => ValueVariant::Array(
+                array.description.clone(),
+                Box::new(array.values.iter().map(Built)),
+            ),
+        }
+    }
+
+    fn scalar_kind(&self) -> ScalarKind {
+        match self.0 {
+            DisplayValue::Scalar(scalar) => scalar.kind,
+            _ => ScalarKind::Other,
+        }
+    }
+}
+
+/// A node to build as a child of another one.
+struct Child<'o, V> {
+    /// The key of the node if it is an object member.
+    key: Option<Key>,
+    /// The segment of the path to the node if its location is required to build it.
+    segment: Option<JsonPathSegment>,
+    build: Build<'o, V>,
+}
+
+/// An object or array (or a scalar) whose children are being built by `DisplayValue::build`.
+struct PartialNode<'o, V> {
+    node: DisplayValue,
+    children: ::std::vec::IntoIter<Child<'o, V>>,
+    key: Option<Key>,
+    /// Whether the segment of the node was pushed to the location.
+    located: bool,
+}
+
+impl<'o, V: Value> PartialNode<'o, V> {
+    fn start(
+        child: Child<'o, V>,
+        options: UpdateOptions,
+        location: &mut JsonPath,
+        keys: &KeyPool,
+    ) -> Self {
+        let located = child.segment.is_some();
+        if let Some(segment) = child.segment {
+            location.push(segment);
+        }
+        let (node, children) = match child.build {
+            Build::New(value) => DisplayValue::start_new(value, keys),
+            Build::Update(old, value) => old.start_update(value, options, location, keys),
+            Build::Done(node) => (*node, Vec::new()),
+            Build::Merge(old, node) => {
+                let node = old.update_with(Built(&node), options, location, keys);
+                (node, Vec::new())
+            }
+        };
+        PartialNode {
+            node,
+            children: children.into_iter(),
+            key: child.key,
+            located,
+        }
+    }
+
+    /// Add a built child (with `key` if the node is an object).
+    fn push(&mut self, key: Option<Key>, child: DisplayValue) {
+        match (&mut self.node, key) {
+            (DisplayValue::Object(obj), Some(key)) => obj.members.push(key, child),
+            (DisplayValue::Array(array), None) => array.values.push(child),
+            _ => panic!("Mismatched DisplayValue and child type!"),
+        }
+    }
+}
+
+impl DisplayValue {
+    /// Whether both values display the same data (ignoring fold state and change highlighting).
+    pub fn same_content(&self, other: &DisplayValue) -> bool {
+        let mut stack = vec![(self, other)];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (DisplayValue::Scalar(a), DisplayValue::Scalar(b)) => {
+                    if a.description != b.description || a.value != b.value || a.kind != b.kind {
+                        return false;
+                    }
+                }
+                (DisplayValue::Object(a), DisplayValue::Object(b)) => {
+                    if a.description != b.description || a.members.len() != b.members.len() {
+                        return false;
+                    }
+                    for (a, b) in a.members.iter().zip(b.members.iter()) {
+                        if a.key != b.key {
+                            return false;
+                        }
+                        stack.push((&a.value, &b.value));
+                    }
+                }
+                (DisplayValue::Array(a), DisplayValue::Array(b)) => {
+                    if a.description != b.description || a.values.len() != b.values.len() {
+                        return false;
+                    }
+                    stack.extend(a.values.iter().zip(&b.values));
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// Whether the node displays `value`, in terms of `same_content`, without building a node for
+    /// it.
+    pub fn same_value<V: Value>(&self, value: V) -> bool {
+        let mut stack = vec![(self, value)];
+        while let Some((node, value)) = stack.pop() {
+            let kind = value.scalar_kind();
+            match (node, value.visit()) {
+                (DisplayValue::Scalar(a), ValueVariant::Scalar(d, s)) => {
+                    if a.description != d || a.value != s || a.kind != kind {
+                        return false;
+                    }
+                }
+                (DisplayValue::Object(a), ValueVariant::Map(d, members)) => {
+                    if a.description != d {
+                        return false;
+                    }
+                    let mut own = a.members.iter();
+                    for (key, value) in members {
+                        match own.next() {
+                            Some(member) if member.key == key => stack.push((&member.value, value)),
+                            _ => return false,
+                        }
+                    }
+                    if own.next().is_some() {
+                        return false;
+                    }
+                }
+                (DisplayValue::Array(a), ValueVariant::Array(d, values)) => {
+                    if a.description != d {
+                        return false;
+                    }
+                    let mut own = a.values.iter();
+                    for value in values {
+                        match own.next() {
+                            Some(node) => stack.push((node, value)),
+                            None => return false,
+                        }
+                    }
+                    if own.next().is_some() {
+                        return false;
+                    }
+                }
+                _ => return false,
+            }
+        }
+        true
+    }
+
+    /// This node updated to show `value`, with the differences marked as changed. `location` is
+    /// the path of this node, and the keys of new object members are taken from `keys`.
+    pub fn update_with(
+        &self,
+        value: impl Value,
+        options: UpdateOptions,
+        location: &mut JsonPath,
+        keys: &KeyPool,
+    ) -> Self {
+        let root = Child {
+            key: None,
+            segment: None,
+            build: Build::Update(self, value),
+        };
+        Self::build(root, options, location, keys)
+    }
+
+    /// Build the node described by `child` (at `location`, or below it if it has a segment).
+    /// Objects and arrays are built using an explicit stack (rather than recursively) so that
+    /// arbitrarily deep values do not overflow the stack.
+    fn build<V: Value>(
+        child: Child<V>,
+        options: UpdateOptions,
+        location: &mut JsonPath,
+        keys: &KeyPool,
+    ) -> Self {
+        let mut stack = vec![PartialNode::start(child, options, location, keys)];
+        loop {
+            let next = stack.last_mut().and_then(|node| node.children.next());
+            if let Some(child) = next {
+                stack.push(PartialNode::start(child, options, location, keys));
+                continue;
+            }
+            let mut done = stack
+                .pop()
+                .expect("only popped after the children are built");
+            if let DisplayValue::Array(array) = &mut done.node {
+                if array.sort.is_some() {
+                    array.update_order();
+                }
+            }
+            if done.located {
+                location.pop();
+            }
+            match stack.last_mut() {
+                Some(parent) => parent.push(done.key, done.node),
+                None => return done.node,
+            }
+        }
+    }
+
+    /// The update of this node (without children) with `value` and the children to build.
+    fn start_update<'o, V: Value>(
+        &'o self,
+        value: V,
+        options: UpdateOptions,
+        location: &mut JsonPath,
+        keys: &KeyPool,
+    ) -> (Self, Vec<Child<'o, V>>) {
+        let kind = value.scalar_kind();
+        match (self, value.clone().visit()) {
+            (DisplayValue::Scalar(old), ValueVariant::Scalar(d, s)) => (
+                DisplayValue::Scalar(old.update(d, s, kind, options)),
+                Vec::new(),
+            ),
+            (DisplayValue::Object(old), ValueVariant::Map(d, s)) => {
+                let (obj, children) = old.update(d, s, keys);
+                (DisplayValue::Object(obj), children)
+            }
+            (DisplayValue::Array(old), ValueVariant::Array(d, s)) => {
+                let (array, children) = old.update(d, s, options, location, keys);
+                (DisplayValue::Array(array), children)
+            }
+            _ => {
+                // The type of the value has changed: Everything is new, but a container that
+                // replaces another one stays folded (or unfolded).
+                let mut val = Self::new_with_keys(value, keys);
+                val.set_changed(true);
+                if let Some(extended) = self.extended() {
+                    match &mut val {
+                        DisplayValue::Object(obj) => obj.extended = extended,
+                        DisplayValue::Array(array) => array.extended = extended,
+                        DisplayValue::Scalar(_) => {}
+                    }
+                }
+                (val, Vec::new())
+            }
+        }
+    }
+
+    /// The text drawn instead of an object or array below the maximum render depth.
+    pub fn cut_placeholder(&self) -> String {
+        match self {
+            DisplayValue::Object(_) => format!("{{ {} }}", ELLIPSIS),
+            _ => format!("[ {} ]", ELLIPSIS),
+        }
+    }
+
+    /// Whether the node is unfolded, or None for scalars.
+    pub fn extended(&self) -> Option<bool> {
+        match self {
+            DisplayValue::Scalar(_) => None,
+            DisplayValue::Object(obj) => Some(obj.extended),
+            DisplayValue::Array(array) => Some(array.extended),
+        }
+    }
+
+    /// Fold (or unfold) this node and all objects and arrays below it.
+    pub fn set_extended_recursive(&mut self, extended: bool) {
+        self.update_nodes(&mut JsonPath::root(), &mut |node, _, _| {
+            match node {
+                DisplayValue::Scalar(_) => return false,
+                DisplayValue::Object(obj) => obj.extended = extended,
+                DisplayValue::Array(array) => array.extended = extended,
+            }
+            true
+        });
+    }
+
+    /// The fold state of the node if it is an object, array or binary scalar.
+    pub fn fold(&self) -> Option<Fold> {
+        match self {
+            DisplayValue::Scalar(scalar) if scalar.binary.is_some() => {
+                Some(Fold::new(scalar.expanded, 0, 0))
+            }
+            DisplayValue::Scalar(_) => None,
+            DisplayValue::Object(obj) => Some(Fold::new(obj.extended, 0, 0)),
+            DisplayValue::Array(array) => Some(Fold {
+                page_size: array.page_size,
+                page_start: array.page_start,
+                ..Fold::new(array.extended, array.num_extended, array.num_tail)
+            }),
+        }
+    }
+
+    /// Restore the fold state of the node (see `fold`).
+    pub fn restore_fold(&mut self, fold: &Fold) {
+        match self {
+            DisplayValue::Scalar(scalar) => scalar.expanded = fold.extended,
+            DisplayValue::Object(obj) => obj.extended = fold.extended,
+            DisplayValue::Array(array) => {
+                array.extended = fold.extended;
+                array.num_extended = min(fold.num_extended, array.values.len());
+                array.num_tail = min(fold.num_tail, array.values.len() - array.num_extended);
+                array.page_size = fold.page_size;
+                array.page_start = fold.page_start;
+                array.fix_page();
+            }
+        }
+    }
+
+    /// Collect the fold state of all objects, arrays and binary scalars in the subtree at
+    /// `location`, e.g., to restore it later using `restore_folds`.
+    pub fn folds(&self, location: &mut JsonPath, folds: &mut BTreeMap<JsonPath, Fold>) {
+        self.walk(location, &mut |node, location, _| {
+            if let Some(fold) = node.fold() {
+                folds.insert(location.clone(), fold);
+            }
+        });
+    }
+
+    /// Restore the fold state of the nodes in the subtree at `location` that are in `folds`.
+    pub fn restore_folds(&mut self, location: &mut JsonPath, folds: &BTreeMap<JsonPath, Fold>) {
+        self.update_nodes(
+            location,
+            &mut |node, location, _| match folds.get(location) {
+                Some(fold) => {
+                    node.restore_fold(fold);
+                    true
+                }
+                None => false,
+            },
+        );
+    }
+
+    /// The width of the node if it fits on a single line of at most `max` characters. If
+    /// `rendered` is set, nodes drawn by its custom renderer are measured as drawn.
+    fn single_line_width(&self, max: usize, rendered: Option<&mut Rendered>) -> Option<usize> {
+        if let Some(width) = rendered
+            .as_ref()
+            .and_then(|rendered| rendered.measure(self))
+        {
+            return width.filter(|&w| w <= max);
+        }
+        match self {
+            DisplayValue::Scalar(scalar) => scalar.single_line_width(max),
+            DisplayValue::Object(obj) => obj.single_line_width(max, rendered),
+            DisplayValue::Array(array) => array.single_line_width(max, rendered),
+        }
+    }
+
+    /// Like `single_line_width`, for this node as the child of the node measured by `rendered`
+    /// (at `segment`).
+    fn child_single_line_width(
+        &self,
+        segment: impl FnOnce() -> JsonPathSegment,
+        max: usize,
+        rendered: Option<&mut Rendered>,
+    ) -> Option<usize> {
+        match rendered {
+            Some(rendered) => {
+                rendered.location.push(segment());
+                let width = self.single_line_width(max, Some(&mut *rendered));
+                rendered.location.pop();
+                width
+            }
+            None => self.single_line_width(max, None),
+        }
+    }
+
+    /// The extent of the node as drawn by `draw` (if no custom renderer is used). The extents of
+    /// objects and arrays are cached.
+    pub fn extent(&self, params: LayoutParams) -> Extent {
+        if !self.has_extent(params) {
+            self.compute_descendant_extents(params);
+        }
+        match self {
+            DisplayValue::Scalar(scalar) => scalar.extent(),
+            DisplayValue::Object(obj) => obj.extent(params),
+            DisplayValue::Array(array) => array.extent(params),
+        }
+    }
+
+    /// Whether the extent is available without computing the extents of any children.
+    fn has_extent(&self, params: LayoutParams) -> bool {
+        match self {
+            DisplayValue::Scalar(_) => true,
+            DisplayValue::Object(obj) => obj.extent.is_cached(params),
+            DisplayValue::Array(array) => array.extent.is_cached(params),
+        }
+    }
+
+    /// All children of this node (in contrast to `shown_children`).
+    fn children(&self) -> Box<dyn Iterator<Item = &DisplayValue> + '_> {
+        match self {
+            DisplayValue::Scalar(_) => Box::new(::std::iter::empty()),
+            DisplayValue::Object(obj) => Box::new(obj.members.iter().map(|member| &member.value)),
+            DisplayValue::Array(array) => Box::new(array.values.iter()),
+        }
+    }
+
+    /// A copy of this node with `children` (in order) instead of its children.
+    fn with_children(&self, children: Vec<DisplayValue>) -> DisplayValue {
+        match self {
+            DisplayValue::Scalar(scalar) => DisplayValue::Scalar(scalar.clone()),
+            DisplayValue::Object(obj) => DisplayValue::Object(DisplayObject {
+                description: obj.description.clone(),
+                members: obj.members.with_values(children),
+                extended: obj.extended,
+                description_changed: obj.description_changed,
+                extent: obj.extent.clone(),
+                stats: obj.stats.clone(),
+            }),
+            DisplayValue::Array(array) => DisplayValue::Array(DisplayArray {
+                description: array.description.clone(),
+                values: children,
+                extended: array.extended,
+                num_extended: array.num_extended,
+                num_tail: array.num_tail,
+                page_size: array.page_size,
+                page_start: array.page_start,
+                sort: array.sort.clone(),
+                order: array.order.clone(),
+                rank: array.rank.clone(),
+                length_changed: array.length_changed,
+                description_changed: array.description_changed,
+                extent: array.extent.clone(),
+                stats: array.stats.clone(),
+            }),
+        }
+    }
+
+    /// The children of this node that are drawn if it is drawn.
+    fn shown_children(&self) -> Box<dyn Iterator<Item = &DisplayValue> + '_> {
+        match self {
+            DisplayValue::Object(obj) if obj.extended => {
+                Box::new(obj.members.iter().map(|member| &member.value))
+            }
+            DisplayValue::Array(array) if array.extended => {
+                Box::new(array.shown_indices().map(move |i| &array.values[i]))
+            }
+            _ => Box::new(::std::iter::empty()),
+        }
+    }
+
+    /// Compute the missing extents of all shown descendants, deepest first, so that computing the
+    /// extent of this node does not recurse (and overflow the stack for deeply nested values).
+    fn compute_descendant_extents(&self, params: LayoutParams) {
+        let missing = |params| move |node: &&DisplayValue| !node.has_extent(params);
+        let children = params.nested();
+        let mut stack = self
+            .shown_children()
+            .filter(missing(children))
+            .map(|node| (node, children, false))
+            .collect::<Vec<_>>();
+        while let Some((node, params, children_done)) = stack.pop() {
+            if children_done {
+                node.extent(params);
+            } else {
+                stack.push((node, params, true));
+                let children = params.nested();
+                stack.extend(
+                    node.shown_children()
+                        .filter(missing(children))
+                        .map(|n| (n, children, false)),
+                );
+            }
+        }
+    }
+
+    /// Call `f` with the lines at which the shown children of this node start (see
+    /// `child_lines`).
+    fn with_child_lines<R>(&self, params: LayoutParams, f: impl FnOnce(&[usize]) -> R) -> R {
+        match self {
+            DisplayValue::Scalar(_) => f(&[0]),
+            DisplayValue::Object(obj) => obj.with_child_lines(params, f),
+            DisplayValue::Array(array) => array.with_child_lines(params, f),
+        }
+    }
+
+    /// The index of the child reached via `segment` among the shown children of this node.
+    fn shown_child_index(&self, segment: &JsonPathSegment) -> Option<usize> {
+        match (self, segment) {
+            (DisplayValue::Object(obj), JsonPathSegment::Key(id)) if obj.extended => {
+                obj.members.position(id)
+            }
+            (DisplayValue::Array(array), &JsonPathSegment::Index(i)) if array.extended => {
+                array.shown_position(i)
+            }
+            _ => None,
+        }
+    }
+
+    /// The `index`th child of this node and the segment of the path leading to it.
+    fn child(&self, index: usize) -> Option<(JsonPathSegment, &DisplayValue)> {
+        match self {
+            DisplayValue::Scalar(_) => None,
+            DisplayValue::Object(obj) => obj
+                .members
+                .iter()
+                .nth(index)
+                .map(|m| (JsonPathSegment::Key(m.id()), &m.value)),
+            DisplayValue::Array(array) if index < array.num_shown() => {
+                let i = array.shown_index(index);
+                Some((JsonPathSegment::Index(i), &array.values[i]))
+            }
+            DisplayValue::Array(_) => None,
+        }
+    }
+
+    /// The path (relative to this node) of the innermost node drawn in `line` (relative to the
+    /// first line of this node). Uses the cached lines of the children of all nodes on the way, so
+    /// that only the lines of one child per level have to be searched.
+    pub fn path_at_line(&self, mut params: LayoutParams, mut line: usize) -> Option<JsonPath> {
+        if line >= self.extent(params).height {
+            return None;
+        }
+        let mut path = JsonPath::root();
+        let mut node = self;
+        loop {
+            let child = node.with_child_lines(params, |lines| {
+                // The last line belongs to the node itself (its closing bracket).
+                match lines.partition_point(|&start| start <= line) {
+                    i if i == 0 || i == lines.len() => None,
+                    i => Some((i - 1, lines[i - 1])),
+                }
+            });
+            let (index, start) = match child {
+                Some(child) => child,
+                None => return Some(path),
+            };
+            let (segment, child) = node.child(index)?;
+            path.push(segment);
+            node = child;
+            params = params.nested();
+            line -= start;
+        }
+    }
+
+    /// The line (relative to the first line of this node) in which the node at `path` starts or
+    /// None if it is not shown, e.g., because an object containing it is folded.
+    pub fn line_of(&self, mut params: LayoutParams, path: &JsonPath) -> Option<usize> {
+        let mut line = 0;
+        let mut node = self;
+        for segment in path.segments() {
+            let index = node.shown_child_index(segment)?;
+            line += node.with_child_lines(params, |lines| lines[index]);
+            node = node.child(index)?.1;
+            params = params.nested();
+        }
+        Some(line)
+    }
+
+    /// Drop the cached extent of this node. This has to be called for all ancestors of a
+    /// modified node, too.
+    pub fn invalidate_extent(&mut self) {
+        match self {
+            DisplayValue::Scalar(_) => {}
+            DisplayValue::Object(obj) => obj.extent.invalidate(),
+            DisplayValue::Array(array) => array.extent.invalidate(),
+        }
+    }
+
+    /// The size of the subtree of this node. The statistics of objects and arrays are cached.
+    pub fn stats(&self) -> SubtreeStats {
+        if !self.has_stats() {
+            self.compute_descendant_stats();
+        }
+        match self {
+            DisplayValue::Scalar(scalar) => {
+                SubtreeStats::single(NodeKind::Scalar, scalar.value.as_str().len())
+            }
+            DisplayValue::Object(obj) => obj.stats.get_or_compute(|| {
+                let mut stats = SubtreeStats::single(NodeKind::Object, 0);
+                for member in obj.members.iter() {
+                    stats.bytes += member.key.to_string().len();
+                    stats.add_child(member.value.stats());
+                }
+                stats
+            }),
+            DisplayValue::Array(array) => array.stats.get_or_compute(|| {
+                let mut stats = SubtreeStats::single(NodeKind::Array, 0);
+                for value in &array.values {
+                    stats.add_child(value.stats());
+                }
+                stats
+            }),
+        }
+    }
+
+    fn has_stats(&self) -> bool {
+        match self {
+            DisplayValue::Scalar(_) => true,
+            DisplayValue::Object(obj) => obj.stats.is_cached(),
+            DisplayValue::Array(array) => array.stats.is_cached(),
+        }
+    }
+
+    /// Compute the missing statistics of all descendants, deepest first (see
+    /// `compute_descendant_extents`).
+    fn compute_descendant_stats(&self) {
+        let missing = |node: &&DisplayValue| !node.has_stats();
+        let mut stack = self
+            .children()
+            .filter(missing)
+            .map(|node| (node, false))
+            .collect::<Vec<_>>();
+        while let Some((node, children_done)) = stack.pop() {
+            if children_done {
+                node.stats();
+            } else {
+                stack.push((node, true));
+                stack.extend(node.children().filter(missing).map(|n| (n, false)));
+            }
+        }
+    }
+
+    fn invalidate_stats(&mut self) {
+        match self {
+            DisplayValue::Scalar(_) => {}
+            DisplayValue::Object(obj) => obj.stats.invalidate(),
+            DisplayValue::Array(array) => array.stats.invalidate(),
+        }
+    }
+
+    /// Get the node at `path`, if it exists.
+    pub fn get(&self, path: &JsonPath) -> Option<&DisplayValue> {
+        let mut current = self;
+        for segment in path.segments() {
+            current = match (current, segment) {
+                (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => {
+                    obj.members.get_by_id(id)?
+                }
+                (DisplayValue::Array(array), &JsonPathSegment::Index(i)) => array.values.get(i)?,
+                _ => return None,
+            };
+        }
+        Some(current)
+    }
+
+    /// Get the node at `path` for modification, if it exists. The cached extents and statistics
+    /// of the node and all of its ancestors are invalidated.
+    pub fn get_mut(&mut self, path: &JsonPath) -> Option<&mut DisplayValue> {
+        let mut current = self;
+        for segment in path.segments() {
+            current.invalidate_extent();
+            current.invalidate_stats();
+            current = match (current, segment) {
+                (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => {
+                    obj.members.get_by_id_mut(id)?
+                }
+                (DisplayValue::Array(array), &JsonPathSegment::Index(i)) => {
+                    array.values.get_mut(i)?
+                }
+                _ => return None,
+            };
+        }
+        current.invalidate_extent();
+        current.invalidate_stats();
+        Some(current)
+    }
+
+    /// Drop members of objects (in the whole subtree) according to `policy`.
+    pub fn resolve_duplicate_keys(&mut self, policy: DuplicateKeys) {
+        if policy == DuplicateKeys::All {
+            return;
+        }
+        self.update_nodes(&mut JsonPath::root(), &mut |node, _, _| match node {
+            DisplayValue::Object(obj) => {
+                let len = obj.members.len();
+                obj.members.resolve_duplicates(policy);
+                obj.members.len() != len
+            }
+            _ => false,
+        });
+    }
+
+    /// Call `f` for every node in the subtree at `location` (parents before their children) with
+    /// its location and its key if it is an object member. The nodes are visited using an
+    /// explicit stack (rather than recursively) so that deeply nested values do not overflow the
+    /// stack.
+    pub fn walk(
+        &self,
+        location: &mut JsonPath,
+        f: &mut dyn FnMut(&DisplayValue, &JsonPath, Option<&Key>),
+    ) {
+        f(self, location, member_key(location));
+        let mut stack = vec![Children::of(self)];
+        while let Some(children) = stack.last_mut() {
+            match children.next() {
+                Some((segment, key, child)) => {
+                    location.push(segment);
+                    f(child, location, key);
+                    stack.push(Children::of(child));
+                }
+                None => {
+                    stack.pop();
+                    if !stack.is_empty() {
+                        location.pop();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Like `walk`, but `f` may modify the nodes (before their children are visited). It returns
+    /// whether it modified a node, in which case the cached extents and statistics of the node
+    /// and all of its ancestors are invalidated. Returns whether any node was modified.
+    fn update_nodes(
+        &mut self,
+        location: &mut JsonPath,
+        f: &mut dyn FnMut(&mut DisplayValue, &JsonPath, Option<&Key>) -> bool,
+    ) -> bool {
+        let changed = f(self, location, member_key(location));
+        let mut stack = vec![NodeUpdate::new(self, changed)];
+        loop {
+            let next = stack.last_mut().expect("not empty").next_child();
+            if let Some((segment, key, child)) = next {
+                location.push(segment);
+                let changed = f(child, location, key);
+                stack.push(NodeUpdate::new(child, changed));
+                continue;
+            }
+            let done = stack.pop().expect("not empty");
+            if let (true, Some((extent, stats))) = (done.changed, done.caches) {
+                extent.invalidate();
+                stats.invalidate();
+            }
+            match stack.last_mut() {
+                Some(parent) => {
+                    parent.changed |= done.changed;
+                    location.pop();
+                }
+                None => return done.changed,
+            }
+        }
+    }
+
+    /// Call `f` for every scalar with its path and its key if it is an object member. `f` returns
+    /// whether it changed how the scalar is shown (see `update_nodes`). Returns whether any
+    /// scalar changed.
+    fn update_scalars(
+        &mut self,
+        location: &JsonPath,
+        f: &mut dyn FnMut(&mut DisplayScalar, &JsonPath, Option<&Key>) -> bool,
+    ) -> bool {
+        self.update_nodes(
+            &mut location.clone(),
+            &mut |node, location, key| match node {
+                DisplayValue::Scalar(scalar) => f(scalar, location, key),
+                _ => false,
+            },
+        )
+    }
+
+    /// Mark the scalars of this node, which is at `location`, as redacted according to `redaction`
+    /// (or none if it is None). Returns whether any scalar changed its state.
+    pub fn redact(&mut self, location: &JsonPath, redaction: Option<&Redaction>) -> bool {
+        self.update_scalars(location, &mut |scalar, location, key| {
+            let redacted = redaction
+                .map(|redaction| redaction(location, key))
+                .unwrap_or(false);
+            let changed = scalar.redacted != redacted;
+            scalar.redacted = redacted;
+            changed
+        })
+    }
+
+    /// Detect scalars that look like binary data (or forget about them if `enabled` is false).
+    /// Returns whether any scalar changed its state.
+    pub fn detect_binary(&mut self, enabled: bool) -> bool {
+        self.update_scalars(&JsonPath::root(), &mut |scalar, _, _| {
+            let binary = if enabled {
+                binary::detect(scalar.value.as_str())
+            } else {
+                None
+            };
+            let changed = scalar.binary != binary;
+            scalar.binary = binary;
+            changed
+        })
+    }
+
+    /// Compute the humanized form of all scalars that are timestamps according to `options` (or
+    /// forget them if it is None). Returns whether any scalar changed its state.
+    #[cfg(feature = "chrono")]
+    pub fn humanize_timestamps(&mut self, options: Option<&TimestampOptions>) -> bool {
+        self.update_scalars(&JsonPath::root(), &mut |scalar, _, _| {
+            let humanized =
+                options.and_then(|options| timestamp::humanize(scalar.value.as_str(), options));
+            let changed = scalar.humanized != humanized;
+            scalar.humanized = humanized;
+            changed
+        })
+    }
+
+    /// Drop the oldest previous values of scalars beyond `limit`. Returns whether any were
+    /// dropped.
+    pub fn limit_history(&mut self, limit: usize) -> bool {
+        self.update_scalars(&JsonPath::root(), &mut |scalar, _, _| {
+            let dropped = scalar.history.len().saturating_sub(limit);
+            scalar.history.drain(..dropped);
+            dropped > 0
+        })
+    }
+
+    /// Remove the differences of numbers to their previous values. Returns whether any were
+    /// shown.
+    pub fn clear_deltas(&mut self) -> bool {
+        self.update_scalars(&JsonPath::root(), &mut |scalar, _, _| {
+            scalar.delta.take().is_some()
+        })
+    }
+
+    /// Set the changed state of all nodes in the subtree to `changed`.
+    pub fn set_changed(&mut self, changed: bool) {
+        let mut nodes = vec![self];
+        while let Some(node) = nodes.pop() {
+            match node {
+                DisplayValue::Scalar(scalar) => {
+                    scalar.description_changed = changed;
+                    scalar.changed = changed;
+                    scalar.changed_ranges = None;
+                    scalar.delta = None;
+                }
+                DisplayValue::Object(obj) => {
+                    obj.description_changed = changed;
+                    nodes.extend(obj.members.values_mut());
+                }
+                DisplayValue::Array(array) => {
+                    array.description_changed = changed;
+                    array.length_changed = changed;
+                    nodes.extend(array.values.iter_mut());
                 }
-                write!(cursor, "{}", OPEN_SYMBOL).unwrap();
             }
-            write!(cursor, " }}").unwrap();
         }
     }
-}
 
-pub struct DisplayArray {
-    description: Option<String>,
-    pub values: Vec<DisplayValue>,
-    pub extended: bool,
-    pub num_extended: usize,
-    pub length_changed: bool,
-    description_changed: bool,
-}
-impl DisplayArray {
-    pub fn toggle_visibility(&mut self) {
-        self.extended ^= true;
-    }
-    pub fn grow(&mut self) {
-        self.num_extended += 1;
-        assert!(self.num_extended <= self.values.len());
+    /// Whether the node itself (rather than one of its children) is highlighted as changed.
+    pub fn is_changed(&self) -> bool {
+        match self {
+            DisplayValue::Scalar(scalar) => scalar.changed || scalar.description_changed,
+            DisplayValue::Object(obj) => obj.description_changed,
+            DisplayValue::Array(array) => array.description_changed || array.length_changed,
+        }
     }
-    pub fn shrink(&mut self) {
-        self.num_extended -= 1;
+
+    /// Collect the paths of all nodes in the subtree at `location` that are highlighted as
+    /// changed, in the order they are drawn.
+    pub fn changed_paths(&self, location: &mut JsonPath, paths: &mut Vec<JsonPath>) {
+        self.walk(location, &mut |node, location, _| {
+            if node.is_changed() {
+                paths.push(location.clone());
+            }
+        });
     }
 
-    pub fn can_grow(&self) -> bool {
-        self.num_extended < self.values.len()
+    /// Highlight the differences to `baseline` as changed as if this node was the result of an
+    /// update of it (instead of the changes of the last update). Object members are compared by
+    /// key, array elements by index.
+    pub fn mark_differences(&mut self, baseline: &DisplayValue) {
+        // Compare the nodes pair by pair using an explicit stack (rather than recursively, see
+        // `same_content`).
+        let mut stack = vec![(self, baseline)];
+        while let Some(pair) = stack.pop() {
+            match pair {
+                (DisplayValue::Scalar(scalar), DisplayValue::Scalar(old)) => {
+                    scalar.changed = scalar.value != old.value;
+                    scalar.description_changed = scalar.description != old.description;
+                    scalar.changed_ranges = None;
+                    scalar.delta = None;
+                }
+                (DisplayValue::Object(obj), DisplayValue::Object(old)) => {
+                    obj.description_changed = obj.description != old.description;
+                    obj.extent.invalidate();
+                    for member in obj.members.iter_mut() {
+                        match old.members.get_by_id(&member.id()) {
+                            Some(old_value) => stack.push((&mut member.value, old_value)),
+                            None => member.value.set_changed(false),
+                        }
+                    }
+                }
+                (DisplayValue::Array(array), DisplayValue::Array(old)) => {
+                    array.description_changed = array.description != old.description;
+                    array.length_changed = array.values.len() != old.values.len();
+                    array.extent.invalidate();
+                    for (i, value) in array.values.iter_mut().enumerate() {
+                        match old.values.get(i) {
+                            Some(old_value) => stack.push((value, old_value)),
+                            None => value.set_changed(false),
+                        }
+                    }
+                }
+                (value, _) => value.set_changed(true),
+            }
+        }
     }
 
-    pub fn can_shrink(&self) -> bool {
-        self.num_extended > 0
+    pub fn new(value: impl Value) -> Self {
+        Self::new_with_keys(value, &KeyPool::new())
     }
 
-    fn update<'s, V: Value>(
-        &self,
-        description: Option<String>,
-        values: Box<dyn Iterator<Item = V> + 's>,
-    ) -> Self {
-        let mut old_vals = self.values.iter();
-        let values = values
-            .into_iter()
-            .map(|value| {
-                if let Some(old_val) = old_vals.next() {
-                    old_val.update(value)
-                } else {
-                    DisplayValue::new(value)
-                }
-            })
-            .collect::<Vec<_>>();
-        let num_extended = min(self.num_extended, values.len());
-        let length_changed = self.values.len() != values.len();
-        let description_changed = self.description != description;
-        DisplayArray {
-            description,
-            values,
-            extended: self.extended,
-            num_extended,
-            length_changed,
-            description_changed,
-        }
+    /// Like `new`, but take the keys of object members from `keys`.
+    pub fn new_with_keys(value: impl Value, keys: &KeyPool) -> Self {
+        let root = Child {
+            key: None,
+            segment: None,
+            build: Build::New(value),
+        };
+        Self::build(root, UpdateOptions::default(), &mut JsonPath::root(), keys)
     }
 
-    fn new<'s, V: Value>(
-        description: Option<String>,
-        values: Box<dyn Iterator<Item = V> + 's>,
-    ) -> Self {
-        let values = values
-            .into_iter()
-            .map(DisplayValue::new)
-            .collect::<Vec<_>>();
-        let num_extended = min(3, values.len());
-        DisplayArray {
-            description,
-            values,
-            extended: true,
-            num_extended,
-            length_changed: false,
-            description_changed: false,
+    /// The node for `value` (without children) and the children to build.
+    fn start_new<'o, V: Value>(value: V, keys: &KeyPool) -> (Self, Vec<Child<'o, V>>) {
+        let kind = value.scalar_kind();
+        match value.visit() {
+            ValueVariant::Scalar(d, s) => (
+                DisplayValue::Scalar(DisplayScalar::new(d, s, kind)),
+                Vec::new(),
+            ),
+            ValueVariant::Map(d, s) => {
+                let (obj, children) = DisplayObject::new(d, s, keys);
+                (DisplayValue::Object(obj), children)
+            }
+            ValueVariant::Array(d, s) => {
+                let (array, children) = DisplayArray::new(d, s);
+                (DisplayValue::Array(array), children)
+            }
         }
     }
-
-    fn draw<T: CursorTarget>(
+    pub fn draw<T: CursorTarget>(
         &self,
         cursor: &mut Cursor<T>,
-        path: Option<&ArrayPath>,
+        path: Option<&Path>,
         info: &RenderingInfo,
         indentation: Width,
+        location: &mut JsonPath,
     ) {
-        use std::fmt::Write;
+        // The unfolded objects and arrays whose children are being drawn, innermost last. Using an
+        // explicit stack (rather than recursion) keeps deeply nested values from overflowing the
+        // stack.
+        let mut open: Vec<OpenNode> = Vec::new();
+        let mut node = (self, path);
+        draw_badge(cursor, info, location, self);
+        loop {
+            match node
+                .0
+                .draw_start(cursor, node.1, info, indentation, location)
+            {
+                Some(started) => open.push(started),
+                None => match open.last() {
+                    Some(parent) => parent.child_done(cursor, info, location),
+                    None => return,
+                },
+            }
+            node = loop {
+                let innermost = match open.last_mut() {
+                    Some(innermost) => innermost,
+                    None => return,
+                };
+                if let Some(child) = innermost.next_child(cursor, info, indentation, location) {
+                    break child;
+                }
+                let done = open.pop().expect("innermost node exists");
+                done.close(cursor, info, indentation, location);
+                match open.last() {
+                    Some(parent) => parent.child_done(cursor, info, location),
+                    None => return,
+                }
+            };
+        }
+    }
 
-        {
-            let mut cursor = cursor.save().style_modifier();
-            if self.description_changed {
-                cursor.apply_style_modifier(info.item_changed_style);
+    /// Draw this node up to its first child. Returns the node if it is an unfolded object or
+    /// array, whose children and closing bracket are still to be drawn.
+    fn draw_start<'v, 'p, T: CursorTarget>(
+        &'v self,
+        cursor: &mut Cursor<T>,
+        path: Option<&'p Path>,
+        info: &RenderingInfo,
+        indentation: Width,
+        location: &JsonPath,
+    ) -> Option<OpenNode<'v, 'p>> {
+        if is_below_target(cursor) {
+            return None;
+        }
+        let top = cursor.get_row();
+        let error = info.errors.get(location);
+        let kind = Node::new(self).kind();
+        let mut node_style = info
+            .node_style
+            .and_then(|style| style(location, kind))
+            .unwrap_or_default();
+        if info.duplicates.contains(location) {
+            node_style = info.duplicate_style.on_top_of(node_style);
+        }
+        let in_selection = info
+            .selection
+            .is_some_and(|selection| selection.contains(location));
+        let layers = StyleLayers::default()
+            .with(StyleLayer::Node, node_style)
+            .with_if(error.is_some(), StyleLayer::Error, info.error_style)
+            .with_if(in_selection, StyleLayer::Selected, info.selected_style);
+        let selected = path.filter(|p| p.is_on_node()).is_some();
+        if selected {
+            info.mark_active_row(cursor);
+        }
+        let placeholder_style = info.style(info.focus_if(selected, kind, layers));
+        if info.hidden.contains(location) {
+            write_styled(cursor, HIDDEN_PLACEHOLDER, placeholder_style);
+            return None;
+        }
+        if info.cut.contains(location) {
+            write_styled(cursor, &self.cut_placeholder(), placeholder_style);
+            return None;
+        }
+        if let Some(renderer) = info.renderer {
+            // The node stands in for all interaction points within it.
+            let active = path.is_some();
+            if active && !selected {
+                info.mark_active_row(cursor);
             }
-            if let Some(description) = &self.description {
-                write!(cursor, "{} ", description).unwrap();
+            let style = info.style(info.focus_if(active, kind, layers));
+            let mut cursor = cursor.save().style_modifier();
+            cursor.apply_style_modifier(style);
+            if renderer.draw(location, Node::new(self), active, &mut *cursor) {
+                if let Some(overridden) = info.overridden {
+                    overridden.borrow_mut().insert(location.clone());
+                }
+                return None;
             }
         }
-        if self.extended {
-            write!(cursor, "[ ").unwrap();
-            {
-                let mut cursor = cursor.save().style_modifier();
-                if let Some(&ArrayPath::Toggle) = path {
-                    cursor.apply_style_modifier(info.get_focused_style());
-                }
-                write!(cursor, "{}", CLOSE_SYMBOL).unwrap();
+        let summary = match self.extended() {
+            Some(false) => info
+                .summary
+                .and_then(|summary| summary(location, Node::new(self))),
+            _ => None,
+        };
+        let depth = location.segments().len();
+        let depth_style = info.depth_style(depth);
+        // The indentation of the node's lines is not available to its single line form.
+        let indentation_width = indentation.raw_value() as usize;
+        let compact_width = info
+            .compact_width
+            .map(|w| w.saturating_sub(depth * indentation_width));
+        let style = ContainerStyle {
+            layers: layers.with(StyleLayer::Node, node_style.on_top_of(depth_style)),
+            summary: summary.as_deref(),
+        };
+        let single_line = match (self, path) {
+            (DisplayValue::Scalar(scalar), Some(&Path::Scalar)) => {
+                scalar.draw(cursor, true, info, layers);
+                None
             }
-            {
-                let mut cursor = cursor.save().line_start_column();
-                cursor.move_line_start_column(indentation.into());
-                for (i, value) in self.values.iter().enumerate().take(self.num_extended) {
-                    cursor.wrap_line();
-
-                    let subpath = if let Some(&ArrayPath::Item(active_i, ref subpath)) = path {
-                        if i == active_i {
-                            Some(subpath.as_ref())
-                        } else {
-                            None
-                        }
-                    } else {
-                        None
-                    };
-
-                    value.draw(&mut cursor, subpath, info, indentation);
-                    write!(cursor, ",",).unwrap();
-                }
+            (DisplayValue::Scalar(scalar), None) => {
+                scalar.draw(cursor, false, info, layers);
+                None
             }
-            write!(cursor, "\n] ").unwrap();
-            let mut cursor = cursor.save().style_modifier();
-            if self.length_changed {
-                cursor.apply_style_modifier(info.item_changed_style);
+            (DisplayValue::Object(obj), Some(Path::Object(op))) => {
+                obj.draw_open(cursor, Some(op), info, style, compact_width, location)
             }
-            write!(cursor, "<").unwrap();
-            if self.can_shrink() {
-                let mut cursor = cursor.save().style_modifier();
-                if let Some(&ArrayPath::Shrink) = path {
-                    cursor.apply_style_modifier(info.get_focused_style());
-                }
-                write!(cursor, "-").unwrap();
-            } else {
-                write!(cursor, " ").unwrap();
+            (DisplayValue::Object(obj), None) => {
+                obj.draw_open(cursor, None, info, style, compact_width, location)
             }
-            write!(cursor, "{}/{}", self.num_extended, self.values.len()).unwrap();
-            if self.can_grow() {
-                let mut cursor = cursor.save().style_modifier();
-                if let Some(&ArrayPath::Grow) = path {
-                    cursor.apply_style_modifier(info.get_focused_style());
-                }
-                write!(cursor, "+").unwrap();
-            } else {
-                write!(cursor, " ").unwrap();
+            (DisplayValue::Array(array), Some(Path::Array(ap))) => {
+                array.draw_open(cursor, Some(ap), info, style, compact_width, location)
             }
-            write!(cursor, ">").unwrap();
-        } else {
-            write!(cursor, "[ ").unwrap();
-            {
-                let mut cursor = cursor.save().style_modifier();
-                if let Some(&ArrayPath::Toggle) = path {
-                    cursor.apply_style_modifier(info.get_focused_style());
-                }
-                write!(cursor, "{}", OPEN_SYMBOL).unwrap();
+            (DisplayValue::Array(array), None) => {
+                array.draw_open(cursor, None, info, style, compact_width, location)
+            }
+            _ => panic!("Mismatched DisplayValue and path type!"),
+        };
+        let single_line = match single_line {
+            Some(single_line) => single_line,
+            None => {
+                draw_annotation(cursor, info.annotations.get(location));
+                draw_error(cursor, error, selected, info);
+                return None;
             }
-            write!(cursor, " ]").unwrap();
+        };
+        if single_line.is_none() {
+            draw_annotation(cursor, info.annotations.get(location));
+            draw_error(cursor, error, selected, info);
         }
+        cursor.move_line_start_column(indentation.into());
+        let skipped = match info.layout {
+            Some(params) if single_line.is_none() => self
+                .with_child_lines(params.at_depth(depth), |lines| {
+                    skip_children_above_target(cursor, top, lines)
+                }),
+            _ => 0,
+        };
+        let (active_key, active_child) = match (self, path) {
+            (DisplayValue::Object(obj), Some(Path::Object(ObjectPath::Key(id)))) => {
+                (obj.members.position(id), None)
+            }
+            (DisplayValue::Object(obj), Some(Path::Object(ObjectPath::Item(id, subpath)))) => (
+                None,
+                obj.members.position(id).map(|i| (i, subpath.as_ref())),
+            ),
+            (DisplayValue::Array(array), Some(Path::Array(ArrayPath::Item(i, subpath)))) => (
+                None,
+                array.shown_position(*i).map(|p| (p, subpath.as_ref())),
+            ),
+            _ => (None, None),
+        };
+        Some(OpenNode {
+            node: self,
+            path,
+            single_line,
+            layers: style.layers,
+            selected,
+            next: skipped,
+            skipped,
+            active_key,
+            active_child,
+            guide: None,
+        })
     }
 }
 
-pub struct DisplayScalar {
-    pub value: String,
-    pub changed: bool,
-}
-
-impl DisplayScalar {
-    fn update(&self, new_value: String) -> Self {
-        let changed = self.value != new_value;
-        DisplayScalar {
-            value: new_value,
-            changed,
-        }
+/// Draw the badge of the node at `location` (followed by a space), if it has one.
+fn draw_badge<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    info: &RenderingInfo,
+    location: &JsonPath,
+    value: &DisplayValue,
+) {
+    let badge = info
+        .badge
+        .and_then(|badge| badge(location, Node::new(value)));
+    if let Some(badge) = badge {
+        badge.draw(cursor);
+        cursor.write(" ");
     }
+}
 
-    fn new(value: String) -> Self {
-        DisplayScalar {
-            value,
-            changed: false,
-        }
+/// Draw the annotation of a node after it (or after the opening bracket of objects and arrays
+/// drawn on multiple lines).
+fn draw_annotation<T: CursorTarget>(cursor: &mut Cursor<T>, annotation: Option<&Annotation>) {
+    if let Some(annotation) = annotation {
+        let mut cursor = cursor.save().style_modifier();
+        cursor.apply_style_modifier(annotation.style);
+        cursor.write("  // ");
+        cursor.write(&annotation.text.replace('\n', " "));
     }
+}
 
-    fn draw<T: CursorTarget>(&self, cursor: &mut Cursor<T>, active: bool, info: &RenderingInfo) {
+/// Draw the validation error of a node in the same place as its annotation. Only the message of
+/// the selected node is shown to avoid cluttering the view.
+fn draw_error<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    error: Option<&String>,
+    selected: bool,
+    info: &RenderingInfo,
+) {
+    if let (Some(message), true) = (error, selected) {
         let mut cursor = cursor.save().style_modifier();
-        if active {
-            cursor.apply_style_modifier(info.get_focused_style());
-        }
-        if self.changed {
-            cursor.apply_style_modifier(info.item_changed_style);
-        }
-        cursor.write(&self.value);
+        cursor.apply_style_modifier(info.error_style);
+        cursor.write("  ! ");
+        cursor.write(message);
     }
 }
 
-pub enum DisplayValue {
-    Scalar(DisplayScalar),
-    Object(DisplayObject),
-    Array(DisplayArray),
+/// An unfolded object or array whose children are being drawn by `DisplayValue::draw`.
+struct OpenNode<'v, 'p> {
+    node: &'v DisplayValue,
+    path: Option<&'p Path>,
+    /// Whether the children are drawn on a single line (see `DisplayArray::single_line`).
+    single_line: Option<bool>,
+    layers: StyleLayers,
+    selected: bool,
+    /// The index of the next child to draw.
+    next: usize,
+    /// The number of children above the target that are not drawn at all.
+    skipped: usize,
+    /// The index of the member whose key is selected.
+    active_key: Option<usize>,
+    /// The index of the child containing the selection and the path within it.
+    active_child: Option<(usize, &'p Path)>,
+    /// The position of the first child, where the indentation guide starts.
+    guide: Option<(ColIndex, RowIndex)>,
 }
 
-impl DisplayValue {
-    pub fn update(&self, value: impl Value) -> Self {
-        match (self, value.clone().visit()) {
-            (DisplayValue::Scalar(old), ValueVariant::Scalar(s)) => {
-                DisplayValue::Scalar(old.update(s))
-            }
-            (DisplayValue::Object(old), ValueVariant::Map(d, s)) => {
-                DisplayValue::Object(old.update(d, s))
-            }
-            (DisplayValue::Array(old), ValueVariant::Array(d, s)) => {
-                DisplayValue::Array(old.update(d, s))
+impl<'v, 'p> OpenNode<'v, 'p> {
+    /// The number of children that are drawn (if they fit into the target).
+    fn shown(&self) -> usize {
+        match self.node {
+            DisplayValue::Scalar(_) => 0,
+            DisplayValue::Object(obj) => obj.members.len(),
+            DisplayValue::Array(array) => array.num_shown(),
+        }
+    }
+
+    /// Draw what precedes the next child (e.g., its key) and return the child and the path within
+    /// it, or `None` if there are no more (visible) children.
+    fn next_child<T: CursorTarget>(
+        &mut self,
+        cursor: &mut Cursor<T>,
+        info: &RenderingInfo,
+        indentation: Width,
+        location: &mut JsonPath,
+    ) -> Option<(&'v DisplayValue, Option<&'p Path>)> {
+        let i = self.next;
+        if i >= self.shown() || is_below_target(cursor) {
+            return None;
+        }
+        self.next += 1;
+        if self.single_line.is_none() {
+            cursor.wrap_line();
+            let start = (cursor.get_col() - indentation, cursor.get_row());
+            self.guide = self.guide.or(Some(start));
+        } else if i > 0 {
+            cursor.write(", ");
+        } else {
+            cursor.write(" ");
+        }
+        let subpath = match self.active_child {
+            Some((active_i, subpath)) if active_i == i => Some(subpath),
+            _ => None,
+        };
+        if location.segments().is_empty() {
+            info.member_rows
+                .borrow_mut()
+                .push((cursor.get_row(), Some(i)));
+        }
+        match self.node {
+            DisplayValue::Scalar(_) => None,
+            DisplayValue::Object(obj) => {
+                let member = obj.members.get_index(i)?;
+                location.push(JsonPathSegment::Key(member.id()));
+                draw_badge(cursor, info, location, &member.value);
+                let depth = location.segments().len();
+                if self.active_key == Some(i) {
+                    info.mark_active_row(cursor);
+                }
+                obj.draw_key(cursor, member, self.active_key == Some(i), info, depth);
+                Some((&member.value, subpath))
             }
-            _ => {
-                // The type of the value has changed
-                let mut val = Self::new(value);
-                match &mut val {
-                    DisplayValue::Scalar(v) => {
-                        v.changed = true;
+            DisplayValue::Array(array) => {
+                // The marker is above the target if the last element before it is.
+                if i == array.num_extended && (i == 0 || self.skipped < i) {
+                    if let Some(marker) = array.elided_marker() {
+                        write_styled(cursor, &marker, info.summary_style);
+                        cursor.wrap_line();
                     }
-                    DisplayValue::Object(_) | DisplayValue::Array(_) => { /*TODO: Propagate changed state further*/
+                }
+                let index = array.shown_index(i);
+                location.push(JsonPathSegment::Index(index));
+                draw_badge(cursor, info, location, &array.values[index]);
+                match info.index_label {
+                    Some(style) if self.single_line.is_none() => {
+                        write_styled(cursor, &format!("[{}]: ", index), style);
                     }
+                    _ => {}
                 }
-                val
+                Some((&array.values[index], subpath))
             }
         }
     }
 
-    pub fn new(value: impl Value) -> Self {
-        match value.visit() {
-            ValueVariant::Scalar(s) => DisplayValue::Scalar(DisplayScalar::new(s.to_owned())),
-            ValueVariant::Map(d, s) => DisplayValue::Object(DisplayObject::new(d, s)),
-            ValueVariant::Array(d, s) => DisplayValue::Array(DisplayArray::new(d, s)),
+    /// Draw what follows the child returned by `next_child` once it is drawn.
+    fn child_done<T: CursorTarget>(
+        &self,
+        cursor: &mut Cursor<T>,
+        info: &RenderingInfo,
+        location: &mut JsonPath,
+    ) {
+        location.pop();
+        if self.single_line.is_none() {
+            cursor.write(info.separators.after(self.next - 1, self.shown()));
         }
     }
-    pub fn draw<T: CursorTarget>(
-        &self,
+
+    /// Draw what follows the last child, i.e., (at least) the closing bracket.
+    fn close<T: CursorTarget>(
+        self,
         cursor: &mut Cursor<T>,
-        path: Option<&Path>,
         info: &RenderingInfo,
         indentation: Width,
+        location: &JsonPath,
     ) {
-        match (self, path) {
-            (&DisplayValue::Scalar(ref scalar), Some(&Path::Scalar)) => {
-                scalar.draw(cursor, true, info)
-            }
-            (&DisplayValue::Scalar(ref scalar), None) => scalar.draw(cursor, false, info),
-            (&DisplayValue::Object(ref obj), Some(&Path::Object(ref op))) => {
-                obj.draw(cursor, Some(op), info, indentation)
+        if self.single_line == Some(true) && !is_below_target(cursor) {
+            cursor.write(if self.shown() > 0 { ", " } else { " " });
+            cursor.write(ELLIPSIS);
+        }
+        if let (Some((column, row)), Some(style)) = (self.guide, info.indentation_guide) {
+            draw_indentation_guide(cursor, column, row, style);
+        }
+        let indentation: ColDiff = indentation.into();
+        cursor.move_line_start_column(-indentation);
+        let separator = if self.single_line.is_some() {
+            " "
+        } else {
+            info.closing_brackets.separator()
+        };
+        if self.single_line.is_none() && location.segments().is_empty() {
+            let row = cursor.get_row() + 1;
+            info.member_rows.borrow_mut().push((row, None));
+        }
+        match self.node {
+            DisplayValue::Scalar(_) => {}
+            DisplayValue::Object(_) => {
+                draw_closing_bracket(cursor, separator, "}", info.style(self.layers));
             }
-            (&DisplayValue::Object(ref obj), None) => obj.draw(cursor, None, info, indentation),
-            (&DisplayValue::Array(ref array), Some(&Path::Array(ref ap))) => {
-                array.draw(cursor, Some(ap), info, indentation)
+            DisplayValue::Array(array) => {
+                draw_closing_bracket(cursor, separator, "]", info.style(self.layers));
+                cursor.write(" ");
+                let path = match self.path {
+                    Some(Path::Array(ap)) => Some(ap),
+                    _ => None,
+                };
+                if matches!(path, Some(ArrayPath::Grow) | Some(ArrayPath::Shrink)) {
+                    info.mark_active_row(cursor);
+                }
+                array.draw_length_indicator(cursor, path, info);
             }
-            (&DisplayValue::Array(ref array), None) => array.draw(cursor, None, info, indentation),
-            _ => panic!("Mismatched DisplayValue and path type!"),
+        }
+        // Nodes drawn on multiple lines show these after their opening bracket.
+        if self.single_line.is_some() {
+            draw_annotation(cursor, info.annotations.get(location));
+            draw_error(cursor, info.errors.get(location), self.selected, info);
         }
     }
 }
 
 #[cfg(test)]
 impl DisplayValue {
+    pub fn update(&self, value: impl Value) -> Self {
+        self.update_with(
+            value,
+            UpdateOptions::default(),
+            &mut JsonPath::root(),
+            &KeyPool::new(),
+        )
+    }
+
     pub fn unwrap_scalar_ref(&self) -> &DisplayScalar {
-        if let &DisplayValue::Scalar(ref val) = self {
+        if let DisplayValue::Scalar(val) = self {
             val
         } else {
             panic!("Tried to unwrap non-scalar DisplayValue");
@@ -415,7 +3383,7 @@ impl DisplayValue {
     }
 
     pub fn unwrap_object_ref(&self) -> &DisplayObject {
-        if let &DisplayValue::Object(ref val) = self {
+        if let DisplayValue::Object(val) = self {
             val
         } else {
             panic!("Tried to unwrap non-object DisplayValue");
@@ -430,7 +3398,7 @@ impl DisplayValue {
     }
 
     pub fn unwrap_array_ref(&self) -> &DisplayArray {
-        if let &DisplayValue::Array(ref val) = self {
+        if let DisplayValue::Array(val) = self {
             val
         } else {
             panic!("Tried to unwrap non-array DisplayValue");
@@ -444,3 +3412,494 @@ impl DisplayValue {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::Nested;
+    use json::JsonValue;
+
+    fn single_line_width(val: JsonValue, max: usize) -> Option<usize> {
+        DisplayValue::new(&val).single_line_width(max, None)
+    }
+
+    #[test]
+    fn test_single_line_width() {
+        assert_eq!(
+            single_line_width(JsonValue::String("foo".to_owned()), 3),
+            Some(3)
+        );
+        assert_eq!(
+            single_line_width(JsonValue::String("foo".to_owned()), 2),
+            None
+        );
+        assert_eq!(
+            single_line_width(JsonValue::String("a\nb".to_owned()), 10),
+            None
+        );
+
+        // { [-] a: 1, b: 2 }
+        assert_eq!(
+            single_line_width(object! { "a" => 1, "b" => 2 }, 100),
+            Some(18)
+        );
+        assert_eq!(single_line_width(object! { "a" => 1, "b" => 2 }, 17), None);
+        // { [-] }
+        assert_eq!(single_line_width(object! {}, 100), Some(7));
+
+        // [ [-] 1, 2, 3 ] <-3/4+>
+        assert_eq!(single_line_width(array! { 1, 2, 3, 4 }, 100), Some(23));
+        // [ [-] ] < 0/0 >
+        assert_eq!(single_line_width(array! {}, 100), Some(15));
+
+        // { [-] a: [ [-] 1 ] <-1/1 > }
+        assert_eq!(
+            single_line_width(object! { "a" => array! { 1 } }, 100),
+            Some(28)
+        );
+    }
+
+    /// The size of `value` according to `extent` and according to actually drawing it.
+    fn extent_and_drawn_size(value: &DisplayValue, params: LayoutParams) -> [usize; 4] {
+        use unsegen::base::ExtentEstimationWindow;
+
+        let errors = BTreeMap::new();
+        let annotations = BTreeMap::new();
+        let hidden = BTreeSet::new();
+        let cut = BTreeSet::new();
+        let duplicates = BTreeSet::new();
+        let info = RenderingInfo {
+            hints: RenderingHints::default(),
+            active_focused_style: FocusStyles::uniform(StyleModifier::new()),
+            inactive_focused_style: FocusStyles::uniform(StyleModifier::new()),
+            precedence: StylePrecedence::default(),
+            item_changed_style: StyleModifier::new(),
+            link_style: StyleModifier::new(),
+            error_style: StyleModifier::new(),
+            duplicate_key_style: StyleModifier::new(),
+            duplicate_style: StyleModifier::new(),
+            compact_width: params.compact_width,
+            inline_width: params.inline_width,
+            separators: params.separators,
+            closing_brackets: params.closing_brackets,
+            errors: &errors,
+            annotations: &annotations,
+            hidden: &hidden,
+            duplicates: &duplicates,
+            cut: &cut,
+            timestamp_style: StyleModifier::new(),
+            history_style: StyleModifier::new(),
+            renderer: None,
+            overridden: None,
+            highlight: None,
+            highlight_style: StyleModifier::new(),
+            selection: None,
+            selected_style: StyleModifier::new(),
+            node_style: None,
+            badge: None,
+            indentation_guide: None,
+            rainbow: &[],
+            summary: None,
+            summary_style: StyleModifier::new(),
+            layout: None,
+            active_row: Cell::new(None),
+            member_rows: RefCell::new(Vec::new()),
+            wrap_values: false,
+            index_label: None,
+        };
+        let mut window = ExtentEstimationWindow::unbounded();
+        value.draw(
+            &mut Cursor::new(&mut window),
+            None,
+            &info,
+            Width::new(2).unwrap(),
+            &mut JsonPath::root(),
+        );
+        let extent = value.extent(params);
+        [
+            extent.width_at(0),
+            extent.height,
+            window.extent_x().raw_value() as usize,
+            window.extent_y().raw_value() as usize,
+        ]
+    }
+
+    #[test]
+    fn test_extent() {
+        let mut value = DisplayValue::new(&object! {
+            "foo" => array!{ 0, 1, object!{ "bar" => "multi\nline string" }, 3 },
+            "a long key" => object!{ "x" => 1 },
+            "baz" => array!{},
+            "s" => "something",
+            "n" => array!{ 1, 2, 3, 4, "a string" }
+        });
+        for compact_width in [None, Some(10), Some(40)].iter().cloned() {
+            for inline_width in [None, Some(25), Some(40)].iter().cloned() {
+                for separators in [Separators::Trailing, Separators::Json, Separators::Omitted] {
+                    for closing_brackets in [ClosingBrackets::OwnLine, ClosingBrackets::Hanging] {
+                        let params = LayoutParams {
+                            indentation: 2,
+                            compact_width,
+                            inline_width,
+                            separators,
+                            closing_brackets,
+                        };
+                        let [w, h, drawn_w, drawn_h] = extent_and_drawn_size(&value, params);
+                        assert_eq!((w, h), (drawn_w, drawn_h));
+                    }
+                }
+            }
+        }
+
+        let plain = LayoutParams {
+            indentation: 2,
+            compact_width: None,
+            inline_width: None,
+            separators: Separators::Trailing,
+            closing_brackets: ClosingBrackets::OwnLine,
+        };
+        let path = JsonPath::root().key("foo");
+        let before = extent_and_drawn_size(&value, plain);
+        value.get_mut(&path).unwrap().unwrap_array_ref_mut().grow();
+        let after = extent_and_drawn_size(&value, plain);
+        assert_eq!(after[1], before[1] + 1);
+        assert_eq!((after[0], after[1]), (after[2], after[3]));
+
+        value
+            .get_mut(&path)
+            .unwrap()
+            .unwrap_array_ref_mut()
+            .toggle_visibility();
+        let [w, h, drawn_w, drawn_h] = extent_and_drawn_size(&value, plain);
+        assert_eq!((w, h), (drawn_w, drawn_h));
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+        let value = DisplayValue::new(Nested(DEPTH, "a"));
+        let value = value.update(Nested(DEPTH, "b"));
+        let params = LayoutParams {
+            indentation: 2,
+            compact_width: None,
+            inline_width: None,
+            separators: Separators::Trailing,
+            closing_brackets: ClosingBrackets::OwnLine,
+        };
+        let [w, h, drawn_w, drawn_h] = extent_and_drawn_size(&value, params);
+        assert_eq!((w, h), (drawn_w, drawn_h));
+        assert_eq!(h, 2 * DEPTH + 1);
+
+        let mut path = JsonPath::root();
+        for _ in 0..DEPTH {
+            path = path.index(0);
+        }
+        assert!(value.get(&path).unwrap().unwrap_scalar_ref().changed);
+
+        let mut copy = value.clone();
+        assert!(copy.same_content(&value));
+        assert_eq!(copy.stats().depth, DEPTH + 1);
+        copy.set_extended_recursive(false);
+        assert_eq!(copy.extent(params).height, 1);
+        assert!(!copy.detect_binary(true));
+        copy.resolve_duplicate_keys(DuplicateKeys::First);
+        let copy = copy.update(Nested(DEPTH, "c"));
+        assert!(!copy.same_content(&value));
+        assert!(value.same_value(Nested(DEPTH, "b")));
+    }
+
+    #[test]
+    fn test_deep_folds() {
+        // Fold states are stored per path, so their size is quadratic in the depth. A smaller
+        // stack has to do.
+        const DEPTH: usize = 2_000;
+        let run = || {
+            let mut value = DisplayValue::new(Nested(DEPTH, "a"));
+            let mut folds = BTreeMap::new();
+            value.folds(&mut JsonPath::root(), &mut folds);
+            assert_eq!(folds.len(), DEPTH);
+            for fold in folds.values_mut() {
+                fold.extended = false;
+            }
+            value.restore_folds(&mut JsonPath::root(), &folds);
+            assert_eq!(value.extended(), Some(false));
+        };
+        let thread = ::std::thread::Builder::new().stack_size(64 * 1024);
+        thread.spawn(run).unwrap().join().unwrap();
+    }
+
+    #[derive(Clone)]
+    struct Described(&'static str, &'static str);
+
+    impl Value for Described {
+        fn visit<'s>(self) -> ValueVariant<'s, Self> {
+            ValueVariant::Scalar(Some(self.0.into()), self.1.into())
+        }
+    }
+
+    #[test]
+    fn test_scalar_description() {
+        let value = DisplayValue::new(Described("len=5", "hello"));
+        // len=5 hello
+        assert_eq!(value.single_line_width(100, None), Some(11));
+        assert_eq!(value.single_line_width(10, None), None);
+
+        let value = value.update(Described("len=5", "world"));
+        assert!(value.unwrap_scalar_ref().changed);
+        assert!(!value.unwrap_scalar_ref().description_changed);
+
+        let value = value.update(Described("len=2", "world"));
+        assert!(!value.unwrap_scalar_ref().changed);
+        assert!(value.unwrap_scalar_ref().description_changed);
+    }
+
+    #[test]
+    fn test_scalar_kind() {
+        let value = DisplayValue::new(JsonValue::from(1));
+        assert_eq!(value.unwrap_scalar_ref().kind, ScalarKind::Number);
+
+        let value = value.update(JsonValue::from("1"));
+        assert_eq!(value.unwrap_scalar_ref().kind, ScalarKind::String);
+        assert!(value.unwrap_scalar_ref().changed);
+        assert!(!value.same_content(&DisplayValue::new(JsonValue::from(1))));
+
+        let value = value.update(JsonValue::from("1"));
+        assert!(!value.unwrap_scalar_ref().changed);
+        assert_eq!(
+            DisplayValue::new("1").unwrap_scalar_ref().kind,
+            ScalarKind::Other
+        );
+    }
+
+    #[test]
+    fn test_append() {
+        let mut value = DisplayValue::new(&array! { 1, 2 });
+        let array = value.unwrap_array_ref_mut();
+        array.append(&[JsonValue::from(3)], true, &KeyPool::new());
+        assert_eq!(array.values.len(), 3);
+        assert_eq!(array.num_extended, 3);
+        assert!(array.length_changed);
+
+        array.append(&[JsonValue::from(4)], false, &KeyPool::new());
+        assert_eq!(array.values.len(), 4);
+        assert_eq!(array.num_extended, 3);
+
+        // Hidden elements stay hidden
+        array.append(&[JsonValue::from(5)], true, &KeyPool::new());
+        assert_eq!(array.values.len(), 5);
+        assert_eq!(array.num_extended, 3);
+        assert_eq!(array.values[4].unwrap_scalar_ref().value, "5");
+    }
+
+    #[test]
+    fn test_update_with_identity() {
+        let identity = |_: &JsonPath, node: Node| node.scalar().map(str::to_owned);
+        let options = UpdateOptions {
+            array_identity: Some(&identity),
+            ..Default::default()
+        };
+        let mut value = DisplayValue::new(&array! { 1, 2, object! { "a" => 1 } });
+        value.unwrap_array_ref_mut().values[2]
+            .unwrap_object_ref_mut()
+            .extended = false;
+
+        let reordered = array! { 2, 1, object! { "a" => 1 }, 3 };
+        let by_index = value.update(&reordered);
+        assert!(
+            by_index.unwrap_array_ref().values[0]
+                .unwrap_scalar_ref()
+                .changed
+        );
+
+        let by_identity =
+            value.update_with(&reordered, options, &mut JsonPath::root(), &KeyPool::new());
+        let values = &by_identity.unwrap_array_ref().values;
+        assert!(!values[0].unwrap_scalar_ref().changed);
+        assert!(!values[1].unwrap_scalar_ref().changed);
+        assert!(!values[2].unwrap_object_ref().extended);
+        assert_eq!(values[3].unwrap_scalar_ref().value, "3");
+        assert!(values[3].unwrap_scalar_ref().changed);
+
+        // Elements with an identity not present before are inserted, including nested values
+        let with_new_object = array! { 1, object! { "b" => 1 }, 2 };
+        let updated = by_identity.update_with(
+            &with_new_object,
+            options,
+            &mut JsonPath::root(),
+            &KeyPool::new(),
+        );
+        let values = &updated.unwrap_array_ref().values;
+        assert!(!values[0].unwrap_scalar_ref().changed);
+        assert!(values[1].is_changed());
+        assert!(values[1]
+            .unwrap_object_ref()
+            .members
+            .iter()
+            .all(|member| member.value.is_changed()));
+
+        // Identities are computed from the unmasked values of redacted scalars.
+        let identity = |_: &JsonPath, node: Node| {
+            let id = node.child(&JsonPathSegment::Key(MemberId::new("id")))?;
+            id.scalar().map(str::to_owned)
+        };
+        let options = UpdateOptions {
+            array_identity: Some(&identity),
+            ..Default::default()
+        };
+        let mut value = DisplayValue::new(&array! {
+            object! { "id" => "a", "v" => 1 },
+            object! { "id" => "b", "v" => 2 }
+        });
+        let is_id = |_: &JsonPath, key: Option<&Key>| key == Some(&Key::from("id"));
+        value.redact(&JsonPath::root(), Some(&is_id));
+        let reordered = array! {
+            object! { "id" => "b", "v" => 2 },
+            object! { "id" => "a", "v" => 1 }
+        };
+        let updated =
+            value.update_with(&reordered, options, &mut JsonPath::root(), &KeyPool::new());
+        let mut changed = Vec::new();
+        updated.changed_paths(&mut JsonPath::root(), &mut changed);
+        assert!(changed.is_empty());
+    }
+
+    #[test]
+    fn test_update_with_sequence_diff() {
+        let options = UpdateOptions {
+            sequence_diff: true,
+            ..Default::default()
+        };
+        let mut value = DisplayValue::new(&array! { 1, array! { 2 }, 3, 4 });
+        value.unwrap_array_ref_mut().values[1]
+            .unwrap_array_ref_mut()
+            .extended = false;
+
+        let new = array! { 0, 1, array! { 2 }, 5, 4, 6 };
+        let updated = value.update_with(&new, options, &mut JsonPath::root(), &KeyPool::new());
+        let array = updated.unwrap_array_ref();
+        assert!(array.length_changed);
+        let values = &array.values;
+        assert!(values[0].unwrap_scalar_ref().changed);
+        assert!(!values[1].unwrap_scalar_ref().changed);
+        assert!(!values[2].unwrap_array_ref().extended);
+        assert!(values[3].unwrap_scalar_ref().changed);
+        assert!(!values[4].unwrap_scalar_ref().changed);
+        assert!(values[5].unwrap_scalar_ref().changed);
+        assert!(updated.same_content(&DisplayValue::new(&new)));
+
+        let removed = array! { 1, 4 };
+        let updated = value.update_with(&removed, options, &mut JsonPath::root(), &KeyPool::new());
+        let values = &updated.unwrap_array_ref().values;
+        assert!(!values[0].unwrap_scalar_ref().changed);
+        assert!(!values[1].unwrap_scalar_ref().changed);
+    }
+
+    #[test]
+    fn test_common_subsequence() {
+        let same = |a: &char, b: &char| a == b;
+        let chars = |s: &str| s.chars().collect::<Vec<_>>();
+        let common = common_subsequence_by(&chars("abcabba"), &chars("cbabac"), same);
+        assert_eq!(common.len(), 4);
+        assert!(common
+            .windows(2)
+            .all(|w| w[0].0 < w[1].0 && w[0].1 < w[1].1));
+        assert!(common
+            .iter()
+            .all(|&(i, j)| "abcabba".as_bytes()[i] == "cbabac".as_bytes()[j]));
+        assert_eq!(
+            common_subsequence_by(&chars("abc"), &chars(""), same),
+            vec![]
+        );
+        assert_eq!(
+            common_subsequence_by(&chars("ab"), &chars("xaby"), same),
+            vec![(0, 1), (1, 2)]
+        );
+
+        // Long sequences with few differences
+        let old = (0..100_000).collect::<Vec<_>>();
+        let mut new = old.clone();
+        new.remove(50_000);
+        new.insert(10, 7);
+        let common = common_subsequence_by(&old, &new, |a, b| a == b);
+        assert_eq!(common.len(), 99_999);
+
+        // Too many differences: Only the common prefix and suffix are matched
+        let new = (0..100_000)
+            .map(|i| if i % 2 == 0 { i } else { 0 })
+            .collect::<Vec<_>>();
+        let common = common_subsequence_by(&old, &new, |a, b| a == b);
+        assert_eq!(common, vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_same_value() {
+        let value = object! { "a" => array! { 1, "x", JsonValue::Null }, "b" => object! {} };
+        let node = DisplayValue::new(&value);
+        assert!(node.same_value(&value));
+        assert!(!node.same_value(&object! { "a" => array! { 1, "x", JsonValue::Null } }));
+        assert!(!node.same_value(&object! { "a" => array! { 1, "x", "null" }, "b" => object! {} }));
+        assert!(!node.same_value(&object! { "a" => array! { 1, "x" }, "b" => object! {} }));
+        assert!(!node
+            .same_value(&object! { "b" => object! {}, "a" => array! { 1, "x", JsonValue::Null } }));
+    }
+
+    #[test]
+    fn test_update_with_type_change() {
+        let mut value = DisplayValue::new(&object! {
+            "a" => object! { "x" => 1 },
+            "b" => array! { 1 },
+            "c" => 1
+        });
+        {
+            let members = &mut value.unwrap_object_ref_mut().members;
+            members
+                .get_by_id_mut(&MemberId::new("a"))
+                .unwrap()
+                .unwrap_object_ref_mut()
+                .extended = false;
+        }
+        let updated = value.update(&object! {
+            "a" => array! { 1, 2 },
+            "b" => object! { "y" => 2 },
+            "c" => array! { 3 }
+        });
+        let members = &updated.unwrap_object_ref().members;
+        let a = members["a"].unwrap_array_ref();
+        assert!(!a.extended);
+        assert!(a.length_changed);
+        assert!(a.values.iter().all(|v| v.unwrap_scalar_ref().changed));
+        let b = members["b"].unwrap_object_ref();
+        assert!(b.extended);
+        assert!(b.members["y"].unwrap_scalar_ref().changed);
+        assert!(members["c"].unwrap_array_ref().extended);
+
+        let updated = updated.update(&object! { "a" => 1, "b" => 2, "c" => 3 });
+        let members = &updated.unwrap_object_ref().members;
+        assert!(members["a"].unwrap_scalar_ref().changed);
+    }
+
+    #[test]
+    fn test_shared_keys() {
+        let key_ptr = |value: &DisplayValue| match &value
+            .unwrap_object_ref()
+            .members
+            .iter()
+            .next()
+            .unwrap()
+            .key
+        {
+            Key::String(s) => s.clone(),
+            _ => panic!("Not a string key"),
+        };
+        let keys = KeyPool::new();
+        let value = DisplayValue::new_with_keys(
+            &array! { object! { "name" => 1 }, object! { "name" => 2 } },
+            &keys,
+        );
+        let array = value.unwrap_array_ref();
+        assert!(::std::sync::Arc::ptr_eq(
+            &key_ptr(&array.values[0]),
+            &key_ptr(&array.values[1])
+        ));
+    }
+}
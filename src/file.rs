@@ -0,0 +1,380 @@
+//! Documents read from files that are reloaded when they change.
+#[cfg(feature = "notify")]
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+#[cfg(feature = "notify")]
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+#[cfg(feature = "notify")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "notify")]
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use super::json_ext::JsonValue;
+use super::{DocumentSource, JsonViewer, JsonViewerWidget};
+
+/// Parses the content of a file, e.g., YAML or TOML converted to a `JsonValue`.
+pub type FileParser = dyn Fn(&str) -> Result<JsonValue, String> + Send + Sync;
+
+/// A `DocumentSource` that rereads a file whenever its modification time or size has changed
+/// since it was last polled. Files ending in `.yaml`/`.yml` (with the `yaml` feature) or `.toml`
+/// (with the `toml` feature) are parsed as such, all others as JSON unless another `parser` is
+/// set.
+///
+/// With the `notify` feature, the file is also reread whenever the file system reports a change.
+/// Otherwise (or if the file cannot be watched), changes that keep both the size and the
+/// modification time (which may be coarse, depending on the file system) are not noticed.
+pub struct FileSource {
+    path: PathBuf,
+    parser: Box<FileParser>,
+    // The modification time and size of the file when it was last polled, or why they could not be
+    // determined.
+    last: Option<Result<(Option<SystemTime>, u64), String>>,
+    #[cfg(feature = "notify")]
+    watch: Option<Watch>,
+    // The hash of the content of the file when it was last read (if it could be read).
+    #[cfg(feature = "notify")]
+    hash: Option<u64>,
+}
+
+impl FileSource {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        FileSource {
+            parser: default_parser(&path),
+            #[cfg(feature = "notify")]
+            watch: Watch::new(&path).ok(),
+            #[cfg(feature = "notify")]
+            hash: None,
+            path,
+            last: None,
+        }
+    }
+
+    /// Parse the file using `parser` instead of as JSON.
+    pub fn parser(
+        mut self,
+        parser: impl Fn(&str) -> Result<JsonValue, String> + Send + Sync + 'static,
+    ) -> Self {
+        self.parser = Box::new(parser);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn error(&self, error: impl ToString) -> String {
+        format!("{}: {}", self.path.display(), error.to_string())
+    }
+}
+
+impl DocumentSource for FileSource {
+    type Value = JsonValue;
+
+    fn poll(&mut self) -> Option<Result<JsonValue, String>> {
+        // Reset the notification before reading so that changes while reading are not missed.
+        #[cfg(feature = "notify")]
+        let notified = self.watch.as_ref().is_some_and(Watch::take_changed);
+        #[cfg(not(feature = "notify"))]
+        let notified = false;
+        let state = fs::metadata(&self.path)
+            .map(|metadata| (metadata.modified().ok(), metadata.len()))
+            .map_err(|e| self.error(e));
+        let unchanged = self.last.as_ref() == Some(&state);
+        if !notified && unchanged {
+            return None;
+        }
+        self.last = Some(state.clone());
+        let text = state.and_then(|_| fs::read_to_string(&self.path).map_err(|e| self.error(e)));
+        // Notifications may be delivered late or for changes that were already read.
+        #[cfg(feature = "notify")]
+        {
+            let hash = text.as_ref().ok().map(|text| {
+                let mut hasher = DefaultHasher::new();
+                text.hash(&mut hasher);
+                hasher.finish()
+            });
+            if unchanged && hash == self.hash {
+                return None;
+            }
+            self.hash = hash;
+        }
+        Some(text.and_then(|text| (self.parser)(&text).map_err(|e| self.error(e))))
+    }
+}
+
+fn default_parser(path: &Path) -> Box<FileParser> {
+    match path.extension().and_then(|extension| extension.to_str()) {
+        #[cfg(feature = "yaml")]
+        Some("yaml") | Some("yml") => Box::new(parse_yaml),
+        #[cfg(feature = "toml")]
+        Some("toml") => Box::new(parse_toml),
+        _ => Box::new(|text| json::parse(text).map_err(|e| e.to_string())),
+    }
+}
+
+/// Watches the directory of a file (so that files replaced by renaming them are noticed as well)
+/// for changes of the file.
+#[cfg(feature = "notify")]
+struct Watch {
+    changed: Arc<AtomicBool>,
+    _watcher: notify::RecommendedWatcher,
+}
+
+#[cfg(feature = "notify")]
+impl Watch {
+    fn new(path: &Path) -> notify::Result<Self> {
+        use notify::Watcher;
+
+        let name = path.file_name().map(ToOwned::to_owned);
+        let directory = match path.parent() {
+            Some(parent) if parent != Path::new("") => parent,
+            _ => Path::new("."),
+        };
+        let changed = Arc::new(AtomicBool::new(false));
+        let flag = changed.clone();
+        let mut watcher =
+            notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+                // Errors may mean that events were lost, so the file may have changed as well.
+                let relevant = event.map_or(true, |event| {
+                    event.need_rescan()
+                        || event.paths.iter().any(|p| p.file_name() == name.as_deref())
+                });
+                if relevant {
+                    flag.store(true, Ordering::SeqCst);
+                }
+            })?;
+        watcher.watch(directory, notify::RecursiveMode::NonRecursive)?;
+        Ok(Watch {
+            changed,
+            _watcher: watcher,
+        })
+    }
+
+    fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::SeqCst)
+    }
+}
+
+/// Parse YAML text, e.g., to pass to `FileSource::parser`. Keys that are not strings are
+/// converted to their YAML representation and tags are dropped.
+#[cfg(feature = "yaml")]
+pub fn parse_yaml(text: &str) -> Result<JsonValue, String> {
+    fn convert(value: serde_yaml::Value) -> JsonValue {
+        use serde_yaml::Value;
+        match value {
+            Value::Null => JsonValue::Null,
+            Value::Bool(b) => JsonValue::Boolean(b),
+            Value::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    i.into()
+                } else if let Some(u) = n.as_u64() {
+                    u.into()
+                } else {
+                    n.as_f64().unwrap_or(f64::NAN).into()
+                }
+            }
+            Value::String(s) => s.into(),
+            Value::Sequence(values) => JsonValue::Array(values.into_iter().map(convert).collect()),
+            Value::Mapping(mapping) => {
+                let mut object = json::object::Object::new();
+                for (key, value) in mapping {
+                    let key = match key {
+                        Value::String(key) => key,
+                        key => serde_yaml::to_string(&key)
+                            .map(|key| key.trim_end().to_owned())
+                            .unwrap_or_default(),
+                    };
+                    object.insert(&key, convert(value));
+                }
+                JsonValue::Object(object)
+            }
+            Value::Tagged(tagged) => convert(tagged.value),
+        }
+    }
+    serde_yaml::from_str(text)
+        .map(convert)
+        .map_err(|e| e.to_string())
+}
+
+/// Parse TOML text, e.g., to pass to `FileSource::parser`. Date-times are converted to strings.
+#[cfg(feature = "toml")]
+pub fn parse_toml(text: &str) -> Result<JsonValue, String> {
+    fn convert(value: toml::Value) -> JsonValue {
+        use toml::Value;
+        match value {
+            Value::String(s) => s.into(),
+            Value::Integer(i) => i.into(),
+            Value::Float(f) => f.into(),
+            Value::Boolean(b) => JsonValue::Boolean(b),
+            Value::Datetime(datetime) => datetime.to_string().into(),
+            Value::Array(values) => JsonValue::Array(values.into_iter().map(convert).collect()),
+            Value::Table(table) => table_to_object(table),
+        }
+    }
+    fn table_to_object(table: toml::Table) -> JsonValue {
+        let mut object = json::object::Object::new();
+        for (key, value) in table {
+            object.insert(&key, convert(value));
+        }
+        JsonValue::Object(object)
+    }
+    text.parse::<toml::Table>()
+        .map(table_to_object)
+        .map_err(|e| e.to_string().trim_end().to_owned())
+}
+
+/// Shows a file and reloads it when it changes (see `FileSource`), highlighting the changes as
+/// if passed to `JsonViewer::update`, e.g., to watch a configuration file. Errors (e.g., while the
+/// file is being rewritten) are shown as if passed to `JsonViewer::set_error`.
+pub struct JsonFileViewer {
+    viewer: JsonViewer,
+    source: FileSource,
+}
+
+impl JsonFileViewer {
+    /// Show the JSON file at `path`.
+    pub fn open(path: impl Into<PathBuf>) -> Self {
+        Self::new(FileSource::new(path))
+    }
+
+    /// Show the file of `source` (read immediately, without highlighting).
+    pub fn new(mut source: FileSource) -> Self {
+        let viewer = match source.poll() {
+            Some(Ok(value)) => JsonViewer::new(value),
+            Some(Err(message)) => {
+                let mut viewer = JsonViewer::new("");
+                viewer.set_error(&message);
+                viewer
+            }
+            None => unreachable!("a new source always reports the file state"),
+        };
+        JsonFileViewer { viewer, source }
+    }
+
+    /// Reload the file if it has changed (e.g., once per frame). Returns whether it was reloaded.
+    pub fn poll(&mut self) -> bool {
+        self.viewer.poll(&mut self.source)
+    }
+
+    pub fn path(&self) -> &Path {
+        self.source.path()
+    }
+
+    pub fn viewer(&self) -> &JsonViewer {
+        &self.viewer
+    }
+
+    /// The viewer showing the file, e.g., to handle input using `JsonViewerBehavior`.
+    pub fn viewer_mut(&mut self) -> &mut JsonViewer {
+        &mut self.viewer
+    }
+
+    pub fn as_widget(&self) -> JsonViewerWidget<'_> {
+        self.viewer.as_widget()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::JsonPath;
+
+    #[test]
+    fn test_reload() {
+        let path = std::env::temp_dir().join(format!("jsonviewer-{}.json", std::process::id()));
+        let a = JsonPath::root().key("a");
+        fs::write(&path, r#"{"a": 1}"#).unwrap();
+        let mut viewer = JsonFileViewer::open(&path);
+        assert_eq!(viewer.viewer().node(&a).unwrap().scalar(), Some("1"));
+        assert!(!viewer.viewer().node(&a).unwrap().changed());
+        assert!(!viewer.poll());
+
+        fs::write(&path, r#"{"a": 22}"#).unwrap();
+        assert!(viewer.poll());
+        assert_eq!(viewer.viewer().node(&a).unwrap().scalar(), Some("22"));
+        assert!(viewer.viewer().node(&a).unwrap().changed());
+        assert!(!viewer.poll());
+
+        fs::write(&path, "{").unwrap();
+        assert!(viewer.poll());
+        assert!(viewer
+            .viewer()
+            .error()
+            .unwrap()
+            .starts_with(&*path.to_string_lossy()));
+        assert_eq!(viewer.viewer().node(&a).unwrap().scalar(), Some("22"));
+
+        fs::remove_file(&path).unwrap();
+        assert!(viewer.poll());
+        assert!(viewer.viewer().error().is_some());
+        assert!(!viewer.poll());
+    }
+
+    #[cfg(feature = "notify")]
+    #[test]
+    fn test_same_size_rewrite() {
+        use std::time::{Duration, Instant};
+
+        let path =
+            std::env::temp_dir().join(format!("jsonviewer-{}-same.json", std::process::id()));
+        let a = JsonPath::root().key("a");
+        fs::write(&path, r#"{"a": 1}"#).unwrap();
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        let mut viewer = JsonFileViewer::open(&path);
+        assert_eq!(viewer.viewer().node(&a).unwrap().scalar(), Some("1"));
+
+        // Neither the size nor the modification time changes.
+        fs::write(&path, r#"{"a": 2}"#).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !viewer.poll() {
+            assert!(Instant::now() < deadline, "change was not noticed");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(viewer.viewer().node(&a).unwrap().scalar(), Some("2"));
+        assert!(viewer.viewer().node(&a).unwrap().changed());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn test_yaml() {
+        assert_eq!(
+            parse_yaml("a: 1\nb: [x, true, 1.5, -2]\n3: !tag ~\n").unwrap(),
+            object! { "a" => 1, "b" => array! { "x", true, 1.5, -2 }, "3" => json::Null }
+        );
+        assert!(parse_yaml("a: [").unwrap_err().contains("line"));
+
+        let path = std::env::temp_dir().join(format!("jsonviewer-{}.yaml", std::process::id()));
+        fs::write(&path, "a: {b: c}").unwrap();
+        let viewer = JsonFileViewer::open(&path);
+        let b = JsonPath::root().key("a").key("b");
+        assert_eq!(viewer.viewer().node(&b).unwrap().scalar(), Some("c"));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn test_toml() {
+        assert_eq!(
+            parse_toml("a = 1\nd = 1979-05-27\n[b]\nc = [\"x\", 0.5, false]\n").unwrap(),
+            object! { "a" => 1, "d" => "1979-05-27", "b" => object! { "c" => array! { "x", 0.5, false } } }
+        );
+        assert!(parse_toml("a = ").is_err());
+
+        let path = std::env::temp_dir().join(format!("jsonviewer-{}.toml", std::process::id()));
+        fs::write(&path, "[a]\nb = \"c\"").unwrap();
+        let viewer = JsonFileViewer::open(&path);
+        let b = JsonPath::root().key("a").key("b");
+        assert_eq!(viewer.viewer().node(&b).unwrap().scalar(), Some("c"));
+        fs::remove_file(&path).unwrap();
+    }
+}
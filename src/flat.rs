@@ -0,0 +1,117 @@
+//! A flat view of the value with one `path = value` line per scalar (like `gron`).
+use std::collections::BTreeSet;
+
+use super::displayvalue::DisplayValue;
+use super::path::{ArrayPath, JsonPath, JsonPathSegment, ObjectPath, Path};
+
+/// How a `JsonViewer` shows its value (see `JsonViewer::set_view_mode`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ViewMode {
+    /// Nested objects and arrays.
+    #[default]
+    Tree,
+    /// One `path = value` line per scalar, e.g., `.items[0].name = foo`. Folded and empty objects
+    /// and arrays and the elements of arrays that are not shown take up a single line each.
+    Flat,
+}
+
+/// A line of the flat view.
+pub struct FlatLine {
+    /// The node the line belongs to.
+    pub location: JsonPath,
+    pub text: String,
+    pub changed: bool,
+}
+
+/// Whether the interaction point at `path` is shown as a line in the flat view.
+pub fn is_line(path: &Path, value: &DisplayValue) -> bool {
+    let (mut path, mut value) = (path, value);
+    loop {
+        match (value, path) {
+            (_, Path::Scalar) => return true,
+            (DisplayValue::Array(array), Path::Array(ArrayPath::Item(i, subpath))) => {
+                value = &array.values[*i];
+                path = subpath;
+            }
+            (DisplayValue::Array(array), Path::Array(ArrayPath::Toggle)) => {
+                return !array.extended || array.values.is_empty();
+            }
+            (DisplayValue::Array(_), Path::Array(ArrayPath::Grow)) => return true,
+            (DisplayValue::Object(obj), Path::Object(ObjectPath::Item(id, subpath))) => {
+                match obj.members.get_by_id(id) {
+                    Some(member) => value = member,
+                    None => return false,
+                }
+                path = subpath;
+            }
+            (DisplayValue::Object(obj), Path::Object(ObjectPath::Toggle)) => {
+                return !obj.extended || obj.members.len() == 0;
+            }
+            _ => return false,
+        }
+    }
+}
+
+/// All lines of the flat view of `value`. Nodes in `hidden` are shown as `(hidden)` and objects
+/// and arrays in `cut` as `{ … }` or `[ … ]`.
+pub fn lines(
+    value: &DisplayValue,
+    hidden: &BTreeSet<JsonPath>,
+    cut: &BTreeSet<JsonPath>,
+) -> Vec<FlatLine> {
+    fn collect(
+        value: &DisplayValue,
+        location: &mut JsonPath,
+        hidden: &BTreeSet<JsonPath>,
+        cut: &BTreeSet<JsonPath>,
+        lines: &mut Vec<FlatLine>,
+    ) {
+        let mut line = |text: &str, changed: bool| {
+            lines.push(FlatLine {
+                location: location.clone(),
+                text: format!("{} = {}", location, text),
+                changed,
+            })
+        };
+        if hidden.contains(location) {
+            return line("(hidden)", false);
+        }
+        if cut.contains(location) {
+            return line(&value.cut_placeholder(), value.is_changed());
+        }
+        match value {
+            DisplayValue::Scalar(scalar) => {
+                line(&scalar.text().replace('\n', " "), value.is_changed())
+            }
+            DisplayValue::Object(obj) if obj.members.len() == 0 => line("{}", value.is_changed()),
+            DisplayValue::Object(obj) if !obj.extended => line("{ [+] }", value.is_changed()),
+            DisplayValue::Object(obj) => {
+                for member in obj.members.iter() {
+                    location.push(JsonPathSegment::Key(member.id()));
+                    collect(&member.value, location, hidden, cut, lines);
+                    location.pop();
+                }
+            }
+            DisplayValue::Array(array) if array.values.is_empty() => line("[]", value.is_changed()),
+            DisplayValue::Array(array) if !array.extended => line("[ [+] ]", value.is_changed()),
+            DisplayValue::Array(array) => {
+                for i in array.shown_indices() {
+                    location.push(JsonPathSegment::Index(i));
+                    collect(&array.values[i], location, hidden, cut, lines);
+                    location.pop();
+                }
+                if array.can_grow() {
+                    let text = format!("{} = [ … ] <{}+>", location, array.length_text());
+                    lines.push(FlatLine {
+                        location: location.clone(),
+                        text,
+                        changed: array.length_changed,
+                    });
+                }
+            }
+        }
+    }
+    let mut lines = Vec::new();
+    collect(value, &mut JsonPath::root(), hidden, cut, &mut lines);
+    lines
+}
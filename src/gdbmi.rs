@@ -0,0 +1,511 @@
+//! Values of the GDB/MI protocol, e.g., the results of `-stack-list-frames` or variable objects
+//! created using `-var-create`, shown without converting them to JSON first.
+//!
+//! ```
+//! # extern crate unsegen_jsonviewer;
+//! # use unsegen_jsonviewer::gdbmi::MiValue;
+//! # use unsegen_jsonviewer::JsonViewer;
+//! # fn main() {
+//! let record = r#"^done,stack=[frame={level="0",func="main"},frame={level="1",func="start"}]"#;
+//! let results = MiValue::parse_record(record).unwrap();
+//! let viewer = JsonViewer::new(&results);
+//! # }
+//! ```
+use std::fmt;
+
+use super::{Key, Value, ValueVariant};
+
+/// Why text could not be parsed as a GDB/MI value or record.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    /// The byte offset in the parsed text at which the error was detected.
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "position {}: {}", self.position, self.message)
+    }
+}
+
+/// A value of a GDB/MI output record.
+#[derive(Clone, PartialEq, Debug)]
+pub enum MiValue {
+    /// A (c-string) constant, e.g., `"42"`.
+    Const(String),
+    /// `{name=value,...}`
+    Tuple(Vec<(String, MiValue)>),
+    /// `[value,...]`
+    List(Vec<MiValue>),
+    /// `[name=value,...]`, e.g., the frames of `-stack-list-frames`. Names may repeat.
+    ResultList(Vec<(String, MiValue)>),
+}
+
+impl MiValue {
+    /// Parse a single value, e.g., `{level="0",func="main"}`.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser { text, rest: text };
+        let value = parser.value()?;
+        parser.end()?;
+        Ok(value)
+    }
+
+    /// Parse the results of a result or async record (e.g., `^done,value="1"` or
+    /// `*stopped,reason="breakpoint-hit"`) as a tuple. The record class is dropped. The text of
+    /// a stream record (e.g., `~"Starting program\n"`) is returned as a `Const`.
+    pub fn parse_record(line: &str) -> Result<Self, ParseError> {
+        let line = line.trim_end();
+        let start = line
+            .find(|c| "^*+=~@&".contains(c))
+            .filter(|&start| line[..start].bytes().all(|b| b.is_ascii_digit()))
+            .ok_or_else(|| ParseError {
+                position: line
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(line.len()),
+                message: "expected a record class".to_owned(),
+            })?;
+        let mut parser = Parser {
+            text: line,
+            rest: &line[start + 1..],
+        };
+        if "~@&".contains(&line[start..start + 1]) {
+            if !parser.eat('"') {
+                return parser.error("expected a string");
+            }
+            let text = parser.c_string()?;
+            parser.end()?;
+            return Ok(MiValue::Const(text));
+        }
+        parser.take_while(|c| c != ',');
+        let mut results = Vec::new();
+        while parser.eat(',') {
+            results.push(parser.result()?);
+        }
+        parser.end()?;
+        Ok(MiValue::Tuple(results))
+    }
+
+    /// The value of the first result `name` of a tuple or result list.
+    pub fn get(&self, name: &str) -> Option<&MiValue> {
+        match self {
+            MiValue::Tuple(results) | MiValue::ResultList(results) => results
+                .iter()
+                .find(|(n, _)| n == name)
+                .map(|(_, value)| value),
+            _ => None,
+        }
+    }
+
+    // Move the values of a tuple or list to `into`.
+    fn take_children(&mut self, into: &mut Vec<MiValue>) {
+        match self {
+            MiValue::Const(_) => {}
+            MiValue::List(values) => into.append(values),
+            MiValue::Tuple(results) | MiValue::ResultList(results) => {
+                into.extend(results.drain(..).map(|(_, value)| value))
+            }
+        }
+    }
+
+    /// The string of a `Const`.
+    pub fn as_const(&self) -> Option<&str> {
+        match self {
+            MiValue::Const(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl Drop for MiValue {
+    fn drop(&mut self) {
+        // Drop the nested values one by one rather than recursively so that arbitrarily deep
+        // values do not overflow the stack.
+        let mut descendants = Vec::new();
+        self.take_children(&mut descendants);
+        while let Some(mut value) = descendants.pop() {
+            value.take_children(&mut descendants);
+        }
+    }
+}
+
+impl Value for &MiValue {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        match self {
+            MiValue::Const(value) => ValueVariant::Scalar(None, value.as_str().into()),
+            MiValue::List(values) => ValueVariant::Array(None, Box::new(values.iter())),
+            MiValue::Tuple(results) | MiValue::ResultList(results) => ValueVariant::Map(
+                None,
+                Box::new(
+                    results
+                        .iter()
+                        .map(|(name, value)| (name.as_str().into(), value)),
+                ),
+            ),
+        }
+    }
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    rest: &'t str,
+}
+
+impl<'t> Parser<'t> {
+    fn error<T>(&self, message: &str) -> Result<T, ParseError> {
+        Err(ParseError {
+            position: self.text.len() - self.rest.len(),
+            message: message.to_owned(),
+        })
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.rest.starts_with(c) {
+            self.rest = &self.rest[c.len_utf8()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> &'t str {
+        let end = self.rest.find(|c| !f(c)).unwrap_or(self.rest.len());
+        let (taken, rest) = self.rest.split_at(end);
+        self.rest = rest;
+        taken
+    }
+
+    fn end(&self) -> Result<(), ParseError> {
+        if self.rest.is_empty() {
+            Ok(())
+        } else {
+            self.error("expected the end of the input")
+        }
+    }
+
+    fn result(&mut self) -> Result<(String, MiValue), ParseError> {
+        Ok((self.name()?, self.value()?))
+    }
+
+    // The name of a result, including the `=`.
+    fn name(&mut self) -> Result<String, ParseError> {
+        let name = self.take_while(|c| c != '=' && c != ',' && c != '}' && c != ']');
+        if name.is_empty() {
+            return self.error("expected a name");
+        }
+        if !self.eat('=') {
+            return self.error("expected '='");
+        }
+        Ok(name.to_owned())
+    }
+
+    fn value(&mut self) -> Result<MiValue, ParseError> {
+        // The tuples and lists that have not been closed yet, innermost last, with the name of
+        // the result they are the value of (if any). Keeping them on an explicit stack (rather
+        // than recursing) keeps deeply nested values from overflowing the stack.
+        let mut open: Vec<(Option<String>, MiValue)> = Vec::new();
+        // The name of the result whose value is parsed next.
+        let mut name = None;
+        loop {
+            let mut value = if self.eat('"') {
+                MiValue::Const(self.c_string()?)
+            } else if self.eat('{') {
+                if self.eat('}') {
+                    MiValue::Tuple(Vec::new())
+                } else {
+                    open.push((name.take(), MiValue::Tuple(Vec::new())));
+                    name = Some(self.name()?);
+                    continue;
+                }
+            } else if self.eat('[') {
+                if self.eat(']') {
+                    MiValue::List(Vec::new())
+                } else if self.rest.starts_with(['"', '{', '[']) {
+                    open.push((name.take(), MiValue::List(Vec::new())));
+                    continue;
+                } else {
+                    open.push((name.take(), MiValue::ResultList(Vec::new())));
+                    name = Some(self.name()?);
+                    continue;
+                }
+            } else {
+                return self.error("expected a value");
+            };
+            // Add the value to the innermost open tuple or list, closing it (and adding it to
+            // its parent in turn) if it ends.
+            loop {
+                let close = match open.last_mut() {
+                    None => return Ok(value),
+                    Some((_, MiValue::List(values))) => {
+                        values.push(value);
+                        ']'
+                    }
+                    Some((_, MiValue::Tuple(results))) => {
+                        results.push((name.take().expect("tuple member has a name"), value));
+                        '}'
+                    }
+                    Some((_, MiValue::ResultList(results))) => {
+                        results.push((name.take().expect("result has a name"), value));
+                        ']'
+                    }
+                    Some((_, MiValue::Const(_))) => unreachable!("constants are never open"),
+                };
+                if self.eat(close) {
+                    let (parent_name, closed) = open.pop().expect("not empty");
+                    name = parent_name;
+                    value = closed;
+                    continue;
+                }
+                if !self.eat(',') {
+                    return self.error(&format!("expected ',' or '{}'", close));
+                }
+                if !matches!(open.last(), Some((_, MiValue::List(_)))) {
+                    name = Some(self.name()?);
+                }
+                break;
+            }
+        }
+    }
+
+    // The rest of a c-string after the opening quote. Escapes are decoded to bytes (e.g., gdb
+    // prints the bytes of non-ASCII characters as octal escapes), which are read as UTF-8.
+    fn c_string(&mut self) -> Result<String, ParseError> {
+        let input = self.rest.as_bytes();
+        let mut bytes = Vec::new();
+        let mut i = 0;
+        while let Some(&b) = input.get(i) {
+            i += 1;
+            match b {
+                b'"' => {
+                    self.rest = &self.rest[i..];
+                    return Ok(String::from_utf8_lossy(&bytes).into_owned());
+                }
+                b'\\' => {
+                    let escaped = match input.get(i) {
+                        Some(&escaped) => escaped,
+                        None => break,
+                    };
+                    i += 1;
+                    bytes.push(match escaped {
+                        b'n' => b'\n',
+                        b't' => b'\t',
+                        b'r' => b'\r',
+                        b'a' => 0x07,
+                        b'b' => 0x08,
+                        b'f' => 0x0c,
+                        b'v' => 0x0b,
+                        b'e' => 0x1b,
+                        b'0'..=b'7' => {
+                            let mut code = escaped - b'0';
+                            for _ in 0..2 {
+                                match input.get(i) {
+                                    Some(&digit @ b'0'..=b'7') => {
+                                        code = code.wrapping_mul(8).wrapping_add(digit - b'0');
+                                        i += 1;
+                                    }
+                                    _ => break,
+                                }
+                            }
+                            code
+                        }
+                        other => other,
+                    });
+                }
+                b => bytes.push(b),
+            }
+        }
+        self.rest = &self.rest[self.rest.len()..];
+        self.error("unterminated string")
+    }
+}
+
+/// A node of a variable object tree, e.g., built from the results of `-var-create` and
+/// `-var-list-children`. Shown with the type as description, e.g., `struct point { x: 1, ... }`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct VarObj {
+    /// The expression of the variable, e.g., `x` for a member of a struct.
+    pub expression: String,
+    pub type_name: Option<String>,
+    /// The value reported by gdb (e.g., `{...}` for structs), shown if there are no children.
+    pub value: String,
+    pub children: Vec<VarObj>,
+}
+
+impl VarObj {
+    /// Convert the results of `-var-create` or a `child` of `-var-list-children`. The children
+    /// are taken from a `children` result, if any.
+    pub fn from_mi(results: &MiValue) -> Option<Self> {
+        let field = |name| results.get(name).and_then(MiValue::as_const);
+        let children = match results.get("children") {
+            Some(MiValue::ResultList(children)) => children
+                .iter()
+                .map(|(_, child)| VarObj::from_mi(child))
+                .collect::<Option<Vec<_>>>()?,
+            Some(MiValue::List(children)) => children
+                .iter()
+                .map(VarObj::from_mi)
+                .collect::<Option<Vec<_>>>()?,
+            _ => Vec::new(),
+        };
+        Some(VarObj {
+            expression: field("exp").or_else(|| field("name"))?.to_owned(),
+            type_name: field("type").map(str::to_owned),
+            value: field("value").unwrap_or("").to_owned(),
+            children,
+        })
+    }
+}
+
+impl Value for &VarObj {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        let description = self.type_name.as_ref().map(|t| t.as_str().into());
+        if self.children.is_empty() {
+            return ValueVariant::Scalar(description, self.value.as_str().into());
+        }
+        ValueVariant::Map(
+            description,
+            Box::new(
+                self.children
+                    .iter()
+                    .map(|child| (Key::from(child.expression.as_str()), child)),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use crate::JsonViewer;
+    use unsegen::base::{Height, Width, WindowBuffer};
+    use unsegen::widget::{RenderingHints, Widget};
+
+    fn draw(viewer: &mut JsonViewer) -> Vec<String> {
+        viewer.expand_all();
+        let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(6).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        lines(&mut buffer)
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            MiValue::parse(r#"{a="1\"2",b=[],c=["x",{}],d=[e="3",e="4"]}"#),
+            Ok(MiValue::Tuple(vec![
+                ("a".to_owned(), MiValue::Const("1\"2".to_owned())),
+                ("b".to_owned(), MiValue::List(vec![])),
+                (
+                    "c".to_owned(),
+                    MiValue::List(vec![MiValue::Const("x".to_owned()), MiValue::Tuple(vec![])])
+                ),
+                (
+                    "d".to_owned(),
+                    MiValue::ResultList(vec![
+                        ("e".to_owned(), MiValue::Const("3".to_owned())),
+                        ("e".to_owned(), MiValue::Const("4".to_owned())),
+                    ])
+                ),
+            ]))
+        );
+        let error = |position, message: &str| {
+            Err(ParseError {
+                position,
+                message: message.to_owned(),
+            })
+        };
+        assert_eq!(MiValue::parse(r#"{a="1""#), error(6, "expected ',' or '}'"));
+        assert_eq!(
+            MiValue::parse(r#"{a="1"} "#),
+            error(7, "expected the end of the input")
+        );
+        assert_eq!(MiValue::parse(r#"[a="1",b]"#), error(8, "expected '='"));
+        assert_eq!(MiValue::parse(r#"{a="1\"#), error(6, "unterminated string"));
+        assert_eq!(
+            MiValue::parse("x").unwrap_err().to_string(),
+            "position 0: expected a value"
+        );
+
+        let record = MiValue::parse_record("12^done,value=\"42\"\n").unwrap();
+        assert_eq!(record.get("value").and_then(MiValue::as_const), Some("42"));
+        assert_eq!(
+            MiValue::parse_record("^running"),
+            Ok(MiValue::Tuple(vec![]))
+        );
+        assert_eq!(
+            MiValue::parse_record("(gdb)"),
+            error(0, "expected a record class")
+        );
+        assert_eq!(
+            MiValue::parse_record("^done,value=1"),
+            error(12, "expected a value")
+        );
+        assert_eq!(
+            MiValue::parse_record(r#"~"ab""#),
+            Ok(MiValue::Const("ab".to_owned()))
+        );
+        assert_eq!(
+            MiValue::parse_record(r#"~"a,b""#),
+            Ok(MiValue::Const("a,b".to_owned()))
+        );
+        assert_eq!(
+            MiValue::parse_record(r#"&"x\n""#),
+            Ok(MiValue::Const("x\n".to_owned()))
+        );
+        assert_eq!(MiValue::parse_record("~ab"), error(1, "expected a string"));
+    }
+
+    #[test]
+    fn test_c_string_escapes() {
+        assert_eq!(
+            MiValue::parse(r#""caf\303\251 \a\b\f\v\e\0\\\x""#),
+            Ok(MiValue::Const("café \x07\x08\x0c\x0b\x1b\0\\x".to_owned()))
+        );
+        // Invalid UTF-8 is replaced rather than rejected.
+        assert_eq!(
+            MiValue::parse(r#""\377a""#),
+            Ok(MiValue::Const("\u{fffd}a".to_owned()))
+        );
+    }
+
+    #[test]
+    fn test_var_obj() {
+        let created = MiValue::parse_record(
+            r#"^done,name="var1",numchild="2",value="{...}",type="struct point",children=[child={name="var1.x",exp="x",numchild="0",value="1",type="int"},child={name="var1.y",exp="y",numchild="0",value="2",type="int"}]"#,
+        )
+        .unwrap();
+        let var = VarObj::from_mi(&created).unwrap();
+        assert_eq!(var.expression, "var1");
+        let mut viewer = JsonViewer::new(&var);
+        assert_eq!(
+            draw(&mut viewer),
+            vec![
+                "struct point { [-]",
+                "  x: int 1,",
+                "  y: int 2,",
+                "}",
+                "",
+                "",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+        let text = "{a=[".repeat(DEPTH) + "\"x\"" + &"]}".repeat(DEPTH);
+        let value = MiValue::parse(&text).unwrap();
+        let mut depth = 0;
+        let mut node = &value;
+        while let Some(MiValue::List(values)) = node.get("a") {
+            node = &values[0];
+            depth += 1;
+        }
+        assert_eq!(depth, DEPTH);
+        assert_eq!(node.as_const(), Some("x"));
+        let viewer = JsonViewer::new(&value);
+        assert_eq!(viewer.stats().max_depth, 2 * DEPTH + 1);
+    }
+}
@@ -0,0 +1,243 @@
+//! Grouping of the elements of an array by one of their members.
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use super::path::{JsonPath, JsonPathSegment};
+use super::{Key, ScalarKind, Value, ValueVariant};
+
+/// The group of elements that do not have the member (or where it is not a scalar).
+const MISSING: &str = "<missing>";
+
+/// Wraps a `Value` so that the array at `path` is shown as a map from the values of the member
+/// `field` of its elements to the arrays of elements with that value, e.g., to group the items of
+/// an API result by their status:
+///
+/// ```
+/// # extern crate unsegen_jsonviewer;
+/// # use unsegen_jsonviewer::{GroupBy, JsonPath, JsonViewer};
+/// # use unsegen_jsonviewer::json_ext::JsonValue;
+/// # fn main() {}
+/// # fn show(viewer: &mut JsonViewer, response: &JsonValue) {
+/// viewer.update(GroupBy::new(response, JsonPath::root().key("items"), "status"));
+/// # }
+/// ```
+///
+/// Groups are shown in the order in which their first elements occur, elements without a scalar
+/// `field` in the group `<missing>`. If there is no array at `path`, the value is shown unchanged.
+#[derive(Clone)]
+pub struct GroupBy<V> {
+    node: Grouped<V>,
+    path: Arc<JsonPath>,
+    field: Arc<str>,
+}
+
+#[derive(Clone)]
+enum Grouped<V> {
+    /// A node `depth` segments below the root on the way to the grouped array.
+    OnPath(V, usize),
+    /// A node that is shown unchanged.
+    Plain(V),
+    /// The elements of one group.
+    Group(Vec<V>),
+}
+
+impl<V: Value> GroupBy<V> {
+    pub fn new(value: V, path: JsonPath, field: &str) -> Self {
+        GroupBy {
+            node: Grouped::OnPath(value, 0),
+            path: Arc::new(path),
+            field: field.into(),
+        }
+    }
+}
+
+/// The value of the member `field` of `element` if it is an object and the member is a scalar.
+fn group_of<V: Value>(element: &V, field: &str) -> Option<String> {
+    match element.clone().visit() {
+        ValueVariant::Map(_, mut members) => {
+            let (_, value) = members.find(|(key, _)| key.to_string() == field)?;
+            match value.visit() {
+                ValueVariant::Scalar(_, scalar) => Some(scalar.as_str().to_owned()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+impl<V: Value> Value for GroupBy<V> {
+    fn scalar_kind(&self) -> ScalarKind {
+        match &self.node {
+            Grouped::OnPath(value, _) | Grouped::Plain(value) => value.scalar_kind(),
+            Grouped::Group(_) => ScalarKind::Other,
+        }
+    }
+
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        let GroupBy { node, path, field } = self;
+        let (value, depth) = match node {
+            Grouped::OnPath(value, depth) => (value, depth),
+            Grouped::Plain(value) => {
+                return plain(value.visit(), move |value| GroupBy {
+                    node: Grouped::Plain(value),
+                    path: path.clone(),
+                    field: field.clone(),
+                })
+            }
+            Grouped::Group(values) => {
+                return ValueVariant::Array(
+                    None,
+                    Box::new(values.into_iter().map(move |value| GroupBy {
+                        node: Grouped::Plain(value),
+                        path: path.clone(),
+                        field: field.clone(),
+                    })),
+                )
+            }
+        };
+        let segment = path.segments().get(depth).cloned();
+        let description = format!("grouped by {}", field);
+        let with = {
+            let field = field.clone();
+            move |node| GroupBy {
+                node,
+                path: path.clone(),
+                field: field.clone(),
+            }
+        };
+        match (value.visit(), segment) {
+            (ValueVariant::Array(_, values), None) => {
+                let mut groups: Vec<(Key, Vec<V>)> = Vec::new();
+                let mut lookup = BTreeMap::new();
+                for value in values {
+                    let key = match group_of(&value, &field) {
+                        Some(group) => Key::String(group.into()),
+                        None => Key::Other(MISSING.into()),
+                    };
+                    let i = *lookup.entry(key.clone()).or_insert_with(|| {
+                        groups.push((key, Vec::new()));
+                        groups.len() - 1
+                    });
+                    groups[i].1.push(value);
+                }
+                ValueVariant::Map(
+                    Some(description.into()),
+                    Box::new(
+                        groups
+                            .into_iter()
+                            .map(move |(key, values)| (key, with(Grouped::Group(values)))),
+                    ),
+                )
+            }
+            (ValueVariant::Array(d, values), Some(JsonPathSegment::Index(index))) => {
+                ValueVariant::Array(
+                    d,
+                    Box::new(values.enumerate().map(move |(i, value)| {
+                        with(if i == index {
+                            Grouped::OnPath(value, depth + 1)
+                        } else {
+                            Grouped::Plain(value)
+                        })
+                    })),
+                )
+            }
+            (ValueVariant::Map(d, members), Some(JsonPathSegment::Key(id))) => ValueVariant::Map(
+                d,
+                Box::new(members.scan(0, move |occurrence, (k, value)| {
+                    let on_path = k == id.key && {
+                        *occurrence += 1;
+                        *occurrence - 1 == id.occurrence
+                    };
+                    let node = if on_path {
+                        Grouped::OnPath(value, depth + 1)
+                    } else {
+                        Grouped::Plain(value)
+                    };
+                    Some((k, with(node)))
+                })),
+            ),
+            (variant, _) => plain(variant, move |value| with(Grouped::Plain(value))),
+        }
+    }
+}
+
+/// `variant` with its children wrapped using `wrap`.
+fn plain<'s, V: Value + 's, W: Value + 's>(
+    variant: ValueVariant<'s, V>,
+    wrap: impl Fn(V) -> W + 's,
+) -> ValueVariant<'s, W> {
+    match variant {
+        ValueVariant::Scalar(d, s) => ValueVariant::Scalar(d, s),
+        ValueVariant::Array(d, values) => ValueVariant::Array(d, Box::new(values.map(wrap))),
+        ValueVariant::Map(d, members) => ValueVariant::Map(
+            d,
+            Box::new(members.map(move |(key, value)| (key, wrap(value)))),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use crate::JsonViewer;
+    use unsegen::base::{Height, Width, WindowBuffer};
+    use unsegen::widget::{RenderingHints, Widget};
+
+    #[test]
+    fn test_group_by() {
+        let value = object! {
+            "items" => array! {
+                object! { "id" => 1, "status" => "ok" },
+                object! { "id" => 2, "status" => "failed" },
+                object! { "id" => 3 },
+                object! { "id" => 4, "status" => "ok" }
+            },
+            "total" => 4
+        };
+        let mut viewer = JsonViewer::new(GroupBy::new(
+            &value,
+            JsonPath::root().key("items"),
+            "status",
+        ));
+        let items = JsonPath::root().key("items");
+        let missing = Key::Other(MISSING.into());
+        for (group, len) in [("ok".into(), 2), ("failed".into(), 1), (missing.clone(), 1)].iter() {
+            for i in 0..*len {
+                let element = items.clone().key(group.clone()).index(i);
+                viewer.set_expanded(&element, false).unwrap();
+            }
+        }
+        let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(15).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  items: grouped by status { [-]",
+                "    ok: [ [-]",
+                "      { [+] },",
+                "      { [+] },",
+                "    ] <-2/2 >,",
+                "    failed: [ [-]",
+                "      { [+] },",
+                "    ] <-1/1 >,",
+                "    <missing>: [ [-]",
+                "      { [+] },",
+                "    ] <-1/1 >,",
+                "  },",
+                "  total: 4,",
+                "}",
+            ]
+        );
+
+        let mut value = value;
+        value["items"][2]["status"] = "ok".into();
+        viewer.update(GroupBy::new(&value, items.clone(), "status"));
+        let ok = viewer.node(&items.clone().key("ok")).unwrap();
+        assert_eq!(ok.len(), 3);
+        assert!(viewer.node(&items.key(missing)).is_none());
+    }
+}
@@ -0,0 +1,97 @@
+//! INI-style configuration files.
+use json::object::Object;
+use json::JsonValue;
+
+/// Parse INI-style text into an object of sections, each an object of its keys and values (all
+/// strings). Keys before the first section header are members of the top level object. The value
+/// can be passed to `FileSource::parser` to watch a configuration file:
+///
+/// ```
+/// # extern crate unsegen_jsonviewer;
+/// # use unsegen_jsonviewer::{ini, FileSource, JsonFileViewer};
+/// # fn main() {}
+/// # fn open() -> JsonFileViewer {
+/// JsonFileViewer::new(FileSource::new("config.ini").parser(ini::parse))
+/// # }
+/// ```
+///
+/// Lines starting with `;` or `#` are comments. Keys and values are separated by `=` or `:`, and
+/// keys without a separator have an empty value. Quotes around values are removed. Sections that
+/// occur multiple times are merged, and later values of a key replace earlier ones.
+pub fn parse(text: &str) -> Result<JsonValue, String> {
+    let mut document = Object::new();
+    let mut section: Option<String> = None;
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') {
+            if !line.ends_with(']') || line.len() < 2 {
+                return Err(format!("line {}: unterminated section header", number + 1));
+            }
+            let name = line[1..line.len() - 1].trim().to_owned();
+            if !document.get(&name).is_some_and(JsonValue::is_object) {
+                document.insert(&name, JsonValue::new_object());
+            }
+            section = Some(name);
+            continue;
+        }
+        let (key, value) = match line.find(['=', ':']) {
+            Some(separator) => (line[..separator].trim(), line[separator + 1..].trim()),
+            None => (line, ""),
+        };
+        if key.is_empty() {
+            return Err(format!("line {}: missing key", number + 1));
+        }
+        let value = JsonValue::from(unquote(value));
+        match &section {
+            Some(name) => document[name.as_str()][key] = value,
+            None => document[key] = value,
+        }
+    }
+    Ok(JsonValue::Object(document))
+}
+
+fn unquote(value: &str) -> &str {
+    let quoted = value.len() >= 2
+        && (value.starts_with('"') && value.ends_with('"')
+            || value.starts_with('\'') && value.ends_with('\''));
+    if quoted {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let text = "name = demo\n\
+                    ; comment\n\
+                    [server]\n\
+                    host: \"localhost\"\n\
+                    port = 80\n\
+                    \n\
+                    [client]\n\
+                    verbose\n\
+                    [server]\n\
+                    port = 8080\n";
+        assert_eq!(
+            parse(text),
+            Ok(object! {
+                "name" => "demo",
+                "server" => object! { "host" => "localhost", "port" => "8080" },
+                "client" => object! { "verbose" => "" }
+            })
+        );
+        assert_eq!(parse("[a]\n= 1"), Err("line 2: missing key".to_owned()));
+        assert_eq!(
+            parse("[a"),
+            Err("line 1: unterminated section header".to_owned())
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Mapping of input events to operations of a `JsonViewer`.
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use unsegen::input::{Event, Key, Navigatable, OperationResult, Scrollable, ToEvent};
+
+use super::{CopyFormat, JsonViewer};
+
+/// An operation of a `JsonViewer` that can be bound to an event in a `KeyMap`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Action {
+    SelectNext,
+    SelectPrevious,
+    SelectFirst,
+    SelectLast,
+    /// See `JsonViewer::extend_selection_next`.
+    ExtendSelectionNext,
+    /// See `JsonViewer::extend_selection_previous`.
+    ExtendSelectionPrevious,
+    /// See `JsonViewer::toggle_active_element`.
+    Toggle,
+    /// See `JsonViewer::toggle_active_container`.
+    ToggleContainer,
+    /// Fold the active object or array or the one containing it (see `Navigatable::move_left`).
+    Collapse,
+    /// Unfold the active object or array or descend into it (see `Navigatable::move_right`).
+    Expand,
+    Grow,
+    Shrink,
+    /// See `JsonViewer::grow_active_array_tail`.
+    GrowTail,
+    /// See `JsonViewer::shrink_active_array_tail`.
+    ShrinkTail,
+    ExpandAll,
+    CollapseAll,
+    /// See `JsonViewer::select_next_container`.
+    NextContainer,
+    /// See `JsonViewer::select_previous_container`.
+    PreviousContainer,
+    NextMatch,
+    PreviousMatch,
+    ClearSearch,
+    /// See `JsonViewer::next_change`.
+    NextChange,
+    /// See `JsonViewer::previous_change`.
+    PreviousChange,
+    CycleNumberBase,
+    /// See `JsonViewer::toggle_active_history`.
+    ToggleHistory,
+    ToggleRedaction,
+    /// See `JsonViewer::toggle_view_mode`.
+    ToggleViewMode,
+    /// See `JsonViewer::copy_active`.
+    Copy(CopyFormat),
+    /// See `JsonViewer::undo_view_change`.
+    Undo,
+    /// See `JsonViewer::redo_view_change`.
+    Redo,
+    /// See `JsonViewer::mark_duplicates`.
+    MarkDuplicates,
+    /// See `JsonViewer::next_duplicate`.
+    NextDuplicate,
+}
+
+impl Action {
+    /// Perform the action on `viewer`. Fails if the operation is not possible, e.g., because there
+    /// is no array to grow.
+    pub fn apply(self, viewer: &mut JsonViewer) -> OperationResult {
+        match self {
+            Action::SelectNext => viewer.select_next(),
+            Action::SelectPrevious => viewer.select_previous(),
+            Action::SelectFirst => viewer.scroll_to_beginning(),
+            Action::SelectLast => viewer.scroll_to_end(),
+            Action::ExtendSelectionNext => viewer.extend_selection_next(),
+            Action::ExtendSelectionPrevious => viewer.extend_selection_previous(),
+            Action::Toggle => viewer.toggle_active_element().map(|_| ()),
+            Action::ToggleContainer => viewer.toggle_active_container().map(|_| ()),
+            Action::Collapse => viewer.move_left(),
+            Action::Expand => viewer.move_right(),
+            Action::Grow => viewer.grow_active_array().map(|_| ()),
+            Action::Shrink => viewer.shrink_active_array().map(|_| ()),
+            Action::GrowTail => viewer.grow_active_array_tail().map(|_| ()),
+            Action::ShrinkTail => viewer.shrink_active_array_tail().map(|_| ()),
+            Action::ExpandAll => {
+                viewer.expand_all();
+                Ok(())
+            }
+            Action::CollapseAll => {
+                viewer.collapse_all();
+                Ok(())
+            }
+            Action::NextContainer => viewer.select_next_container(),
+            Action::PreviousContainer => viewer.select_previous_container(),
+            Action::NextMatch => viewer.next_match(),
+            Action::PreviousMatch => viewer.previous_match(),
+            Action::NextChange => viewer.next_change(),
+            Action::PreviousChange => viewer.previous_change(),
+            Action::ClearSearch => {
+                viewer.clear_search();
+                Ok(())
+            }
+            Action::CycleNumberBase => viewer.cycle_number_base(),
+            Action::ToggleHistory => viewer.toggle_active_history().map(|_| ()),
+            Action::ToggleViewMode => {
+                viewer.toggle_view_mode();
+                Ok(())
+            }
+            Action::Copy(format) => viewer.copy_active(format),
+            Action::Undo => viewer.undo_view_change(),
+            Action::Redo => viewer.redo_view_change(),
+            Action::MarkDuplicates => viewer.mark_duplicates().map(|_| ()),
+            Action::NextDuplicate => viewer.next_duplicate(),
+            Action::ToggleRedaction => {
+                let enabled = viewer.redaction_enabled();
+                viewer.set_redaction_enabled(!enabled);
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Which `Action` is triggered by which event. The default keymap is:
+///
+/// * Down, `j`: select the next element
+/// * Up, `k`: select the previous element
+/// * Home, `g`, End, `G`: select the first or last element
+/// * `J`, `K`: extend the selection to the next or previous sibling
+/// * Enter, Space: toggle the active element
+/// * Left, `h`: fold the active object or array or the one containing it
+/// * Right, `l`: unfold the active object or array or descend into it
+/// * `z`: fold or unfold the object or array containing the active element
+/// * `+`, `-`: grow or shrink the array containing the active element (or show its next or previous
+///   page)
+/// * `>`, `<`: grow or shrink the end of the array containing the active element
+/// * `*`, `_`: expand or collapse all objects and arrays
+/// * `]`, `[`: select the next or previous object or array (see `JsonOutline`)
+/// * `n`, `N`: select the next or previous search match
+/// * Esc: clear the search
+/// * `c`, `C`: select the next or previous changed node
+/// * `b`: show the active integer in the next base
+/// * `H`: show or hide the previous values of the active scalar
+/// * `v`: switch between the tree and the flat view
+/// * `y`, `Y`: copy the active subtree as json or as `path = value` lines
+/// * `u`, `U`: undo or redo the last change of the fold state
+/// * `d`, `D`: mark the duplicate elements of the array containing the active element or select
+///   the next one
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    bindings: HashMap<Event, Action>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        KeyMap::empty()
+            .bind(Key::Down, Action::SelectNext)
+            .bind(Key::Char('j'), Action::SelectNext)
+            .bind(Key::Up, Action::SelectPrevious)
+            .bind(Key::Char('k'), Action::SelectPrevious)
+            .bind(Key::Char('J'), Action::ExtendSelectionNext)
+            .bind(Key::Char('K'), Action::ExtendSelectionPrevious)
+            .bind(Key::Home, Action::SelectFirst)
+            .bind(Key::Char('g'), Action::SelectFirst)
+            .bind(Key::End, Action::SelectLast)
+            .bind(Key::Char('G'), Action::SelectLast)
+            .bind(Key::Char('\n'), Action::Toggle)
+            .bind(Key::Char(' '), Action::Toggle)
+            .bind(Key::Left, Action::Collapse)
+            .bind(Key::Char('h'), Action::Collapse)
+            .bind(Key::Right, Action::Expand)
+            .bind(Key::Char('l'), Action::Expand)
+            .bind(Key::Char('z'), Action::ToggleContainer)
+            .bind(Key::Char('+'), Action::Grow)
+            .bind(Key::Char('-'), Action::Shrink)
+            .bind(Key::Char('>'), Action::GrowTail)
+            .bind(Key::Char('<'), Action::ShrinkTail)
+            .bind(Key::Char('*'), Action::ExpandAll)
+            .bind(Key::Char('_'), Action::CollapseAll)
+            .bind(Key::Char(']'), Action::NextContainer)
+            .bind(Key::Char('['), Action::PreviousContainer)
+            .bind(Key::Char('n'), Action::NextMatch)
+            .bind(Key::Char('N'), Action::PreviousMatch)
+            .bind(Key::Esc, Action::ClearSearch)
+            .bind(Key::Char('c'), Action::NextChange)
+            .bind(Key::Char('C'), Action::PreviousChange)
+            .bind(Key::Char('b'), Action::CycleNumberBase)
+            .bind(Key::Char('H'), Action::ToggleHistory)
+            .bind(Key::Char('v'), Action::ToggleViewMode)
+            .bind(Key::Char('y'), Action::Copy(CopyFormat::Json))
+            .bind(Key::Char('Y'), Action::Copy(CopyFormat::Flat))
+            .bind(Key::Char('u'), Action::Undo)
+            .bind(Key::Char('U'), Action::Redo)
+            .bind(Key::Char('d'), Action::MarkDuplicates)
+            .bind(Key::Char('D'), Action::NextDuplicate)
+    }
+}
+
+impl KeyMap {
+    /// The default keymap, which is only built once.
+    pub(crate) fn shared_default() -> &'static KeyMap {
+        static DEFAULT: OnceLock<KeyMap> = OnceLock::new();
+        DEFAULT.get_or_init(KeyMap::default)
+    }
+
+    /// Create a keymap without any bindings.
+    pub fn empty() -> Self {
+        KeyMap {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Trigger `action` on `event`, replacing the previous binding of `event` (if any).
+    pub fn bind<E: ToEvent>(mut self, event: E, action: Action) -> Self {
+        self.bindings.insert(event.to_event(), action);
+        self
+    }
+
+    /// Remove the binding of `event`.
+    pub fn unbind<E: ToEvent>(mut self, event: E) -> Self {
+        self.bindings.remove(&event.to_event());
+        self
+    }
+
+    /// Remove all bindings of `action`.
+    pub fn unbind_action(mut self, action: Action) -> Self {
+        self.bindings.retain(|_, bound| *bound != action);
+        self
+    }
+
+    /// The action triggered by `event`.
+    pub fn action(&self, event: &Event) -> Option<Action> {
+        self.bindings.get(event).cloned()
+    }
+
+    /// All events that trigger `action`, e.g., to show them in a help screen.
+    pub fn events(&self, action: Action) -> Vec<Event> {
+        self.bindings
+            .iter()
+            .filter(|&(_, bound)| *bound == action)
+            .map(|(event, _)| event.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_bindings() {
+        let keymap = KeyMap::default()
+            .bind(Key::Char('j'), Action::Grow)
+            .unbind(Key::Down)
+            .unbind_action(Action::SelectPrevious);
+        assert_eq!(
+            keymap.action(&Event::Key(Key::Char('j'))),
+            Some(Action::Grow)
+        );
+        assert_eq!(keymap.action(&Event::Key(Key::Down)), None);
+        assert_eq!(keymap.action(&Event::Key(Key::Up)), None);
+        assert!(keymap.events(Action::SelectNext).is_empty());
+        assert_eq!(
+            keymap.events(Action::Shrink),
+            vec![Event::Key(Key::Char('-'))]
+        );
+    }
+}
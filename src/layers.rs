@@ -0,0 +1,129 @@
+use unsegen::base::StyleModifier;
+
+/// A source of styling of a part of a node. Where several of them apply to the same cell, they
+/// are combined in the order set using `JsonViewerWidget::style_precedence`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StyleLayer {
+    /// The style returned by `JsonViewerWidget::node_style`, the rainbow colors of brackets and
+    /// keys, and the styles of links, duplicate keys and duplicate elements.
+    Node,
+    /// Nodes marked by `JsonViewer::validate`.
+    Error,
+    /// The selected range of siblings (see `JsonViewer::extend_selection_next`).
+    Selected,
+    /// The active element.
+    Focused,
+    /// Values changed by the last update.
+    Changed,
+    /// The parts of keys and values that match the search (or `JsonViewer::highlight_matches`).
+    Match,
+}
+
+const LAYERS: usize = 6;
+
+const DEFAULT_ORDER: [StyleLayer; LAYERS] = [
+    StyleLayer::Node,
+    StyleLayer::Error,
+    StyleLayer::Selected,
+    StyleLayer::Focused,
+    StyleLayer::Changed,
+    StyleLayer::Match,
+];
+
+/// The order in which the styles of the layers are applied, from the lowest to the highest
+/// precedence.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StylePrecedence([StyleLayer; LAYERS]);
+
+impl StylePrecedence {
+    /// `layers` from the lowest to the highest precedence. Layers that are not mentioned are
+    /// applied below all others (in their default order).
+    pub fn new(layers: &[StyleLayer]) -> Self {
+        let mut order = DEFAULT_ORDER
+            .iter()
+            .filter(|layer| !layers.contains(layer))
+            .cloned()
+            .collect::<Vec<_>>();
+        for &layer in layers {
+            if !order.contains(&layer) {
+                order.push(layer);
+            }
+        }
+        let mut result = DEFAULT_ORDER;
+        result.copy_from_slice(&order);
+        StylePrecedence(result)
+    }
+}
+
+impl Default for StylePrecedence {
+    fn default() -> Self {
+        StylePrecedence(DEFAULT_ORDER)
+    }
+}
+
+/// The styles of the layers that apply to a part of a node.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct StyleLayers([Option<StyleModifier>; LAYERS]);
+
+impl StyleLayers {
+    /// Set the style of `layer`, replacing a previous one.
+    pub fn with(mut self, layer: StyleLayer, style: StyleModifier) -> Self {
+        self.0[layer as usize] = Some(style);
+        self
+    }
+
+    /// Like `with`, but only if `condition` holds.
+    pub fn with_if(self, condition: bool, layer: StyleLayer, style: StyleModifier) -> Self {
+        if condition {
+            self.with(layer, style)
+        } else {
+            self
+        }
+    }
+
+    pub fn get(&self, layer: StyleLayer) -> StyleModifier {
+        self.0[layer as usize].unwrap_or_default()
+    }
+
+    /// The styles of all layers combined in the order of `precedence`.
+    pub fn resolve(&self, precedence: &StylePrecedence) -> StyleModifier {
+        precedence
+            .0
+            .iter()
+            .filter_map(|&layer| self.0[layer as usize])
+            .fold(StyleModifier::new(), |result, style| {
+                style.on_top_of(result)
+            })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_precedence() {
+        let precedence = StylePrecedence::new(&[StyleLayer::Changed, StyleLayer::Focused]);
+        assert_eq!(
+            precedence.0,
+            [
+                StyleLayer::Node,
+                StyleLayer::Error,
+                StyleLayer::Selected,
+                StyleLayer::Match,
+                StyleLayer::Changed,
+                StyleLayer::Focused,
+            ]
+        );
+        let layers = StyleLayers::default()
+            .with(StyleLayer::Focused, StyleModifier::new().bold(false))
+            .with(StyleLayer::Changed, StyleModifier::new().bold(true))
+            .with_if(false, StyleLayer::Match, StyleModifier::new().italic(true));
+        let bold = StyleModifier::new().bold(true);
+        assert_eq!(layers.resolve(&StylePrecedence::default()), bold);
+        assert_eq!(
+            layers.resolve(&precedence),
+            StyleModifier::new().bold(false)
+        );
+    }
+}
@@ -0,0 +1,311 @@
+use unsegen::base::GraphemeCluster;
+
+use std::cmp::max;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// The shape of the lines that a node occupies when it is drawn: Its first line starts at the
+/// current cursor position, all other lines start at the current line start column.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Extent {
+    /// The number of lines (at least one).
+    pub height: usize,
+    /// The width of the first line.
+    pub first: usize,
+    /// The maximum width of all other lines (relative to the line start column).
+    pub rest: usize,
+    /// The width of the last line (relative to the line start column if `height > 1`).
+    pub last: usize,
+}
+
+fn line_width(line: &str) -> usize {
+    GraphemeCluster::all_from_str(line).map(|c| c.width()).sum()
+}
+
+impl Extent {
+    /// A line break, i.e., two empty lines.
+    pub fn line_break() -> Self {
+        Extent {
+            height: 2,
+            first: 0,
+            rest: 0,
+            last: 0,
+        }
+    }
+
+    /// The extent of `text` as written by `Cursor::write`.
+    pub fn text(text: &str) -> Self {
+        let mut lines = text.split('\n');
+        let first = line_width(lines.next().unwrap_or(""));
+        let mut result = Extent {
+            height: 1,
+            first,
+            rest: 0,
+            last: first,
+        };
+        for line in lines {
+            let width = line_width(line);
+            result.height += 1;
+            result.rest = max(result.rest, width);
+            result.last = width;
+        }
+        result
+    }
+
+    /// The extent of `self` directly followed by `other`.
+    pub fn then(self, other: Extent) -> Self {
+        match (self.height, other.height) {
+            (1, 1) => Extent {
+                height: 1,
+                first: self.first + other.first,
+                rest: 0,
+                last: self.first + other.first,
+            },
+            (1, _) => Extent {
+                height: other.height,
+                first: self.first + other.first,
+                rest: other.rest,
+                last: other.last,
+            },
+            (_, 1) => Extent {
+                height: self.height,
+                first: self.first,
+                rest: max(self.rest, self.last + other.first),
+                last: self.last + other.first,
+            },
+            (_, _) => Extent {
+                height: self.height + other.height - 1,
+                first: self.first,
+                rest: max(max(self.rest, self.last + other.first), other.rest),
+                last: other.last,
+            },
+        }
+    }
+
+    /// The extent of `self` if the line start column is moved to the right by `indentation`.
+    pub fn indent(self, indentation: usize) -> Self {
+        if self.height == 1 {
+            self
+        } else {
+            Extent {
+                rest: self.rest + indentation,
+                last: self.last + indentation,
+                ..self
+            }
+        }
+    }
+
+    /// The total width if the node is drawn at `column` with the line start column at 0.
+    pub fn width_at(&self, column: usize) -> usize {
+        max(column + self.first, self.rest)
+    }
+}
+
+/// Which commas are drawn after the members of objects and arrays that span multiple lines.
+/// (Single line forms always separate members by `, `.)
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Separators {
+    /// After every member, including the last one (like in Rust).
+    #[default]
+    Trailing,
+    /// Between members only (like in JSON).
+    Json,
+    /// No commas at all.
+    Omitted,
+}
+
+impl Separators {
+    /// The separator after the `i`th of `n` members.
+    pub fn after(self, i: usize, n: usize) -> &'static str {
+        match self {
+            Separators::Trailing => ",",
+            Separators::Json if i + 1 < n => ",",
+            Separators::Json | Separators::Omitted => "",
+        }
+    }
+}
+
+/// Where the closing brackets of objects and arrays that span multiple lines are drawn.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ClosingBrackets {
+    /// On a line of their own, aligned with the start of the node.
+    #[default]
+    OwnLine,
+    /// At the end of the last line of the last member (like in Lisp), which saves a line per
+    /// node.
+    Hanging,
+}
+
+impl ClosingBrackets {
+    /// What is drawn between the last member and the closing bracket.
+    pub fn separator(self) -> &'static str {
+        match self {
+            ClosingBrackets::OwnLine => "\n",
+            ClosingBrackets::Hanging => " ",
+        }
+    }
+}
+
+/// The parameters of the widget that determine the extent of a node.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct LayoutParams {
+    pub indentation: usize,
+    pub compact_width: Option<usize>,
+    /// The maximum width of arrays of short scalars drawn on a single line (see
+    /// `JsonViewerWidget::inline_scalar_arrays`).
+    pub inline_width: Option<usize>,
+    pub separators: Separators,
+    pub closing_brackets: ClosingBrackets,
+}
+
+impl LayoutParams {
+    /// The parameters of the children of a node: Their lines are indented by `indentation` more
+    /// columns, which are not available to their single line forms (see `compact_width`).
+    pub fn nested(self) -> Self {
+        self.at_depth(1)
+    }
+
+    /// The parameters of the nodes `depth` levels below the node with these parameters.
+    pub fn at_depth(self, depth: usize) -> Self {
+        LayoutParams {
+            compact_width: self
+                .compact_width
+                .map(|w| w.saturating_sub(depth * self.indentation)),
+            ..self
+        }
+    }
+}
+
+/// The extent of a node and the lines at which its children start for the layout parameters they
+/// were last computed with. It has to be invalidated whenever the node or any of its children is
+/// modified.
+///
+/// The cache is synchronized so that values can be shared with other threads (see
+/// `JsonViewer::update_base`). Locks are never held while computing.
+#[derive(Default)]
+pub struct ExtentCache {
+    extent: Mutex<Option<(LayoutParams, Extent)>>,
+    child_lines: Mutex<Option<(LayoutParams, Arc<[usize]>)>>,
+}
+
+impl Clone for ExtentCache {
+    fn clone(&self) -> Self {
+        ExtentCache {
+            extent: Mutex::new(*lock(&self.extent)),
+            child_lines: Mutex::new(lock(&self.child_lines).clone()),
+        }
+    }
+}
+
+/// Lock a cache. A panic while it was locked cannot leave it inconsistent, since values are only
+/// replaced as a whole.
+pub fn lock<T>(mutex: &Mutex<T>) -> ::std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl ExtentCache {
+    pub fn get_or_compute(&self, params: LayoutParams, compute: impl FnOnce() -> Extent) -> Extent {
+        let cached = *lock(&self.extent);
+        match cached {
+            Some((cached_params, extent)) if cached_params == params => extent,
+            _ => {
+                let extent = compute();
+                *lock(&self.extent) = Some((params, extent));
+                extent
+            }
+        }
+    }
+
+    pub fn is_cached(&self, params: LayoutParams) -> bool {
+        match *lock(&self.extent) {
+            Some((cached_params, _)) => cached_params == params,
+            None => false,
+        }
+    }
+
+    /// Call `f` with the (cached or computed) lines at which the children of the node start.
+    pub fn with_child_lines<R>(
+        &self,
+        params: LayoutParams,
+        compute: impl FnOnce() -> Vec<usize>,
+        f: impl FnOnce(&[usize]) -> R,
+    ) -> R {
+        let cached = match &*lock(&self.child_lines) {
+            Some((cached_params, lines)) if *cached_params == params => Some(lines.clone()),
+            _ => None,
+        };
+        let lines = cached.unwrap_or_else(|| {
+            let lines = Arc::<[usize]>::from(compute());
+            *lock(&self.child_lines) = Some((params, lines.clone()));
+            lines
+        });
+        f(&lines)
+    }
+
+    pub fn invalidate(&mut self) {
+        *self
+            .extent
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+        *self
+            .child_lines
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_extent() {
+        assert_eq!(
+            Extent::text("foo"),
+            Extent {
+                height: 1,
+                first: 3,
+                rest: 0,
+                last: 3
+            }
+        );
+        let block = Extent::text("ab\ncdef\ng");
+        assert_eq!(
+            block,
+            Extent {
+                height: 3,
+                first: 2,
+                rest: 4,
+                last: 1
+            }
+        );
+        assert_eq!(
+            Extent::text("xyz").then(block),
+            Extent {
+                height: 3,
+                first: 5,
+                rest: 4,
+                last: 1
+            }
+        );
+        assert_eq!(
+            block.then(Extent::text("xyzw")),
+            Extent {
+                height: 3,
+                first: 2,
+                rest: 5,
+                last: 5
+            }
+        );
+        assert_eq!(
+            block.then(Extent::line_break()).then(block),
+            Extent {
+                height: 6,
+                first: 2,
+                rest: 4,
+                last: 1
+            }
+        );
+        assert_eq!(block.indent(2).width_at(3), 6);
+        assert_eq!(block.indent(2).width_at(5), 7);
+    }
+}
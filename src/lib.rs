@@ -56,6 +56,10 @@
 //!     }
 //! }
 //! ```
+// Operations that can fail without further information return `Result<_, ()>`, like unsegen's
+// `OperationResult`.
+#![allow(clippy::result_unit_err)]
+
 #[cfg(test)] //Only tests use macros. Otherwise we get unused_imports warnings.
 #[macro_use]
 extern crate json;
@@ -63,13 +67,45 @@ extern crate json;
 #[cfg(not(test))]
 extern crate json;
 
+#[cfg(test)]
+extern crate proptest;
+
+#[cfg(feature = "regex")]
+extern crate regex;
+
+#[cfg(feature = "chrono")]
+extern crate chrono;
+
+#[cfg(feature = "csv")]
+extern crate csv;
+
+#[cfg(feature = "protobuf")]
+extern crate prost_reflect;
+
+#[cfg(feature = "notify")]
+extern crate notify;
+
+#[cfg(feature = "yaml")]
+extern crate serde_yaml;
+
+#[cfg(feature = "toml")]
+extern crate toml;
+
 extern crate unsegen;
 
 use unsegen::base::basic_types::*;
-use unsegen::base::{BoolModifyMode, Color, Cursor, ExtentEstimationWindow, StyleModifier, Window};
-use unsegen::widget::{Demand, Demand2D, RenderingHints, Widget};
+use unsegen::base::{
+    BoolModifyMode, Color, Cursor, CursorTarget, ExtentEstimationWindow, StyleModifier, Window,
+};
+use unsegen::widget::{Blink, Demand, Demand2D, RenderingHints, Widget};
+
+use unsegen::input::{Navigatable, OperationResult, Scrollable};
 
-use unsegen::input::{OperationResult, Scrollable};
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::sync::Arc;
+use std::time::SystemTime;
 
 /// Convenience reexport of `json` types.
 pub mod json_ext {
@@ -77,43 +113,270 @@ pub mod json_ext {
 }
 
 impl Value for &json_ext::JsonValue {
+    fn scalar_kind(&self) -> ScalarKind {
+        match self {
+            json_ext::JsonValue::Null => ScalarKind::Null,
+            json_ext::JsonValue::Short(_) | json_ext::JsonValue::String(_) => ScalarKind::String,
+            json_ext::JsonValue::Number(_) => ScalarKind::Number,
+            json_ext::JsonValue::Boolean(_) => ScalarKind::Boolean,
+            json_ext::JsonValue::Object(_) | json_ext::JsonValue::Array(_) => ScalarKind::Other,
+        }
+    }
+
     fn visit<'s>(self) -> ValueVariant<'s, Self> {
         match self {
-            json_ext::JsonValue::Null => ValueVariant::Scalar("null".to_string()),
-            json_ext::JsonValue::Short(val) => ValueVariant::Scalar(val.to_string()),
-            json_ext::JsonValue::String(val) => ValueVariant::Scalar(val.to_string()),
-            json_ext::JsonValue::Number(val) => ValueVariant::Scalar(val.to_string()),
-            json_ext::JsonValue::Boolean(val) => ValueVariant::Scalar(val.to_string()),
+            json_ext::JsonValue::Null => ValueVariant::Scalar(None, "null".into()),
+            json_ext::JsonValue::Short(val) => ValueVariant::Scalar(None, val.as_str().into()),
+            json_ext::JsonValue::String(val) => ValueVariant::Scalar(None, val.as_str().into()),
+            json_ext::JsonValue::Number(val) => ValueVariant::Scalar(None, val.to_string().into()),
+            json_ext::JsonValue::Boolean(val) => ValueVariant::Scalar(None, val.to_string().into()),
             json_ext::JsonValue::Object(val) => {
-                ValueVariant::Map(None, Box::new(val.iter().map(|(k, v)| (k.to_owned(), v))))
+                ValueVariant::Map(None, Box::new(val.iter().map(|(k, v)| (k.into(), v))))
             }
             json_ext::JsonValue::Array(val) => ValueVariant::Array(None, Box::new(val.iter())),
         }
     }
 }
 
+/// Like `&JsonValue`, but takes the members of objects and arrays instead of cloning them.
+impl Value for json_ext::JsonValue {
+    fn scalar_kind(&self) -> ScalarKind {
+        (&self).scalar_kind()
+    }
+
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        match self {
+            json_ext::JsonValue::Object(mut val) => {
+                let members = val
+                    .iter_mut()
+                    .map(|(k, v)| (k.into(), v.take()))
+                    .collect::<Vec<_>>();
+                ValueVariant::Map(None, Box::new(members.into_iter()))
+            }
+            json_ext::JsonValue::Array(val) => ValueVariant::Array(None, Box::new(val.into_iter())),
+            scalar => match (&scalar).visit() {
+                ValueVariant::Scalar(description, text) => ValueVariant::Scalar(description, text),
+                _ => unreachable!("objects and arrays are handled above"),
+            },
+        }
+    }
+}
+
 impl Value for &str {
     fn visit<'s>(self) -> ValueVariant<'s, Self> {
-        ValueVariant::Scalar(self.to_owned())
+        ValueVariant::Scalar(None, self.into())
     }
 }
 
+/// The structure of a `Value`. Each variant carries an optional description that is displayed in
+/// front of the value, e.g., `len=5 hello` (scalar) or `Vec<i32> [ [-] ... ]` (array).
+///
+/// Scalars and descriptions can be styled (see `StyledString`).
 pub enum ValueVariant<'s, V: Value + 's> {
-    Scalar(String),
-    Array(Option<String>, Box<dyn Iterator<Item = V> + 's>),
-    Map(Option<String>, Box<dyn Iterator<Item = (String, V)> + 's>),
+    Scalar(Option<StyledString>, StyledString),
+    Array(Option<StyledString>, Box<dyn Iterator<Item = V> + 's>),
+    Map(
+        Option<StyledString>,
+        Box<dyn Iterator<Item = (Key, V)> + 's>,
+    ),
+}
+
+/// The key of an entry of a `ValueVariant::Map`.
+///
+/// Keys of different types are considered different even if they are displayed the same way,
+/// e.g., `Key::String("1")` and `Key::Integer(1)`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum Key {
+    /// A string, e.g., the key of a json object.
+    String(Arc<str>),
+    /// An integer, e.g., the key of a `HashMap<i64, _>` or a CBOR map.
+    Integer(i64),
+    /// Any other key (e.g., a boolean or a composite YAML key) in its formatted form.
+    Other(Arc<str>),
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::String(s) | Key::Other(s) => write!(f, "{}", s),
+            Key::Integer(i) => write!(f, "{}", i),
+        }
+    }
 }
 
+impl From<String> for Key {
+    fn from(key: String) -> Self {
+        Key::String(key.into())
+    }
+}
+
+impl<'a> From<&'a str> for Key {
+    fn from(key: &'a str) -> Self {
+        Key::String(key.into())
+    }
+}
+
+impl From<i64> for Key {
+    fn from(key: i64) -> Self {
+        Key::Integer(key)
+    }
+}
+
+/// The type of a scalar `Value`.
+///
+/// Scalars of different kinds are considered different even if they are displayed the same way,
+/// e.g., the number `1` and the string `"1"`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ScalarKind {
+    String,
+    Number,
+    Boolean,
+    Null,
+    /// Any other (or unknown) type, e.g., of values that only provide a textual representation.
+    Other,
+}
+
+/// A value that can be shown by a `JsonViewer`. Wrap impls that may recurse infinitely (e.g.,
+/// over cyclic graphs) in a `RecursionGuard`.
 pub trait Value: Sized + Clone {
     fn visit<'s>(self) -> ValueVariant<'s, Self>;
+
+    /// The type of the value if it is a scalar. Only consulted for scalars.
+    fn scalar_kind(&self) -> ScalarKind {
+        ScalarKind::Other
+    }
 }
 
+mod behavior;
+mod binary;
+mod copy;
+#[cfg(feature = "csv")]
+mod csv_table;
+mod debug;
+mod diff;
 mod displayvalue;
+mod file;
+mod flat;
+#[cfg(feature = "gdbmi")]
+pub mod gdbmi;
+mod group;
+pub mod ini;
+mod keymap;
+mod layers;
+mod layout;
+mod members;
+mod metrics;
+mod node;
+mod number;
+mod outline;
+mod patch;
 mod path;
+mod process;
+#[cfg(feature = "protobuf")]
+pub mod protobuf;
+mod recursion;
+mod render;
+mod renderer;
+#[cfg(feature = "schema")]
+pub mod schema;
+mod search;
+mod selection;
+mod set;
+mod sort;
+mod source;
+mod stats;
+mod stream;
+mod stripes;
+mod styled;
+#[cfg(test)]
+mod test_util;
+#[cfg(feature = "chrono")]
+mod timestamp;
+mod undo;
+mod worddiff;
+#[cfg(feature = "xml")]
+pub mod xml;
 
 use self::displayvalue::*;
+pub use self::layers::StyleLayer;
+use self::layers::{StyleLayers, StylePrecedence};
+use self::layout::LayoutParams;
+pub use self::layout::{ClosingBrackets, Separators};
 use self::path::*;
 
+pub use self::behavior::JsonViewerBehavior;
+pub use self::copy::CopyFormat;
+#[cfg(feature = "csv")]
+pub use self::csv_table::{CsvTable, CsvValue};
+pub use self::debug::{DebugNode, DebugValue};
+pub use self::diff::{DiffKind, DiffLayout, JsonDiffViewer, JsonDiffViewerWidget};
+#[cfg(feature = "toml")]
+pub use self::file::parse_toml;
+#[cfg(feature = "yaml")]
+pub use self::file::parse_yaml;
+pub use self::file::{FileParser, FileSource, JsonFileViewer};
+pub use self::flat::ViewMode;
+pub use self::group::GroupBy;
+pub use self::keymap::{Action, KeyMap};
+use self::members::KeyPool;
+pub use self::members::{DuplicateKeys, MemberId};
+pub use self::metrics::Metrics;
+pub use self::node::{Node, NodeKind};
+pub use self::number::NumberBase;
+pub use self::outline::JsonOutline;
+pub use self::patch::PatchOp;
+pub use self::path::{JsonPath, JsonPathSegment, SelectionMode};
+pub use self::process::{Arguments, Environment};
+pub use self::recursion::{NodeIdentity, RecursionGuard};
+pub use self::render::{render_value, RenderOptions};
+pub use self::renderer::{NodeCursor, NodeRenderer};
+use self::search::Search;
+pub use self::search::{Pattern, SearchOptions};
+pub use self::selection::Selection;
+pub use self::set::{JsonViewerSet, JsonViewerSetWidget};
+pub use self::sort::{ArraySort, SortKey};
+pub use self::source::DocumentSource;
+pub use self::stats::{Aggregates, DocumentStats, SubtreeStats};
+pub use self::stream::JsonStreamViewer;
+pub use self::stripes::Stripes;
+pub use self::styled::StyledString;
+#[cfg(feature = "chrono")]
+pub use self::timestamp::TimestampOptions;
+use self::undo::{ViewHistory, ViewState};
+
+/// A node that has been activated by the user via `JsonViewer::toggle_active_element`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ActivatedNode<'a> {
+    /// A scalar value, e.g., a string or a number.
+    Scalar(&'a str),
+    /// A scalar value that is a URL (`http://` or `https://`, see `JsonViewerWidget::link`).
+    Link(&'a str),
+    /// The key of an object member whose value is a scalar (see `SelectionMode::AllNodes`).
+    Key(&'a Key),
+}
+
+/// What `JsonViewer::toggle_active_element` did.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum ToggleResult {
+    /// An object or array (or the hexdump of binary data) was unfolded.
+    Expanded,
+    /// An object or array (or the hexdump of binary data) was folded.
+    Collapsed,
+    /// One more element of an array is shown, for a total of the specified number.
+    Grew(usize),
+    /// One element less of an array is shown, for a total of the specified number.
+    Shrank(usize),
+    /// A node hidden by `JsonViewer::hide_path` (or below the maximum render depth) was revealed.
+    Revealed,
+    /// The callback registered with `JsonViewer::on_activate` was invoked for the node at the
+    /// specified path.
+    ActivatedScalar(JsonPath),
+}
+
+type ActivationCallback = Box<dyn FnMut(&JsonPath, &ActivatedNode) + Send>;
+type CopyCallback = Box<dyn FnMut(&str) + Send>;
+type Clock = Box<dyn Fn() -> SystemTime + Send>;
+
 /// A widget for viewing `json` data.
 ///
 /// Set an initial value during construction (via `new`) and replace it either using `update` or `reset`.
@@ -124,9 +387,186 @@ use self::path::*;
 /// Call `toggle_active_element` to interact with the currently active element.
 /// Interacting with an element might hide it, but the widget takes care to select another element
 /// in that case.
+///
+/// Optionally, the keys of object members can be made selectable as well (see
+/// `set_selection_mode`).
+///
+/// `JsonViewer`, `UpdateBase` and `PreparedUpdate` are `Send`, i.e., viewers can be built and
+/// updates can be prepared off the render thread.
 pub struct JsonViewer {
-    value: DisplayValue,
+    value: Arc<DisplayValue>,
     active_element: Path,
+    selection_mode: SelectionMode,
+    selection: Option<Selection>,
+    view_mode: ViewMode,
+    on_activate: Option<ActivationCallback>,
+    on_copy: Option<CopyCallback>,
+    errors: BTreeMap<JsonPath, String>,
+    duplicates: BTreeSet<JsonPath>,
+    annotations: BTreeMap<JsonPath, Annotation>,
+    failure: Option<String>,
+    pending: Option<String>,
+    // Whether `poll` has applied a new document since the last `take_new_data`.
+    new_data: bool,
+    // Whether `Blink::On` was passed to the last draw and the current frame of the spinner.
+    spinner: Cell<(bool, usize)>,
+    follow_tail: bool,
+    duplicate_keys: DuplicateKeys,
+    array_identity: Option<Arc<ArrayIdentity>>,
+    sequence_diff: bool,
+    numeric_deltas: bool,
+    history_limit: usize,
+    // When each node was last changed by an update, if tracked.
+    change_times: Option<BTreeMap<JsonPath, SystemTime>>,
+    clock: Option<Clock>,
+    // The value frozen by `snapshot`.
+    snapshot: Option<DisplayValue>,
+    view_history: ViewHistory,
+    focus_follows_update: bool,
+    // The active path before a change of the value moved the selection, until it is taken.
+    displaced_selection: Option<JsonPath>,
+    // The result of the last `space_demand` (and the layout it was computed for). Cleared whenever
+    // the value or the selection changes.
+    demand: Cell<Option<(LayoutParams, Demand2D)>>,
+    // Shared storage for the keys of all objects of `value`.
+    keys: KeyPool,
+    // Incremented whenever `value` (or the settings of the content passes) are modified, to detect
+    // stale `PreparedUpdate`s.
+    revisions: Revisions,
+    search: Option<Search>,
+    highlight: Option<Pattern>,
+    pinned: Vec<JsonPath>,
+    hidden: BTreeSet<JsonPath>,
+    max_render_depth: Option<usize>,
+    // Objects and arrays below which the render depth is counted from zero again.
+    descended: BTreeSet<JsonPath>,
+    // Objects and arrays below the maximum render depth (relative to `descended`).
+    cut: BTreeSet<JsonPath>,
+    // The nodes drawn by a custom renderer so far. Interaction points within them are skipped
+    // when moving the selection. Cleared whenever the content of `value` changes.
+    overridden: RefCell<BTreeSet<JsonPath>>,
+    redaction: Option<Arc<Redaction>>,
+    redaction_enabled: bool,
+    interactive: bool,
+    binary_detection: bool,
+    #[cfg(feature = "chrono")]
+    timestamps: Option<TimestampOptions>,
+    #[cfg(feature = "chrono")]
+    humanize_timestamps: bool,
+}
+
+/// Counters of the modifications of a `JsonViewer` that `apply_prepared` has to consider.
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug)]
+struct Revisions {
+    /// Modifications of the content of the value and of the state of nodes other than their folds.
+    content: u64,
+    /// Modifications of the fold state of the value and of the selection.
+    view: u64,
+    /// Modifications of the settings of the `ContentPasses`.
+    passes: u64,
+}
+
+/// The passes over the value that follow a change of its content, with the settings of a viewer.
+#[derive(Clone)]
+struct ContentPasses {
+    redaction: Option<Arc<Redaction>>,
+    binary_detection: bool,
+    #[cfg(feature = "chrono")]
+    timestamps: Option<TimestampOptions>,
+    search: Option<Search>,
+}
+
+impl ContentPasses {
+    /// Run the passes over `value`, including finding the matches of the search.
+    fn run(&mut self, value: &mut DisplayValue) {
+        self.run_at(value, &JsonPath::root());
+    }
+
+    /// Like `run`, but only over the subtree at `location`.
+    fn run_at(&mut self, value: &mut DisplayValue, location: &JsonPath) {
+        if let Some(node) = value.get_mut(location) {
+            node.redact(location, self.redaction.as_deref());
+            node.detect_binary(self.binary_detection);
+            #[cfg(feature = "chrono")]
+            node.humanize_timestamps(self.timestamps.as_ref());
+        }
+        if let Some(search) = &mut self.search {
+            search.refresh_at(value, location);
+        }
+    }
+}
+
+/// The displayed value of a `JsonViewer` (see `JsonViewer::update_base`) that updates can be
+/// computed against without access to the viewer, e.g., on another thread.
+#[derive(Clone)]
+pub struct UpdateBase {
+    value: Arc<DisplayValue>,
+    keys: KeyPool,
+    array_identity: Option<Arc<ArrayIdentity>>,
+    sequence_diff: bool,
+    numeric_deltas: bool,
+    history_limit: usize,
+    duplicate_keys: DuplicateKeys,
+    passes: ContentPasses,
+    revisions: Revisions,
+}
+
+impl UpdateBase {
+    /// Compute the result of `JsonViewer::update` with `value` without modifying the viewer.
+    /// Apply it using `JsonViewer::apply_prepared`.
+    pub fn prepare_update(&self, value: impl Value) -> PreparedUpdate {
+        PreparedUpdate::new(
+            &self.value,
+            &self.keys,
+            UpdateOptions {
+                array_identity: self.array_identity.as_deref(),
+                sequence_diff: self.sequence_diff,
+                numeric_deltas: self.numeric_deltas,
+                history_limit: self.history_limit,
+            },
+            self.duplicate_keys,
+            self.passes.clone(),
+            self.revisions,
+            value,
+        )
+    }
+}
+
+/// The result of an update that has been computed in advance (see `JsonViewer::prepare_update`).
+pub struct PreparedUpdate {
+    value: DisplayValue,
+    keys: KeyPool,
+    // The fold state of the value the update was computed against.
+    base_folds: BTreeMap<JsonPath, Fold>,
+    // The search whose matches were found in `value`.
+    search: Option<Search>,
+    revisions: Revisions,
+}
+
+impl PreparedUpdate {
+    fn new(
+        base: &DisplayValue,
+        keys: &KeyPool,
+        options: UpdateOptions,
+        duplicate_keys: DuplicateKeys,
+        mut passes: ContentPasses,
+        revisions: Revisions,
+        value: impl Value,
+    ) -> Self {
+        let keys = keys.clone();
+        let mut value = base.update_with(value, options, &mut JsonPath::root(), &keys);
+        value.resolve_duplicate_keys(duplicate_keys);
+        passes.run(&mut value);
+        let mut base_folds = BTreeMap::new();
+        base.folds(&mut JsonPath::root(), &mut base_folds);
+        PreparedUpdate {
+            value,
+            keys,
+            base_folds,
+            search: passes.search,
+            revisions,
+        }
+    }
 }
 
 impl JsonViewer {
@@ -135,9 +575,53 @@ impl JsonViewer {
     /// It follows that it is impossible to not have content. However, it *is* possible to show an
     /// empty String, so there is that.
     pub fn new(value: impl Value) -> Self {
+        let keys = KeyPool::new();
         let mut res = JsonViewer {
-            value: DisplayValue::new(value),
+            value: DisplayValue::new_with_keys(value, &keys).into(),
             active_element: Path::Scalar, //Will be fixed ...
+            selection_mode: SelectionMode::InteractionPoints,
+            selection: None,
+            view_mode: ViewMode::Tree,
+            on_activate: None,
+            on_copy: None,
+            errors: BTreeMap::new(),
+            duplicates: BTreeSet::new(),
+            annotations: BTreeMap::new(),
+            failure: None,
+            pending: None,
+            new_data: false,
+            spinner: Cell::new((true, 0)),
+            follow_tail: false,
+            focus_follows_update: false,
+            displaced_selection: None,
+            duplicate_keys: DuplicateKeys::All,
+            array_identity: None,
+            sequence_diff: false,
+            numeric_deltas: false,
+            history_limit: 0,
+            change_times: None,
+            clock: None,
+            snapshot: None,
+            view_history: ViewHistory::default(),
+            demand: Cell::new(None),
+            keys,
+            revisions: Revisions::default(),
+            search: None,
+            highlight: None,
+            pinned: Vec::new(),
+            hidden: BTreeSet::new(),
+            max_render_depth: None,
+            descended: BTreeSet::new(),
+            cut: BTreeSet::new(),
+            overridden: RefCell::new(BTreeSet::new()),
+            redaction: None,
+            redaction_enabled: true,
+            interactive: true,
+            binary_detection: false,
+            #[cfg(feature = "chrono")]
+            timestamps: None,
+            #[cfg(feature = "chrono")]
+            humanize_timestamps: false,
         };
         res.fix_active_element_path(); //... here!
         res
@@ -145,137 +629,5165 @@ impl JsonViewer {
 
     /// Set a new value to display and do not highlight any changes (in contrast to `update`).
     pub fn reset(&mut self, value: impl Value) {
-        self.value = DisplayValue::new(value);
-        self.fix_active_element_path();
+        self.value = DisplayValue::new_with_keys(value, &self.keys).into();
+        Arc::make_mut(&mut self.value).resolve_duplicate_keys(self.duplicate_keys);
+        self.keys.prune();
+        self.failure = None;
+        self.pending = None;
+        if let Some(times) = &mut self.change_times {
+            times.clear();
+        }
+        self.content_modified();
     }
 
     /// Set a new value to display and highlight changes from the previous value (which will be
     /// shown until the next `update` or `reset`.
     pub fn update(&mut self, value: impl Value) {
-        self.value = self.value.update(value);
-        self.fix_active_element_path();
+        let mut value = self.value.update_with(
+            value,
+            self.update_options(),
+            &mut JsonPath::root(),
+            &self.keys,
+        );
+        value.resolve_duplicate_keys(self.duplicate_keys);
+        self.value = Arc::new(value);
+        self.keys.prune();
+        self.failure = None;
+        self.pending = None;
+        self.record_change_times(&JsonPath::root());
+        self.content_modified();
+        self.focus_first_change();
     }
 
-    /// Select the next interaction point of the widget (generally "down" from the current one).
-    pub fn select_next(&mut self) -> Result<(), ()> {
-        if let Some(new_path) = self.active_element.clone().find_next_path(&self.value) {
-            self.active_element = new_path;
-            Ok(())
-        } else {
-            Err(())
+    /// Compute the result of `update` with `value` without modifying the viewer. Apply it later
+    /// using `apply_prepared`.
+    ///
+    /// To keep the UI responsive during huge updates, compute the update on another thread using
+    /// `update_base` instead.
+    pub fn prepare_update(&self, value: impl Value) -> PreparedUpdate {
+        PreparedUpdate::new(
+            &self.value,
+            &self.keys,
+            self.update_options(),
+            self.duplicate_keys,
+            self.content_passes(),
+            self.revisions,
+            value,
+        )
+    }
+
+    /// The displayed value and the update settings to compute updates against using
+    /// `UpdateBase::prepare_update`. The value is shared with the viewer rather than copied; it is
+    /// only copied if the viewer modifies it (e.g., by folding a node) while the base is alive.
+    pub fn update_base(&self) -> UpdateBase {
+        UpdateBase {
+            value: self.value.clone(),
+            keys: self.keys.clone(),
+            array_identity: self.array_identity.clone(),
+            sequence_diff: self.sequence_diff,
+            numeric_deltas: self.numeric_deltas,
+            history_limit: self.history_limit,
+            duplicate_keys: self.duplicate_keys,
+            passes: self.content_passes(),
+            revisions: self.revisions,
         }
     }
 
-    /// Select the previous interaction point of the widget (generally "up" from the current one).
-    pub fn select_previous(&mut self) -> Result<(), ()> {
-        if let Some(new_path) = self.active_element.clone().find_previous_path(&self.value) {
-            self.active_element = new_path;
-            Ok(())
+    /// Show the result of an update computed using `prepare_update` (or
+    /// `UpdateBase::prepare_update`), as if `update` had been called.
+    ///
+    /// Nodes folded or unfolded since the update was prepared keep their new state and the
+    /// selection is kept as well. Fails without any effect if the displayed content has been
+    /// modified (e.g., by another update or by changing the number base of a node) since.
+    pub fn apply_prepared(&mut self, update: PreparedUpdate) -> Result<(), ()> {
+        if update.revisions.content != self.revisions.content {
+            return Err(());
+        }
+        let PreparedUpdate {
+            mut value,
+            keys,
+            base_folds,
+            search,
+            revisions,
+        } = update;
+        if revisions.view != self.revisions.view {
+            let mut folds = BTreeMap::new();
+            self.value.folds(&mut JsonPath::root(), &mut folds);
+            folds.retain(|path, fold| base_folds.get(path) != Some(fold));
+            value.restore_folds(&mut JsonPath::root(), &folds);
+        }
+        self.value = Arc::new(value);
+        self.keys = keys;
+        self.keys.prune();
+        self.failure = None;
+        self.pending = None;
+        self.record_change_times(&JsonPath::root());
+        if revisions.passes == self.revisions.passes {
+            self.content_replaced(search);
         } else {
-            Err(())
+            self.content_modified();
         }
+        self.focus_first_change();
+        Ok(())
     }
 
-    fn fix_active_element_path(&mut self) {
-        let mut tmp = Path::Scalar;
-        ::std::mem::swap(&mut self.active_element, &mut tmp);
-        self.active_element = tmp.fix_path_for_value(&self.value)
+    /// Take all documents that are available from `source` without blocking. The last one is shown
+    /// as if passed to `update`, a failure received after it as if passed to `set_error`.
+    ///
+    /// Returns whether anything was received.
+    pub fn poll<S: DocumentSource>(&mut self, source: &mut S) -> bool {
+        let mut document = None;
+        let mut failure = None;
+        let mut received = false;
+        while let Some(result) = source.poll() {
+            received = true;
+            match result {
+                Ok(value) => {
+                    document = Some(value);
+                    failure = None;
+                }
+                Err(message) => failure = Some(message),
+            }
+        }
+        if let Some(value) = document {
+            self.update(value);
+            self.new_data = true;
+        }
+        if let Some(message) = failure {
+            self.set_error(&message);
+        }
+        received
     }
 
-    /// Interact with the currently active interaction point and, for example, fold/unfold
-    /// structures.
-    pub fn toggle_active_element(&mut self) -> Result<(), ()> {
-        let res = self.active_element.find_and_act_on_element(&mut self.value);
-        self.fix_active_element_path();
-        res
+    /// Whether `poll` has shown a new document since the last call to `take_new_data`, e.g., to
+    /// indicate new data in a live view.
+    pub fn has_new_data(&self) -> bool {
+        self.new_data
     }
 
-    pub fn as_widget<'a>(&'a self) -> JsonViewerWidget<'a> {
-        JsonViewerWidget {
-            inner: self,
-            indentation: Width::new(2).unwrap(),
-            active_focused_style: StyleModifier::new()
-                .invert(BoolModifyMode::Toggle)
-                .bold(true),
-            inactive_focused_style: StyleModifier::new().bold(true),
-            item_changed_style: StyleModifier::new().bg_color(Color::Red),
+    /// Like `has_new_data`, but reset the indicator.
+    pub fn take_new_data(&mut self) -> bool {
+        std::mem::replace(&mut self.new_data, false)
+    }
+
+    /// Like `update`, but only replace the node at `path` with `value`. The rest of the displayed
+    /// value is left untouched, including the highlighting of previous changes.
+    pub fn update_at(&mut self, path: &JsonPath, value: impl Value) -> Result<(), ()> {
+        let mut new_node = self.value.get(path).ok_or(())?.update_with(
+            value,
+            self.update_options(),
+            &mut path.clone(),
+            &self.keys,
+        );
+        new_node.resolve_duplicate_keys(self.duplicate_keys);
+        *Arc::make_mut(&mut self.value).get_mut(path).ok_or(())? = new_node;
+        self.record_change_times(path);
+        let mut passes = self.content_passes();
+        passes.run_at(Arc::make_mut(&mut self.value), path);
+        self.content_replaced(passes.search);
+        Ok(())
+    }
+
+    /// Append `values` to the array at `path` without rebuilding the rest of the displayed value
+    /// (in contrast to `update`), e.g., to show a growing log. Only the length indicator of the
+    /// array is highlighted.
+    pub fn append_array_elements<V: Value>(
+        &mut self,
+        path: &JsonPath,
+        values: impl IntoIterator<Item = V>,
+    ) -> Result<(), ()> {
+        let array = match Arc::make_mut(&mut self.value).get_mut(path) {
+            Some(DisplayValue::Array(array)) => array,
+            _ => return Err(()),
+        };
+        let old_len = array.values.len();
+        array.append(values, self.follow_tail, &self.keys);
+        let new_len = array.values.len();
+        for value in &mut array.values[old_len..] {
+            value.resolve_duplicate_keys(self.duplicate_keys);
+        }
+        self.content_modified();
+        if self.follow_tail && new_len > old_len {
+            let tail = if old_len > 0 {
+                path.clone().index(old_len - 1)
+            } else {
+                path.clone()
+            };
+            let active = self.active_element.to_json_path();
+            if active.segments().starts_with(tail.segments()) {
+                return self.select_path(&path.clone().index(new_len - 1));
+            }
         }
+        Ok(())
     }
-}
 
-pub struct JsonViewerWidget<'a> {
-    inner: &'a JsonViewer,
-    indentation: Width,
-    active_focused_style: StyleModifier,
-    inactive_focused_style: StyleModifier,
-    item_changed_style: StyleModifier,
-}
+    /// Keep the end of arrays in view when elements are added using `append_array_elements`:
+    /// If all elements of an array were shown, the new elements are shown as well. If the last
+    /// element was selected, the selection moves to the new last element.
+    pub fn set_follow_tail(&mut self, follow: bool) {
+        self.follow_tail = follow;
+    }
 
-impl<'a> JsonViewerWidget<'a> {
-    pub fn indentation(mut self, w: Width) -> Self {
-        self.indentation = w;
-        self
+    /// Select the first changed node after each `update` (unfolding containers as necessary to
+    /// show it), instead of keeping the previously active element. If nothing changed, the
+    /// selection stays put. Changes within hidden nodes are ignored.
+    pub fn set_focus_follows_update(&mut self, follow: bool) {
+        self.focus_follows_update = follow;
     }
-    pub fn active_focused(mut self, style: StyleModifier) -> Self {
-        self.active_focused_style = style;
-        self
+
+    fn focus_first_change(&mut self) {
+        if !self.focus_follows_update {
+            return;
+        }
+        if let Some(path) = self.changes().into_iter().next() {
+            let _ = self.select_path(&path);
+        }
     }
-    pub fn inactive_focused(mut self, style: StyleModifier) -> Self {
-        self.inactive_focused_style = style;
-        self
+
+    /// The paths of all nodes highlighted as changed (except within hidden nodes) in the order
+    /// they are drawn.
+    fn changes(&self) -> Vec<JsonPath> {
+        let mut changes = Vec::new();
+        self.value
+            .changed_paths(&mut JsonPath::root(), &mut changes);
+        let hidden = &self.hidden;
+        changes.retain(|path| {
+            !hidden
+                .iter()
+                .any(|hidden| path.segments().starts_with(hidden.segments()))
+        });
+        changes
     }
-    pub fn item_changed(mut self, style: StyleModifier) -> Self {
-        self.item_changed_style = style;
-        self
+
+    /// Select the next node highlighted as changed by the last `update`, wrapping around at the
+    /// end, e.g., to review a diff. Fails if nothing changed.
+    pub fn next_change(&mut self) -> Result<(), ()> {
+        let changes = self.changes();
+        let next = match self.current_change_in(&changes) {
+            Some(i) if i + 1 < changes.len() => i + 1,
+            _ => 0,
+        };
+        self.select_path(changes.get(next).ok_or(())?)
     }
-}
 
-impl<'a> Widget for JsonViewerWidget<'a> {
-    fn space_demand(&self) -> Demand2D {
-        let mut window = ExtentEstimationWindow::unbounded();
-        //TODO: We may want to consider passing hints to space_demand as well for an accurate estimate
-        {
-            let mut cursor = Cursor::<ExtentEstimationWindow>::new(&mut window);
-            let info = RenderingInfo {
-                hints: RenderingHints::default(),
-                active_focused_style: self.active_focused_style,
-                inactive_focused_style: self.inactive_focused_style,
-                item_changed_style: self.item_changed_style,
-            };
-            self.inner.value.draw(
-                &mut cursor,
-                Some(&self.inner.active_element),
-                &info,
-                self.indentation,
-            );
+    /// Select the previous node highlighted as changed by the last `update`, wrapping around at
+    /// the beginning. Fails if nothing changed.
+    pub fn previous_change(&mut self) -> Result<(), ()> {
+        let changes = self.changes();
+        let previous = match self.current_change_in(&changes) {
+            Some(i) if i > 0 => i - 1,
+            _ => changes.len().saturating_sub(1),
+        };
+        self.select_path(changes.get(previous).ok_or(())?)
+    }
+
+    /// The number of nodes highlighted as changed (except within hidden nodes).
+    pub fn change_count(&self) -> usize {
+        self.changes().len()
+    }
+
+    /// The (0-based) index of the selected node among all changed nodes if it is one of them,
+    /// e.g., to show "n of m".
+    pub fn current_change(&self) -> Option<usize> {
+        self.current_change_in(&self.changes())
+    }
+
+    /// The number of changed nodes after the selected one, or all of them if the selected node
+    /// is not a changed one.
+    pub fn remaining_changes(&self) -> usize {
+        let changes = self.changes();
+        match self.current_change_in(&changes) {
+            Some(i) => changes.len() - i - 1,
+            None => changes.len(),
         }
-        Demand2D {
-            width: Demand::at_least(window.extent_x()),
-            height: Demand::exact(window.extent_y()),
+    }
+
+    fn current_change_in(&self, changes: &[JsonPath]) -> Option<usize> {
+        let active = self.active_path();
+        changes.iter().position(|path| *path == active)
+    }
+
+    /// Apply a JSON Patch to the displayed value and highlight the nodes affected by it (instead of
+    /// all changes since the last `update`).
+    ///
+    /// If any of the operations fails (e.g., because its path does not exist), the displayed value
+    /// is not modified at all.
+    pub fn apply_patch<V: Value>(&mut self, ops: &[PatchOp<V>]) -> Result<(), ()> {
+        self.value = patch::apply_patch(&self.value, ops, &self.keys)?.into();
+        Arc::make_mut(&mut self.value).resolve_duplicate_keys(self.duplicate_keys);
+        self.record_change_times(&JsonPath::root());
+        self.content_modified();
+        Ok(())
+    }
+
+    /// Compute a JSON Patch that transforms the displayed value into `other`.
+    pub fn diff_to_patch<V: Value>(&self, other: V) -> Vec<PatchOp<V>> {
+        patch::diff_to_patch(&self.value, other)
+    }
+
+    /// Freeze the displayed value as a baseline to compare later values to using
+    /// `compare_to_snapshot`, replacing any previous snapshot.
+    pub fn snapshot(&mut self) {
+        self.snapshot = Some((*self.value).clone());
+    }
+
+    /// Highlight everything that differs from the value frozen by `snapshot`, instead of the
+    /// changes of the last `update`. The next `update` highlights its own changes again. Fails if
+    /// there is no snapshot.
+    pub fn compare_to_snapshot(&mut self) -> Result<(), ()> {
+        let snapshot = self.snapshot.as_ref().ok_or(())?;
+        Arc::make_mut(&mut self.value).mark_differences(snapshot);
+        self.node_state_modified();
+        self.focus_first_change();
+        Ok(())
+    }
+
+    /// Drop the value frozen by `snapshot`.
+    pub fn discard_snapshot(&mut self) {
+        self.snapshot = None;
+    }
+
+    /// Choose which members of objects with duplicate keys are shown. By default, all members are
+    /// shown (`DuplicateKeys::All`).
+    ///
+    /// Members that are hidden from the current value cannot be restored by switching back to
+    /// `DuplicateKeys::All`, only values set afterwards (e.g., via `update`) are affected.
+    pub fn set_duplicate_keys(&mut self, policy: DuplicateKeys) {
+        self.duplicate_keys = policy;
+        Arc::make_mut(&mut self.value).resolve_duplicate_keys(policy);
+        self.content_modified();
+    }
+
+    /// Match the elements of arrays by an identity (e.g., an `"id"` member) instead of their index
+    /// in `update` and `update_at`. This way, reordering the elements of an array does not
+    /// highlight them as changed and their fold state moves along with them.
+    ///
+    /// `identity` is called with the path of the array and one of its elements. Elements without
+    /// an identity are matched by index with old elements that do not have an identity either.
+    ///
+    /// `identity` has to be `Send + Sync` so that updates can be prepared on other threads (see
+    /// `update_base`).
+    pub fn set_array_identity(
+        &mut self,
+        identity: impl Fn(&JsonPath, Node) -> Option<String> + Send + Sync + 'static,
+    ) {
+        self.array_identity = Some(Arc::new(identity));
+    }
+
+    /// Compare arrays in `update` and `update_at` using a sequence diff instead of comparing
+    /// elements with the same index. This way, inserting or removing an element only highlights
+    /// the inserted element instead of all following ones. Disabled by default.
+    ///
+    /// The diff takes time proportional to the product of the array lengths in the worst case.
+    /// An identity set using `set_array_identity` takes precedence.
+    pub fn set_sequence_diff(&mut self, enabled: bool) {
+        self.sequence_diff = enabled;
+    }
+
+    /// Show how much numbers changed by an `update` next to them, e.g., `42 (+5)`, until the next
+    /// update. Disabling this hides the differences shown currently.
+    pub fn set_numeric_deltas(&mut self, enabled: bool) {
+        self.numeric_deltas = enabled;
+        if !enabled && Arc::make_mut(&mut self.value).clear_deltas() {
+            self.node_state_modified();
         }
     }
-    fn draw(&self, mut window: Window, hints: RenderingHints) {
-        let mut cursor = Cursor::new(&mut window);
-        let info = RenderingInfo {
-            hints,
-            active_focused_style: self.active_focused_style,
-            inactive_focused_style: self.inactive_focused_style,
-            item_changed_style: self.item_changed_style,
+
+    /// Keep up to `limit` previous values of each scalar that is changed by an `update` (none by
+    /// default). They can be shown below the value using `toggle_history`. Lowering the limit
+    /// drops the oldest values kept so far.
+    pub fn set_history_limit(&mut self, limit: usize) {
+        self.history_limit = limit;
+        if Arc::make_mut(&mut self.value).limit_history(limit) {
+            self.node_state_modified();
+        }
+    }
+
+    /// Show or hide the previous values of the scalar at `path` (e.g., `was: 3, 5, 8`) below it
+    /// and return whether they are shown now. Fails if there is no scalar with previous values at
+    /// `path`.
+    pub fn toggle_history(&mut self, path: &JsonPath) -> Result<bool, ()> {
+        match self.value.get(path) {
+            Some(DisplayValue::Scalar(scalar)) if !scalar.history.is_empty() => {}
+            _ => return Err(()),
+        }
+        let expanded = match Arc::make_mut(&mut self.value).get_mut(path) {
+            Some(DisplayValue::Scalar(scalar)) => {
+                scalar.history_expanded = !scalar.history_expanded;
+                scalar.history_expanded
+            }
+            _ => return Err(()),
         };
-        self.inner.value.draw(
-            &mut cursor,
-            Some(&self.inner.active_element),
-            &info,
-            self.indentation,
-        );
+        self.node_state_modified();
+        Ok(expanded)
     }
-}
 
-impl Scrollable for JsonViewer {
-    fn scroll_forwards(&mut self) -> OperationResult {
-        self.select_next()
+    /// `toggle_history` for the active scalar.
+    pub fn toggle_active_history(&mut self) -> Result<bool, ()> {
+        let path = self.active_path();
+        self.toggle_history(&path)
     }
-    fn scroll_backwards(&mut self) -> OperationResult {
-        self.select_previous()
+
+    /// Record when each node was last changed by an update (off by default), e.g., to see which
+    /// parts of a live view are stale. The time of the active node is shown in the status line.
+    /// Disabling it forgets all recorded times.
+    pub fn set_track_change_times(&mut self, enabled: bool) {
+        if enabled != self.change_times.is_some() {
+            self.change_times = if enabled { Some(BTreeMap::new()) } else { None };
+        }
+    }
+
+    /// Replace the source of the current time used by `set_track_change_times` (which is
+    /// `SystemTime::now` by default), e.g., for tests.
+    pub fn set_clock(&mut self, clock: impl Fn() -> SystemTime + Send + 'static) {
+        self.clock = Some(Box::new(clock));
+    }
+
+    /// When the node at `path` was last changed by an update while change times were tracked.
+    pub fn last_changed(&self, path: &JsonPath) -> Option<SystemTime> {
+        self.change_times.as_ref()?.get(path).cloned()
+    }
+
+    fn now(&self) -> SystemTime {
+        self.clock
+            .as_ref()
+            .map_or_else(SystemTime::now, |clock| clock())
+    }
+
+    // Set the change time of all nodes at or below `path` that are highlighted as changed to now
+    // and forget those of removed nodes.
+    fn record_change_times(&mut self, path: &JsonPath) {
+        if self.change_times.is_none() {
+            return;
+        }
+        let mut changes = Vec::new();
+        if let Some(node) = self.value.get(path) {
+            node.changed_paths(&mut path.clone(), &mut changes);
+        }
+        let now = self.now();
+        let value = &self.value;
+        let times = self.change_times.as_mut().expect("checked above");
+        // Only nodes below `path` may have been removed, and their paths follow it in the map.
+        let removed: Vec<_> = times
+            .range(path.clone()..)
+            .map(|(time_path, _)| time_path)
+            .take_while(|time_path| time_path.segments().starts_with(path.segments()))
+            .filter(|time_path| value.get(time_path).is_none())
+            .cloned()
+            .collect();
+        for path in removed {
+            times.remove(&path);
+        }
+        for path in changes {
+            times.insert(path, now);
+        }
+    }
+
+    fn update_options(&self) -> UpdateOptions<'_> {
+        UpdateOptions {
+            array_identity: self.array_identity.as_deref(),
+            sequence_diff: self.sequence_diff,
+            numeric_deltas: self.numeric_deltas,
+            history_limit: self.history_limit,
+        }
+    }
+
+    /// Disable (`false`) or enable (`true`, the default) interaction with the displayed value,
+    /// e.g., to embed it as a passive display pane. A non-interactive viewer does not highlight
+    /// the active element, and toggling, folding, growing and shrinking it fails without any
+    /// effect. Setting the fold state programmatically (e.g., `set_expanded`) still works.
+    pub fn set_interactive(&mut self, interactive: bool) {
+        self.interactive = interactive;
+    }
+
+    pub fn interactive(&self) -> bool {
+        self.interactive
+    }
+
+    /// Choose which nodes can be selected using `select_next` and `select_previous`.
+    ///
+    /// In `SelectionMode::AllNodes` the keys of object members are selectable, too. Toggling a
+    /// key folds or unfolds the member's value if it is an object or array.
+    pub fn set_selection_mode(&mut self, mode: SelectionMode) {
+        self.selection_mode = mode;
+        self.fix_active_element_path();
+    }
+
+    /// Show the value as a tree or as flat `path = value` lines. The fold state is shared by both
+    /// modes: Folding an object or array (e.g., the one containing the active scalar using
+    /// `Navigatable::move_left`) collapses all lines with its path as a prefix into one.
+    pub fn set_view_mode(&mut self, mode: ViewMode) {
+        self.view_mode = mode;
+        self.fix_active_element_path();
+    }
+
+    pub fn view_mode(&self) -> ViewMode {
+        self.view_mode
+    }
+
+    /// Switch between `ViewMode::Tree` and `ViewMode::Flat`.
+    pub fn toggle_view_mode(&mut self) {
+        self.set_view_mode(match self.view_mode {
+            ViewMode::Tree => ViewMode::Flat,
+            ViewMode::Flat => ViewMode::Tree,
+        });
+    }
+
+    /// The next (or previous) selectable path after `path`, skipping interaction points that are
+    /// not shown as a line in `ViewMode::Flat`.
+    fn find_path(&self, mut path: Path, forwards: bool) -> Option<Path> {
+        loop {
+            path = if forwards {
+                path.find_next_path(&self.value, self.selection_mode)?
+            } else {
+                path.find_previous_path(&self.value, self.selection_mode)?
+            };
+            if self.view_mode == ViewMode::Flat && !flat::is_line(&path, &self.value) {
+                continue;
+            }
+            if !self.is_overridden(&path) {
+                return Some(path);
+            }
+        }
+    }
+
+    /// Whether `path` is an interaction point within a node drawn by a custom renderer. The main
+    /// interaction point of such a node stands in for the whole node.
+    fn is_overridden(&self, path: &Path) -> bool {
+        let overridden = self.overridden.borrow();
+        if overridden.is_empty() {
+            return false;
+        }
+        let location = path.to_json_path();
+        let mut ancestor = Some(location.clone());
+        while let Some(current) = ancestor {
+            if overridden.contains(&current) {
+                return current != location || !path.ends_at_main_point();
+            }
+            ancestor = current.parent();
+        }
+        false
+    }
+
+    /// `path` or, if it is not shown as a line in `ViewMode::Flat`, the closest path that is.
+    fn shown_path_near(&self, path: Path) -> Path {
+        if self.view_mode == ViewMode::Tree || flat::is_line(&path, &self.value) {
+            return path;
+        }
+        self.find_path(path.clone(), true)
+            .or_else(|| self.find_path(path.clone(), false))
+            .unwrap_or(path)
+    }
+
+    /// Select the next interaction point of the widget (generally "down" from the current one).
+    pub fn select_next(&mut self) -> Result<(), ()> {
+        if let Some(new_path) = self.find_path(self.active_element.clone(), true) {
+            self.active_element = new_path;
+            self.invalidate_demand();
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Extend the selection to the next sibling of the active node (which becomes active), or
+    /// shrink it if it extends above the active node. Fails if there is no next sibling.
+    pub fn extend_selection_next(&mut self) -> Result<(), ()> {
+        self.extend_selection(true)
+    }
+
+    /// Extend the selection to the previous sibling of the active node (which becomes active), or
+    /// shrink it if it extends below the active node. Fails if there is no previous sibling.
+    pub fn extend_selection_previous(&mut self) -> Result<(), ()> {
+        self.extend_selection(false)
+    }
+
+    fn extend_selection(&mut self, forward: bool) -> Result<(), ()> {
+        let active = self.active_path();
+        let parent = active.parent().ok_or(())?;
+        let siblings = self
+            .node(&parent)
+            .ok_or(())?
+            .children()
+            .map(|(segment, _)| segment)
+            .collect::<Vec<_>>();
+        let current = siblings
+            .iter()
+            .position(|s| active.segments().last() == Some(s))
+            .ok_or(())?;
+        let next = if forward {
+            current + 1
+        } else {
+            current.checked_sub(1).ok_or(())?
+        };
+        let mut end = parent.clone();
+        end.push(siblings.get(next).ok_or(())?.clone());
+        let anchor = match self.selection() {
+            Some(selection) => selection.anchor().clone(),
+            None => active,
+        };
+        self.select_path(&end)?;
+        self.selection = self
+            .node(&parent)
+            .and_then(|parent| Selection::between(parent, anchor, self.active_path()));
+        Ok(())
+    }
+
+    /// The range of siblings selected using `extend_selection_next` or
+    /// `extend_selection_previous`. The range is dropped as soon as another node is selected or
+    /// the value is updated.
+    pub fn selection(&self) -> Option<&Selection> {
+        self.selection
+            .as_ref()
+            .filter(|selection| *selection.end() == self.active_path())
+    }
+
+    /// Drop the range of selected siblings, leaving only the active node selected.
+    pub fn clear_selection(&mut self) {
+        self.selection = None;
+    }
+
+    /// Select the previous interaction point of the widget (generally "up" from the current one).
+    pub fn select_previous(&mut self) -> Result<(), ()> {
+        if let Some(new_path) = self.find_path(self.active_element.clone(), false) {
+            self.active_element = new_path;
+            self.invalidate_demand();
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Select `path` (which has to be valid for the current value). Fails if it is selected
+    /// already.
+    fn select_element(&mut self, path: Path) -> Result<(), ()> {
+        let path = self.shown_path_near(path);
+        if path == self.active_element {
+            return Err(());
+        }
+        self.active_element = path;
+        self.invalidate_demand();
+        Ok(())
+    }
+
+    /// Count the render depth from all objects and arrays on the way to `path` (and the one at
+    /// `path`) so that it is not cut off by the maximum render depth.
+    fn descend_to(&mut self, path: &JsonPath) {
+        if self.max_render_depth.is_none() {
+            return;
+        }
+        let mut prefix = Some(path.clone());
+        while let Some(current) = prefix {
+            prefix = current.parent();
+            self.descended.insert(current);
+        }
+    }
+
+    fn invalidate_demand(&mut self) {
+        *self.demand.get_mut() = None;
+    }
+
+    fn value_modified(&mut self) {
+        self.revisions.view += 1;
+        self.limit_render_depth();
+        self.fix_active_element_path();
+    }
+
+    // Like `value_modified`, for changes of the state of nodes that `apply_prepared` does not
+    // carry over (e.g., their number base).
+    fn node_state_modified(&mut self) {
+        self.revisions.content += 1;
+        self.value_modified();
+    }
+
+    // Like `value_modified`, for changes of the content (rather than the fold state) of `value`.
+    fn content_modified(&mut self) {
+        let mut passes = self.content_passes();
+        passes.run(Arc::make_mut(&mut self.value));
+        self.content_replaced(passes.search);
+    }
+
+    // Like `content_modified`, if the content passes have been run already. `search` is the
+    // current search with the matches in the new content.
+    fn content_replaced(&mut self, search: Option<Search>) {
+        let active = self.active_path();
+        self.selection = None;
+        // Validation errors, duplicate marks and overridden nodes refer to the previous content.
+        self.errors.clear();
+        self.duplicates.clear();
+        self.overridden.get_mut().clear();
+        if let (Some(current), Some(search)) = (&mut self.search, search) {
+            current.replace_matches(search.matches);
+        }
+        self.revisions.content += 1;
+        for path in &self.hidden {
+            fold_hidden(Arc::make_mut(&mut self.value), path);
+        }
+        self.value_modified();
+        if self.active_path() != active && self.displaced_selection.is_none() {
+            self.displaced_selection = Some(active);
+        }
+    }
+
+    /// If a change of the displayed value (e.g., an `update` that removed the active node) has
+    /// moved the selection since the last call, return the path of the element that was active
+    /// before, e.g., to notify the user.
+    pub fn take_displaced_selection(&mut self) -> Option<JsonPath> {
+        self.displaced_selection.take()
+    }
+
+    /// The passes to run over the value after its content changed.
+    fn content_passes(&self) -> ContentPasses {
+        #[cfg(feature = "chrono")]
+        let timestamps = if self.humanize_timestamps {
+            Some(self.timestamps.clone().unwrap_or_default())
+        } else {
+            None
+        };
+        ContentPasses {
+            redaction: self.redaction.clone().filter(|_| self.redaction_enabled),
+            binary_detection: self.binary_detection,
+            #[cfg(feature = "chrono")]
+            timestamps,
+            search: self.search.clone(),
+        }
+    }
+
+    fn apply_redaction(&mut self) {
+        let redaction = self.redaction.as_deref().filter(|_| self.redaction_enabled);
+        if Arc::make_mut(&mut self.value).redact(&JsonPath::root(), redaction) {
+            self.invalidate_demand();
+        }
+    }
+
+    fn redaction_modified(&mut self) {
+        self.revisions.passes += 1;
+        self.apply_redaction();
+        if let Some(search) = &mut self.search {
+            search.refresh(&self.value);
+        }
+    }
+
+    fn fix_active_element_path(&mut self) {
+        self.invalidate_demand();
+        let mut tmp = Path::Scalar;
+        ::std::mem::swap(&mut self.active_element, &mut tmp);
+        let fixed = tmp.fix_path_for_value(&self.value, self.selection_mode);
+        self.active_element = self.shown_path_near(fixed);
+    }
+
+    /// Get the location of the currently active element.
+    pub fn active_path(&self) -> JsonPath {
+        self.active_element.to_json_path()
+    }
+
+    /// Get the location of the currently active element as a JSON Pointer (RFC 6901).
+    pub fn active_pointer(&self) -> String {
+        self.active_path().to_pointer()
+    }
+
+    /// Select the node at `path`, unfolding (and growing) all containers on the way to make it
+    /// visible. Fails if there is no node at `path`.
+    pub fn select_path(&mut self, path: &JsonPath) -> Result<(), ()> {
+        let new_path = reveal_path(path.segments(), Arc::make_mut(&mut self.value)).ok_or(())?;
+        self.hidden
+            .retain(|hidden| !path.segments().starts_with(hidden.segments()));
+        self.descend_to(path);
+        self.active_element = new_path;
+        self.value_modified();
+        Ok(())
+    }
+
+    /// Select the element at `index` of the array at `array_path`, growing the array (or showing
+    /// the page containing it if the array is paged) to make it visible, e.g., to show the item
+    /// that an error message refers to. Fails if there is no array at `array_path` or no element
+    /// at `index`.
+    pub fn select_array_index(&mut self, array_path: &JsonPath, index: usize) -> Result<(), ()> {
+        match self.value.get(array_path) {
+            Some(DisplayValue::Array(array)) if index < array.values.len() => {}
+            _ => return Err(()),
+        }
+        self.select_path(&array_path.clone().index(index))
+    }
+
+    /// Select the next object or array in the order of the outline (see `JsonOutline`), revealing
+    /// it if necessary. Fails if the innermost object or array containing the selection is the
+    /// last one.
+    pub fn select_next_container(&mut self) -> Result<(), ()> {
+        let entries = outline::outline_entries(self.root(), None);
+        let next = match outline::entry_containing(&entries, &self.active_path()) {
+            Some(current) => current + 1,
+            None => 0,
+        };
+        self.select_path(&entries.get(next).ok_or(())?.path)
+    }
+
+    /// Select the innermost object or array containing the selection or, if it is selected
+    /// already, the previous one in the order of the outline (see `JsonOutline`).
+    pub fn select_previous_container(&mut self) -> Result<(), ()> {
+        let entries = outline::outline_entries(self.root(), None);
+        let active = self.active_path();
+        let current = outline::entry_containing(&entries, &active).ok_or(())?;
+        let previous = if entries[current].path == active {
+            current.checked_sub(1).ok_or(())?
+        } else {
+            current
+        };
+        self.select_path(&entries[previous].path)
+    }
+
+    /// Unfold (`expanded == true`) or fold the object or array at `path`. Unfolding also unfolds
+    /// all containers on the way to make the node visible. Fails if there is no object or array at
+    /// `path`.
+    pub fn set_expanded(&mut self, path: &JsonPath, expanded: bool) -> Result<(), ()> {
+        match self.value.get(path) {
+            Some(DisplayValue::Object(_)) | Some(DisplayValue::Array(_)) => {}
+            _ => return Err(()),
+        }
+        // Unfolding a node also unfolds its ancestors.
+        let mut paths = vec![path.clone()];
+        if expanded {
+            let mut ancestor = path.parent();
+            while let Some(current) = ancestor {
+                ancestor = current.parent();
+                paths.push(current);
+            }
+        }
+        let before = self.view_state_of(paths);
+        if expanded {
+            reveal_path(path.segments(), Arc::make_mut(&mut self.value)).ok_or(())?;
+            self.descend_to(path);
+        }
+        match Arc::make_mut(&mut self.value).get_mut(path) {
+            Some(DisplayValue::Object(obj)) => obj.extended = expanded,
+            Some(DisplayValue::Array(array)) => array.extended = expanded,
+            _ => return Err(()),
+        }
+        self.view_history.push(before);
+        self.value_modified();
+        Ok(())
+    }
+
+    /// Show the first `count` elements of the array at `path` (or all of them if there are
+    /// fewer). Fails if there is no array at `path`.
+    pub fn set_array_visible_count(&mut self, path: &JsonPath, count: usize) -> Result<(), ()> {
+        self.change_array(path, |array| {
+            array.set_num_extended(count);
+            Ok(())
+        })
+    }
+
+    /// Also show the last `count` elements of the array at `path` (or all that are not among the
+    /// first ones shown), with a marker like `… 994 elided …` for the elements in between, e.g.,
+    /// to monitor the end of a long list. Fails if there is no array at `path` or it is paged.
+    pub fn set_array_tail_count(&mut self, path: &JsonPath, count: usize) -> Result<(), ()> {
+        self.change_array(path, |array| {
+            if array.page_size.is_some() {
+                return Err(());
+            }
+            array.set_num_tail(count);
+            Ok(())
+        })
+    }
+
+    /// Show the elements of the array at `path` in pages of `size` elements instead of growing or
+    /// shrinking the number of shown ones, e.g., for arrays with thousands of elements. The grow
+    /// and shrink knobs then show the next or previous page and the length indicator shows the
+    /// number of the page, e.g., `<-page 2/7+>`. None ends paging. Fails if there is no array at
+    /// `path` or `size` is zero.
+    pub fn set_array_page_size(&mut self, path: &JsonPath, size: Option<usize>) -> Result<(), ()> {
+        self.change_array(path, |array| {
+            if size == Some(0) {
+                return Err(());
+            }
+            array.set_page_size(size);
+            Ok(())
+        })
+    }
+
+    /// Show the next page of the paged array at `path`. Fails if there is no paged array at
+    /// `path` or the last page is shown already.
+    pub fn next_page(&mut self, path: &JsonPath) -> Result<(), ()> {
+        self.change_array(path, |array| {
+            if array.page_size.is_none() || !array.can_grow() {
+                return Err(());
+            }
+            array.grow();
+            Ok(())
+        })
+    }
+
+    /// Show the previous page of the paged array at `path`. Fails if there is no paged array at
+    /// `path` or the first page is shown already.
+    pub fn prev_page(&mut self, path: &JsonPath) -> Result<(), ()> {
+        self.change_array(path, |array| {
+            if array.page_size.is_none() || !array.can_shrink() {
+                return Err(());
+            }
+            array.shrink();
+            Ok(())
+        })
+    }
+
+    /// Show the page of the paged array at `path` that contains the element at `index`. Fails if
+    /// there is no paged array at `path` or no element at `index`.
+    pub fn jump_to_index(&mut self, path: &JsonPath, index: usize) -> Result<(), ()> {
+        self.change_array(path, |array| {
+            if array.page_size.is_none() || index >= array.values.len() {
+                return Err(());
+            }
+            array.jump_to_index(index);
+            Ok(())
+        })
+    }
+
+    /// Show the elements of the array at `path` in the order of `sort` (or in document order again,
+    /// None) without modifying the value. Heads, tails and pages of the array refer to the sorted
+    /// elements, but paths (and index labels) still refer to the elements by their index in the
+    /// value, so that the right elements are highlighted as changed after updates. Fails if there
+    /// is no array at `path`.
+    pub fn set_array_sort(&mut self, path: &JsonPath, sort: Option<ArraySort>) -> Result<(), ()> {
+        match Arc::make_mut(&mut self.value).get_mut(path) {
+            Some(DisplayValue::Array(array)) => array.set_sort(sort),
+            _ => return Err(()),
+        }
+        self.node_state_modified();
+        Ok(())
+    }
+
+    /// Apply `change` to the array at `path`, which fails if it is not applicable.
+    fn change_array(
+        &mut self,
+        path: &JsonPath,
+        change: impl FnOnce(&mut DisplayArray) -> Result<(), ()>,
+    ) -> Result<(), ()> {
+        let before = self.view_state_of(Some(path.clone()));
+        match Arc::make_mut(&mut self.value).get_mut(path) {
+            Some(DisplayValue::Array(array)) => change(array)?,
+            _ => return Err(()),
+        }
+        self.view_history.push(before);
+        self.value_modified();
+        Ok(())
+    }
+
+    /// Unfold all objects and arrays (except for hidden ones).
+    pub fn expand_all(&mut self) {
+        let before = self.complete_view_state();
+        self.view_history.push(before);
+        Arc::make_mut(&mut self.value).set_extended_recursive(true);
+        for path in &self.hidden {
+            fold_hidden(Arc::make_mut(&mut self.value), path);
+        }
+        self.value_modified();
+    }
+
+    /// Fold all objects and arrays below the root.
+    pub fn collapse_all(&mut self) {
+        let before = self.complete_view_state();
+        self.view_history.push(before);
+        Arc::make_mut(&mut self.value).set_extended_recursive(false);
+        match Arc::make_mut(&mut self.value) {
+            DisplayValue::Object(obj) => obj.extended = true,
+            DisplayValue::Array(array) => array.extended = true,
+            DisplayValue::Scalar(_) => {}
+        }
+        self.value_modified();
+    }
+
+    /// The closest object or array (only arrays if `arrays_only`) that is the active element or
+    /// contains it.
+    fn active_container(&self, arrays_only: bool) -> Option<JsonPath> {
+        let mut path = Some(self.active_path());
+        while let Some(current) = path {
+            match self.value.get(&current) {
+                Some(DisplayValue::Array(_)) => return Some(current),
+                Some(DisplayValue::Object(_)) if !arrays_only => return Some(current),
+                _ => path = current.parent(),
+            }
+        }
+        None
+    }
+
+    /// Show one more element of the array containing the active element (which does not have to
+    /// be the grow knob). Fails if there is no such array or all of its elements are shown.
+    pub fn grow_active_array(&mut self) -> Result<ToggleResult, ()> {
+        self.resize_active_array(DisplayArray::can_grow, DisplayArray::grow)
+            .map(ToggleResult::Grew)
+    }
+
+    /// Show one element less of the array containing the active element (which does not have to
+    /// be the shrink knob). Fails if there is no such array or none of its elements are shown.
+    pub fn shrink_active_array(&mut self) -> Result<ToggleResult, ()> {
+        self.resize_active_array(DisplayArray::can_shrink, DisplayArray::shrink)
+            .map(ToggleResult::Shrank)
+    }
+
+    /// Like `grow_active_array`, but show one more element at the end of the array (see
+    /// `set_array_tail_count`).
+    pub fn grow_active_array_tail(&mut self) -> Result<ToggleResult, ()> {
+        self.resize_active_array(DisplayArray::can_grow_tail, DisplayArray::grow_tail)
+            .map(ToggleResult::Grew)
+    }
+
+    /// Like `shrink_active_array`, but show one element less at the end of the array. Fails if
+    /// no elements are shown there.
+    pub fn shrink_active_array_tail(&mut self) -> Result<ToggleResult, ()> {
+        self.resize_active_array(DisplayArray::can_shrink_tail, DisplayArray::shrink_tail)
+            .map(ToggleResult::Shrank)
+    }
+
+    /// Apply `resize` to the array containing the active element if `can_resize` and return the
+    /// number of its shown elements.
+    fn resize_active_array(
+        &mut self,
+        can_resize: fn(&DisplayArray) -> bool,
+        resize: fn(&mut DisplayArray),
+    ) -> Result<usize, ()> {
+        if !self.interactive {
+            return Err(());
+        }
+        let path = self.active_container(true).ok_or(())?;
+        let before = self.view_state_of(Some(path.clone()));
+        let count = match Arc::make_mut(&mut self.value).get_mut(&path) {
+            Some(DisplayValue::Array(array)) if can_resize(array) => {
+                resize(array);
+                array.num_shown()
+            }
+            _ => return Err(()),
+        };
+        self.view_history.push(before);
+        self.value_modified();
+        Ok(count)
+    }
+
+    /// Fold or unfold the active element if it is an object or array, or else the object or array
+    /// containing it. Fails if the active element is a scalar at the root.
+    pub fn toggle_active_container(&mut self) -> Result<ToggleResult, ()> {
+        if !self.interactive {
+            return Err(());
+        }
+        let path = self.active_container(false).ok_or(())?;
+        let before = self.view_state_of(Some(path.clone()));
+        let extended = match Arc::make_mut(&mut self.value).get_mut(&path) {
+            Some(DisplayValue::Object(obj)) => {
+                obj.toggle_visibility();
+                obj.extended
+            }
+            Some(DisplayValue::Array(array)) => {
+                array.toggle_visibility();
+                array.extended
+            }
+            _ => return Err(()),
+        };
+        self.view_history.push(before);
+        self.value_modified();
+        Ok(if extended {
+            ToggleResult::Expanded
+        } else {
+            ToggleResult::Collapsed
+        })
+    }
+
+    /// Revert the last change of the fold state (e.g., an accidental `collapse_all`) and select the
+    /// node that was active before it. Fails if there is nothing to undo.
+    ///
+    /// Folding and unfolding nodes and growing or shrinking arrays can be undone, even across
+    /// updates, as far as the nodes still exist.
+    pub fn undo_view_change(&mut self) -> Result<(), ()> {
+        let current = self.current_view_state(self.view_history.next_undo().ok_or(())?);
+        let state = self.view_history.undo(current).expect("checked above");
+        self.restore_view_state(&state);
+        Ok(())
+    }
+
+    /// Apply the last change reverted by `undo_view_change` again. Fails if there is nothing to
+    /// redo, e.g., because the fold state was changed since.
+    pub fn redo_view_change(&mut self) -> Result<(), ()> {
+        let current = self.current_view_state(self.view_history.next_redo().ok_or(())?);
+        let state = self.view_history.redo(current).expect("checked above");
+        self.restore_view_state(&state);
+        Ok(())
+    }
+
+    /// The view state of the nodes at `paths`, which are about to be changed.
+    fn view_state_of(&self, paths: impl IntoIterator<Item = JsonPath>) -> ViewState {
+        let folds = paths
+            .into_iter()
+            .filter_map(|path| {
+                let fold = self.value.get(&path)?.fold()?;
+                Some((path, fold))
+            })
+            .collect();
+        ViewState {
+            folds,
+            complete: false,
+            active: self.active_path(),
+        }
+    }
+
+    /// The view state of all nodes, e.g., before folding all of them.
+    fn complete_view_state(&self) -> ViewState {
+        let mut folds = BTreeMap::new();
+        self.value.folds(&mut JsonPath::root(), &mut folds);
+        ViewState {
+            folds,
+            complete: true,
+            active: self.active_path(),
+        }
+    }
+
+    /// The current view state of the nodes in `state`.
+    fn current_view_state(&self, state: &ViewState) -> ViewState {
+        if state.complete {
+            self.complete_view_state()
+        } else {
+            self.view_state_of(state.folds.keys().cloned())
+        }
+    }
+
+    fn restore_view_state(&mut self, state: &ViewState) {
+        let value = Arc::make_mut(&mut self.value);
+        if state.complete {
+            value.restore_folds(&mut JsonPath::root(), &state.folds);
+        } else {
+            for (path, fold) in &state.folds {
+                if let Some(node) = value.get_mut(path) {
+                    node.restore_fold(fold);
+                }
+            }
+        }
+        self.value_modified();
+        let _ = self.select_path(&state.active);
+    }
+
+    /// Select the first member of the object containing the active element whose key starts with
+    /// `prefix` (ignoring case), like the type-ahead find of a file manager. If the active element
+    /// is not contained in an object, the members of the active element itself are searched.
+    pub fn select_key_starting_with(&mut self, prefix: &str) -> Result<(), ()> {
+        let active = self.active_path();
+        let prefix = prefix.to_lowercase();
+        let matches = |key: &&Key| key.to_string().to_lowercase().starts_with(&prefix);
+        let path = match sibling_keys(&self.value, &active) {
+            Some((parent, mut keys)) => parent.key(keys.find(matches).ok_or(())?.clone()),
+            None => match self.value.get(&active) {
+                Some(DisplayValue::Object(obj)) => {
+                    let key = obj.members.keys().find(matches).ok_or(())?;
+                    active.clone().key(key.clone())
+                }
+                _ => return Err(()),
+            },
+        };
+        self.select_path(&path)
+    }
+
+    /// Search for `pattern` (a substring or, with the `regex` feature, a `regex::Regex`) as
+    /// configured by `options` and select the first match. Objects and arrays containing a match
+    /// are unfolded when it is selected. Fails if there is no match, but the search stays active
+    /// for `next_match` and `previous_match` (e.g., after an `update`).
+    ///
+    /// A member whose key matches is one match, even if its value matches as well.
+    pub fn search(
+        &mut self,
+        pattern: impl Into<Pattern>,
+        options: SearchOptions,
+    ) -> Result<(), ()> {
+        self.search = Some(Search::new(pattern.into(), options, &self.value));
+        self.revisions.passes += 1;
+        self.select_match(0)
+    }
+
+    /// Highlight all parts of (visible) keys and scalars that match `pattern`, independently of
+    /// the current search. This replaces previous highlights.
+    pub fn highlight_matches(&mut self, pattern: impl Into<Pattern>) {
+        self.highlight = Some(pattern.into());
+        self.invalidate_demand();
+    }
+
+    /// Remove the highlights of `highlight_matches`.
+    pub fn clear_highlights(&mut self) {
+        self.highlight = None;
+        self.invalidate_demand();
+    }
+
+    /// Stop the current search.
+    pub fn clear_search(&mut self) {
+        self.search = None;
+        self.revisions.passes += 1;
+    }
+
+    /// Select the match following the current one, wrapping around at the end.
+    pub fn next_match(&mut self) -> Result<(), ()> {
+        let search = self.search.as_ref().ok_or(())?;
+        let next = search.current.map(|i| i + 1).unwrap_or(0);
+        self.select_match(if next < search.matches.len() { next } else { 0 })
+    }
+
+    /// Select the match preceding the current one, wrapping around at the beginning.
+    pub fn previous_match(&mut self) -> Result<(), ()> {
+        let search = self.search.as_ref().ok_or(())?;
+        let len = search.matches.len();
+        let previous = search.current.unwrap_or(0);
+        self.select_match(if previous > 0 {
+            previous - 1
+        } else {
+            len.saturating_sub(1)
+        })
+    }
+
+    /// The number of matches of the current search (0 if there is none).
+    pub fn match_count(&self) -> usize {
+        self.search.as_ref().map(|s| s.matches.len()).unwrap_or(0)
+    }
+
+    /// The (0-based) index of the selected match among all matches, e.g., to show "n of m".
+    pub fn current_match(&self) -> Option<usize> {
+        self.search.as_ref().and_then(|s| s.current)
+    }
+
+    fn select_match(&mut self, index: usize) -> Result<(), ()> {
+        let search = self.search.as_mut().ok_or(())?;
+        let path = search.matches.get(index).ok_or(())?.clone();
+        search.current = Some(index);
+        self.select_path(&path)
+    }
+
+    /// Select the node at the specified JSON Pointer (RFC 6901), e.g., `/foo/3/bar`.
+    pub fn select_pointer(&mut self, pointer: &str) -> Result<(), ()> {
+        let path = json_path_from_pointer(pointer, &self.value).ok_or(())?;
+        self.select_path(&path)
+    }
+
+    /// The root of the displayed value, e.g., to traverse it.
+    pub fn root(&self) -> Node<'_> {
+        Node::new(&self.value)
+    }
+
+    /// Get the node at `path`, if it exists.
+    pub fn node(&self, path: &JsonPath) -> Option<Node<'_>> {
+        self.value.get(path).map(Node::new)
+    }
+
+    /// Get the node at the specified JSON Pointer (RFC 6901), if it exists.
+    pub fn value_at_pointer<'a>(&'a self, pointer: &str) -> Option<Node<'a>> {
+        let path = json_path_from_pointer(pointer, &self.value)?;
+        self.value.get(&path).map(Node::new)
+    }
+
+    /// Always show the node at `path` in a region above the value, regardless of the fold state
+    /// and scroll position. The node does not have to exist (yet): Pinned paths are kept across
+    /// updates and only shown while there is a node at the path.
+    pub fn pin_path(&mut self, path: JsonPath) {
+        if !self.pinned.contains(&path) {
+            self.pinned.push(path);
+        }
+    }
+
+    /// Stop showing `path` above the value. Fails if it is not pinned.
+    pub fn unpin_path(&mut self, path: &JsonPath) -> Result<(), ()> {
+        let index = self.pinned.iter().position(|p| p == path).ok_or(())?;
+        self.pinned.remove(index);
+        Ok(())
+    }
+
+    /// All pinned paths in the order they were pinned (and are shown in).
+    pub fn pinned_paths(&self) -> &[JsonPath] {
+        &self.pinned
+    }
+
+    /// Show the node at `path` as a `(hidden)` placeholder instead of its content, e.g., to
+    /// suppress large, uninteresting subtrees. Toggling the placeholder (or selecting a node
+    /// within it via `select_path`) reveals the node again. Fails if there is no node at `path`.
+    pub fn hide_path(&mut self, path: JsonPath) -> Result<(), ()> {
+        if self.value.get(&path).is_none() {
+            return Err(());
+        }
+        // Folding the node leaves the placeholder as its only interaction point.
+        fold_hidden(Arc::make_mut(&mut self.value), &path);
+        self.hidden.insert(path);
+        self.value_modified();
+        Ok(())
+    }
+
+    /// Only draw objects and arrays up to `depth` levels below the root and show deeper ones as
+    /// `{ … }` or `[ … ]`, e.g., to keep adversarially deep documents from freezing the UI.
+    /// Toggling such a placeholder (see `descend`) shows the node, counting the depth from it
+    /// until it is folded again. `None` (the default) removes the limit.
+    pub fn set_max_render_depth(&mut self, depth: Option<usize>) {
+        self.max_render_depth = depth;
+        self.descended.clear();
+        self.value_modified();
+    }
+
+    pub fn max_render_depth(&self) -> Option<usize> {
+        self.max_render_depth
+    }
+
+    /// Unfold the object or array at `path`, which is shown as a placeholder because it is below
+    /// the maximum render depth, and count the depth of the nodes below it from there. Fails if
+    /// the node at `path` is not such a placeholder.
+    pub fn descend(&mut self, path: &JsonPath) -> Result<(), ()> {
+        if !self.cut.contains(path) {
+            return Err(());
+        }
+        self.descended.insert(path.clone());
+        reveal_path(path.segments(), Arc::make_mut(&mut self.value)).ok_or(())?;
+        match Arc::make_mut(&mut self.value).get_mut(path) {
+            Some(DisplayValue::Object(obj)) => obj.extended = true,
+            Some(DisplayValue::Array(array)) => array.extended = true,
+            _ => {}
+        }
+        self.value_modified();
+        Ok(())
+    }
+
+    /// Fold all objects and arrays below the maximum render depth and collect them in `cut`.
+    fn limit_render_depth(&mut self) {
+        self.cut.clear();
+        let max_depth = match self.max_render_depth {
+            Some(depth) => depth,
+            None => return self.descended.clear(),
+        };
+        // Nodes stay descended into until they are folded.
+        let value = &self.value;
+        self.descended
+            .retain(|path| value.get(path).and_then(DisplayValue::extended) == Some(true));
+        cut_below_depth(
+            Arc::make_mut(&mut self.value),
+            max_depth,
+            &self.descended,
+            &mut self.cut,
+        );
+    }
+
+    /// Show the content of a node hidden by `hide_path` again (and unfold it if it is an object
+    /// or array). Fails if it is not hidden.
+    pub fn unhide_path(&mut self, path: &JsonPath) -> Result<(), ()> {
+        if !self.hidden.remove(path) {
+            return Err(());
+        }
+        match Arc::make_mut(&mut self.value).get_mut(path) {
+            Some(DisplayValue::Object(obj)) => obj.extended = true,
+            Some(DisplayValue::Array(array)) => array.extended = true,
+            _ => {}
+        }
+        self.value_modified();
+        Ok(())
+    }
+
+    /// All paths hidden by `hide_path`.
+    pub fn hidden_paths(&self) -> impl Iterator<Item = &JsonPath> {
+        self.hidden.iter()
+    }
+
+    /// Mask all scalars for which `redaction` returns true as `•••`, e.g., to hide tokens or
+    /// passwords during a demo. `redaction` is called with the path of each scalar and its key if
+    /// it is an object member. Masked values are also masked in `value_at_pointer`, passed to
+    /// `on_activate` callbacks and custom renderers and are not found by `search`.
+    pub fn set_redaction(
+        &mut self,
+        redaction: impl Fn(&JsonPath, Option<&Key>) -> bool + Send + Sync + 'static,
+    ) {
+        self.redaction = Some(Arc::new(redaction));
+        self.redaction_modified();
+    }
+
+    /// Remove the function set by `set_redaction` and show all values.
+    pub fn clear_redaction(&mut self) {
+        self.redaction = None;
+        self.redaction_modified();
+    }
+
+    /// Temporarily show redacted values (`false`) or mask them again (`true`, the default).
+    pub fn set_redaction_enabled(&mut self, enabled: bool) {
+        self.redaction_enabled = enabled;
+        self.redaction_modified();
+    }
+
+    pub fn redaction_enabled(&self) -> bool {
+        self.redaction_enabled
+    }
+
+    /// Show long strings that look like hex or base64 encoded binary data as a summary of the
+    /// decoded size, e.g., `[binary, 4.2 KB] [+]`. Toggling the summary shows a hexdump of the
+    /// beginning of the data.
+    pub fn set_binary_detection(&mut self, enabled: bool) {
+        self.binary_detection = enabled;
+        self.revisions.passes += 1;
+        if Arc::make_mut(&mut self.value).detect_binary(enabled) {
+            self.value_modified();
+        }
+    }
+
+    /// Show a human readable form of scalars that look like timestamps after them, e.g.,
+    /// `1700000000 (2023-11-14 22:13 UTC)`. Which scalars are considered to be timestamps is
+    /// configured using `set_timestamp_options`.
+    #[cfg(feature = "chrono")]
+    pub fn set_humanize_timestamps(&mut self, enabled: bool) {
+        self.humanize_timestamps = enabled;
+        self.revisions.passes += 1;
+        if self.apply_timestamps() {
+            self.value_modified();
+        }
+    }
+
+    /// Configure the heuristic that detects timestamps (see `set_humanize_timestamps`).
+    #[cfg(feature = "chrono")]
+    pub fn set_timestamp_options(&mut self, options: TimestampOptions) {
+        self.timestamps = Some(options);
+        self.revisions.passes += 1;
+        if self.apply_timestamps() {
+            self.value_modified();
+        }
+    }
+
+    #[cfg(feature = "chrono")]
+    fn apply_timestamps(&mut self) -> bool {
+        let default = TimestampOptions::default();
+        let options = self.timestamps.as_ref().unwrap_or(&default);
+        let options = Some(options).filter(|_| self.humanize_timestamps);
+        Arc::make_mut(&mut self.value).humanize_timestamps(options)
+    }
+
+    /// Show the integer at `path` in `base`, e.g., `0xff` instead of `255`. The original value is
+    /// still used everywhere else (e.g., in `value_at_pointer` or `search`). Fails if there is no
+    /// integer at `path`.
+    pub fn set_number_base(&mut self, path: &JsonPath, base: NumberBase) -> Result<(), ()> {
+        match self.value.get(path) {
+            Some(DisplayValue::Scalar(scalar)) if scalar.number_base == base => return Ok(()),
+            Some(DisplayValue::Scalar(_)) => {}
+            _ => return Err(()),
+        }
+        match Arc::make_mut(&mut self.value).get_mut(path) {
+            Some(DisplayValue::Scalar(scalar)) => scalar.set_number_base(base)?,
+            _ => return Err(()),
+        }
+        self.node_state_modified();
+        Ok(())
+    }
+
+    /// Show the active integer in the next base (decimal, hexadecimal, binary, octal). Fails if
+    /// the active element is not an integer.
+    pub fn cycle_number_base(&mut self) -> Result<(), ()> {
+        let path = self.active_path();
+        let base = match (
+            &self.active_element.activated_node(&self.value),
+            self.value.get(&path),
+        ) {
+            (Some(ActivatedNode::Scalar(_)), Some(DisplayValue::Scalar(scalar))) => {
+                scalar.number_base
+            }
+            _ => return Err(()),
+        };
+        self.set_number_base(&path, base.next())
+    }
+
+    /// The count, minimum, maximum and mean of the numbers in the array at `path` or, with
+    /// `field`, of the numbers in the members `field` of its elements. They are computed on demand
+    /// and cached until the array is modified. None if there is no array at `path` or there are no
+    /// such numbers.
+    pub fn aggregates(&self, path: &JsonPath, field: Option<&str>) -> Option<Aggregates> {
+        match self.value.get(path)? {
+            DisplayValue::Array(array) => array.aggregates(field),
+            _ => None,
+        }
+    }
+
+    /// The aggregates of the numbers related to the active node (and the member they are computed
+    /// for): Of the active array, of the array containing the active element or of the member
+    /// across the elements of the array if the active node is a member of one of them.
+    fn active_aggregates(&self) -> Option<(Option<String>, Aggregates)> {
+        let path = self.active_path();
+        if let Some(aggregates) = self.aggregates(&path, None) {
+            return Some((None, aggregates));
+        }
+        let parent = path.parent()?;
+        if let Some(aggregates) = self.aggregates(&parent, None) {
+            return Some((None, aggregates));
+        }
+        match path.segments().last()? {
+            JsonPathSegment::Key(id) => {
+                let key = id.key.to_string();
+                let aggregates = self.aggregates(&parent.parent()?, Some(&key))?;
+                Some((Some(key), aggregates))
+            }
+            JsonPathSegment::Index(_) => None,
+        }
+    }
+
+    /// Statistics of the displayed value. They are computed on demand and cached until the value
+    /// is modified.
+    pub fn stats(&self) -> DocumentStats {
+        let stats = self.value.stats();
+        DocumentStats {
+            objects: stats.objects,
+            arrays: stats.arrays,
+            scalars: stats.scalars,
+            max_depth: stats.depth,
+            rendered_lines: self
+                .as_widget()
+                .value_space_demand(None)
+                .height
+                .min
+                .raw_value() as usize,
+        }
+    }
+
+    /// Get the URL of the currently active element if it is a link.
+    pub fn active_link(&self) -> Option<&str> {
+        match self.active_element.activated_node(&self.value) {
+            Some(ActivatedNode::Link(link)) => Some(link),
+            _ => None,
+        }
+    }
+
+    /// Register a callback that is invoked when a scalar (or the key of a scalar member) is
+    /// activated via `toggle_active_element`. This replaces any previously registered callback.
+    ///
+    /// Use this to react to the user "opening" a value, e.g., to follow a reference.
+    pub fn on_activate(
+        &mut self,
+        callback: impl FnMut(&JsonPath, &ActivatedNode) + Send + 'static,
+    ) {
+        self.on_activate = Some(Box::new(callback));
+    }
+
+    /// Register a callback that receives the text copied via `copy_active`, e.g., to put it into
+    /// the system clipboard. This replaces any previously registered callback.
+    pub fn on_copy(&mut self, callback: impl FnMut(&str) + Send + 'static) {
+        self.on_copy = Some(Box::new(callback));
+    }
+
+    /// Pass the subtree of the active node (whether it is folded or not) formatted as `format` to
+    /// the callback registered using `on_copy`. If a range of siblings is selected (see
+    /// `selection`), all of them are copied, as an object or array in `CopyFormat::Json`. Redacted
+    /// values are masked. Fails if no callback has been registered.
+    pub fn copy_active(&mut self, format: CopyFormat) -> Result<(), ()> {
+        if self.on_copy.is_none() {
+            return Err(());
+        }
+        let text = match self.selection() {
+            Some(selection) => {
+                let parent = selection.parent();
+                let value = self.value.get(&parent).ok_or(())?;
+                copy::format_selection(value, &parent, selection, format)
+            }
+            None => {
+                let path = self.active_path();
+                copy::format(self.value.get(&path).ok_or(())?, &path, format)
+            }
+        };
+        let callback = self.on_copy.as_mut().expect("checked above");
+        callback(&text);
+        Ok(())
+    }
+
+    /// Interact with the currently active interaction point and, for example, fold/unfold
+    /// structures.
+    ///
+    /// Scalars cannot be interacted with, unless a callback has been registered using
+    /// `on_activate`.
+    pub fn toggle_active_element(&mut self) -> Result<ToggleResult, ()> {
+        if !self.interactive {
+            return Err(());
+        }
+        if self.hidden.remove(&self.active_path()) {
+            // Reveal the node (and unfold it if it is an object or array).
+            let _ = self
+                .active_element
+                .find_and_act_on_element(Arc::make_mut(&mut self.value));
+            self.value_modified();
+            return Ok(ToggleResult::Revealed);
+        }
+        let active = self.active_path();
+        if self.cut.contains(&active) {
+            self.descend(&active)?;
+            return Ok(ToggleResult::Revealed);
+        }
+        let before = self.view_state_of(Some(self.active_element.to_json_path()));
+        let mut res = self
+            .active_element
+            .find_and_act_on_element(Arc::make_mut(&mut self.value));
+        if res.is_ok() {
+            self.view_history.push(before);
+        } else {
+            if let (Some(callback), Some(node)) = (
+                self.on_activate.as_mut(),
+                self.active_element.activated_node(&self.value),
+            ) {
+                let path = self.active_element.to_json_path();
+                callback(&path, &node);
+                res = Ok(ToggleResult::ActivatedScalar(path));
+            }
+        }
+        self.value_modified();
+        res
+    }
+
+    /// Validate `document` against `schema` (see the `schema` module) and mark all nodes that
+    /// violate the schema. The message of an error is shown next to the node when it is selected.
+    ///
+    /// `document` should be the value that is currently displayed. Previous validation errors are
+    /// replaced. Since they refer to the displayed content, they are cleared whenever it changes
+    /// (e.g., by `update` or `reset`), so validate the new document again after an update.
+    #[cfg(feature = "schema")]
+    pub fn validate(&mut self, document: &json_ext::JsonValue, schema: &json_ext::JsonValue) {
+        self.errors.clear();
+        self.invalidate_demand();
+        for error in schema::validate(document, schema) {
+            let message = self.errors.entry(error.path).or_default();
+            if !message.is_empty() {
+                message.push_str("; ");
+            }
+            message.push_str(&error.message);
+        }
+    }
+
+    /// All nodes marked by the last call to `validate` and the corresponding error messages.
+    #[cfg(feature = "schema")]
+    pub fn validation_errors(&self) -> impl Iterator<Item = (&JsonPath, &str)> {
+        self.errors
+            .iter()
+            .map(|(path, message)| (path, message.as_str()))
+    }
+
+    /// Remove all marks of the last call to `validate`.
+    #[cfg(feature = "schema")]
+    pub fn clear_validation_errors(&mut self) {
+        self.errors.clear();
+        self.invalidate_demand();
+    }
+
+    /// Mark the elements of the active array (or of the array containing the active node) that
+    /// are equal to another one of its elements, e.g., to find repeats produced by a data pipeline.
+    /// Returns the number of marked elements. Like validation errors, the marks replace previous
+    /// ones and are cleared when the displayed content changes. Fails if there is no such array.
+    pub fn mark_duplicates(&mut self) -> Result<usize, ()> {
+        let path = self.active_container(true).ok_or(())?;
+        let indices = match self.value.get(&path) {
+            Some(DisplayValue::Array(array)) => duplicate_indices(&array.values),
+            _ => return Err(()),
+        };
+        self.duplicates = indices.into_iter().map(|i| path.clone().index(i)).collect();
+        self.invalidate_demand();
+        Ok(self.duplicates.len())
+    }
+
+    /// All elements marked by the last call to `mark_duplicates`.
+    pub fn duplicates(&self) -> impl Iterator<Item = &JsonPath> {
+        self.duplicates.iter()
+    }
+
+    /// Remove all marks of the last call to `mark_duplicates`.
+    pub fn clear_duplicates(&mut self) {
+        self.duplicates.clear();
+        self.invalidate_demand();
+    }
+
+    /// Select the next element marked by `mark_duplicates` after the active node, wrapping around
+    /// at the end. Fails if none of them exists (anymore).
+    pub fn next_duplicate(&mut self) -> Result<(), ()> {
+        let active = self.active_path();
+        let next = self
+            .duplicates
+            .iter()
+            .filter(|path| **path > active)
+            .chain(self.duplicates.iter())
+            .find(|path| self.value.get(path).is_some())
+            .ok_or(())?
+            .clone();
+        self.select_path(&next)
+    }
+
+    /// Show `text` next to the node at `path` (after the opening bracket of unfolded objects and
+    /// arrays), e.g., a breakpoint or a note of the user. Like pinned paths, annotations are kept
+    /// across updates and only shown while there is a node at `path`. This replaces a previous
+    /// annotation of the node.
+    pub fn annotate(&mut self, path: JsonPath, text: &str, style: StyleModifier) {
+        let text = text.to_owned();
+        self.annotations.insert(path, Annotation { text, style });
+        self.invalidate_demand();
+    }
+
+    /// The text of the annotation of the node at `path`, if any.
+    pub fn annotation(&self, path: &JsonPath) -> Option<&str> {
+        self.annotations.get(path).map(|a| a.text.as_str())
+    }
+
+    /// Remove the annotation of the node at `path`. Fails if there is none.
+    pub fn remove_annotation(&mut self, path: &JsonPath) -> Result<(), ()> {
+        self.annotations.remove(path).ok_or(())?;
+        self.invalidate_demand();
+        Ok(())
+    }
+
+    pub fn clear_annotations(&mut self) {
+        self.annotations.clear();
+        self.invalidate_demand();
+    }
+
+    /// Show `message` (e.g., why a document could not be parsed) in place of the value until the
+    /// next `reset`, `update` or `apply_prepared`. The value itself is kept.
+    pub fn set_error(&mut self, message: &str) {
+        self.failure = Some(message.to_owned());
+        self.pending = None;
+        self.invalidate_demand();
+    }
+
+    /// The message set using `set_error`, if it is currently shown.
+    pub fn error(&self) -> Option<&str> {
+        self.failure.as_deref()
+    }
+
+    /// Show the value again instead of the message set using `set_error`.
+    pub fn clear_error(&mut self) {
+        self.failure = None;
+        self.invalidate_demand();
+    }
+
+    /// Show `message` (e.g., while a document is fetched) in place of the value until the next
+    /// `reset`, `update`, `apply_prepared` or `set_error`. See `JsonViewerWidget::spinner`.
+    pub fn set_pending(&mut self, message: &str) {
+        self.pending = Some(message.to_owned());
+        self.failure = None;
+        self.invalidate_demand();
+    }
+
+    /// The message set using `set_pending`, if it is currently shown.
+    pub fn pending(&self) -> Option<&str> {
+        self.pending.as_deref()
+    }
+
+    /// Show the value again instead of the message set using `set_pending`.
+    pub fn clear_pending(&mut self) {
+        self.pending = None;
+        self.invalidate_demand();
+    }
+
+    // The frame of the spinner to draw, advanced whenever `blink` changes.
+    fn spinner_frame(&self, blink: Blink) -> usize {
+        let on = matches!(blink, Blink::On);
+        let (last, mut frame) = self.spinner.get();
+        if on != last {
+            frame = (frame + 1) % SPINNER.len();
+        }
+        self.spinner.set((on, frame));
+        frame
+    }
+
+    pub fn as_widget<'a>(&'a self) -> JsonViewerWidget<'a> {
+        JsonViewerWidget {
+            inner: self,
+            indentation: Width::new(2).unwrap(),
+            active_focused_style: FocusStyles::uniform(
+                StyleModifier::new()
+                    .invert(BoolModifyMode::Toggle)
+                    .bold(true),
+            ),
+            inactive_focused_style: FocusStyles::uniform(StyleModifier::new().bold(true)),
+            hide_inactive_focus: false,
+            active_line: false,
+            active_line_style: StyleModifier::new().bg_color(Color::LightBlack),
+            stripes: None,
+            stripe_styles: [
+                StyleModifier::new(),
+                StyleModifier::new().bg_color(Color::Ansi(236)),
+            ],
+            wrap_values: false,
+            index_labels: false,
+            index_label_style: StyleModifier::new().fg_color(Color::LightBlack),
+            min_height: None,
+            max_height: None,
+            style_precedence: StylePrecedence::default(),
+            item_changed_style: StyleModifier::new().bg_color(Color::Red),
+            link_style: StyleModifier::new().underline(true),
+            error_style: StyleModifier::new().fg_color(Color::Red),
+            duplicate_key_style: StyleModifier::new().fg_color(Color::Yellow),
+            duplicate_style: StyleModifier::new().fg_color(Color::Magenta),
+            compact_width: None,
+            inline_width: None,
+            separators: Separators::default(),
+            closing_brackets: ClosingBrackets::default(),
+            renderer: None,
+            node_style: None,
+            badge: None,
+            indentation_guides: false,
+            indentation_guide_style: StyleModifier::new().fg_color(Color::LightBlack),
+            rainbow: Vec::new(),
+            summary: None,
+            summary_style: StyleModifier::new().fg_color(Color::LightBlack),
+            scroll_offset: 0,
+            status_line: false,
+            aggregates: false,
+            status_style: StyleModifier::new().invert(BoolModifyMode::Toggle),
+            highlight_style: StyleModifier::new()
+                .bg_color(Color::Yellow)
+                .fg_color(Color::Black),
+            pinned_style: StyleModifier::new().fg_color(Color::Cyan),
+            selected_style: StyleModifier::new().bg_color(Color::Blue),
+            timestamp_style: StyleModifier::new().fg_color(Color::LightBlack),
+            history_style: StyleModifier::new().fg_color(Color::LightBlack),
+            spinner: false,
+            pending_style: StyleModifier::new(),
+        }
+    }
+}
+
+pub struct JsonViewerWidget<'a> {
+    inner: &'a JsonViewer,
+    indentation: Width,
+    active_focused_style: FocusStyles,
+    inactive_focused_style: FocusStyles,
+    hide_inactive_focus: bool,
+    active_line: bool,
+    active_line_style: StyleModifier,
+    stripes: Option<Stripes>,
+    stripe_styles: [StyleModifier; 2],
+    wrap_values: bool,
+    index_labels: bool,
+    index_label_style: StyleModifier,
+    min_height: Option<Height>,
+    max_height: Option<Height>,
+    style_precedence: StylePrecedence,
+    item_changed_style: StyleModifier,
+    link_style: StyleModifier,
+    error_style: StyleModifier,
+    duplicate_key_style: StyleModifier,
+    duplicate_style: StyleModifier,
+    compact_width: Option<usize>,
+    inline_width: Option<usize>,
+    separators: Separators,
+    closing_brackets: ClosingBrackets,
+    renderer: Option<&'a dyn NodeRenderer>,
+    node_style: Option<Box<NodeStyle<'a>>>,
+    badge: Option<Box<Badge<'a>>>,
+    indentation_guides: bool,
+    indentation_guide_style: StyleModifier,
+    rainbow: Vec<StyleModifier>,
+    summary: Option<Box<Summary<'a>>>,
+    summary_style: StyleModifier,
+    scroll_offset: usize,
+    status_line: bool,
+    aggregates: bool,
+    status_style: StyleModifier,
+    highlight_style: StyleModifier,
+    pinned_style: StyleModifier,
+    selected_style: StyleModifier,
+    timestamp_style: StyleModifier,
+    history_style: StyleModifier,
+    spinner: bool,
+    pending_style: StyleModifier,
+}
+
+impl<'a> JsonViewerWidget<'a> {
+    pub fn indentation(mut self, w: Width) -> Self {
+        self.indentation = w;
+        self
+    }
+    pub fn active_focused(mut self, style: StyleModifier) -> Self {
+        self.active_focused_style = FocusStyles::uniform(style);
+        self
+    }
+    pub fn inactive_focused(mut self, style: StyleModifier) -> Self {
+        self.inactive_focused_style = FocusStyles::uniform(style);
+        self
+    }
+    /// Like `active_focused`, but only for the active element if it belongs to a node of `kind`
+    /// (e.g., the knob of an array or the key of a member whose value is an array).
+    pub fn active_focused_kind(mut self, kind: NodeKind, style: StyleModifier) -> Self {
+        self.active_focused_style.set(kind, style);
+        self
+    }
+    /// Like `inactive_focused`, but only for the active element if it belongs to a node of `kind`.
+    pub fn inactive_focused_kind(mut self, kind: NodeKind, style: StyleModifier) -> Self {
+        self.inactive_focused_style.set(kind, style);
+        self
+    }
+    /// Do not highlight the active element at all while the widget is not active (see
+    /// `RenderingHints::active`), e.g., if several viewers are shown side by side.
+    pub fn hide_inactive_focus(mut self, hide: bool) -> Self {
+        self.hide_inactive_focus = hide;
+        self
+    }
+    /// Highlight the whole line in which the active element is drawn (across the width of the
+    /// window), not just the element itself. Cells that have a background color of their own
+    /// (e.g., changed values) keep it.
+    pub fn highlight_active_line(mut self, show: bool) -> Self {
+        self.active_line = show;
+        self
+    }
+    pub fn active_line(mut self, style: StyleModifier) -> Self {
+        self.active_line_style = style;
+        self
+    }
+    /// Shade the background of the rows of the value alternately (in the styles set using
+    /// `stripe`) to make wide values easier to read. Cells that have a background color of their
+    /// own keep it.
+    pub fn stripes(mut self, stripes: Option<Stripes>) -> Self {
+        self.stripes = stripes;
+        self
+    }
+    /// Styles of the even and odd stripes (counted from the first line of the value).
+    pub fn stripe(mut self, even: StyleModifier, odd: StyleModifier) -> Self {
+        self.stripe_styles = [even, odd];
+        self
+    }
+    /// Choose which styles win where several of them apply to the same part of a node (e.g., the
+    /// active element that changed and matches the search), from the lowest to the highest
+    /// precedence. Layers that are not mentioned are applied below the others.
+    ///
+    /// By default, the order is `Node`, `Error`, `Selected`, `Focused`, `Changed`, `Match`.
+    pub fn style_precedence(mut self, layers: &[StyleLayer]) -> Self {
+        self.style_precedence = StylePrecedence::new(layers);
+        self
+    }
+    pub fn item_changed(mut self, style: StyleModifier) -> Self {
+        self.item_changed_style = style;
+        self
+    }
+    /// Style of scalars that are a URL as a whole (e.g., `https://example.com`, but not `see
+    /// https://example.com`). Redacted values are never links.
+    pub fn link(mut self, style: StyleModifier) -> Self {
+        self.link_style = style;
+        self
+    }
+    /// Style of nodes that violate the schema passed to `JsonViewer::validate` and of the message
+    /// set using `JsonViewer::set_error`.
+    pub fn error(mut self, style: StyleModifier) -> Self {
+        self.error_style = style;
+        self
+    }
+    /// Style of keys that occur multiple times in the same object (see
+    /// `JsonViewer::set_duplicate_keys`).
+    pub fn duplicate_key(mut self, style: StyleModifier) -> Self {
+        self.duplicate_key_style = style;
+        self
+    }
+    /// Style of the elements marked by `JsonViewer::mark_duplicates`.
+    pub fn duplicate(mut self, style: StyleModifier) -> Self {
+        self.duplicate_style = style;
+        self
+    }
+    /// Render objects and arrays on a single line (e.g., `{ [-] a: 1, b: 2 }`) if that line is
+    /// at most `max_width` columns wide, counting the indentation of nested nodes (but not their
+    /// keys). Larger structures are still rendered one member per line.
+    pub fn compact_width(mut self, max_width: Width) -> Self {
+        self.compact_width = Some(max_width.raw_value() as usize);
+        self
+    }
+    /// Render unfolded arrays whose shown elements are all short scalars on a single line (e.g.,
+    /// `[ [-] 1, 2, 3, … ] <-3/120+>`) if that line is at most `max_width` columns wide (not
+    /// counting indentation and keys, so usually a bit less than the width of the window).
+    /// Otherwise they are rendered one element per line as usual.
+    pub fn inline_scalar_arrays(mut self, max_width: Width) -> Self {
+        self.inline_width = Some(max_width.raw_value() as usize);
+        self
+    }
+    /// Choose which commas are drawn after the members of objects and arrays, e.g., to show valid
+    /// JSON (apart from the knobs and indicators).
+    pub fn separators(mut self, separators: Separators) -> Self {
+        self.separators = separators;
+        self
+    }
+    /// Choose where the closing brackets of objects and arrays that span multiple lines are
+    /// drawn, e.g., at the end of their last member to save lines.
+    pub fn closing_brackets(mut self, closing_brackets: ClosingBrackets) -> Self {
+        self.closing_brackets = closing_brackets;
+        self
+    }
+    /// Draw (some) nodes using a custom renderer instead of the default representation.
+    pub fn renderer(mut self, renderer: &'a dyn NodeRenderer) -> Self {
+        self.renderer = Some(renderer);
+        self
+    }
+    /// Style specific nodes, e.g., show the `.error` member in red. `style` is called with the
+    /// path and kind of each drawn node and returns None for nodes drawn in the default style.
+    pub fn node_style(
+        mut self,
+        style: impl Fn(&JsonPath, NodeKind) -> Option<StyleModifier> + 'a,
+    ) -> Self {
+        self.node_style = Some(Box::new(style));
+        self
+    }
+    /// Draw a badge (e.g., `ƒ` for functions or `⚠` for invalid values) in front of specific
+    /// nodes and their keys. `badge` is called with the path of each drawn node and returns None
+    /// for nodes without a badge.
+    pub fn badge(mut self, badge: impl Fn(&JsonPath, Node) -> Option<StyledString> + 'a) -> Self {
+        self.badge = Some(Box::new(badge));
+        self
+    }
+    /// Draw vertical lines (`│`) next to the members of unfolded objects and arrays to make deeply
+    /// nested values easier to follow.
+    pub fn indentation_guides(mut self, show: bool) -> Self {
+        self.indentation_guides = show;
+        self
+    }
+    pub fn indentation_guide(mut self, style: StyleModifier) -> Self {
+        self.indentation_guide_style = style;
+        self
+    }
+    /// Draw the brackets of objects and arrays and the keys of object members in a style that
+    /// depends on their depth, cycling through `palette` (e.g., a few colors). The style of the
+    /// root is the first one. An empty palette disables this.
+    pub fn rainbow(mut self, palette: Vec<StyleModifier>) -> Self {
+        self.rainbow = palette;
+        self
+    }
+    /// Show the text returned by `summary` within folded objects and arrays, e.g.,
+    /// `{ [+] name=foo, id=42 … }` using `|_, node| node.summary()`. `summary` is called with the
+    /// path of each drawn folded node and returns None if nothing should be shown.
+    pub fn collapsed_summary(
+        mut self,
+        summary: impl Fn(&JsonPath, Node) -> Option<String> + 'a,
+    ) -> Self {
+        self.summary = Some(Box::new(summary));
+        self
+    }
+    pub fn summary(mut self, style: StyleModifier) -> Self {
+        self.summary_style = style;
+        self
+    }
+    /// Wrap scalars that do not fit into the window (instead of cutting them off) and continue
+    /// them under their start, e.g., under the value rather than the key of an object member.
+    /// `line_of`, `path_at_line` and `scroll_offset` count the lines of the unwrapped value.
+    pub fn wrap_values(mut self, wrap: bool) -> Self {
+        self.wrap_values = wrap;
+        self
+    }
+    /// Draw the index of each element of arrays in front of it, e.g., `[3]: foo`, unless the
+    /// elements are drawn on a single line.
+    pub fn index_labels(mut self, show: bool) -> Self {
+        self.index_labels = show;
+        self
+    }
+    pub fn index_label(mut self, style: StyleModifier) -> Self {
+        self.index_label_style = style;
+        self
+    }
+    /// Demand at least `height` lines (including the status line and pinned nodes), even if the
+    /// value is shorter.
+    pub fn min_height(mut self, height: Height) -> Self {
+        self.min_height = Some(height);
+        self
+    }
+    /// Demand at most `height` lines (including the status line and pinned nodes), even if the
+    /// value is longer. The rest can be scrolled to using `scroll_offset`.
+    pub fn max_height(mut self, height: Height) -> Self {
+        self.max_height = Some(height);
+        self
+    }
+    /// Draw the value starting at `line` (see `line_of`). Objects and arrays above the window are
+    /// skipped without walking through their members (unless custom renderers, summaries,
+    /// validation errors or hidden nodes are involved).
+    pub fn scroll_offset(mut self, line: usize) -> Self {
+        self.scroll_offset = line;
+        self
+    }
+    /// Show a line below the value with the path, type and size of the selected node and the
+    /// number of shown elements of the innermost array containing it, e.g.,
+    /// `.foo[3]  object  12 nodes, 85 bytes  [3/10]`.
+    pub fn status_line(mut self, show: bool) -> Self {
+        self.status_line = show;
+        self
+    }
+    /// Add the count, minimum, maximum and mean of the numbers of the selected array (or of the
+    /// selected member across the elements of an array) to the status line, e.g.,
+    /// `price: count 12, min 0.5, max 20, mean 7.25` (see `JsonViewer::aggregates`).
+    pub fn aggregates(mut self, show: bool) -> Self {
+        self.aggregates = show;
+        self
+    }
+    pub fn status(mut self, style: StyleModifier) -> Self {
+        self.status_style = style;
+        self
+    }
+    /// Style of the matches of `JsonViewer::highlight_matches`.
+    pub fn match_highlight(mut self, style: StyleModifier) -> Self {
+        self.highlight_style = style;
+        self
+    }
+
+    /// Style of the human readable form of timestamps (see `JsonViewer::set_humanize_timestamps`).
+    pub fn timestamp(mut self, style: StyleModifier) -> Self {
+        self.timestamp_style = style;
+        self
+    }
+    /// Style of the previous values of scalars (see `JsonViewer::toggle_history`).
+    pub fn history(mut self, style: StyleModifier) -> Self {
+        self.history_style = style;
+        self
+    }
+    /// Style of the nodes shown above the value (see `JsonViewer::pin_path`).
+    pub fn pinned(mut self, style: StyleModifier) -> Self {
+        self.pinned_style = style;
+        self
+    }
+    /// Style of the range of siblings selected using `JsonViewer::extend_selection_next` or
+    /// `extend_selection_previous`.
+    pub fn selected(mut self, style: StyleModifier) -> Self {
+        self.selected_style = style;
+        self
+    }
+    /// Show a spinner in front of the message set using `JsonViewer::set_pending`. It advances
+    /// whenever `RenderingHints::blink` changes between draws.
+    pub fn spinner(mut self, show: bool) -> Self {
+        self.spinner = show;
+        self
+    }
+    /// Style of the message set using `JsonViewer::set_pending`.
+    pub fn pending(mut self, style: StyleModifier) -> Self {
+        self.pending_style = style;
+        self
+    }
+
+    /// The message shown in place of the value (see `JsonViewer::set_error` and
+    /// `JsonViewer::set_pending`) with the spinner at `frame`.
+    fn placeholder(&self, frame: usize) -> Option<(String, StyleModifier)> {
+        if let Some(ref message) = self.inner.failure {
+            return Some((error_block(message), self.error_style));
+        }
+        let message = self.inner.pending.as_ref()?;
+        let text = if self.spinner {
+            format!("{} {}", SPINNER[frame], message.replace('\n', "\n  "))
+        } else {
+            message.clone()
+        };
+        Some((text, self.pending_style))
+    }
+
+    /// The line shown for each pinned node that exists: The path followed by the value of scalars
+    /// or the size of objects and arrays, e.g., `.items: [ 3 elements ]`.
+    fn pinned_lines(&self) -> Vec<(String, &DisplayValue)> {
+        self.inner
+            .pinned
+            .iter()
+            .filter_map(|path| {
+                let node = self.inner.value.get(path)?;
+                let summary = match node {
+                    DisplayValue::Scalar(scalar) => scalar.text().replace('\n', " "),
+                    DisplayValue::Object(obj) => format!("{{ {} members }}", obj.members.len()),
+                    DisplayValue::Array(array) => format!("[ {} elements ]", array.values.len()),
+                };
+                Some((format!("{}: {}", path, summary), node))
+            })
+            .collect()
+    }
+
+    fn draw_pinned(&self, mut window: Window, lines: &[(String, &DisplayValue)]) {
+        let mut cursor = Cursor::new(&mut window);
+        cursor.apply_style_modifier(self.pinned_style);
+        for (line, node) in lines {
+            let mut cursor = cursor.save().style_modifier();
+            if let DisplayValue::Scalar(DisplayScalar { changed: true, .. }) = node {
+                cursor.apply_style_modifier(self.item_changed_style);
+            }
+            cursor.write(line);
+            cursor.wrap_line();
+        }
+    }
+
+    fn status_text(&self) -> String {
+        let path = self.inner.active_path();
+        let node = match self.inner.value.get(&path) {
+            Some(node) => node,
+            None => return path.to_string(),
+        };
+        let kind = match node {
+            DisplayValue::Scalar(_) => "scalar",
+            DisplayValue::Object(_) => "object",
+            DisplayValue::Array(_) => "array",
+        };
+        let stats = node.stats();
+        let mut text = format!(
+            "{}  {}  {} nodes, {} bytes",
+            path, kind, stats.nodes, stats.bytes
+        );
+        // The innermost array on the path (which may be the node itself)
+        let mut current = &*self.inner.value;
+        let mut array = None;
+        for segment in path.segments() {
+            if let DisplayValue::Array(a) = current {
+                array = Some(a);
+            }
+            current = match (current, segment) {
+                (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => {
+                    match obj.members.get_by_id(id) {
+                        Some(value) => value,
+                        None => break,
+                    }
+                }
+                (DisplayValue::Array(a), &JsonPathSegment::Index(i)) => &a.values[i],
+                _ => break,
+            };
+        }
+        if let DisplayValue::Array(a) = current {
+            array = Some(a);
+        }
+        if let Some(array) = array {
+            text += &format!("  [{}]", array.length_text());
+        }
+        let aggregates = if self.aggregates {
+            self.inner.active_aggregates()
+        } else {
+            None
+        };
+        if let Some((field, aggregates)) = aggregates {
+            text += "  ";
+            if let Some(field) = field {
+                text += &format!("{}: ", field);
+            }
+            text += &aggregates.to_string();
+        }
+        if let Some(time) = self.inner.last_changed(&path) {
+            let age = self.inner.now().duration_since(time).unwrap_or_default();
+            text += &format!("  changed {} ago", format_age(age.as_secs()));
+        }
+        text
+    }
+
+    /// The path of the innermost node drawn in `line` of the value (counted from its first line,
+    /// regardless of `scroll_offset`), e.g., to select a node that was clicked on. Custom
+    /// renderers, summaries, validation errors and hidden nodes are not considered.
+    pub fn path_at_line(&self, line: usize) -> Option<JsonPath> {
+        if self.inner.view_mode == ViewMode::Flat {
+            return self.flat_lines().into_iter().nth(line).map(|l| l.location);
+        }
+        self.inner.value.path_at_line(self.layout_params(), line)
+    }
+
+    /// The line of the value in which the node at `path` starts or None if it is not shown, e.g.,
+    /// to choose a `scroll_offset` that keeps the selected node visible. The same limitations as
+    /// for `path_at_line` apply.
+    pub fn line_of(&self, path: &JsonPath) -> Option<usize> {
+        if self.inner.view_mode == ViewMode::Flat {
+            let lines = self.flat_lines();
+            return lines.iter().position(|line| line.location == *path);
+        }
+        self.inner.value.line_of(self.layout_params(), path)
+    }
+
+    /// Like `space_demand`, but for a window that is `width` wide, e.g., to size the widget in a
+    /// layout of known width. Only differs if values are wrapped (see `wrap_values`).
+    pub fn space_demand_for_width(&self, width: Width) -> Demand2D {
+        self.space_demand_within(Some(width))
+    }
+
+    /// Whether the value is drawn exactly as described by the extents of its nodes, i.e., there are
+    /// no custom renderers, summaries, badges, error messages, annotations, hidden nodes, wrapped
+    /// scalars or index labels.
+    fn extents_are_exact(&self) -> bool {
+        !self.wrap_values
+            && !self.index_labels
+            && self.renderer.is_none()
+            && self.summary.is_none()
+            && self.badge.is_none()
+            && self.inner.errors.is_empty()
+            && self.inner.annotations.is_empty()
+            && self.inner.hidden.is_empty()
+            && self.inner.cut.is_empty()
+    }
+
+    fn layout_params(&self) -> LayoutParams {
+        LayoutParams {
+            indentation: self.indentation.raw_value() as usize,
+            compact_width: self.compact_width,
+            inline_width: self.inline_width,
+            separators: self.separators,
+            closing_brackets: self.closing_brackets,
+        }
+    }
+
+    fn rendering_info(&self, hints: RenderingHints) -> RenderingInfo<'_> {
+        RenderingInfo {
+            hints,
+            active_focused_style: self.focused_style(true),
+            inactive_focused_style: self.focused_style(false),
+            precedence: self.style_precedence,
+            item_changed_style: self.item_changed_style,
+            link_style: self.link_style,
+            error_style: self.error_style,
+            duplicate_key_style: self.duplicate_key_style,
+            duplicate_style: self.duplicate_style,
+            compact_width: self.compact_width,
+            inline_width: self.inline_width,
+            separators: self.separators,
+            closing_brackets: self.closing_brackets,
+            errors: &self.inner.errors,
+            annotations: &self.inner.annotations,
+            hidden: &self.inner.hidden,
+            duplicates: &self.inner.duplicates,
+            cut: &self.inner.cut,
+            timestamp_style: self.timestamp_style,
+            history_style: self.history_style,
+            renderer: self.renderer,
+            overridden: Some(&self.inner.overridden),
+            highlight: self.inner.highlight.as_ref(),
+            highlight_style: self.highlight_style,
+            selection: self.inner.selection(),
+            selected_style: self.selected_style,
+            node_style: self.node_style.as_deref(),
+            badge: self.badge.as_deref(),
+            indentation_guide: Some(self.indentation_guide_style)
+                .filter(|_| self.indentation_guides),
+            rainbow: &self.rainbow,
+            summary: self.summary.as_deref(),
+            summary_style: self.summary_style,
+            layout: Some(self.layout_params()).filter(|_| self.extents_are_exact()),
+            active_row: Cell::new(None),
+            member_rows: RefCell::new(Vec::new()),
+            wrap_values: self.wrap_values,
+            index_label: Some(self.index_label_style).filter(|_| self.index_labels),
+        }
+    }
+
+    /// The styles of the active element if the widget is `active` (none if the viewer is not
+    /// interactive).
+    fn focused_style(&self, active: bool) -> FocusStyles {
+        if !self.inner.interactive || !active && self.hide_inactive_focus {
+            FocusStyles::uniform(StyleModifier::new())
+        } else if active {
+            self.active_focused_style
+        } else {
+            self.inactive_focused_style
+        }
+    }
+
+    /// The lines of the value in `ViewMode::Flat`.
+    fn flat_lines(&self) -> Vec<flat::FlatLine> {
+        flat::lines(&self.inner.value, &self.inner.hidden, &self.inner.cut)
+    }
+
+    fn draw_flat(&self, mut window: Window, hints: RenderingHints) {
+        let active = Some(self.inner.active_path())
+            .filter(|_| flat::is_line(&self.inner.active_element, &self.inner.value));
+        let kind = self
+            .inner
+            .node(&self.inner.active_path())
+            .map(|node| node.kind());
+        let focused_style = kind.map(|kind| self.focused_style(hints.active).get(kind));
+        let height = window.get_height().raw_value() as usize;
+        let mut cursor = Cursor::new(&mut window);
+        let lines = self.flat_lines();
+        let mut active_row = None;
+        // The index of the top-level member of each line.
+        let mut members = Vec::new();
+        let (mut previous, mut member) = (None, None);
+        for line in lines.iter() {
+            let first = line.location.segments().first();
+            if first.is_some() && first != previous {
+                member = Some(member.map_or(0, |m| m + 1));
+            }
+            previous = first;
+            members.push(member.filter(|_| first.is_some()));
+        }
+        for (row, line) in lines
+            .iter()
+            .skip(self.scroll_offset)
+            .take(height)
+            .enumerate()
+        {
+            if active.as_ref() == Some(&line.location) {
+                active_row = Some(RowIndex::new(row as i32));
+            }
+            let mut cursor = cursor.save().style_modifier();
+            let selected = self.inner.selection().is_some_and(|selection| {
+                selection
+                    .paths()
+                    .iter()
+                    .any(|path| line.location.segments().starts_with(path.segments()))
+            });
+            let layers = StyleLayers::default()
+                .with_if(line.changed, StyleLayer::Changed, self.item_changed_style)
+                .with_if(selected, StyleLayer::Selected, self.selected_style)
+                .with_if(
+                    active.as_ref() == Some(&line.location),
+                    StyleLayer::Focused,
+                    focused_style.unwrap_or_default(),
+                );
+            cursor.apply_style_modifier(layers.resolve(&self.style_precedence));
+            cursor.write(&line.text);
+            cursor.wrap_line();
+        }
+        self.draw_active_line(&mut window, active_row, hints);
+        self.draw_stripes(&mut window, |row| {
+            let line = self.scroll_offset + row.raw_value() as usize;
+            members.get(line).cloned().flatten()
+        });
+    }
+
+    /// Apply the style of the active line to all cells of `row` without a background color.
+    fn draw_active_line(&self, window: &mut Window, row: Option<RowIndex>, hints: RenderingHints) {
+        let row = match row {
+            Some(row) if self.active_line && self.inner.interactive => row,
+            _ => return,
+        };
+        if !hints.active && self.hide_inactive_focus {
+            return;
+        }
+        let no_bg = StyleModifier::new().bg_color(Color::Default);
+        for x in 0..window.get_width().raw_value() {
+            if let Some(cell) = window.get_cell_mut(ColIndex::new(x), row) {
+                if no_bg.apply(cell.style) == cell.style {
+                    cell.style = self.active_line_style.apply(cell.style);
+                }
+            }
+        }
+    }
+
+    /// Apply the styles of the stripes to all cells without a background color. For
+    /// `Stripes::Members`, `member` returns the index of the top-level member drawn in a row.
+    fn draw_stripes(&self, window: &mut Window, member: impl Fn(RowIndex) -> Option<usize>) {
+        let no_bg = StyleModifier::new().bg_color(Color::Default);
+        for y in 0..window.get_height().raw_value() {
+            let row = RowIndex::new(y);
+            let stripe = match self.stripes {
+                None => return,
+                Some(Stripes::Lines) => self.scroll_offset + y as usize,
+                Some(Stripes::Members) => match member(row) {
+                    Some(member) => member,
+                    None => continue,
+                },
+            };
+            let style = self.stripe_styles[stripe % 2];
+            for x in 0..window.get_width().raw_value() {
+                if let Some(cell) = window.get_cell_mut(ColIndex::new(x), row) {
+                    if no_bg.apply(cell.style) == cell.style {
+                        cell.style = style.apply(cell.style);
+                    }
+                }
+            }
+        }
+    }
+
+    fn compute_space_demand(&self, width: Option<Width>) -> Demand2D {
+        if let Some((text, _)) = self.placeholder(0) {
+            let extent = layout::Extent::text(&text);
+            return Demand2D {
+                width: Demand::at_least(Width::new(extent.width_at(0) as i32).unwrap()),
+                height: Demand::exact(Height::new(extent.height as i32).unwrap()),
+            };
+        }
+        if self.inner.view_mode == ViewMode::Flat {
+            let lines = self.flat_lines();
+            let width = lines
+                .iter()
+                .map(|line| layout::Extent::text(&line.text).first)
+                .max()
+                .unwrap_or(0);
+            return Demand2D {
+                width: Demand::at_least(Width::new(width as i32).unwrap()),
+                height: Demand::exact(Height::new(lines.len() as i32).unwrap()),
+            };
+        }
+        // Custom renderers, summaries, error messages and hidden nodes are only considered by
+        // actually drawing the value.
+        if self.extents_are_exact() {
+            let extent = self.inner.value.extent(self.layout_params());
+            return Demand2D {
+                width: Demand::at_least(Width::new(extent.width_at(0) as i32).unwrap()),
+                height: Demand::exact(Height::new(extent.height as i32).unwrap()),
+            };
+        }
+        // Wrapped scalars take up more lines the narrower the window is.
+        let mut window = match width {
+            Some(width) => ExtentEstimationWindow::with_width(width),
+            None => ExtentEstimationWindow::unbounded(),
+        };
+        {
+            let mut cursor = Cursor::<ExtentEstimationWindow>::new(&mut window);
+            let info = self.rendering_info(RenderingHints::default());
+            self.inner.value.draw(
+                &mut cursor,
+                Some(&self.inner.active_element),
+                &info,
+                self.indentation,
+                &mut JsonPath::root(),
+            );
+        }
+        Demand2D {
+            width: Demand::at_least(window.extent_x()),
+            height: Demand::exact(window.extent_y()),
+        }
+    }
+}
+
+// The frames of the spinner shown in front of the message set using `JsonViewer::set_pending`.
+const SPINNER: [&str; 4] = ["|", "/", "-", "\\"];
+
+// The message set using `JsonViewer::set_error` as it is drawn: marked and with continuation
+// lines indented below the first one.
+fn error_block(message: &str) -> String {
+    format!("! {}", message.replace('\n', "\n  "))
+}
+
+// A duration of `secs` seconds in its largest unit, e.g., `2m` or `3d`.
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m", secs / 60),
+        3600..=86399 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+impl<'a> Widget for JsonViewerWidget<'a> {
+    fn space_demand(&self) -> Demand2D {
+        self.space_demand_within(None)
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        let pinned = self.pinned_lines();
+        let window = if pinned.is_empty() {
+            window
+        } else {
+            match window.split(RowIndex::new(pinned.len() as i32)) {
+                Ok((pinned_window, window)) => {
+                    self.draw_pinned(pinned_window, &pinned);
+                    window
+                }
+                Err(window) => return self.draw_pinned(window, &pinned),
+            }
+        };
+        if !self.status_line {
+            return self.draw_value(window, hints);
+        }
+        let height = window.get_height().raw_value();
+        let (value_window, mut status_window) = match window.split(RowIndex::new(height - 1)) {
+            Ok(split) => split,
+            Err(_) => return,
+        };
+        self.draw_value(value_window, hints);
+        let mut cursor = Cursor::new(&mut status_window);
+        cursor.apply_style_modifier(self.status_style);
+        cursor.fill_and_wrap_line();
+        cursor.move_to_y(RowIndex::new(0));
+        cursor.write(&self.status_text());
+    }
+}
+
+impl<'a> JsonViewerWidget<'a> {
+    /// The space demand if the widget is at most `width` wide (see `space_demand_for_width`).
+    fn space_demand_within(&self, width: Option<Width>) -> Demand2D {
+        let mut demand = self.value_space_demand(width);
+        let mut lines = self
+            .pinned_lines()
+            .into_iter()
+            .map(|(line, _)| line)
+            .collect::<Vec<_>>();
+        if self.status_line {
+            lines.push(self.status_text());
+        }
+        for line in lines {
+            let width = layout::Extent::text(&line).first;
+            demand = Demand2D {
+                width: demand
+                    .width
+                    .max(Demand::at_least(Width::new(width as i32).unwrap())),
+                height: demand.height + Demand::exact(Height::new(1).unwrap()),
+            };
+        }
+        if self.min_height.is_some() || self.max_height.is_some() {
+            let mut height = demand.height.min;
+            if let Some(max) = self.max_height {
+                height = height.min(max);
+            }
+            if let Some(min) = self.min_height {
+                height = height.max(min);
+            }
+            demand.height = Demand::exact(height);
+        }
+        demand
+    }
+    fn value_space_demand(&self, width: Option<Width>) -> Demand2D {
+        // Custom renderers and summaries may depend on state we do not know about, so we cannot
+        // cache in this case. The same holds for badges. The pending message is cheap to measure,
+        // but depends on `spinner`. Wrapped scalars and index labels change the size of the value
+        // regardless of the layout parameters.
+        if self.renderer.is_some()
+            || self.summary.is_some()
+            || self.badge.is_some()
+            || self.inner.pending.is_some()
+            || self.wrap_values
+            || self.index_labels
+        {
+            return self.compute_space_demand(width);
+        }
+        let params = self.layout_params();
+        match self.inner.demand.get() {
+            Some((cached_params, demand)) if cached_params == params => demand,
+            _ => {
+                let demand = self.compute_space_demand(width);
+                self.inner.demand.set(Some((params, demand)));
+                demand
+            }
+        }
+    }
+    fn draw_value(&self, mut window: Window, hints: RenderingHints) {
+        if let Some((text, style)) = self.placeholder(self.inner.spinner_frame(hints.blink)) {
+            let mut cursor = Cursor::new(&mut window);
+            cursor.apply_style_modifier(style);
+            cursor.write(&text);
+            return;
+        }
+        if self.inner.view_mode == ViewMode::Flat {
+            return self.draw_flat(window, hints);
+        }
+        let mut cursor = Cursor::new(&mut window);
+        cursor.move_to_y(RowIndex::new(-(self.scroll_offset as i32)));
+        let info = self.rendering_info(hints);
+        self.inner.value.draw(
+            &mut cursor,
+            Some(&self.inner.active_element),
+            &info,
+            self.indentation,
+            &mut JsonPath::root(),
+        );
+        self.draw_active_line(&mut window, info.active_row.get(), hints);
+        let member_rows = info.member_rows.borrow();
+        self.draw_stripes(&mut window, |row| stripes::member_at(&member_rows, row));
+    }
+}
+
+/// Fold the object or array at `path` (if there is one) so that it is not navigated into while it
+/// is hidden.
+fn fold_hidden(value: &mut DisplayValue, path: &JsonPath) {
+    let extended = match value.get(path) {
+        Some(DisplayValue::Object(obj)) => obj.extended,
+        Some(DisplayValue::Array(array)) => array.extended,
+        _ => false,
+    };
+    if extended {
+        match value.get_mut(path) {
+            Some(DisplayValue::Object(obj)) => obj.extended = false,
+            Some(DisplayValue::Array(array)) => array.extended = false,
+            _ => {}
+        }
+    }
+}
+
+/// Fold all shown objects and arrays at least `max_depth` levels below the root (or the closest
+/// node in `descended`) and add them to `cut`. The nodes are visited using an explicit stack
+/// (rather than recursively) so that deeply nested values do not overflow the stack.
+fn cut_below_depth(
+    value: &mut DisplayValue,
+    max_depth: usize,
+    descended: &BTreeSet<JsonPath>,
+    cut: &mut BTreeSet<JsonPath>,
+) {
+    enum Visit<'a> {
+        /// A node with its path segment (unless it is the root) and its depth.
+        Enter(Option<JsonPathSegment>, usize, &'a mut DisplayValue),
+        /// The end of the subtree of the node entered last (and not left yet).
+        Leave,
+    }
+    let mut location = JsonPath::root();
+    let mut folded = Vec::new();
+    let mut stack = vec![Visit::Enter(None, 0, &mut *value)];
+    while let Some(visit) = stack.pop() {
+        let (segment, depth, node) = match visit {
+            Visit::Enter(segment, depth, node) => (segment, depth, node),
+            Visit::Leave => {
+                location.pop();
+                continue;
+            }
+        };
+        if let Some(segment) = segment {
+            location.push(segment);
+            stack.push(Visit::Leave);
+        }
+        let is_empty = match node {
+            DisplayValue::Scalar(_) => continue,
+            DisplayValue::Object(obj) => obj.members.len() == 0,
+            DisplayValue::Array(array) => array.values.is_empty(),
+        };
+        let depth = if descended.contains(&location) {
+            0
+        } else if depth >= max_depth && !is_empty {
+            cut.insert(location.clone());
+            if node.extended() == Some(true) {
+                folded.push(location.clone());
+            }
+            match node {
+                DisplayValue::Object(obj) => obj.extended = false,
+                DisplayValue::Array(array) => array.extended = false,
+                DisplayValue::Scalar(_) => {}
+            }
+            continue;
+        } else {
+            depth
+        };
+        let children: Vec<(JsonPathSegment, &mut DisplayValue)> = match node {
+            DisplayValue::Object(obj) if obj.extended => obj
+                .members
+                .iter_mut()
+                .map(|member| (JsonPathSegment::Key(member.id()), &mut member.value))
+                .collect(),
+            DisplayValue::Array(array) if array.extended => {
+                let mut shown = array.shown_indices().collect::<Vec<_>>();
+                shown.sort_unstable();
+                let mut values = array.values.iter_mut();
+                let mut next = 0;
+                shown
+                    .into_iter()
+                    .map(|i| {
+                        let element = values.nth(i - next).expect("shown element exists");
+                        next = i + 1;
+                        (JsonPathSegment::Index(i), element)
+                    })
+                    .collect()
+            }
+            _ => Vec::new(),
+        };
+        stack.extend(
+            children
+                .into_iter()
+                .rev()
+                .map(|(segment, child)| Visit::Enter(Some(segment), depth + 1, child)),
+        );
+    }
+    // Folding a node changes the extents of all of its ancestors.
+    for path in folded {
+        value.get_mut(&path);
+    }
+}
+
+impl Scrollable for JsonViewer {
+    fn scroll_forwards(&mut self) -> OperationResult {
+        self.select_next()
+    }
+    fn scroll_backwards(&mut self) -> OperationResult {
+        self.select_previous()
+    }
+    fn scroll_to_beginning(&mut self) -> OperationResult {
+        let first = first_path_in(&self.value);
+        self.select_element(first)
+    }
+    fn scroll_to_end(&mut self) -> OperationResult {
+        let last = last_path_in(&self.value);
+        self.select_element(last)
+    }
+}
+
+/// Up and down select the previous and next element. Left folds the active object or array (or
+/// else the one containing the active element), right unfolds the active object or array or
+/// descends into it if it is unfolded already.
+impl Navigatable for JsonViewer {
+    fn move_up(&mut self) -> OperationResult {
+        self.select_previous()
+    }
+    fn move_down(&mut self) -> OperationResult {
+        self.select_next()
+    }
+    fn move_left(&mut self) -> OperationResult {
+        if !self.interactive {
+            return Err(());
+        }
+        let active = self.active_path();
+        let path = match self.value.get(&active).and_then(DisplayValue::extended) {
+            Some(true) => active,
+            _ => active.parent().ok_or(())?,
+        };
+        self.set_expanded(&path, false)
+    }
+    fn move_right(&mut self) -> OperationResult {
+        let active = self.active_path();
+        match self.value.get(&active).and_then(DisplayValue::extended) {
+            Some(true) => self.select_next(),
+            Some(false) if self.interactive => self.set_expanded(&active, true),
+            Some(false) => Err(()),
+            None => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use json::JsonValue;
+    use proptest::prelude::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+    use unsegen::base::WindowBuffer;
+
+    #[test]
+    fn test_cached_space_demand() {
+        let mut viewer = JsonViewer::new(&object! { "foo" => array!{ 1, 2 }, "bar" => "baz" });
+        let height = |viewer: &JsonViewer| viewer.as_widget().space_demand().height.min;
+        assert_eq!(height(&viewer), Height::new(7).unwrap());
+        assert!(viewer.demand.get().is_some());
+        assert_eq!(height(&viewer), Height::new(7).unwrap());
+
+        viewer.select_next().unwrap();
+        assert!(viewer.demand.get().is_none());
+        viewer.toggle_active_element().unwrap();
+        assert_eq!(height(&viewer), Height::new(4).unwrap());
+
+        let compact = viewer
+            .as_widget()
+            .compact_width(Width::new(80).unwrap())
+            .space_demand();
+        assert_eq!(compact.height.min, Height::new(1).unwrap());
+        assert_eq!(height(&viewer), Height::new(4).unwrap());
+    }
+
+    #[test]
+    fn test_prepared_update() {
+        let old = object! { "foo" => array!{ 1, 2 }, "bar" => "baz" };
+        let new = object! { "foo" => array!{ 1, 3 }, "bar" => "baz" };
+        let mut updated = JsonViewer::new(&old);
+        let mut prepared = JsonViewer::new(&old);
+        updated.update(&new);
+        let update = prepared.prepare_update(&new);
+        prepared.apply_prepared(update).unwrap();
+        assert!(prepared.diff_to_patch(&new).is_empty());
+        let changed = |viewer: &JsonViewer, path: &JsonPath| {
+            viewer.value.get(path).unwrap().unwrap_scalar_ref().changed
+        };
+        for path in &[
+            JsonPath::root().key("foo").index(1),
+            JsonPath::root().key("bar"),
+        ] {
+            assert_eq!(changed(&updated, path), changed(&prepared, path));
+        }
+
+        // Updates computed before the content was modified are rejected
+        let base = prepared.update_base();
+        prepared
+            .update_at(&JsonPath::root().key("bar"), JsonValue::from("qux"))
+            .unwrap();
+        assert!(prepared.apply_prepared(base.prepare_update(&old)).is_err());
+        let base = prepared.update_base();
+        prepared
+            .set_number_base(
+                &JsonPath::root().key("foo").index(0),
+                NumberBase::Hexadecimal,
+            )
+            .unwrap();
+        assert!(prepared.apply_prepared(base.prepare_update(&old)).is_err());
+        let update = prepared.update_base().prepare_update(&old);
+        prepared.apply_prepared(update).unwrap();
+        assert!(prepared.diff_to_patch(&old).is_empty());
+    }
+
+    #[test]
+    fn test_prepared_update_after_view_changes() {
+        let old = object! { "a" => array! { 1, 2 }, "b" => object! { "c" => 1 }, "d" => 1 };
+        let new = object! { "a" => array! { 1, 2, 3 }, "b" => object! { "c" => 2 }, "d" => 1 };
+        let mut viewer = JsonViewer::new(&old);
+        viewer.set_focus_follows_update(false);
+        let update = viewer.prepare_update(&new);
+
+        // Folds and the selection changed since the update was prepared are kept
+        viewer.select_path(&JsonPath::root().key("b")).unwrap();
+        viewer.toggle_active_element().unwrap();
+        viewer.select_path(&JsonPath::root().key("d")).unwrap();
+        viewer.apply_prepared(update).unwrap();
+        assert!(viewer.diff_to_patch(&new).is_empty());
+        assert_eq!(viewer.active_path(), JsonPath::root().key("d"));
+        assert_eq!(
+            viewer
+                .value
+                .get(&JsonPath::root().key("b"))
+                .unwrap()
+                .extended(),
+            Some(false)
+        );
+        assert_eq!(
+            viewer
+                .value
+                .get(&JsonPath::root().key("a"))
+                .unwrap()
+                .extended(),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_prepared_update_runs_content_passes() {
+        let old = object! { "token" => "secret", "name" => "foo" };
+        let new = object! { "token" => "other", "name" => "bar" };
+        let mut viewer = JsonViewer::new(&old);
+        viewer.set_redaction(|_, key| key.is_some_and(|key| key.to_string() == "token"));
+        let options = SearchOptions {
+            keys: false,
+            ..SearchOptions::default()
+        };
+        // Nothing matches yet, but the search stays active.
+        assert!(viewer.search("r", options).is_err());
+        let token = JsonPath::root().key("token");
+        let is_redacted = |viewer: &JsonViewer| {
+            viewer
+                .value
+                .get(&token)
+                .unwrap()
+                .unwrap_scalar_ref()
+                .redacted
+        };
+
+        let base = viewer.update_base();
+        let update = ::std::thread::spawn(move || base.prepare_update(&new))
+            .join()
+            .unwrap();
+        assert!(
+            update
+                .value
+                .get(&token)
+                .unwrap()
+                .unwrap_scalar_ref()
+                .redacted
+        );
+        let name = JsonPath::root().key("name");
+        assert_eq!(update.search.as_ref().unwrap().matches, vec![name.clone()]);
+        viewer.apply_prepared(update).unwrap();
+        assert!(is_redacted(&viewer));
+        assert_eq!(viewer.search.as_ref().unwrap().matches, vec![name]);
+
+        // Passes are run again if their settings changed since the update was prepared
+        let update = viewer.prepare_update(&old);
+        viewer.set_redaction_enabled(false);
+        viewer.apply_prepared(update).unwrap();
+        assert!(!is_redacted(&viewer));
+        assert_eq!(viewer.search.as_ref().unwrap().matches, vec![token]);
+    }
+
+    #[test]
+    fn test_status_line() {
+        let mut viewer = JsonViewer::new(&object! { "foo" => array! { 1, 2, 3, 4, 5 } });
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("foo"));
+        let status = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(3).unwrap());
+            viewer
+                .as_widget()
+                .status_line(true)
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer).pop().unwrap()
+        };
+        assert_eq!(status(&viewer), ".foo  array  6 nodes, 5 bytes  [3/5]");
+
+        viewer
+            .append_array_elements(&JsonPath::root().key("foo"), &[JsonValue::from(66)])
+            .unwrap();
+        assert_eq!(status(&viewer), ".foo  array  7 nodes, 7 bytes  [3/6]");
+        assert_eq!(
+            viewer.as_widget().status_line(true).space_demand().height,
+            viewer.as_widget().space_demand().height + Demand::exact(Height::new(1).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_aggregates() {
+        let mut viewer = JsonViewer::new(&object! {
+            "prices" => array! { 1, 2.5, "n/a", 6 },
+            "items" => array! { object! { "n" => 3 }, object! { "n" => 5 }, object! { "m" => 1 } }
+        });
+        let status = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(100).unwrap(), Height::new(1).unwrap());
+            viewer
+                .as_widget()
+                .status_line(true)
+                .aggregates(true)
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer).pop().unwrap()
+        };
+        let prices = JsonPath::root().key("prices");
+        viewer.select_path(&prices).unwrap();
+        assert!(status(&viewer).ends_with("  [3/4]  count 3, min 1, max 6, mean 3.167"));
+        viewer.select_path(&prices.clone().index(2)).unwrap();
+        assert!(status(&viewer).ends_with("  count 3, min 1, max 6, mean 3.167"));
+
+        let items = JsonPath::root().key("items");
+        viewer
+            .select_path(&items.clone().index(1).key("n"))
+            .unwrap();
+        assert!(status(&viewer).ends_with("  [3/3]  n: count 2, min 3, max 5, mean 4"));
+        assert_eq!(viewer.aggregates(&items, None), None);
+        assert_eq!(
+            viewer.aggregates(&items, Some("m")).map(|a| a.count),
+            Some(1)
+        );
+
+        viewer
+            .append_array_elements(&prices, &[JsonValue::from(-2)])
+            .unwrap();
+        let aggregates = viewer.aggregates(&prices, None).unwrap();
+        assert_eq!((aggregates.count, aggregates.min), (4, -2.0));
+        viewer.select_path(&JsonPath::root()).unwrap();
+        assert!(!status(&viewer).contains("count"));
+    }
+
+    #[test]
+    fn test_change_times() {
+        let now = Arc::new(Mutex::new(SystemTime::UNIX_EPOCH));
+        let mut viewer = JsonViewer::new(&object! { "a" => 1, "b" => 2 });
+        let clock = now.clone();
+        viewer.set_clock(move || *clock.lock().unwrap());
+        viewer.set_track_change_times(true);
+        let a = JsonPath::root().key("a");
+        let b = JsonPath::root().key("b");
+
+        *now.lock().unwrap() += Duration::from_secs(10);
+        viewer.update(&object! { "a" => 5, "b" => 2 });
+        let changed = SystemTime::UNIX_EPOCH + Duration::from_secs(10);
+        assert_eq!(viewer.last_changed(&a), Some(changed));
+        assert_eq!(viewer.last_changed(&b), None);
+
+        *now.lock().unwrap() += Duration::from_secs(150);
+        viewer.update(&object! { "a" => 5, "b" => 3 });
+        assert_eq!(viewer.last_changed(&a), Some(changed));
+        viewer.select_path(&a).unwrap();
+        let mut buffer = WindowBuffer::new(Width::new(60).unwrap(), Height::new(4).unwrap());
+        viewer
+            .as_widget()
+            .status_line(true)
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer).pop().unwrap(),
+            ".a  scalar  1 nodes, 1 bytes  changed 2m ago"
+        );
+
+        viewer.set_track_change_times(false);
+        assert_eq!(viewer.last_changed(&b), None);
+    }
+
+    #[test]
+    fn test_snapshot() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1, "b" => 2, "c" => 3 });
+        assert_eq!(viewer.compare_to_snapshot(), Err(()));
+        viewer.snapshot();
+        viewer.update(&object! { "a" => 5, "b" => 2, "c" => 3 });
+        viewer.update(&object! { "a" => 5, "b" => 7, "c" => 3, "d" => 4 });
+        assert_eq!(viewer.change_count(), 1);
+
+        viewer.compare_to_snapshot().unwrap();
+        assert_eq!(
+            viewer.changes(),
+            vec![JsonPath::root().key("a"), JsonPath::root().key("b")]
+        );
+
+        viewer.update(&object! { "a" => 5, "b" => 7, "c" => 3, "d" => 4 });
+        assert_eq!(viewer.change_count(), 0);
+        viewer.discard_snapshot();
+        assert_eq!(viewer.compare_to_snapshot(), Err(()));
+    }
+
+    #[test]
+    fn test_undo_view_change() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => array! { 1, 2, 3 },
+            "b" => object! { "c" => 4 }
+        });
+        let a = JsonPath::root().key("a");
+        let b = JsonPath::root().key("b");
+        let extended = |viewer: &JsonViewer, path: &JsonPath| viewer.value.get(path)?.extended();
+        assert_eq!(viewer.undo_view_change(), Err(()));
+
+        viewer.select_path(&b).unwrap();
+        viewer.toggle_active_element().unwrap();
+        viewer.select_path(&a).unwrap();
+        viewer.shrink_active_array().unwrap();
+        viewer.collapse_all();
+        assert_eq!(extended(&viewer, &a), Some(false));
+        assert_eq!(extended(&viewer, &b), Some(false));
+
+        viewer.undo_view_change().unwrap();
+        assert_eq!(extended(&viewer, &a), Some(true));
+        assert_eq!(extended(&viewer, &b), Some(false));
+        assert_eq!(viewer.active_path(), a);
+
+        viewer.undo_view_change().unwrap();
+        let count = |viewer: &JsonViewer| match viewer.value.get(&a) {
+            Some(DisplayValue::Array(array)) => array.num_extended,
+            _ => unreachable!(),
+        };
+        assert_eq!(count(&viewer), 3);
+        viewer.undo_view_change().unwrap();
+        assert_eq!(extended(&viewer, &b), Some(true));
+        assert_eq!(viewer.active_path(), b);
+        assert_eq!(viewer.undo_view_change(), Err(()));
+
+        viewer.redo_view_change().unwrap();
+        assert_eq!(extended(&viewer, &b), Some(false));
+        viewer.select_path(&a).unwrap();
+        viewer.toggle_active_element().unwrap();
+        assert_eq!(viewer.redo_view_change(), Err(()));
+
+        // Only the nodes affected by a change are recorded (unless all of them are).
+        let recorded = |viewer: &JsonViewer| {
+            let state = viewer.view_history.next_undo().unwrap();
+            (state.folds.len(), state.complete)
+        };
+        assert_eq!(recorded(&viewer), (1, false));
+        viewer.collapse_all();
+        assert_eq!(recorded(&viewer), (3, true));
+        viewer.set_expanded(&b, true).unwrap();
+        assert_eq!(recorded(&viewer), (2, false));
+        viewer.undo_view_change().unwrap();
+        assert_eq!(extended(&viewer, &b), Some(false));
+        viewer.redo_view_change().unwrap();
+        assert_eq!(extended(&viewer, &b), Some(true));
+    }
+
+    #[test]
+    fn test_interactive() {
+        let mut viewer = JsonViewer::new(&object! { "a" => array! { 1, 2 } });
+        viewer.select_next().unwrap();
+        viewer.set_interactive(false);
+        assert_eq!(viewer.toggle_active_element(), Err(()));
+        assert_eq!(viewer.toggle_active_container(), Err(()));
+        assert_eq!(viewer.shrink_active_array(), Err(()));
+        assert_eq!(viewer.move_left(), Err(()));
+        let a = JsonPath::root().key("a");
+        assert_eq!(viewer.value.get(&a).unwrap().extended(), Some(true));
+        {
+            let widget = viewer.as_widget();
+            let info = widget.rendering_info(RenderingHints::default());
+            let none = FocusStyles::uniform(StyleModifier::new());
+            assert_eq!(info.active_focused_style, none);
+            assert_eq!(info.inactive_focused_style, none);
+        }
+
+        viewer.set_expanded(&a, false).unwrap();
+        viewer.set_interactive(true);
+        viewer.select_path(&a).unwrap();
+        assert_eq!(viewer.toggle_active_element(), Ok(ToggleResult::Expanded));
+    }
+
+    #[test]
+    fn test_focus_styles() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1, "b" => array! { 2 } });
+        let bold = |viewer: &JsonViewer, active: bool, hide: bool, (x, y): (i32, i32)| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(6).unwrap());
+            viewer
+                .as_widget()
+                .active_focused(StyleModifier::new())
+                .active_focused_kind(NodeKind::Scalar, StyleModifier::new().bold(true))
+                .hide_inactive_focus(hide)
+                .draw(buffer.as_window(), RenderingHints::new().active(active));
+            test_util::bold_at(&mut buffer, x, y)
+        };
+        // `  a: 1,` and `  b: [ [-]`
+        viewer.select_path(&JsonPath::root().key("a")).unwrap();
+        assert!(bold(&viewer, true, false, (5, 1)));
+        viewer.select_path(&JsonPath::root().key("b")).unwrap();
+        assert!(!bold(&viewer, true, false, (7, 2)));
+        assert!(bold(&viewer, false, false, (7, 2)));
+        assert!(!bold(&viewer, false, true, (7, 2)));
+    }
+
+    #[test]
+    fn test_style_precedence() {
+        let mut viewer = JsonViewer::new(&object! { "a" => "x" });
+        viewer.update(&object! { "a" => "y" });
+        viewer.select_path(&JsonPath::root().key("a")).unwrap();
+        viewer.highlight_matches("y");
+        let bold = |layers: &[StyleLayer]| {
+            let mut buffer = WindowBuffer::new(Width::new(10).unwrap(), Height::new(3).unwrap());
+            viewer
+                .as_widget()
+                .active_focused(StyleModifier::new().bold(false))
+                .item_changed(StyleModifier::new().bold(true))
+                .match_highlight(StyleModifier::new())
+                .style_precedence(layers)
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::bold_at(&mut buffer, 5, 1)
+        };
+        // `  a: y`
+        assert!(bold(&[]));
+        assert!(!bold(&[StyleLayer::Changed, StyleLayer::Focused]));
+        assert!(bold(&[
+            StyleLayer::Focused,
+            StyleLayer::Match,
+            StyleLayer::Changed
+        ]));
+    }
+
+    #[test]
+    fn test_wrap_values() {
+        let viewer = JsonViewer::new(&object! {
+            "key" => "abcdefghijklmnopqrstuvwxyz0123456789",
+            "deep" => object! { "a" => "x" }
+        });
+        let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(5).unwrap());
+        viewer
+            .as_widget()
+            .wrap_values(true)
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  key: abcdefghijklmnopqrstuvw",
+                "       xyz0123456789,",
+                "  deep: { [-]",
+                "    a: x,",
+            ]
+        );
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(4).unwrap());
+        viewer
+            .as_widget()
+            .wrap_values(true)
+            .draw(buffer.as_window(), RenderingHints::default());
+        // Too little room right of the key to indent up to the value.
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  key: abcdefghijklm",
+                "  nopqrstuvwxyz01234",
+                "  56789,"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_space_demand_for_width() {
+        let viewer = JsonViewer::new(&object! { "key" => "abcdefghijklmnopqrstuvwxyz0123456789" });
+        let widget = viewer.as_widget().wrap_values(true).status_line(true);
+        assert_eq!(
+            widget.space_demand().height,
+            Demand::exact(Height::new(4).unwrap())
+        );
+        let demand = widget.space_demand_for_width(Width::new(30).unwrap());
+        assert_eq!(demand.height, Demand::exact(Height::new(5).unwrap()));
+        assert_eq!(demand.width.min, Width::new(30).unwrap());
+        let widget = viewer.as_widget();
+        let demand = widget.space_demand_for_width(Width::new(30).unwrap());
+        assert_eq!(demand, widget.space_demand());
+    }
+
+    #[test]
+    fn test_height_demand() {
+        let viewer = JsonViewer::new(&object! { "a" => 1, "b" => 2 });
+        let height = |widget: JsonViewerWidget| widget.space_demand().height;
+        let exact = |lines| Demand::exact(Height::new(lines).unwrap());
+        assert_eq!(height(viewer.as_widget()), exact(4));
+        let max = Height::new(3).unwrap();
+        assert_eq!(height(viewer.as_widget().max_height(max)), exact(3));
+        let min = Height::new(6).unwrap();
+        assert_eq!(height(viewer.as_widget().min_height(min)), exact(6));
+        let widget = viewer
+            .as_widget()
+            .status_line(true)
+            .max_height(Height::new(8).unwrap());
+        assert_eq!(height(widget), exact(5));
+    }
+
+    #[test]
+    fn test_index_labels() {
+        let viewer = JsonViewer::new(&array! { object! { "a" => array! { 1, 2 } }, "b" });
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(8).unwrap());
+        let widget = viewer.as_widget().index_labels(true);
+        widget.draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                "[ [-]",
+                "  [0]: { [-]",
+                "    a: [ [-]",
+                "      [0]: 1,",
+                "      [1]: 2,",
+                "    ] <-2/2 >,",
+                "  },",
+                "  [1]: b,",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_array_tail() {
+        let mut viewer = JsonViewer::new(&array! { 0, 1, 2, 3, 4, 5, 6, 7, 8, 9 });
+        viewer
+            .set_array_visible_count(&JsonPath::root(), 2)
+            .unwrap();
+        viewer.set_array_tail_count(&JsonPath::root(), 2).unwrap();
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(7).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                "[ [-]",
+                "  0,",
+                "  1,",
+                "  … 6 elided …",
+                "  8,",
+                "  9,",
+                "] <-4/10+>"
+            ]
+        );
+        viewer.select_path(&JsonPath::root().index(1)).unwrap();
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().index(8));
+        viewer.select_previous().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().index(1));
+
+        assert_eq!(viewer.grow_active_array_tail(), Ok(ToggleResult::Grew(5)));
+        assert_eq!(viewer.shrink_active_array(), Ok(ToggleResult::Shrank(4)));
+        // The active element was hidden, so the grow knob is active.
+        assert_eq!(viewer.active_path(), JsonPath::root());
+        viewer
+            .set_array_visible_count(&JsonPath::root(), 8)
+            .unwrap();
+        assert_eq!(viewer.stats().rendered_lines, 12);
+        assert_eq!(
+            viewer.shrink_active_array_tail(),
+            Ok(ToggleResult::Shrank(9))
+        );
+    }
+
+    #[test]
+    fn test_array_paging() {
+        let root = JsonPath::root();
+        let mut viewer = JsonViewer::new(&array! { 0, 1, 2, 3, 4, 5, 6, 7, 8, 9 });
+        assert!(viewer.next_page(&root).is_err());
+        assert!(viewer.set_array_page_size(&root, Some(0)).is_err());
+        viewer.set_array_page_size(&root, Some(4)).unwrap();
+        viewer.next_page(&root).unwrap();
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(6).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec!["[ [-]", "  4,", "  5,", "  6,", "  7,", "] <-page 2/3+>"]
+        );
+        viewer.select_path(&root.clone().index(5)).unwrap();
+        assert_eq!(viewer.grow_active_array(), Ok(ToggleResult::Grew(2)));
+        assert!(viewer.next_page(&root).is_err());
+        assert!(viewer.set_array_tail_count(&root, 1).is_err());
+        viewer.jump_to_index(&root, 1).unwrap();
+        assert!(viewer.prev_page(&root).is_err());
+        assert!(viewer.jump_to_index(&root, 10).is_err());
+
+        viewer.select_path(&root.clone().index(9)).unwrap();
+        assert_eq!(viewer.stats().rendered_lines, 4);
+        viewer.update(&array! { 0, 1, 2, 3, 4, 5 });
+        assert_eq!(viewer.stats().rendered_lines, 4);
+        viewer.set_array_page_size(&root, None).unwrap();
+        assert_eq!(viewer.stats().rendered_lines, 8);
+    }
+
+    #[test]
+    fn test_select_array_index() {
+        let items = JsonValue::Array((0..100).map(JsonValue::from).collect());
+        let mut viewer = JsonViewer::new(&object! { "items" => items, "count" => 100 });
+        let items = JsonPath::root().key("items");
+        viewer.select_array_index(&items, 42).unwrap();
+        assert_eq!(viewer.active_path(), items.clone().index(42));
+        assert_eq!(viewer.stats().rendered_lines, 48);
+
+        viewer.set_array_page_size(&items, Some(10)).unwrap();
+        viewer.select_array_index(&items, 87).unwrap();
+        assert_eq!(viewer.active_path(), items.clone().index(87));
+        assert_eq!(viewer.stats().rendered_lines, 15);
+
+        assert!(viewer.select_array_index(&items, 100).is_err());
+        assert!(viewer
+            .select_array_index(&JsonPath::root().key("count"), 0)
+            .is_err());
+        assert_eq!(viewer.active_path(), items.clone().index(87));
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let value = array! { array! { 1, 2 }, 2, array! { 1, 2 }, 3, 2, array! { 2, 1 } };
+        let mut viewer = JsonViewer::new(&value);
+        let root = JsonPath::root();
+        viewer.select_path(&root.clone().index(3)).unwrap();
+        assert_eq!(viewer.mark_duplicates(), Ok(4));
+        assert_eq!(
+            viewer.duplicates().cloned().collect::<Vec<_>>(),
+            [0, 1, 2, 4]
+                .iter()
+                .map(|&i| root.clone().index(i))
+                .collect::<Vec<_>>()
+        );
+        viewer.next_duplicate().unwrap();
+        assert_eq!(viewer.active_path(), root.clone().index(4));
+        viewer.next_duplicate().unwrap();
+        assert_eq!(viewer.active_path(), root.clone().index(0));
+        // The nested array of the active element is not searched.
+        viewer.select_path(&root.clone().index(0).index(1)).unwrap();
+        viewer.next_duplicate().unwrap();
+        assert_eq!(viewer.active_path(), root.clone().index(1));
+
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(13).unwrap());
+        viewer
+            .as_widget()
+            .active_focused(StyleModifier::new())
+            .duplicate(StyleModifier::new().bold(true))
+            .draw(buffer.as_window(), RenderingHints::default());
+        let mut bold = |x: i32, y: i32| test_util::bold_at(&mut buffer, x, y);
+        // `  [ [-]`, `  2,` and `  2,`; not the elements of the array or `  3,`
+        assert!(bold(2, 1) && bold(2, 5) && bold(2, 11));
+        assert!(!bold(4, 2) && !bold(2, 10));
+
+        viewer.clear_duplicates();
+        assert_eq!(viewer.duplicates().count(), 0);
+        viewer.mark_duplicates().unwrap();
+        viewer.update(&value);
+        assert_eq!(viewer.duplicates().count(), 0);
+        assert!(viewer.next_duplicate().is_err());
+        let mut viewer = JsonViewer::new(&object! { "a" => 1 });
+        assert!(viewer.mark_duplicates().is_err());
+    }
+
+    #[test]
+    fn test_array_sort() {
+        let root = JsonPath::root();
+        let mut viewer = JsonViewer::new(&array! { 3, "b", 1, 2, "a" });
+        let sort = ArraySort::new(SortKey::Value, false);
+        viewer.set_array_sort(&root, Some(sort)).unwrap();
+        let lines = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(5).unwrap());
+            viewer
+                .as_widget()
+                .index_labels(true)
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(
+            lines(&viewer),
+            vec![
+                "[ [-]",
+                "  [2]: 1,",
+                "  [3]: 2,",
+                "  [0]: 3,",
+                "] <-3/5, sorted ↑+>"
+            ]
+        );
+        viewer.select_path(&root.clone().index(2)).unwrap();
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.active_path(), root.clone().index(3));
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.active_path(), root.clone().index(0));
+
+        // Updated elements are sorted again.
+        viewer.update(&array! { 3, "b", 1, 5, "a", 0 });
+        assert_eq!(
+            lines(&viewer),
+            vec![
+                "[ [-]",
+                "  [5]: 0,",
+                "  [2]: 1,",
+                "  [0]: 3,",
+                "] <-3/6, sorted ↑+>"
+            ]
+        );
+        assert!(viewer.set_array_sort(&root.clone().index(0), None).is_err());
+        viewer.set_array_sort(&root, None).unwrap();
+        assert_eq!(
+            lines(&viewer),
+            vec!["[ [-]", "  [0]: 3,", "  [1]: b,", "  [2]: 1,", "] <-3/6+>"]
+        );
+    }
+
+    #[test]
+    fn test_active_line() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1, "b" => array! { 2, 3 } });
+        viewer.select_path(&JsonPath::root().key("b")).unwrap();
+        let underlined_rows = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(6).unwrap());
+            viewer
+                .as_widget()
+                .highlight_active_line(true)
+                .active_line(StyleModifier::new().underline(true))
+                .scroll_offset(1)
+                .draw(buffer.as_window(), RenderingHints::default());
+            (0..6)
+                .filter(|&y| test_util::format_at(&mut buffer, 19, y).underline)
+                .collect::<Vec<_>>()
+        };
+        // `  a: 1`, `  b: [ …`
+        assert_eq!(underlined_rows(&viewer), vec![1]);
+        viewer.set_view_mode(ViewMode::Flat);
+        // `.a = 1`, `.b[0] = 2`, `.b[1] = 3`
+        viewer
+            .select_path(&JsonPath::root().key("b").index(1))
+            .unwrap();
+        assert_eq!(underlined_rows(&viewer), vec![1]);
+        viewer.set_interactive(false);
+        assert_eq!(underlined_rows(&viewer), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn test_stripes() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => 1,
+            "b" => object! { "c" => 2 },
+            "d" => 3
+        });
+        // The stripe of each row: 0 (underlined), 1 (italic) or none.
+        let stripes = |viewer: &JsonViewer, stripes: Stripes| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(7).unwrap());
+            viewer
+                .as_widget()
+                .stripes(Some(stripes))
+                .stripe(
+                    StyleModifier::new().underline(true),
+                    StyleModifier::new().italic(true),
+                )
+                .draw(buffer.as_window(), RenderingHints::default());
+            (0..7)
+                .map(|y| {
+                    let format = test_util::format_at(&mut buffer, 19, y);
+                    match (format.underline, format.italic) {
+                        (true, false) => Some(0),
+                        (false, true) => Some(1),
+                        _ => None,
+                    }
+                })
+                .collect::<Vec<_>>()
+        };
+        let (even, odd) = (Some(0), Some(1));
+        assert_eq!(
+            stripes(&viewer, Stripes::Lines),
+            vec![even, odd, even, odd, even, odd, even]
+        );
+        // `{`, `  a: 1`, `  b: {`, `    c: 2`, `  }`, `  d: 3`, `}`
+        assert_eq!(
+            stripes(&viewer, Stripes::Members),
+            vec![None, even, odd, odd, odd, even, None]
+        );
+        viewer.set_view_mode(ViewMode::Flat);
+        // `.a = 1`, `.b.c = 2`, `.d = 3`
+        assert_eq!(
+            stripes(&viewer, Stripes::Members),
+            vec![even, odd, even, None, None, None, None]
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => array! { 1, object! { "b" => true } },
+            "c" => "d"
+        });
+        let stats = viewer.stats();
+        assert_eq!(
+            stats,
+            DocumentStats {
+                objects: 2,
+                arrays: 1,
+                scalars: 3,
+                max_depth: 4,
+                rendered_lines: 9,
+            }
+        );
+        viewer.toggle_active_element().unwrap();
+        assert_eq!(viewer.stats().rendered_lines, 1);
+        viewer.update(&object! { "c" => "d" });
+        assert_eq!(viewer.stats().objects, 1);
+        assert_eq!(viewer.stats().max_depth, 2);
+    }
+
+    #[test]
+    fn test_select_key_starting_with() {
+        let mut viewer = JsonViewer::new(&object! {
+            "alpha" => 1,
+            "Beta" => object! { "bar" => 2, "baz" => 3 },
+            "bravo" => 4
+        });
+        viewer.select_key_starting_with("b").unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("Beta"));
+        viewer.select_key_starting_with("br").unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("bravo"));
+        assert!(viewer.select_key_starting_with("x").is_err());
+        assert_eq!(viewer.active_path(), JsonPath::root().key("bravo"));
+
+        // Within a nested object, only its members are considered
+        viewer
+            .select_path(&JsonPath::root().key("Beta").key("bar"))
+            .unwrap();
+        viewer.select_key_starting_with("baz").unwrap();
+        assert_eq!(
+            viewer.active_path(),
+            JsonPath::root().key("Beta").key("baz")
+        );
+        assert!(viewer.select_key_starting_with("alpha").is_err());
+    }
+
+    #[test]
+    fn test_search() {
+        let mut viewer = JsonViewer::new(&object! {
+            "name" => "foo",
+            "items" => array! { 1, 2, 3, "foo", object! { "food" => true } }
+        });
+        viewer.search("foo", SearchOptions::default()).unwrap();
+        assert_eq!(viewer.match_count(), 3);
+        assert_eq!(viewer.current_match(), Some(0));
+        assert_eq!(viewer.active_path(), JsonPath::root().key("name"));
+
+        // Folded and hidden nodes are revealed
+        viewer.select_path(&JsonPath::root().key("items")).unwrap();
+        viewer.toggle_active_element().unwrap();
+        viewer.next_match().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("items").index(3));
+        viewer.next_match().unwrap();
+        let food = JsonPath::root().key("items").index(4).key("food");
+        assert_eq!(viewer.active_path(), food);
+        viewer.next_match().unwrap();
+        assert_eq!(viewer.current_match(), Some(0));
+        viewer.previous_match().unwrap();
+        assert_eq!(viewer.active_path(), food);
+
+        let keys = SearchOptions {
+            values: false,
+            ..SearchOptions::default()
+        };
+        viewer.search("foo", keys).unwrap();
+        assert_eq!(viewer.match_count(), 1);
+        let values = SearchOptions {
+            keys: false,
+            ..SearchOptions::default()
+        };
+        viewer.search("foo", values).unwrap();
+        assert_eq!(viewer.match_count(), 2);
+        viewer.search("FOO", values).unwrap_err();
+        let whole_words = SearchOptions {
+            case_insensitive: true,
+            whole_word: true,
+            ..SearchOptions::default()
+        };
+        viewer.search("FOO", whole_words).unwrap();
+        assert_eq!(viewer.match_count(), 2);
+
+        // Matches are updated along with the value
+        viewer.update(&object! { "name" => "bar" });
+        assert_eq!(viewer.match_count(), 0);
+        assert!(viewer.next_match().is_err());
+        viewer.clear_search();
+        assert_eq!(viewer.current_match(), None);
+    }
+
+    #[test]
+    fn test_highlight_matches() {
+        let mut viewer = JsonViewer::new(&object! { "foobar" => "barfoo" });
+        viewer.select_next().unwrap();
+        viewer.highlight_matches("foo");
+        let highlight = StyleModifier::new().bold(true);
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(3).unwrap());
+        viewer
+            .as_widget()
+            .active_focused(StyleModifier::new())
+            .match_highlight(highlight)
+            .draw(buffer.as_window(), RenderingHints::default());
+        let styles = (0..18)
+            .map(|x| test_util::bold_at(&mut buffer, x, 1))
+            .collect::<Vec<_>>();
+        // `  foobar: barfoo,`
+        let expected = [
+            false, false, true, true, true, false, false, false, false, false, false, false, false,
+            true, true, true, false, false,
+        ];
+        assert_eq!(styles, expected);
+        assert_eq!(test_util::lines(&mut buffer)[1], "  foobar: barfoo,");
+
+        viewer.clear_highlights();
+        assert!(viewer.highlight.is_none());
+    }
+
+    #[test]
+    fn test_annotate() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => 1, "b" => object! { "c" => 2 }, "d" => array! { 3 }
+        });
+        viewer
+            .set_expanded(&JsonPath::root().key("d"), false)
+            .unwrap();
+        let style = StyleModifier::new();
+        viewer.annotate(JsonPath::root().key("a"), "checked", style);
+        viewer.annotate(JsonPath::root().key("b"), "from\nconfig", style);
+        viewer.annotate(JsonPath::root().key("d"), "empty?", style);
+        viewer.annotate(JsonPath::root().key("e"), "missing", style);
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(6).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(
+            draw(&viewer),
+            vec![
+                "{ [-]",
+                "  a: 1  // checked,",
+                "  b: { [-]  // from config",
+                "    c: 2,",
+                "  },",
+                "  d: [ [+] ]  // empty?,",
+            ]
+        );
+        assert_eq!(
+            viewer.annotation(&JsonPath::root().key("a")),
+            Some("checked")
+        );
+        viewer
+            .remove_annotation(&JsonPath::root().key("a"))
+            .unwrap();
+        assert!(viewer
+            .remove_annotation(&JsonPath::root().key("a"))
+            .is_err());
+        viewer.clear_annotations();
+        assert_eq!(draw(&viewer)[2], "  b: { [-]");
+    }
+
+    #[test]
+    fn test_word_diff() {
+        let mut viewer = JsonViewer::new(&object! { "a" => "GET /users/1 ok", "b" => 1 });
+        viewer.update(&object! { "a" => "GET /users/2 ok", "b" => 2 });
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(4).unwrap());
+        viewer
+            .as_widget()
+            .item_changed(StyleModifier::new().bold(true))
+            .active_focused(StyleModifier::new())
+            .draw(buffer.as_window(), RenderingHints::default());
+        let mut bold = |x: i32, y: i32| test_util::bold_at(&mut buffer, x, y);
+        // `  a: GET /users/2 ok,` and `  b: 2,`
+        assert!(bold(16, 1));
+        assert!(!bold(5, 1) && !bold(15, 1) && !bold(18, 1));
+        assert!(bold(5, 2));
+    }
+
+    #[test]
+    fn test_numeric_deltas() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 42, "b" => 1.5, "c" => "x" });
+        viewer.set_numeric_deltas(true);
+        viewer.update(&object! { "a" => 47, "b" => 1.25, "c" => "y" });
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(5).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(
+            draw(&viewer)[1..4],
+            ["  a: 47 (+5),", "  b: 1.25 (-0.25),", "  c: y,"]
+        );
+        viewer.update(&object! { "a" => 47, "b" => 1.25, "c" => "y" });
+        assert_eq!(draw(&viewer)[1], "  a: 47,");
+
+        viewer.update(&object! { "a" => 40, "b" => 1.25, "c" => "y" });
+        viewer.set_numeric_deltas(false);
+        assert_eq!(draw(&viewer)[1], "  a: 40,");
+    }
+
+    #[test]
+    fn test_history() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 3, "b" => 0 });
+        let a = JsonPath::root().key("a");
+        viewer.set_history_limit(2);
+        for value in [5, 8, 9].iter() {
+            viewer.update(&object! { "a" => *value, "b" => 0 });
+        }
+        assert_eq!(viewer.node(&a).unwrap().history(), ["5", "8"]);
+        assert!(viewer.toggle_history(&JsonPath::root().key("b")).is_err());
+
+        viewer.select_path(&a).unwrap();
+        assert_eq!(viewer.toggle_active_history(), Ok(true));
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(5).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec!["{ [-]", "  a: 9", "  was: 5, 8,", "  b: 0,", "}"]
+        );
+
+        assert_eq!(viewer.stats().rendered_lines, 5);
+
+        viewer.set_history_limit(1);
+        assert_eq!(viewer.node(&a).unwrap().history(), ["8"]);
+    }
+
+    #[test]
+    fn test_badge() {
+        let viewer = JsonViewer::new(&object! { "f" => "fn()", "xs" => array! { 1, "a" } });
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(7).unwrap());
+        viewer
+            .as_widget()
+            .badge(|path, node| match (path.segments().last(), node.kind()) {
+                (Some(JsonPathSegment::Key(id)), _) if id.key == "f".into() => Some("ƒ".into()),
+                (_, NodeKind::Array) => Some("#".into()),
+                (_, NodeKind::Scalar) if node.scalar() == Some("a") => Some("!".into()),
+                _ => None,
+            })
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  ƒ f: fn(),",
+                "  # xs: [ [-]",
+                "    1,",
+                "    ! a,",
+                "  ] <-2/2 >,",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_badge_space_demand() {
+        let viewer = JsonViewer::new(&object! { "f" => "fn()", "g" => 1 });
+        let width = |widget: JsonViewerWidget| widget.space_demand().width.min;
+        assert_eq!(width(viewer.as_widget()), Width::new(10).unwrap());
+        assert!(viewer.demand.get().is_some());
+        let badged = viewer.as_widget().badge(|path, _| {
+            if path.segments().len() == 1 {
+                Some("[fn]".into())
+            } else {
+                None
+            }
+        });
+        // "  [fn] f: fn(),"
+        assert_eq!(width(badged), Width::new(15).unwrap());
+        assert_eq!(width(viewer.as_widget()), Width::new(10).unwrap());
+    }
+
+    #[test]
+    fn test_node_style() {
+        let viewer = JsonViewer::new(&object! { "a" => "xy", "b" => array! { 1 } });
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(6).unwrap());
+        let a = JsonPath::root().key("a");
+        viewer
+            .as_widget()
+            .active_focused(StyleModifier::new())
+            .node_style(|path, kind| {
+                if *path == a || kind == NodeKind::Array {
+                    Some(StyleModifier::new().bold(true))
+                } else {
+                    None
+                }
+            })
+            .draw(buffer.as_window(), RenderingHints::default());
+        let mut bold = |x: i32, y: i32| test_util::bold_at(&mut buffer, x, y);
+        // `  a: xy,` and `  b: [ [-]`
+        assert!(bold(5, 1) && bold(6, 1));
+        assert!(!bold(0, 0) && !bold(2, 1));
+        assert!(bold(5, 2));
+        assert!(!bold(4, 3));
+    }
+
+    #[test]
+    fn test_indentation_guides() {
+        let viewer = JsonViewer::new(&object! {
+            "a" => object! { "b" => "x\ny" },
+            "c" => array! { 1 }
+        });
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(9).unwrap());
+        viewer
+            .as_widget()
+            .indentation_guides(true)
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "│ a: { [-]",
+                "│ │ b: x",
+                "│ │ y,",
+                "│ },",
+                "│ c: [ [-]",
+                "│ │ 1,",
+                "│ ] <-1/1 >,",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rainbow() {
+        let viewer = JsonViewer::new(&object! { "a" => object! { "b" => 1 } });
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(5).unwrap());
+        viewer
+            .as_widget()
+            .active_focused(StyleModifier::new())
+            .rainbow(vec![
+                StyleModifier::new().bold(true),
+                StyleModifier::new().underline(true),
+            ])
+            .draw(buffer.as_window(), RenderingHints::default());
+        let mut format = |x: i32, y: i32| {
+            let format = test_util::format_at(&mut buffer, x, y);
+            (format.bold, format.underline)
+        };
+        // `{ [-]`, `  a: { [-]`, `    b: 1,`, `  },`, `}`
+        assert_eq!(format(0, 0), (true, false));
+        assert_eq!(format(2, 1), (false, true));
+        assert_eq!(format(5, 1), (false, true));
+        assert_eq!(format(4, 2), (true, false));
+        assert_eq!(format(7, 2), (false, false));
+        assert_eq!(format(2, 3), (false, true));
+        assert_eq!(format(3, 3), (false, false));
+        assert_eq!(format(0, 4), (true, false));
+    }
+
+    #[test]
+    fn test_collapsed_summary() {
+        let mut viewer = JsonViewer::new(&object! {
+            "user" => object! { "name" => "foo", "id" => 42, "tags" => array! {} },
+            "ids" => array! { 1, 2, 3, 4 },
+            "nested" => array! { array! {} }
+        });
+        for key in &["user", "ids", "nested"] {
+            viewer
+                .set_expanded(&JsonPath::root().key(*key), false)
+                .unwrap();
+        }
+        let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(5).unwrap());
+        viewer
+            .as_widget()
+            .collapsed_summary(|_, node| node.summary())
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  user: { [+] name=foo, id=42 … },",
+                "  ids: [ [+] 1, 2, 3 … ],",
+                "  nested: [ [+] ],",
+                "}",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pin_path() {
+        let mut viewer = JsonViewer::new(&object! {
+            "status" => "ok",
+            "items" => array! { 1, 2, 3 }
+        });
+        viewer.pin_path(JsonPath::root().key("status"));
+        viewer.pin_path(JsonPath::root().key("items"));
+        viewer.pin_path(JsonPath::root().key("missing"));
+        viewer.pin_path(JsonPath::root().key("status"));
+        assert_eq!(viewer.pinned_paths().len(), 3);
+        viewer.select_path(&JsonPath::root().key("items")).unwrap();
+        viewer.toggle_active_element().unwrap();
+
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(6).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                ".status: ok",
+                ".items: [ 3 elements",
+                "{ [-]",
+                "  status: ok,",
+                "  items: [ [+] ],",
+                "}"
+            ]
+        );
+        assert_eq!(
+            viewer.as_widget().space_demand().height,
+            Demand::exact(Height::new(6).unwrap())
+        );
+
+        viewer.unpin_path(&JsonPath::root().key("items")).unwrap();
+        assert!(viewer.unpin_path(&JsonPath::root().key("items")).is_err());
+        assert_eq!(
+            viewer.as_widget().space_demand().height,
+            Demand::exact(Height::new(5).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_hide_path() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => 1,
+            "blob" => array! { 1, 2 }
+        });
+        assert!(viewer.hide_path(JsonPath::root().key("c")).is_err());
+        viewer.hide_path(JsonPath::root().key("blob")).unwrap();
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(5).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(
+            draw(&viewer),
+            vec!["{ [-]", "  a: 1,", "  blob: (hidden),", "}", ""]
+        );
+        assert_eq!(viewer.stats().rendered_lines, 4);
+
+        // The placeholder is the last interaction point
+        viewer.select_next().unwrap();
+        viewer.select_next().unwrap();
+        assert!(viewer.select_next().is_err());
+        assert_eq!(viewer.active_path(), JsonPath::root().key("blob"));
+        viewer.toggle_active_element().unwrap();
+        assert_eq!(viewer.hidden_paths().count(), 0);
+        assert_eq!(
+            draw(&viewer),
+            vec!["{ [-]", "  a: 1,", "  blob: [ [-]", "    1,", "    2,"]
+        );
+
+        viewer.hide_path(JsonPath::root().key("blob")).unwrap();
+        viewer
+            .select_path(&JsonPath::root().key("blob").index(1))
+            .unwrap();
+        assert_eq!(viewer.hidden_paths().count(), 0);
+        viewer.hide_path(JsonPath::root().key("a")).unwrap();
+        viewer.unhide_path(&JsonPath::root().key("a")).unwrap();
+        assert!(viewer.unhide_path(&JsonPath::root().key("a")).is_err());
+
+        viewer.hide_path(JsonPath::root().key("blob")).unwrap();
+        assert_eq!(
+            draw(&viewer),
+            vec!["{ [-]", "  a: 1,", "  blob: (hidden),", "}", ""]
+        );
+        viewer.unhide_path(&JsonPath::root().key("blob")).unwrap();
+        assert_eq!(
+            draw(&viewer),
+            vec!["{ [-]", "  a: 1,", "  blob: [ [-]", "    1,", "    2,"]
+        );
+    }
+
+    #[cfg(feature = "schema")]
+    #[test]
+    fn test_validation_errors() {
+        let document = object! { "items" => array! { 1, "x" }, "name" => "foo" };
+        let schema = object! {
+            "properties" => object! {
+                "items" => object! { "maxItems" => 1, "items" => object! { "type" => "integer" } }
+            }
+        };
+        let mut viewer = JsonViewer::new(&document);
+        viewer.validate(&document, &schema);
+        let items = JsonPath::root().key("items");
+        assert_eq!(
+            viewer.validation_errors().collect::<Vec<_>>(),
+            vec![
+                (&items, "must have at most 1 elements"),
+                (&items.clone().index(1), "expected type integer"),
+            ]
+        );
+
+        // The message is shown next to the selected node rather than after its closing bracket.
+        viewer.select_path(&items).unwrap();
+        let mut buffer = WindowBuffer::new(Width::new(50).unwrap(), Height::new(6).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            test_util::lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  items: [ [-]  ! must have at most 1 elements",
+                "    1,",
+                "    x,",
+                "  ] <-2/2 >,",
+                "  name: foo,",
+            ]
+        );
+
+        viewer.update(&document);
+        assert_eq!(viewer.validation_errors().count(), 0);
+        viewer.validate(&document, &schema);
+        viewer.reset(&document);
+        assert_eq!(viewer.validation_errors().count(), 0);
+    }
+
+    #[test]
+    fn test_links() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => "https://example.com/x?y=1",
+            "b" => "http://localhost:80",
+            "c" => "see https://example.com",
+            "d" => "https://example.com/a b",
+            "e" => "ftp://example.com"
+        });
+        let link = |viewer: &mut JsonViewer, key: &str| {
+            viewer.select_path(&JsonPath::root().key(key)).unwrap();
+            viewer.active_link().map(str::to_owned)
+        };
+        assert_eq!(
+            link(&mut viewer, "a"),
+            Some("https://example.com/x?y=1".to_owned())
+        );
+        assert_eq!(
+            link(&mut viewer, "b"),
+            Some("http://localhost:80".to_owned())
+        );
+        // Only values that are a URL as a whole are links.
+        for key in &["c", "d", "e"] {
+            assert_eq!(link(&mut viewer, key), None);
+        }
+
+        let activated = Arc::new(Mutex::new(Vec::new()));
+        {
+            let activated = activated.clone();
+            viewer.on_activate(move |path, node| {
+                let link = match node {
+                    ActivatedNode::Link(link) => Some(link.to_string()),
+                    _ => None,
+                };
+                activated.lock().unwrap().push((path.clone(), link));
+            });
+        }
+        for key in &["a", "c"] {
+            let path = JsonPath::root().key(*key);
+            viewer.select_path(&path).unwrap();
+            assert_eq!(
+                viewer.toggle_active_element(),
+                Ok(ToggleResult::ActivatedScalar(path))
+            );
+        }
+        assert_eq!(
+            *activated.lock().unwrap(),
+            vec![
+                (
+                    JsonPath::root().key("a"),
+                    Some("https://example.com/x?y=1".to_owned())
+                ),
+                (JsonPath::root().key("c"), None),
+            ]
+        );
+
+        let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(4).unwrap());
+        viewer
+            .as_widget()
+            .active_focused(StyleModifier::new())
+            .link(StyleModifier::new().bold(true))
+            .draw(buffer.as_window(), RenderingHints::default());
+        // `  a: https://…`, `  b: http://…` and `  c: see https://…`
+        assert!(test_util::bold_at(&mut buffer, 5, 1));
+        assert!(test_util::bold_at(&mut buffer, 5, 2));
+        assert!(!test_util::bold_at(&mut buffer, 9, 3));
+
+        // Redacted values are not links.
+        viewer.set_redaction(|path, _| *path == JsonPath::root().key("a"));
+        assert_eq!(link(&mut viewer, "a"), None);
+    }
+
+    #[test]
+    fn test_redaction() {
+        let mut viewer = JsonViewer::new(&object! {
+            "user" => "alice",
+            "token" => "secret",
+            "nested" => array! { "secret" }
+        });
+        viewer.set_redaction(|path, key| {
+            key.map(|key| key.to_string()) == Some("token".to_owned()) || path.segments().len() > 1
+        });
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(4).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(
+            draw(&viewer),
+            vec![
+                "{ [-]",
+                "  user: alice,",
+                "  token: •••,",
+                "  nested: [ [-]"
+            ]
+        );
+        assert_eq!(
+            viewer.value_at_pointer("/nested/0").unwrap().scalar(),
+            Some("•••")
+        );
+        assert!(viewer.search("secret", SearchOptions::default()).is_err());
+
+        // Updated values stay redacted
+        viewer.update(&object! { "user" => "bob", "token" => "other" });
+        assert_eq!(
+            viewer.value_at_pointer("/token").unwrap().scalar(),
+            Some("•••")
+        );
+
+        viewer.set_redaction_enabled(false);
+        assert_eq!(
+            viewer.value_at_pointer("/token").unwrap().scalar(),
+            Some("other")
+        );
+        assert_eq!(viewer.match_count(), 0);
+        viewer.search("other", SearchOptions::default()).unwrap();
+        viewer.set_redaction_enabled(true);
+        assert_eq!(viewer.match_count(), 0);
+        viewer.clear_redaction();
+        assert_eq!(viewer.match_count(), 1);
+    }
+
+    #[test]
+    fn test_redaction_of_history() {
+        let mut viewer = JsonViewer::new(&object! { "token" => "old" });
+        let token = JsonPath::root().key("token");
+        viewer.set_history_limit(2);
+        viewer.update(&object! { "token" => "new" });
+        viewer.set_redaction(|_, key| key.map(|key| key.to_string()) == Some("token".to_owned()));
+        assert!(viewer.node(&token).unwrap().history().is_empty());
+        viewer.set_redaction_enabled(false);
+        assert_eq!(viewer.node(&token).unwrap().history(), ["old"]);
+    }
+
+    #[test]
+    fn test_redaction_of_appended_elements() {
+        let mut viewer = JsonViewer::new(&array! { object! { "token" => "old" } });
+        viewer.set_redaction(|_, key| key.map(|key| key.to_string()) == Some("token".to_owned()));
+        viewer.set_follow_tail(true);
+        viewer
+            .append_array_elements(&JsonPath::root(), &[object! { "token" => "leak" }])
+            .unwrap();
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(8).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        let lines = test_util::lines(&mut buffer);
+        assert_eq!(lines[5], "    token: •••,");
+        assert!(!lines.iter().any(|line| line.contains("leak")));
+
+        let copied = Arc::new(Mutex::new(Vec::new()));
+        let sink = copied.clone();
+        viewer.on_copy(move |text| sink.lock().unwrap().push(text.to_owned()));
+        viewer
+            .select_path(&JsonPath::root().index(1).key("token"))
+            .unwrap();
+        viewer.copy_active(CopyFormat::Json).unwrap();
+        assert_eq!(*copied.lock().unwrap(), vec!["\"•••\""]);
+    }
+
+    #[test]
+    fn test_binary_detection() {
+        let mut viewer = JsonViewer::new(&object! {
+            "data" => "SGVsbG8sIHdvcmxkISBIZWxsbyE="
+        });
+        viewer.set_binary_detection(true);
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(80).unwrap(), Height::new(5).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(
+            draw(&viewer),
+            vec!["{ [-]", "  data: [binary, 20 B] [+],", "}", "", ""]
+        );
+        viewer.select_next().unwrap();
+        viewer.toggle_active_element().unwrap();
+        let lines = draw(&viewer);
+        assert_eq!(lines[1], "  data: [binary, 20 B] [-]");
+        assert!(lines[2].contains("|Hello, world! He|"));
+        assert_eq!(viewer.stats().rendered_lines, 5);
+
+        // The expanded state survives updates of the value
+        viewer.update(&object! { "data" => "SGVsbG8sIHdvcmxkISBIZWxsbyEh" });
+        assert_eq!(
+            draw(&viewer)[3],
+            "  0010: 6c 6c 6f 21 21                                  |llo!!|,"
+        );
+
+        viewer.set_binary_detection(false);
+        assert_eq!(draw(&viewer)[1], "  data: SGVsbG8sIHdvcmxkISBIZWxsbyEh,");
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_humanize_timestamps() {
+        let mut viewer = JsonViewer::new(&object! {
+            "created" => 1700000000,
+            "count" => 3
+        });
+        viewer.set_humanize_timestamps(true);
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(50).unwrap(), Height::new(3).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(
+            draw(&viewer),
+            vec![
+                "{ [-]",
+                "  created: 1700000000 (2023-11-14 22:13 UTC),",
+                "  count: 3,"
+            ]
+        );
+        viewer.update(&object! { "created" => 1700000060, "count" => 1700000000 });
+        viewer.set_timestamp_options(TimestampOptions {
+            epoch_seconds: Some(1_700_000_050..1_800_000_000),
+            ..TimestampOptions::default()
+        });
+        assert_eq!(
+            draw(&viewer)[1..],
+            [
+                "  created: 1700000060 (2023-11-14 22:14 UTC),",
+                "  count: 1700000000,"
+            ]
+        );
+        viewer.set_humanize_timestamps(false);
+        assert_eq!(draw(&viewer)[1], "  created: 1700000060,");
+    }
+
+    #[test]
+    fn test_number_base() {
+        let mut viewer = JsonViewer::new(&object! { "flags" => 255, "name" => "x" });
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(3).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)[1].clone()
+        };
+        assert!(viewer.cycle_number_base().is_err());
+        viewer.select_next().unwrap();
+        viewer.cycle_number_base().unwrap();
+        assert_eq!(draw(&viewer), "  flags: 0xff,");
+        viewer.cycle_number_base().unwrap();
+        assert_eq!(draw(&viewer), "  flags: 0b11111111,");
+        assert_eq!(
+            viewer.value_at_pointer("/flags").unwrap().scalar(),
+            Some("255")
+        );
+
+        // The base is kept across updates
+        viewer.update(&object! { "flags" => 8, "name" => "x" });
+        assert_eq!(draw(&viewer), "  flags: 0b1000,");
+        viewer.cycle_number_base().unwrap();
+        assert_eq!(draw(&viewer), "  flags: 0o10,");
+        viewer.cycle_number_base().unwrap();
+        assert_eq!(draw(&viewer), "  flags: 8,");
+
+        let name = JsonPath::root().key("name");
+        assert!(viewer
+            .set_number_base(&name, NumberBase::Hexadecimal)
+            .is_err());
+    }
+
+    #[test]
+    fn test_selection_across_type_change() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1, "b" => object! { "c" => 2 } });
+        viewer
+            .select_path(&JsonPath::root().key("b").key("c"))
+            .unwrap();
+        viewer.update(&object! { "a" => 1, "b" => array! { 2 } });
+        assert_eq!(viewer.active_path(), JsonPath::root().key("b"));
+        viewer.select_path(&JsonPath::root().key("a")).unwrap();
+        viewer.update(&object! { "a" => object! {}, "b" => array! { 2 } });
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a"));
+    }
+
+    #[test]
+    fn test_update_at() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => object! { "b" => array! { 1, 2 }, "c" => 3 },
+            "d" => 4
+        });
+        let a = JsonPath::root().key("a");
+        let b = a.clone().key("b");
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(8).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+
+        // The target stays folded.
+        viewer.set_expanded(&b, false).unwrap();
+        viewer.update_at(&b, &array! { 1, 5, 6 }).unwrap();
+        viewer
+            .update_at(&a.clone().key("c"), &object! { "e" => 7 })
+            .unwrap();
+        assert_eq!(
+            draw(&viewer),
+            vec![
+                "{ [-]",
+                "  a: { [-]",
+                "    b: [ [+] ],",
+                "    c: { [-]",
+                "      e: 7,",
+                "    },",
+                "  },",
+                "  d: 4,",
+            ]
+        );
+        viewer.set_expanded(&b, true).unwrap();
+        let inserted = viewer.value.get(&b.clone().index(2)).unwrap();
+        assert_eq!(inserted.unwrap_scalar_ref().text(), "6");
+
+        // Invalid paths leave the value untouched.
+        let before = draw(&viewer);
+        assert!(viewer
+            .update_at(&JsonPath::root().key("x"), JsonValue::from(1))
+            .is_err());
+        assert!(viewer
+            .update_at(&b.clone().index(3), JsonValue::from(1))
+            .is_err());
+        assert!(viewer
+            .update_at(&JsonPath::root().key("d").key("e"), JsonValue::from(1))
+            .is_err());
+        assert_eq!(draw(&viewer), before);
+    }
+
+    #[test]
+    fn test_update_at_runs_passes() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => array! { "x", "y" },
+            "token" => "old",
+            "b" => "x"
+        });
+        viewer.set_redaction(|_, key| key.map(|key| key.to_string()) == Some("token".to_owned()));
+        viewer.search("x", SearchOptions::default()).unwrap();
+        viewer.next_match().unwrap();
+        assert_eq!(viewer.current_match(), Some(1));
+
+        // The key of the updated member is taken into account.
+        let token = JsonPath::root().key("token");
+        viewer.update_at(&token, JsonValue::from("xyz")).unwrap();
+        assert_eq!(viewer.node(&token).unwrap().scalar(), Some("•••"));
+        assert_eq!(viewer.match_count(), 2);
+
+        // The matches in the subtree are replaced in order, keeping the current one.
+        let a = JsonPath::root().key("a");
+        viewer.update_at(&a, &array! { "z", "x", "x" }).unwrap();
+        assert_eq!(viewer.match_count(), 3);
+        assert_eq!(viewer.current_match(), Some(2));
+        viewer.next_match().unwrap();
+        assert_eq!(viewer.active_path(), a.clone().index(1));
+        viewer.update_at(&a, array! {}).unwrap();
+        assert_eq!(viewer.match_count(), 1);
+        assert_eq!(viewer.current_match(), None);
+    }
+
+    #[test]
+    fn test_set_expanded() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => object! { "b" => array! { 1, 2, 3, 4, 5 } }
+        });
+        let a = JsonPath::root().key("a");
+        let b = a.clone().key("b");
+        viewer.select_path(&b.clone().index(1)).unwrap();
+        viewer.set_expanded(&a, false).unwrap();
+        assert_eq!(viewer.active_path(), a);
+        assert_eq!(viewer.stats().rendered_lines, 3);
+
+        viewer.set_expanded(&b, false).unwrap();
+        viewer.set_expanded(&b, true).unwrap();
+        assert_eq!(viewer.stats().rendered_lines, 9);
+        viewer.set_array_visible_count(&b, 1).unwrap();
+        assert_eq!(viewer.stats().rendered_lines, 7);
+        viewer.set_array_visible_count(&b, 10).unwrap();
+        assert_eq!(viewer.stats().rendered_lines, 11);
+
+        assert!(viewer.set_expanded(&b.clone().index(0), true).is_err());
+        assert!(viewer.set_array_visible_count(&a, 1).is_err());
+        assert!(viewer
+            .set_expanded(&JsonPath::root().key("c"), true)
+            .is_err());
+    }
+
+    #[test]
+    fn test_toggle_result() {
+        let mut viewer = JsonViewer::new(&object! { "a" => array! { 1, 2 }, "b" => 3 });
+        let a = JsonPath::root().key("a");
+        viewer.select_path(&a).unwrap();
+        assert_eq!(viewer.toggle_active_element(), Ok(ToggleResult::Collapsed));
+        assert_eq!(viewer.toggle_active_element(), Ok(ToggleResult::Expanded));
+
+        viewer.select_path(&a.clone().index(1)).unwrap();
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.toggle_active_element(), Ok(ToggleResult::Shrank(1)));
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.toggle_active_element(), Ok(ToggleResult::Grew(2)));
+
+        let b = JsonPath::root().key("b");
+        viewer.select_path(&b).unwrap();
+        assert!(viewer.toggle_active_element().is_err());
+        viewer.on_activate(|_, _| {});
+        assert_eq!(
+            viewer.toggle_active_element(),
+            Ok(ToggleResult::ActivatedScalar(b.clone()))
+        );
+        viewer.hide_path(b).unwrap();
+        assert_eq!(viewer.toggle_active_element(), Ok(ToggleResult::Revealed));
+    }
+
+    #[test]
+    fn test_extend_selection() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => 1, "b" => array! { 2, 3 }, "c" => 4, "d" => 5
+        });
+        let path = |key: &str| JsonPath::root().key(key);
+        viewer.select_path(&path("b")).unwrap();
+        assert!(viewer.selection().is_none());
+
+        viewer.extend_selection_next().unwrap();
+        viewer.extend_selection_next().unwrap();
+        assert_eq!(viewer.active_path(), path("d"));
+        assert_eq!(viewer.extend_selection_next(), Err(()));
+        let selection = viewer.selection().unwrap();
+        assert_eq!(selection.anchor(), &path("b"));
+        assert_eq!(selection.paths(), [path("b"), path("c"), path("d")]);
+
+        // Moving back past the anchor selects the range on its other side.
+        for _ in 0..3 {
+            viewer.extend_selection_previous().unwrap();
+        }
+        assert_eq!(viewer.selection().unwrap().paths(), [path("a"), path("b")]);
+
+        let copied = Arc::new(Mutex::new(Vec::new()));
+        let sink = copied.clone();
+        viewer.on_copy(move |text| sink.lock().unwrap().push(text.to_owned()));
+        viewer.copy_active(CopyFormat::Flat).unwrap();
+        assert_eq!(copied.lock().unwrap()[0], ".a = 1\n.b[0] = 2\n.b[1] = 3");
+
+        let mut buffer = WindowBuffer::new(Width::new(12).unwrap(), Height::new(8).unwrap());
+        viewer
+            .as_widget()
+            .active_focused(StyleModifier::new())
+            .selected(StyleModifier::new().bold(true))
+            .draw(buffer.as_window(), RenderingHints::default());
+        let mut bold = |x: i32, y: i32| test_util::bold_at(&mut buffer, x, y);
+        // `  a: 1,`, `  b: [ [-]` and `  c: 4,`
+        assert!(bold(5, 1) && bold(5, 2));
+        assert!(!bold(5, 6));
+
+        viewer.select_next().unwrap();
+        assert!(viewer.selection().is_none());
+    }
+
+    #[test]
+    fn test_copy_active() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => object! { "b" => array! { 1, "x", JsonValue::Null }, "c" => object! {} }
+        });
+        let a = JsonPath::root().key("a");
+        viewer.select_path(&a).unwrap();
+        assert!(viewer.copy_active(CopyFormat::Json).is_err());
+
+        let copied = Arc::new(Mutex::new(Vec::new()));
+        let sink = copied.clone();
+        viewer.on_copy(move |text| sink.lock().unwrap().push(text.to_owned()));
+        viewer.set_expanded(&a, false).unwrap();
+        viewer.copy_active(CopyFormat::Json).unwrap();
+        viewer.copy_active(CopyFormat::Flat).unwrap();
+        assert_eq!(
+            *copied.lock().unwrap(),
+            vec![
+                "{\n  \"b\": [\n    1,\n    \"x\",\n    null\n  ],\n  \"c\": {}\n}",
+                ".a.b[0] = 1\n.a.b[1] = x\n.a.b[2] = null\n.a.c = {}",
+            ]
+        );
+
+        // Scalars are typed by their kind rather than their text.
+        let mut viewer = JsonViewer::new(&object! {
+            "id" => "42", "flag" => "true", "n" => 4.5, "none" => JsonValue::Null
+        });
+        let sink = copied.clone();
+        viewer.on_copy(move |text| sink.lock().unwrap().push(text.to_owned()));
+        viewer.copy_active(CopyFormat::Json).unwrap();
+        assert_eq!(
+            copied.lock().unwrap()[2],
+            "{\n  \"id\": \"42\",\n  \"flag\": \"true\",\n  \"n\": 4.5,\n  \"none\": null\n}"
+        );
+
+        // Members with duplicate keys are all copied.
+        let scalar = |text: &str| DebugValue {
+            description: None,
+            node: DebugNode::Scalar(text.to_owned()),
+        };
+        let duplicates = DebugValue {
+            description: None,
+            node: DebugNode::Map(vec![
+                ("a".to_owned(), scalar("1")),
+                ("a".to_owned(), scalar("2")),
+            ]),
+        };
+        let mut viewer = JsonViewer::new(&duplicates);
+        let sink = copied.clone();
+        viewer.on_copy(move |text| sink.lock().unwrap().push(text.to_owned()));
+        viewer.copy_active(CopyFormat::Json).unwrap();
+        assert_eq!(
+            copied.lock().unwrap()[3],
+            "{\n  \"a\": \"1\",\n  \"a\": \"2\"\n}"
+        );
+    }
+
+    #[test]
+    fn test_active_container_operations() {
+        let mut viewer = JsonViewer::new(&object! { "a" => array! { 1, object! { "b" => 2 } } });
+        let a = JsonPath::root().key("a");
+        viewer.select_path(&a.clone().index(1).key("b")).unwrap();
+        assert_eq!(viewer.shrink_active_array(), Ok(ToggleResult::Shrank(1)));
+        assert_eq!(viewer.active_path(), a);
+        assert_eq!(viewer.shrink_active_array(), Ok(ToggleResult::Shrank(0)));
+        assert!(viewer.shrink_active_array().is_err());
+        assert_eq!(viewer.grow_active_array(), Ok(ToggleResult::Grew(1)));
+
+        viewer.select_path(&a.clone().index(0)).unwrap();
+        assert_eq!(viewer.grow_active_array(), Ok(ToggleResult::Grew(2)));
+        assert!(viewer.grow_active_array().is_err());
+        assert_eq!(
+            viewer.toggle_active_container(),
+            Ok(ToggleResult::Collapsed)
+        );
+        assert_eq!(viewer.active_path(), a);
+        assert_eq!(viewer.toggle_active_container(), Ok(ToggleResult::Expanded));
+
+        let mut viewer = JsonViewer::new(JsonValue::from(1));
+        assert!(viewer.grow_active_array().is_err());
+        assert!(viewer.toggle_active_container().is_err());
+    }
+
+    #[test]
+    fn test_scroll_to_beginning_and_end() {
+        let value = object! { "a" => array! { 1, 2, 3, 4, 5 }, "b" => object! { "c" => 1 } };
+        for mode in [SelectionMode::InteractionPoints, SelectionMode::AllNodes] {
+            let mut viewer = JsonViewer::new(&value);
+            viewer.set_selection_mode(mode);
+            assert!(viewer.scroll_to_beginning().is_err());
+            viewer.scroll_to_end().unwrap();
+            let last = viewer.active_element.clone();
+            assert!(viewer.select_next().is_err());
+            assert!(viewer.scroll_to_end().is_err());
+
+            viewer.select_previous().unwrap();
+            viewer.scroll_to_beginning().unwrap();
+            assert!(viewer.select_previous().is_err());
+            while viewer.select_next().is_ok() {}
+            assert_eq!(viewer.active_element, last);
+        }
+    }
+
+    #[test]
+    fn test_focus_follows_update() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1, "b" => array! { 1, 2, 3, 4 } });
+        viewer.set_focus_follows_update(true);
+        viewer.update(&object! { "a" => 1, "b" => array! { 1, 2, 3, 5 } });
+        assert_eq!(viewer.active_path(), JsonPath::root().key("b").index(3));
+        viewer.update(&object! { "a" => 1, "b" => array! { 1, 2, 3, 5 } });
+        assert_eq!(viewer.active_path(), JsonPath::root().key("b").index(3));
+
+        viewer.hide_path(JsonPath::root().key("a")).unwrap();
+        viewer.update(&object! { "a" => 2, "b" => array! { 1, 2, 4, 5 } });
+        assert_eq!(viewer.active_path(), JsonPath::root().key("b").index(2));
+
+        viewer.set_focus_follows_update(false);
+        viewer.update(&object! { "a" => 2, "b" => array! { 0, 2, 4, 5 } });
+        assert_eq!(viewer.active_path(), JsonPath::root().key("b").index(2));
+    }
+
+    #[test]
+    fn test_next_change() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1, "b" => array! { 1, 2 }, "c" => 3 });
+        assert_eq!(viewer.next_change(), Err(()));
+        viewer.update(&object! { "a" => 2, "b" => array! { 1, 3 }, "c" => 4 });
+        let a = JsonPath::root().key("a");
+        let b1 = JsonPath::root().key("b").index(1);
+        let c = JsonPath::root().key("c");
+        assert_eq!(viewer.change_count(), 3);
+        assert_eq!(viewer.remaining_changes(), 3);
+
+        viewer.select_path(&JsonPath::root().key("b")).unwrap();
+        viewer.next_change().unwrap();
+        assert_eq!(viewer.active_path(), a);
+        viewer.next_change().unwrap();
+        assert_eq!(viewer.active_path(), b1);
+        assert_eq!(viewer.current_change(), Some(1));
+        assert_eq!(viewer.remaining_changes(), 1);
+        viewer.next_change().unwrap();
+        viewer.next_change().unwrap();
+        assert_eq!(viewer.active_path(), a);
+        viewer.previous_change().unwrap();
+        assert_eq!(viewer.active_path(), c);
+    }
+
+    #[test]
+    fn test_displaced_selection() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1, "b" => object! { "c" => 2 } });
+        let c = JsonPath::root().key("b").key("c");
+        viewer.select_path(&c).unwrap();
+        viewer.update(&object! { "a" => 2, "b" => object! { "c" => 3 } });
+        assert_eq!(viewer.take_displaced_selection(), None);
+
+        viewer.update(&object! { "a" => 2, "b" => object! { "d" => 3 } });
+        viewer.update(&object! { "a" => 2 });
+        assert_eq!(viewer.take_displaced_selection(), Some(c));
+        assert_eq!(viewer.take_displaced_selection(), None);
+
+        // Folding is not a change of the value.
+        viewer.set_expanded(&JsonPath::root(), false).unwrap();
+        assert_eq!(viewer.take_displaced_selection(), None);
+    }
+
+    #[test]
+    fn test_navigatable() {
+        let mut viewer = JsonViewer::new(&object! { "a" => object! { "b" => 1 }, "c" => 2 });
+        let a = JsonPath::root().key("a");
+        viewer.select_path(&a.clone().key("b")).unwrap();
+        viewer.move_left().unwrap();
+        assert_eq!(viewer.active_path(), a);
+        assert_eq!(viewer.value.get(&a).unwrap().extended(), Some(false));
+        viewer.move_right().unwrap();
+        assert_eq!(viewer.active_path(), a);
+        viewer.move_right().unwrap();
+        assert_eq!(viewer.active_path(), a.clone().key("b"));
+        assert!(viewer.move_right().is_err());
+        viewer.move_up().unwrap();
+        viewer.move_left().unwrap();
+        viewer.move_left().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root());
+        assert!(viewer.move_left().is_err());
+    }
+
+    #[cfg(feature = "regex")]
+    #[test]
+    fn test_regex_search() {
+        let mut viewer = JsonViewer::new(&array! { "a1", "b", "a22" });
+        let insensitive = SearchOptions {
+            case_insensitive: true,
+            ..SearchOptions::default()
+        };
+        viewer
+            .search(regex::Regex::new("^A").unwrap(), insensitive)
+            .unwrap();
+        assert_eq!(viewer.match_count(), 2);
+        let regex = regex::Regex::new("^a[0-9]+$").unwrap();
+        let values = SearchOptions {
+            keys: false,
+            ..SearchOptions::default()
+        };
+        viewer.search(regex, values).unwrap();
+        assert_eq!(viewer.match_count(), 2);
+        viewer.next_match().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().index(2));
+    }
+
+    #[test]
+    fn test_scroll_offset() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => object! { "b" => "x\ny", "c" => array! { 1, 2, 3, 4 } },
+            "d" => array! { object! { "e" => 1 }, object! {} },
+            "f" => 2
+        });
+        viewer.expand_all();
+        let draw = |viewer: &JsonViewer, offset: usize, height: i32| {
+            let mut buffer =
+                WindowBuffer::new(Width::new(20).unwrap(), Height::new(height).unwrap());
+            viewer
+                .as_widget()
+                .indentation_guides(true)
+                .scroll_offset(offset)
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        let all = draw(&viewer, 0, 24);
+        let height = viewer.stats().rendered_lines;
+        assert_eq!(all[height - 1], "}");
+        for offset in 0..height {
+            assert_eq!(draw(&viewer, offset, 3)[..], all[offset..offset + 3]);
+        }
+
+        let widget = viewer.as_widget();
+        assert_eq!(widget.path_at_line(0), Some(JsonPath::root()));
+        assert_eq!(
+            widget.path_at_line(3),
+            Some(JsonPath::root().key("a").key("b"))
+        );
+        assert_eq!(
+            widget.path_at_line(6),
+            Some(JsonPath::root().key("a").key("c").index(1))
+        );
+        assert_eq!(
+            widget.path_at_line(8),
+            Some(JsonPath::root().key("a").key("c"))
+        );
+        assert_eq!(widget.path_at_line(height - 1), Some(JsonPath::root()));
+        assert_eq!(widget.path_at_line(height), None);
+        assert_eq!(
+            widget.line_of(&JsonPath::root().key("d").index(1)),
+            Some(14)
+        );
+        assert_eq!(all[14], "│ │ { [-]");
+        for line in 0..height {
+            let path = widget.path_at_line(line).unwrap();
+            assert!(widget.line_of(&path).unwrap() <= line);
+        }
+        drop(widget);
+        viewer
+            .set_expanded(&JsonPath::root().key("a"), false)
+            .unwrap();
+        let widget = viewer.as_widget();
+        assert_eq!(widget.line_of(&JsonPath::root().key("a").key("b")), None);
+        assert_eq!(widget.path_at_line(2), Some(JsonPath::root().key("d")));
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+        let mut viewer = JsonViewer::new(test_util::Nested(DEPTH, "a"));
+        viewer.update(test_util::Nested(DEPTH, "b"));
+        assert_eq!(viewer.stats().rendered_lines, 2 * DEPTH + 1);
+
+        viewer.search("b", SearchOptions::default()).unwrap();
+        assert_eq!(viewer.match_count(), 1);
+        viewer.next_match().unwrap();
+        assert_eq!(viewer.active_path().segments().len(), DEPTH);
+
+        let draw = |viewer: &JsonViewer, offset: usize| {
+            let mut buffer = WindowBuffer::new(Width::new(10).unwrap(), Height::new(3).unwrap());
+            viewer
+                .as_widget()
+                .scroll_offset(offset)
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(draw(&viewer, 0), vec!["[ [-]", "  [ [-]", "    [ [-]"]);
+        assert_eq!(
+            draw(&viewer, 2 * DEPTH - 2),
+            vec!["    ] <-1/", "  ] <-1/1", "] <-1/1 >"]
+        );
+
+        viewer.set_focus_follows_update(true);
+        viewer.set_track_change_times(true);
+        viewer.select_path(&JsonPath::root()).unwrap();
+        viewer.update(test_util::Nested(DEPTH, "c"));
+        assert_eq!(viewer.change_count(), 1);
+        assert_eq!(viewer.active_path().segments().len(), DEPTH);
+        assert!(viewer.last_changed(&viewer.active_path()).is_some());
+        viewer.select_path(&JsonPath::root()).unwrap();
+        viewer.next_change().unwrap();
+        assert_eq!(viewer.active_path().segments().len(), DEPTH);
+
+        viewer.snapshot();
+        viewer.update(test_util::Nested(DEPTH, "d"));
+        viewer.update(test_util::Nested(DEPTH, "d"));
+        assert_eq!(viewer.change_count(), 0);
+        viewer.compare_to_snapshot().unwrap();
+        assert_eq!(viewer.change_count(), 1);
+
+        viewer.set_max_render_depth(Some(2 * DEPTH));
+        assert_eq!(viewer.stats().rendered_lines, 2 * DEPTH + 1);
+        viewer.set_max_render_depth(Some(1));
+        assert_eq!(viewer.stats().rendered_lines, 3);
+    }
+
+    #[test]
+    fn test_hanging_closing_brackets() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => object! { "b" => "x\ny", "c" => array! { 1, 2 } },
+            "d" => array! { object! { "e" => 1 }, object! {} },
+            "f" => 2
+        });
+        viewer.expand_all();
+        let draw = |viewer: &JsonViewer, offset: usize, height: i32| {
+            let mut buffer =
+                WindowBuffer::new(Width::new(30).unwrap(), Height::new(height).unwrap());
+            viewer
+                .as_widget()
+                .closing_brackets(ClosingBrackets::Hanging)
+                .scroll_offset(offset)
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        let all = draw(&viewer, 0, 12);
+        assert_eq!(
+            all,
+            vec![
+                "{ [-]",
+                "  a: { [-]",
+                "    b: x",
+                "    y,",
+                "    c: [ [-]",
+                "      1,",
+                "      2, ] <-2/2 >, },",
+                "  d: [ [-]",
+                "    { [-]",
+                "      e: 1, },",
+                "    { [-] }, ] <-2/2 >,",
+                "  f: 2, }",
+            ]
+        );
+        for offset in 0..all.len() - 2 {
+            assert_eq!(draw(&viewer, offset, 3)[..], all[offset..offset + 3]);
+        }
+
+        let widget = viewer
+            .as_widget()
+            .closing_brackets(ClosingBrackets::Hanging);
+        assert_eq!(
+            widget.value_space_demand(None).height.min,
+            Height::new(12).unwrap()
+        );
+        assert_eq!(
+            widget.path_at_line(6),
+            Some(JsonPath::root().key("a").key("c").index(1))
+        );
+        assert_eq!(widget.path_at_line(11), Some(JsonPath::root().key("f")));
+        assert_eq!(widget.path_at_line(12), None);
+        assert_eq!(
+            widget.line_of(&JsonPath::root().key("d").index(1)),
+            Some(10)
+        );
+        for line in 0..all.len() {
+            let path = widget.path_at_line(line).unwrap();
+            assert!(widget.line_of(&path).unwrap() <= line);
+        }
+    }
+
+    #[test]
+    fn test_flat_view() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => object! { "b" => 1, "c" => array! { 1, 2 } },
+            "d" => object! {},
+            "e" => "x\ny"
+        });
+        viewer.expand_all();
+        viewer.set_view_mode(ViewMode::Flat);
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(5).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        assert_eq!(
+            draw(&viewer),
+            vec![
+                ".a.b = 1",
+                ".a.c[0] = 1",
+                ".a.c[1] = 2",
+                ".d = {}",
+                ".e = x y",
+            ]
+        );
+        assert_eq!(
+            viewer.as_widget().space_demand().height,
+            Demand::exact(Height::new(5).unwrap())
+        );
+
+        // Only lines can be selected.
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a").key("b"));
+        viewer.select_next().unwrap();
+        assert_eq!(
+            viewer.active_path(),
+            JsonPath::root().key("a").key("c").index(0)
+        );
+        viewer.scroll_to_end().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("e"));
+        viewer.select_previous().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("d"));
+        assert_eq!(
+            viewer.as_widget().line_of(&JsonPath::root().key("d")),
+            Some(3)
+        );
+
+        // Folding collapses all lines below a prefix.
+        viewer.select_previous().unwrap();
+        viewer.move_left().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a").key("c"));
+        assert_eq!(
+            draw(&viewer)[..3],
+            [".a.b = 1", ".a.c = [ [+] ]", ".d = {}"]
+        );
+        viewer.select_previous().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a").key("b"));
+
+        viewer.toggle_view_mode();
+        assert_eq!(viewer.view_mode(), ViewMode::Tree);
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a").key("b"));
+        assert_eq!(draw(&viewer)[0], "{ [-]");
+    }
+
+    #[test]
+    fn test_set_error() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1 });
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(24).unwrap(), Height::new(3).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        viewer.set_error("expected value\nat line 1, column 6");
+        assert_eq!(viewer.error(), Some("expected value\nat line 1, column 6"));
+        assert_eq!(
+            draw(&viewer),
+            vec!["! expected value", "  at line 1, column 6", ""]
+        );
+        let demand = viewer.as_widget().space_demand();
+        assert_eq!(demand.width.min, Width::new(21).unwrap());
+        assert_eq!(demand.height.min, Height::new(2).unwrap());
+
+        viewer.clear_error();
+        assert_eq!(draw(&viewer)[0], "{ [-]");
+
+        viewer.set_error("oops");
+        viewer.update(&object! { "a" => 2 });
+        assert_eq!(viewer.error(), None);
+        assert_eq!(draw(&viewer)[0], "{ [-]");
+    }
+
+    #[test]
+    fn test_set_pending() {
+        let mut viewer = JsonViewer::new(&object! { "a" => 1 });
+        let draw = |widget: JsonViewerWidget, blink: Blink| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(2).unwrap());
+            widget.draw(buffer.as_window(), RenderingHints::default().blink(blink));
+            test_util::lines(&mut buffer)
+        };
+        viewer.set_pending("fetching…");
+        assert_eq!(viewer.pending(), Some("fetching…"));
+        assert_eq!(draw(viewer.as_widget(), Blink::On), vec!["fetching…", ""]);
+
+        let frames = [Blink::On, Blink::Off, Blink::Off, Blink::On]
+            .iter()
+            .map(|&blink| draw(viewer.as_widget().spinner(true), blink).remove(0))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            frames,
+            vec!["| fetching…", "/ fetching…", "/ fetching…", "- fetching…"]
+        );
+        let demand = viewer.as_widget().spinner(true).space_demand();
+        assert_eq!(demand.width.min, Width::new(11).unwrap());
+
+        // An error replaces the pending message and vice versa.
+        viewer.set_error("timeout");
+        assert_eq!(viewer.pending(), None);
+        viewer.set_pending("retrying…");
+        assert_eq!(viewer.error(), None);
+
+        viewer.update(&object! { "a" => 2 });
+        assert_eq!(viewer.pending(), None);
+        assert_eq!(draw(viewer.as_widget(), Blink::On)[0], "{ [-]");
+    }
+
+    #[test]
+    fn test_max_render_depth() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => object! { "b" => array! { object! { "c" => 1 } } },
+            "d" => array! {},
+            "e" => 2
+        });
+        viewer.set_max_render_depth(Some(1));
+        viewer.expand_all();
+        let draw = |viewer: &JsonViewer| {
+            let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(8).unwrap());
+            viewer
+                .as_widget()
+                .draw(buffer.as_window(), RenderingHints::default());
+            test_util::lines(&mut buffer)
+        };
+        let cut = vec![
+            "{ [-]",
+            "  a: { … },",
+            "  d: [ [-]",
+            "  ] < 0/0 >,",
+            "  e: 2,",
+            "}",
+            "",
+            "",
+        ];
+        assert_eq!(draw(&viewer), cut);
+        assert!(viewer
+            .set_expanded(&JsonPath::root().key("d"), true)
+            .is_ok());
+
+        // Descending into a placeholder counts the depth from there.
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a"));
+        assert_eq!(viewer.toggle_active_element(), Ok(ToggleResult::Revealed));
+        assert_eq!(draw(&viewer)[1..4], ["  a: { [-]", "    b: [ … ],", "  },"]);
+        assert!(viewer.descend(&JsonPath::root().key("a")).is_err());
+
+        // Selecting a node below the limit reveals it.
+        let c = JsonPath::root().key("a").key("b").index(0).key("c");
+        viewer.select_path(&c).unwrap();
+        assert_eq!(viewer.active_path(), c);
+        assert_eq!(draw(&viewer)[4], "        c: 1,");
+
+        viewer.set_max_render_depth(Some(1));
+        assert_eq!(draw(&viewer), cut);
+        assert_eq!(viewer.active_path(), JsonPath::root().key("a"));
+        // Nodes folded by the limit stay folded.
+        viewer.set_max_render_depth(None);
+        assert_eq!(draw(&viewer)[1], "  a: { [+] },");
+    }
+
+    #[test]
+    fn test_send() {
+        fn assert_send<T: Send>() {}
+        assert_send::<JsonViewer>();
+        assert_send::<UpdateBase>();
+        assert_send::<PreparedUpdate>();
+
+        let viewer = JsonViewer::new(&array! { 1, 2 });
+        let base = viewer.update_base();
+        let update = ::std::thread::spawn(move || base.prepare_update(&array! { 1, 2, 3 }))
+            .join()
+            .unwrap();
+        let mut viewer = ::std::thread::spawn(move || viewer).join().unwrap();
+        viewer.apply_prepared(update).unwrap();
+        assert!(viewer.diff_to_patch(&array! { 1, 2, 3 }).is_empty());
+    }
+
+    #[test]
+    fn test_update_base_is_shared() {
+        let mut viewer = JsonViewer::new(&array! { array! { 1 } });
+        let base = viewer.update_base();
+        assert!(Arc::ptr_eq(&base.value, &viewer.value));
+
+        // Modifying the viewer copies the value, the base is not affected
+        viewer.select_next().unwrap();
+        viewer.toggle_active_element().unwrap();
+        assert!(!Arc::ptr_eq(&base.value, &viewer.value));
+        let inner = JsonPath::root().index(0);
+        assert_eq!(base.value.get(&inner).unwrap().extended(), Some(true));
+        assert_eq!(viewer.value.get(&inner).unwrap().extended(), Some(false));
+
+        // Without a base, the value is modified in place
+        drop(base);
+        let value = Arc::as_ptr(&viewer.value);
+        viewer.toggle_active_element().unwrap();
+        assert_eq!(Arc::as_ptr(&viewer.value), value);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_selection_stays_valid(
+            value in test_util::arbitrary_json(),
+            update in test_util::arbitrary_json(),
+            operations in prop::collection::vec(0..8u8, 0..24),
+        ) {
+            let mut viewer = JsonViewer::new(&value);
+            viewer.set_selection_mode(SelectionMode::AllNodes);
+            for operation in operations {
+                let _ = match operation {
+                    0 | 1 => viewer.select_next(),
+                    2 => viewer.select_previous(),
+                    3 => viewer.toggle_active_element().map(|_| ()),
+                    4 => viewer.hide_path(viewer.active_path()),
+                    5 => viewer.move_left(),
+                    6 => {
+                        viewer.expand_all();
+                        Ok(())
+                    }
+                    _ => {
+                        viewer.update(&update);
+                        Ok(())
+                    }
+                };
+                let active = viewer.active_path();
+                let paths = path::all_paths(&viewer.value, viewer.selection_mode);
+                prop_assert!(paths.contains(&viewer.active_element));
+                let within_hidden = viewer.hidden.iter().any(|hidden| {
+                    active != *hidden && active.segments().starts_with(hidden.segments())
+                });
+                prop_assert!(!within_hidden, "{} is hidden", active);
+            }
+        }
     }
 }
@@ -0,0 +1,486 @@
+use super::displayvalue::DisplayValue;
+use crate::Key;
+
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Index;
+use std::sync::Arc;
+
+/// How to display objects that contain the same key multiple times.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum DuplicateKeys {
+    /// Only show the first member with a key.
+    First,
+    /// Only show the last member with a key.
+    Last,
+    /// Show all members.
+    All,
+}
+
+/// Identifies a member of an object: Its key and the number of preceding members with the same
+/// key.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub struct MemberId {
+    pub key: Key,
+    pub occurrence: usize,
+}
+
+impl MemberId {
+    /// The first member with `key`.
+    pub fn new<K: Into<Key>>(key: K) -> Self {
+        MemberId {
+            key: key.into(),
+            occurrence: 0,
+        }
+    }
+}
+
+/// Objects with more members than this keep an index of the positions of their keys. Smaller
+/// ones are searched linearly, which saves an allocation for each of them.
+const INDEX_THRESHOLD: usize = 16;
+
+/// Whether `key` is displayed as `label`. This is how keys are referred to in a JSON Pointer.
+fn is_labeled(key: &Key, label: &str) -> bool {
+    match key {
+        Key::String(s) | Key::Other(s) => &**s == label,
+        Key::Integer(i) => label.parse() == Ok(*i) && *label == i.to_string(),
+    }
+}
+
+#[derive(Clone)]
+pub struct Member {
+    pub key: Key,
+    pub value: DisplayValue,
+    /// Whether the object contains (or contained) other members with the same key.
+    pub duplicate: bool,
+    // The number of preceding members with the same key.
+    occurrence: usize,
+}
+
+impl Member {
+    /// The identity of the member within its object.
+    pub fn id(&self) -> MemberId {
+        MemberId {
+            key: self.key.clone(),
+            occurrence: self.occurrence,
+        }
+    }
+}
+
+/// The members of an object in their original order. In contrast to a map, the same key may occur
+/// multiple times. Members are identified by their key and occurrence (see `MemberId`), lookups
+/// by label (i.e., displayed key) refer to the first member displayed that way.
+#[derive(Clone, Default)]
+pub struct Members {
+    entries: Vec<Member>,
+    // The positions of the members with each key, in order (see `INDEX_THRESHOLD`).
+    index: Option<HashMap<Key, Vec<usize>>>,
+}
+
+impl Members {
+    pub fn new() -> Self {
+        Members {
+            entries: Vec::new(),
+            index: None,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn iter(&self) -> ::std::slice::Iter<'_, Member> {
+        self.entries.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> ::std::slice::IterMut<'_, Member> {
+        self.entries.iter_mut()
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.entries.iter().map(|m| &m.key)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut DisplayValue> {
+        self.entries.iter_mut().map(|m| &mut m.value)
+    }
+
+    /// The member at `index` (in the order of the members).
+    pub fn get_index(&self, index: usize) -> Option<&Member> {
+        self.entries.get(index)
+    }
+
+    /// A copy of the members with `values` (in the order of the members) instead of their
+    /// values, e.g., to copy an object without copying its values recursively.
+    pub fn with_values(&self, values: Vec<DisplayValue>) -> Self {
+        let entries = self
+            .entries
+            .iter()
+            .zip(values)
+            .map(|(member, value)| Member {
+                key: member.key.clone(),
+                value,
+                duplicate: member.duplicate,
+                occurrence: member.occurrence,
+            })
+            .collect();
+        Members {
+            entries,
+            index: self.index.clone(),
+        }
+    }
+
+    /// Remove all members and return their values.
+    pub fn take_values(&mut self) -> Vec<DisplayValue> {
+        self.index = None;
+        self.entries.drain(..).map(|m| m.value).collect()
+    }
+
+    /// Add a member at the end, even if there already is a member with the same key.
+    pub fn push(&mut self, key: Key, value: DisplayValue) {
+        let occurrence = self.count(&key);
+        if occurrence > 0 {
+            match &self.index {
+                Some(index) => {
+                    for &i in &index[&key] {
+                        self.entries[i].duplicate = true;
+                    }
+                }
+                None => {
+                    for member in self.entries.iter_mut().filter(|m| m.key == key) {
+                        member.duplicate = true;
+                    }
+                }
+            }
+        }
+        if let Some(index) = &mut self.index {
+            index
+                .entry(key.clone())
+                .or_default()
+                .push(self.entries.len());
+        }
+        self.entries.push(Member {
+            key,
+            value,
+            duplicate: occurrence > 0,
+            occurrence,
+        });
+        if self.index.is_none() && self.entries.len() > INDEX_THRESHOLD {
+            self.reindex();
+        }
+    }
+
+    /// Replace the value of the member `id` or add it if it is the next occurrence of its key.
+    pub fn insert(&mut self, id: &MemberId, value: DisplayValue) -> Result<(), ()> {
+        if let Some(old) = self.get_by_id_mut(id) {
+            *old = value;
+            return Ok(());
+        }
+        if id.occurrence != self.count(&id.key) {
+            return Err(());
+        }
+        self.push(id.key.clone(), value);
+        Ok(())
+    }
+
+    /// Remove the member `id`. Later members with the same key move up one occurrence.
+    pub fn remove(&mut self, id: &MemberId) -> Option<DisplayValue> {
+        let i = self.position(id)?;
+        let removed = self.entries.remove(i).value;
+        self.reindex();
+        Some(removed)
+    }
+
+    /// The first member displayed as `label`.
+    pub fn get(&self, label: &str) -> Option<&DisplayValue> {
+        self.position_of(label).map(|i| &self.entries[i].value)
+    }
+
+    /// The identity of the first member displayed as `label`.
+    pub fn id_of(&self, label: &str) -> Option<MemberId> {
+        self.position_of(label).map(|i| self.entries[i].id())
+    }
+
+    /// The index of the member identified by `id`.
+    pub fn position(&self, id: &MemberId) -> Option<usize> {
+        match &self.index {
+            Some(index) => index.get(&id.key)?.get(id.occurrence).cloned(),
+            None => self
+                .entries
+                .iter()
+                .position(|m| m.occurrence == id.occurrence && m.key == id.key),
+        }
+    }
+
+    /// The identity of the member at `index`.
+    pub fn id_at(&self, index: usize) -> Option<MemberId> {
+        self.entries.get(index).map(Member::id)
+    }
+
+    /// The number of members with `key`.
+    fn count(&self, key: &Key) -> usize {
+        match &self.index {
+            Some(index) => index.get(key).map_or(0, Vec::len),
+            None => self.entries.iter().filter(|m| m.key == *key).count(),
+        }
+    }
+
+    /// The index of the first member displayed as `label`.
+    fn position_of(&self, label: &str) -> Option<usize> {
+        let index = match &self.index {
+            Some(index) => index,
+            None => return self.entries.iter().position(|m| is_labeled(&m.key, label)),
+        };
+        let integer = label
+            .parse()
+            .ok()
+            .filter(|i: &i64| *label == i.to_string())
+            .map(Key::Integer);
+        [Key::String(label.into()), Key::Other(label.into())]
+            .iter()
+            .chain(integer.as_ref())
+            .filter_map(|key| index.get(key)?.first().cloned())
+            .min()
+    }
+
+    /// Renumber the occurrences of all keys and rebuild the index after members were removed.
+    fn reindex(&mut self) {
+        let mut positions = HashMap::<Key, Vec<usize>>::new();
+        for (i, member) in self.entries.iter_mut().enumerate() {
+            let key_positions = positions.entry(member.key.clone()).or_default();
+            member.occurrence = key_positions.len();
+            key_positions.push(i);
+        }
+        self.index = if self.entries.len() > INDEX_THRESHOLD {
+            Some(positions)
+        } else {
+            None
+        };
+    }
+
+    pub fn get_by_id(&self, id: &MemberId) -> Option<&DisplayValue> {
+        self.position(id).map(|i| &self.entries[i].value)
+    }
+
+    pub fn get_by_id_mut(&mut self, id: &MemberId) -> Option<&mut DisplayValue> {
+        self.position(id).map(move |i| &mut self.entries[i].value)
+    }
+
+    /// Drop members according to `policy`. Remaining members keep their `duplicate` flag.
+    pub fn resolve_duplicates(&mut self, policy: DuplicateKeys) {
+        let mut seen = BTreeSet::new();
+        match policy {
+            DuplicateKeys::All => return,
+            DuplicateKeys::First => self.entries.retain(|m| seen.insert(m.key.clone())),
+            DuplicateKeys::Last => {
+                self.entries.reverse();
+                self.entries.retain(|m| seen.insert(m.key.clone()));
+                self.entries.reverse();
+            }
+        }
+        self.reindex();
+    }
+}
+
+impl<'a> Index<&'a str> for Members {
+    type Output = DisplayValue;
+
+    fn index(&self, key: &'a str) -> &DisplayValue {
+        self.get(key).expect("No member with the given key")
+    }
+}
+
+/// A pool of the string keys of all objects of a value. Objects with the same keys (e.g., the
+/// elements of an array of records) share the storage for their keys.
+#[derive(Clone, Default)]
+pub struct KeyPool {
+    strings: RefCell<HashSet<Arc<str>>>,
+}
+
+impl KeyPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The pooled version of `key`.
+    pub fn intern(&self, key: Key) -> Key {
+        match key {
+            Key::String(s) => Key::String(self.intern_str(s)),
+            Key::Other(s) => Key::Other(self.intern_str(s)),
+            Key::Integer(i) => Key::Integer(i),
+        }
+    }
+
+    fn intern_str(&self, s: Arc<str>) -> Arc<str> {
+        let mut strings = self.strings.borrow_mut();
+        if let Some(pooled) = strings.get(&s) {
+            return pooled.clone();
+        }
+        strings.insert(s.clone());
+        s
+    }
+
+    /// Forget all keys that are not used outside of the pool anymore.
+    pub fn prune(&mut self) {
+        self.strings.get_mut().retain(|s| Arc::strong_count(s) > 1);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scalar(value: &str) -> DisplayValue {
+        DisplayValue::new(value)
+    }
+
+    fn members() -> Members {
+        let mut members = Members::new();
+        members.push("b".into(), scalar("1"));
+        members.push("a".into(), scalar("2"));
+        members.push("b".into(), scalar("3"));
+        members
+    }
+
+    #[test]
+    fn test_typed_keys() {
+        let mut members = Members::new();
+        members.push(Key::Integer(1), scalar("1"));
+        members.push("1".into(), scalar("2"));
+        assert!(members.iter().all(|m| !m.duplicate));
+        assert_eq!(members["1"].unwrap_scalar_ref().value, "1");
+        assert_eq!(members.id_of("1"), Some(MemberId::new(Key::Integer(1))));
+        assert!(members.get("01").is_none());
+
+        members.insert(&MemberId::new("1"), scalar("3")).unwrap();
+        assert_eq!(members.len(), 2);
+        assert_eq!(members["1"].unwrap_scalar_ref().value, "1");
+        let value = members.get_by_id(&MemberId::new("1")).unwrap();
+        assert_eq!(value.unwrap_scalar_ref().value, "3");
+        members.insert(&MemberId::new("2"), scalar("4")).unwrap();
+        assert_eq!(members.id_of("2"), Some(MemberId::new("2")));
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut members = members();
+        let id = |key: &str, occurrence| MemberId {
+            key: key.into(),
+            occurrence,
+        };
+        // Only the next occurrence of a key can be added
+        assert!(members.insert(&id("b", 3), scalar("4")).is_err());
+        members.insert(&id("b", 2), scalar("4")).unwrap();
+        assert_eq!(members.len(), 4);
+
+        // Later occurrences move up
+        assert!(members.remove(&id("b", 0)).is_some());
+        assert_eq!(members.id_at(0), Some(id("a", 0)));
+        assert_eq!(members.position(&id("b", 1)), Some(2));
+        assert!(members.get_by_id(&id("b", 2)).is_none());
+        assert_eq!(members["b"].unwrap_scalar_ref().value, "3");
+    }
+
+    #[test]
+    fn test_index() {
+        // Enough members to be indexed
+        let mut members = Members::new();
+        for i in 0..2 * INDEX_THRESHOLD {
+            members.push(Key::Integer(i as i64 % 10), scalar(&i.to_string()));
+        }
+        members.push("5".into(), scalar("x"));
+        assert!(members.index.is_some());
+        let id = MemberId {
+            key: Key::Integer(5),
+            occurrence: 2,
+        };
+        assert_eq!(members.position(&id), Some(25));
+        assert_eq!(members.id_at(25), Some(id.clone()));
+        assert!(members.iter().nth(25).unwrap().duplicate);
+        assert_eq!(members["5"].unwrap_scalar_ref().value, "5");
+        assert_eq!(members.id_of("5"), Some(MemberId::new(Key::Integer(5))));
+
+        members.remove(&MemberId::new(Key::Integer(5))).unwrap();
+        assert_eq!(members.position(&id), None);
+        assert_eq!(members["5"].unwrap_scalar_ref().value, "15");
+        members.resolve_duplicates(DuplicateKeys::First);
+        assert!(members.index.is_none());
+        assert_eq!(members.len(), 11);
+        assert_eq!(members["5"].unwrap_scalar_ref().value, "15");
+        assert_eq!(members.id_of("5"), Some(MemberId::new(Key::Integer(5))));
+    }
+
+    #[test]
+    fn test_members() {
+        let members = members();
+        assert_eq!(
+            members.keys().map(|k| k.to_string()).collect::<Vec<_>>(),
+            vec!["b", "a", "b"]
+        );
+        assert_eq!(
+            members.iter().map(|m| m.duplicate).collect::<Vec<_>>(),
+            vec![true, false, true]
+        );
+        assert_eq!(members["b"].unwrap_scalar_ref().value, "1");
+        let second_b = MemberId {
+            key: "b".into(),
+            occurrence: 1,
+        };
+        assert_eq!(members.position(&second_b), Some(2));
+        assert_eq!(members.id_at(2), Some(second_b.clone()));
+        assert_eq!(members.id_at(1), Some(MemberId::new("a")));
+        assert_eq!(members.id_at(3), None);
+        assert_eq!(
+            members
+                .get_by_id(&second_b)
+                .unwrap()
+                .unwrap_scalar_ref()
+                .value,
+            "3"
+        );
+    }
+
+    #[test]
+    fn test_resolve_duplicates() {
+        let mut first = members();
+        first.resolve_duplicates(DuplicateKeys::First);
+        assert_eq!(
+            first.keys().collect::<Vec<_>>(),
+            vec![&Key::from("b"), &Key::from("a")]
+        );
+        assert_eq!(first["b"].unwrap_scalar_ref().value, "1");
+        assert!(first.iter().next().unwrap().duplicate);
+
+        let mut last = members();
+        last.resolve_duplicates(DuplicateKeys::Last);
+        assert_eq!(
+            last.keys().collect::<Vec<_>>(),
+            vec![&Key::from("a"), &Key::from("b")]
+        );
+        assert_eq!(last["b"].unwrap_scalar_ref().value, "3");
+
+        let mut all = members();
+        all.resolve_duplicates(DuplicateKeys::All);
+        assert_eq!(all.len(), 3);
+    }
+
+    #[test]
+    fn test_key_pool() {
+        let key_str = |key: &Key| match key {
+            Key::String(s) => s.clone(),
+            _ => panic!("Not a string key"),
+        };
+        let mut pool = KeyPool::new();
+        let a = key_str(&pool.intern(Key::from("foo")));
+        let b = key_str(&pool.intern(Key::from("foo")));
+        assert!(Arc::ptr_eq(&a, &b));
+        assert_eq!(pool.intern(Key::Integer(1)), Key::Integer(1));
+
+        // Keys that are still in use survive pruning
+        pool.prune();
+        assert!(Arc::ptr_eq(&a, &key_str(&pool.intern(Key::from("foo")))));
+        drop((a, b));
+        pool.prune();
+        assert_eq!(pool.strings.borrow().len(), 0);
+    }
+}
@@ -0,0 +1,111 @@
+//! Snapshots of numeric metrics, grouped by the components of their names.
+use std::collections::BTreeMap;
+use std::iter::FromIterator;
+
+use super::{Value, ValueVariant};
+
+/// A snapshot of metrics (e.g., scraped from a Prometheus endpoint), shown as a tree of the
+/// components of their names (separated by `_` or `.`): `http_requests_total` and
+/// `http_errors_total` are shown as `http: { errors: { total: ... }, requests: { total: ... } }`.
+///
+/// The value of a metric whose name is also the prefix of other metrics (e.g., `http_requests`
+/// and `http_requests_total`) is shown as the description of the group. Pass each snapshot to
+/// `JsonViewer::update` to highlight the metrics that changed.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Metrics {
+    value: Option<f64>,
+    children: BTreeMap<String, Metrics>,
+}
+
+impl Metrics {
+    pub fn new<S: AsRef<str>>(metrics: impl IntoIterator<Item = (S, f64)>) -> Self {
+        metrics.into_iter().collect()
+    }
+
+    /// Add (or replace) the metric `name`.
+    pub fn insert(&mut self, name: &str, value: f64) {
+        let node = name
+            .split(['_', '.'])
+            .filter(|component| !component.is_empty())
+            .fold(self, |node, component| {
+                node.children.entry(component.to_owned()).or_default()
+            });
+        node.value = Some(value);
+    }
+}
+
+impl<S: AsRef<str>> FromIterator<(S, f64)> for Metrics {
+    fn from_iter<I: IntoIterator<Item = (S, f64)>>(metrics: I) -> Self {
+        let mut result = Metrics::default();
+        for (name, value) in metrics {
+            result.insert(name.as_ref(), value);
+        }
+        result
+    }
+}
+
+impl Value for &Metrics {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        let value = self.value.map(|value| value.to_string().into());
+        if self.children.is_empty() {
+            return ValueVariant::Scalar(None, value.unwrap_or_default());
+        }
+        ValueVariant::Map(
+            value,
+            Box::new(
+                self.children
+                    .iter()
+                    .map(|(component, child)| (component.as_str().into(), child)),
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use crate::{JsonPath, JsonViewer};
+    use unsegen::base::{Height, Width, WindowBuffer};
+    use unsegen::widget::{RenderingHints, Widget};
+
+    #[test]
+    fn test_metrics() {
+        let snapshot = |requests| {
+            Metrics::new(vec![
+                ("http_requests_total", requests),
+                ("http_requests", 2.0),
+                ("process.cpu_seconds", 1.5),
+                ("up", 1.0),
+            ])
+        };
+        let mut viewer = JsonViewer::new(&snapshot(7.0));
+        viewer.expand_all();
+        let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(13).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            lines(&mut buffer)[..12],
+            [
+                "{ [-]",
+                "  http: { [-]",
+                "    requests: 2 { [-]",
+                "      total: 7,",
+                "    },",
+                "  },",
+                "  process: { [-]",
+                "    cpu: { [-]",
+                "      seconds: 1.5,",
+                "    },",
+                "  },",
+                "  up: 1,",
+            ]
+        );
+
+        let total = JsonPath::root().key("http").key("requests").key("total");
+        viewer.update(&snapshot(8.0));
+        assert!(viewer.node(&total).unwrap().changed());
+        assert!(!viewer.node(&JsonPath::root().key("up")).unwrap().changed());
+    }
+}
@@ -0,0 +1,238 @@
+use super::displayvalue::*;
+use super::path::JsonPathSegment;
+use super::stats::SubtreeStats;
+
+/// The type of a node of the displayed value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NodeKind {
+    Scalar,
+    Object,
+    Array,
+}
+
+/// A read-only view of a single node of the value displayed in a `JsonViewer`, including its
+/// display state. The tree can be traversed via `children`, e.g., to build an outline:
+///
+/// ```
+/// # extern crate unsegen_jsonviewer;
+/// # use unsegen_jsonviewer::{JsonPathSegment, Node};
+/// fn outline(node: Node, depth: usize, lines: &mut Vec<String>) {
+///     for (segment, child) in node.children() {
+///         let label = match segment {
+///             JsonPathSegment::Key(id) => id.key.to_string(),
+///             JsonPathSegment::Index(i) => format!("[{}]", i),
+///         };
+///         lines.push(format!("{:indent$}{}", "", label, indent = 2 * depth));
+///         outline(child, depth + 1, lines);
+///     }
+/// }
+/// # fn main() {}
+/// ```
+#[derive(Clone, Copy)]
+pub struct Node<'a> {
+    value: &'a DisplayValue,
+    /// Whether redacted values are shown as they are (see `Node::unmasked`).
+    unmasked: bool,
+}
+
+impl<'a> Node<'a> {
+    pub(crate) fn new(value: &'a DisplayValue) -> Self {
+        Node {
+            value,
+            unmasked: false,
+        }
+    }
+
+    /// Like `new`, but redacted values (in the whole subtree) are not masked, e.g., to compute
+    /// the identities of array elements from their actual content.
+    pub(crate) fn unmasked(value: &'a DisplayValue) -> Self {
+        Node {
+            value,
+            unmasked: true,
+        }
+    }
+
+    fn with_value(&self, value: &'a DisplayValue) -> Node<'a> {
+        Node {
+            value,
+            unmasked: self.unmasked,
+        }
+    }
+
+    fn text(&self, scalar: &'a DisplayScalar) -> &'a str {
+        if self.unmasked {
+            scalar.value.as_str()
+        } else {
+            scalar.text()
+        }
+    }
+
+    pub fn kind(&self) -> NodeKind {
+        match self.value {
+            DisplayValue::Scalar(_) => NodeKind::Scalar,
+            DisplayValue::Object(_) => NodeKind::Object,
+            DisplayValue::Array(_) => NodeKind::Array,
+        }
+    }
+
+    /// The (formatted) value of the node if it is a scalar. Redacted values are masked.
+    pub fn scalar(&self) -> Option<&'a str> {
+        if let DisplayValue::Scalar(scalar) = self.value {
+            Some(self.text(scalar))
+        } else {
+            None
+        }
+    }
+
+    /// The previous values of a scalar, oldest first (see `JsonViewer::set_history_limit`). None
+    /// are returned for redacted values.
+    pub fn history(&self) -> Vec<&'a str> {
+        match self.value {
+            DisplayValue::Scalar(scalar) if self.unmasked || !scalar.redacted => {
+                scalar.history.iter().map(String::as_str).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// The number of members of an object or elements of an array. Scalars do not have any.
+    pub fn len(&self) -> usize {
+        match self.value {
+            DisplayValue::Scalar(_) => 0,
+            DisplayValue::Object(obj) => obj.members.len(),
+            DisplayValue::Array(array) => array.values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Whether an object or array is unfolded. None for scalars.
+    pub fn expanded(&self) -> Option<bool> {
+        self.value.extended()
+    }
+
+    /// Whether the node itself is highlighted as changed by the last update, e.g., because a
+    /// scalar got a new value or an array a new length.
+    pub fn changed(&self) -> bool {
+        self.value.is_changed()
+    }
+
+    /// The number of children that are drawn, i.e., none for folded objects and arrays and the
+    /// shown prefix and suffix of arrays (see `JsonViewer::set_array_visible_count`).
+    pub fn shown_len(&self) -> usize {
+        match self.value {
+            DisplayValue::Object(obj) if obj.extended => obj.members.len(),
+            DisplayValue::Array(array) if array.extended => array.num_shown(),
+            _ => 0,
+        }
+    }
+
+    /// All members of an object or elements of an array (whether they are shown or not) together
+    /// with the segment of the path leading to them.
+    pub fn children(&self) -> Box<dyn Iterator<Item = (JsonPathSegment, Node<'a>)> + 'a> {
+        let node = *self;
+        match self.value {
+            DisplayValue::Scalar(_) => Box::new(::std::iter::empty()),
+            DisplayValue::Object(obj) => Box::new(obj.members.iter().map(move |member| {
+                let segment = JsonPathSegment::Key(member.id());
+                (segment, node.with_value(&member.value))
+            })),
+            DisplayValue::Array(array) => Box::new(
+                array
+                    .values
+                    .iter()
+                    .enumerate()
+                    .map(move |(i, value)| (JsonPathSegment::Index(i), node.with_value(value))),
+            ),
+        }
+    }
+
+    /// The child reached via `segment`.
+    pub fn child(&self, segment: &JsonPathSegment) -> Option<Node<'a>> {
+        match (self.value, segment) {
+            (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => obj.members.get_by_id(id),
+            (DisplayValue::Array(array), &JsonPathSegment::Index(i)) => array.values.get(i),
+            _ => None,
+        }
+        .map(|value| self.with_value(value))
+    }
+
+    /// The size of the subtree of the node.
+    pub fn stats(&self) -> SubtreeStats {
+        self.value.stats()
+    }
+
+    /// The first few scalar members of an object (`key=value`) or elements of an array, e.g.,
+    /// `name=foo, id=42 …`, to be shown within a folded node (see
+    /// `JsonViewerWidget::collapsed_summary`). None if there are no scalars.
+    pub fn summary(&self) -> Option<String> {
+        let text = |value: &'a DisplayValue| match value {
+            DisplayValue::Scalar(scalar) => Some(self.text(scalar).replace('\n', " ")),
+            _ => None,
+        };
+        let parts: Vec<String> = match self.value {
+            DisplayValue::Scalar(_) => return None,
+            DisplayValue::Object(obj) => obj
+                .members
+                .iter()
+                .filter_map(|member| Some(format!("{}={}", member.key, text(&member.value)?)))
+                .collect(),
+            DisplayValue::Array(array) => array.values.iter().filter_map(text).collect(),
+        };
+        if parts.is_empty() {
+            return None;
+        }
+        let mut summary = parts[..parts.len().min(SUMMARY_PARTS)].join(", ");
+        if parts.len() < self.len() || parts.len() > SUMMARY_PARTS {
+            summary.push_str(" …");
+        }
+        Some(summary)
+    }
+}
+
+/// The maximum number of scalars shown by `Node::summary`.
+const SUMMARY_PARTS: usize = 3;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{JsonPath, JsonViewer, MemberId};
+
+    #[test]
+    fn test_traversal() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => array! { 1, 2, 3, 4 },
+            "b" => object! { "c" => true }
+        });
+        viewer.update(&object! {
+            "a" => array! { 0, 2, 3, 4, 5 },
+            "b" => object! { "c" => true }
+        });
+        viewer
+            .set_expanded(&JsonPath::root().key("b"), false)
+            .unwrap();
+        let root = viewer.root();
+        let children = root.children().collect::<Vec<_>>();
+        assert_eq!(children.len(), 2);
+        let (ref segment, a) = children[0];
+        assert_eq!(*segment, JsonPathSegment::Key(MemberId::new("a")));
+        assert_eq!(a.kind(), NodeKind::Array);
+        assert_eq!((a.len(), a.shown_len()), (5, 3));
+        assert!(a.changed());
+        assert_eq!(a.expanded(), Some(true));
+        let last = a.child(&JsonPathSegment::Index(4)).unwrap();
+        assert_eq!(last.scalar(), Some("5"));
+        assert!(a.child(&JsonPathSegment::Index(0)).unwrap().changed());
+        assert!(!a.child(&JsonPathSegment::Index(1)).unwrap().changed());
+        let b = viewer.node(&JsonPath::root().key("b")).unwrap();
+        assert_eq!(
+            (b.expanded(), b.shown_len(), b.changed()),
+            (Some(false), 0, false)
+        );
+        assert_eq!(b.children().count(), 1);
+        assert_eq!(last.expanded(), None);
+        assert_eq!(last.children().count(), 0);
+    }
+}
@@ -0,0 +1,105 @@
+//! Alternative representations of numbers and their changes.
+
+/// The base integers are shown in (see `JsonViewer::cycle_number_base`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum NumberBase {
+    #[default]
+    Decimal,
+    Hexadecimal,
+    Binary,
+    Octal,
+}
+
+impl NumberBase {
+    /// The base following this one in the order decimal, hexadecimal, binary, octal.
+    pub fn next(self) -> Self {
+        match self {
+            NumberBase::Decimal => NumberBase::Hexadecimal,
+            NumberBase::Hexadecimal => NumberBase::Binary,
+            NumberBase::Binary => NumberBase::Octal,
+            NumberBase::Octal => NumberBase::Decimal,
+        }
+    }
+}
+
+/// Parse `text` as a (decimal) integer.
+pub fn parse_integer(text: &str) -> Option<i128> {
+    text.parse().ok()
+}
+
+/// Format the integer `text` in `base` with the usual prefix, e.g., `-0x1f`. Returns None if
+/// `text` is not an integer.
+pub fn format_integer(text: &str, base: NumberBase) -> Option<String> {
+    let number = parse_integer(text)?;
+    let sign = if number < 0 { "-" } else { "" };
+    let magnitude = number.unsigned_abs();
+    Some(match base {
+        NumberBase::Decimal => number.to_string(),
+        NumberBase::Hexadecimal => format!("{}0x{:x}", sign, magnitude),
+        NumberBase::Binary => format!("{}0b{:b}", sign, magnitude),
+        NumberBase::Octal => format!("{}0o{:o}", sign, magnitude),
+    })
+}
+
+/// The difference between the numbers `old` and `new` with an explicit sign, e.g., `+5` or
+/// `-0.25`, with as many decimals as the more precise of the two. Returns None if one of them is
+/// not a number.
+pub fn format_delta(old: &str, new: &str) -> Option<String> {
+    if let (Some(old), Some(new)) = (parse_integer(old), parse_integer(new)) {
+        return new.checked_sub(old).map(|delta| format!("{:+}", delta));
+    }
+    let (old_number, new_number) = (parse_float(old)?, parse_float(new)?);
+    let delta = new_number - old_number;
+    if old.contains(['e', 'E']) || new.contains(['e', 'E']) {
+        return Some(format!("{:+}", delta));
+    }
+    let decimals = |text: &str| text.find('.').map(|dot| text.len() - dot - 1).unwrap_or(0);
+    Some(format!("{:+.*}", decimals(old).max(decimals(new)), delta))
+}
+
+/// Parse `text` as a finite number in json syntax (apart from leading zeros).
+pub fn parse_float(text: &str) -> Option<f64> {
+    let is_number = text.starts_with(|c: char| c == '-' || c.is_ascii_digit())
+        && text
+            .chars()
+            .all(|c| c.is_ascii_digit() || "+-.eE".contains(c));
+    if !is_number {
+        return None;
+    }
+    text.parse().ok().filter(|number: &f64| number.is_finite())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_format_integer() {
+        assert_eq!(
+            format_integer("255", NumberBase::Hexadecimal),
+            Some("0xff".to_owned())
+        );
+        assert_eq!(
+            format_integer("-5", NumberBase::Binary),
+            Some("-0b101".to_owned())
+        );
+        assert_eq!(
+            format_integer("18446744073709551615", NumberBase::Octal),
+            Some("0o1777777777777777777777".to_owned())
+        );
+        assert_eq!(format_integer("1.5", NumberBase::Hexadecimal), None);
+        assert_eq!(format_integer("\"12\"", NumberBase::Hexadecimal), None);
+        assert_eq!(NumberBase::Octal.next(), NumberBase::Decimal);
+    }
+
+    #[test]
+    fn test_format_delta() {
+        assert_eq!(format_delta("42", "47"), Some("+5".to_owned()));
+        assert_eq!(format_delta("3", "-2"), Some("-5".to_owned()));
+        assert_eq!(format_delta("0.1", "0.35"), Some("+0.25".to_owned()));
+        assert_eq!(format_delta("1.5", "1"), Some("-0.5".to_owned()));
+        assert_eq!(format_delta("1e3", "2e3"), Some("+1000".to_owned()));
+        assert_eq!(format_delta("1", "inf"), None);
+        assert_eq!(format_delta("1", "\"2\""), None);
+    }
+}
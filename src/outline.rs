@@ -0,0 +1,207 @@
+//! A compact tree of the objects and arrays of a document, e.g., to navigate very large values.
+use unsegen::base::basic_types::*;
+use unsegen::base::{BoolModifyMode, Cursor, StyleModifier, Window};
+use unsegen::widget::{Demand, Demand2D, RenderingHints, Widget};
+
+use super::layout::Extent;
+use super::node::{Node, NodeKind};
+use super::path::{JsonPath, JsonPathSegment};
+use super::JsonViewer;
+
+/// An object or array listed in the outline.
+#[derive(Clone, PartialEq, Debug)]
+pub struct OutlineEntry {
+    pub path: JsonPath,
+    pub kind: NodeKind,
+    /// The number of members or elements.
+    pub len: usize,
+}
+
+impl OutlineEntry {
+    /// The line of the entry, e.g., `  items [120]`.
+    fn text(&self) -> String {
+        let depth = self.path.segments().len();
+        let label = match self.path.segments().last() {
+            None => ".".to_owned(),
+            Some(JsonPathSegment::Key(id)) => id.key.to_string(),
+            Some(JsonPathSegment::Index(i)) => format!("[{}]", i),
+        };
+        let size = match self.kind {
+            NodeKind::Object => format!("{{{}}}", self.len),
+            _ => format!("[{}]", self.len),
+        };
+        format!("{:indent$}{} {}", "", label, size, indent = 2 * depth)
+    }
+}
+
+/// All objects and arrays of the value (whether they are folded or not) up to `max_depth` levels
+/// below the root, in the order they are drawn.
+pub fn outline_entries(root: Node, max_depth: Option<usize>) -> Vec<OutlineEntry> {
+    fn collect(
+        node: Node,
+        path: &mut JsonPath,
+        max_depth: Option<usize>,
+        entries: &mut Vec<OutlineEntry>,
+    ) {
+        if node.kind() == NodeKind::Scalar {
+            return;
+        }
+        entries.push(OutlineEntry {
+            path: path.clone(),
+            kind: node.kind(),
+            len: node.len(),
+        });
+        if max_depth == Some(path.segments().len()) {
+            return;
+        }
+        for (segment, child) in node.children() {
+            path.push(segment);
+            collect(child, path, max_depth, entries);
+            path.pop();
+        }
+    }
+    let mut entries = Vec::new();
+    collect(root, &mut JsonPath::root(), max_depth, &mut entries);
+    entries
+}
+
+/// The index of the entry of the innermost object or array containing (or at) `path`.
+pub fn entry_containing(entries: &[OutlineEntry], path: &JsonPath) -> Option<usize> {
+    entries
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| path.segments().starts_with(entry.path.segments()))
+        .max_by_key(|(_, entry)| entry.path.segments().len())
+        .map(|(i, _)| i)
+}
+
+/// Shows the objects and arrays of the value of a `JsonViewer` as a tree with their sizes, e.g.,
+/// next to the viewer itself. The entry of the object or array containing the selected node is
+/// highlighted. Use `JsonViewer::select_next_container` and `select_previous_container` to jump
+/// through the entries.
+pub struct JsonOutline<'a> {
+    inner: &'a JsonViewer,
+    max_depth: Option<usize>,
+    active_style: StyleModifier,
+}
+
+impl<'a> JsonOutline<'a> {
+    pub fn new(viewer: &'a JsonViewer) -> Self {
+        JsonOutline {
+            inner: viewer,
+            max_depth: None,
+            active_style: StyleModifier::new()
+                .invert(BoolModifyMode::Toggle)
+                .bold(true),
+        }
+    }
+    /// Only list objects and arrays up to `depth` levels below the root.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+    /// Style of the entry of the object or array containing the selected node.
+    pub fn active(mut self, style: StyleModifier) -> Self {
+        self.active_style = style;
+        self
+    }
+
+    fn entries(&self) -> Vec<OutlineEntry> {
+        outline_entries(self.inner.root(), self.max_depth)
+    }
+}
+
+impl<'a> Widget for JsonOutline<'a> {
+    fn space_demand(&self) -> Demand2D {
+        let entries = self.entries();
+        let width = entries
+            .iter()
+            .map(|entry| Extent::text(&entry.text()).first)
+            .max()
+            .unwrap_or(0);
+        Demand2D {
+            width: Demand::at_least(Width::new(width as i32).unwrap()),
+            height: Demand::exact(Height::new(entries.len() as i32).unwrap()),
+        }
+    }
+    fn draw(&self, mut window: Window, _hints: RenderingHints) {
+        let entries = self.entries();
+        let active = entry_containing(&entries, &self.inner.active_path());
+        // Scroll just far enough to show the active entry.
+        let height = window.get_height().raw_value() as usize;
+        let first = match active {
+            Some(active) if active >= height => active + 1 - height,
+            _ => 0,
+        };
+        let mut cursor = Cursor::new(&mut window);
+        for (i, entry) in entries.iter().enumerate().skip(first).take(height) {
+            let mut cursor = cursor.save().style_modifier();
+            if Some(i) == active {
+                cursor.apply_style_modifier(self.active_style);
+            }
+            cursor.write(&entry.text());
+            cursor.wrap_line();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use unsegen::base::WindowBuffer;
+
+    #[test]
+    fn test_outline() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => 1,
+            "items" => array! { object! { "x" => array! {} }, 2, object! {} },
+            "meta" => object! { "tags" => array! { "t" } }
+        });
+        let draw = |viewer: &JsonViewer, height: i32| {
+            let mut buffer =
+                WindowBuffer::new(Width::new(20).unwrap(), Height::new(height).unwrap());
+            JsonOutline::new(viewer).draw(buffer.as_window(), RenderingHints::default());
+            lines(&mut buffer)
+        };
+        assert_eq!(
+            draw(&viewer, 7),
+            vec![
+                ". {3}",
+                "  items [3]",
+                "    [0] {1}",
+                "      x [0]",
+                "    [2] {0}",
+                "  meta {1}",
+                "    tags [1]",
+            ]
+        );
+        assert_eq!(
+            JsonOutline::new(&viewer).max_depth(1).space_demand().height,
+            Demand::exact(Height::new(3).unwrap())
+        );
+
+        // Jumping selects (and reveals) the objects and arrays in order.
+        viewer.select_next_container().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("items"));
+        viewer.select_next_container().unwrap();
+        viewer.select_next_container().unwrap();
+        assert_eq!(
+            viewer.active_path(),
+            JsonPath::root().key("items").index(0).key("x")
+        );
+        viewer.select_next_container().unwrap();
+        viewer.select_next_container().unwrap();
+        viewer.select_next_container().unwrap();
+        assert!(viewer.select_next_container().is_err());
+        assert_eq!(draw(&viewer, 2), vec!["  meta {1}", "    tags [1]"]);
+        viewer.select_next().unwrap();
+        viewer.select_previous_container().unwrap();
+        assert_eq!(
+            viewer.active_path(),
+            JsonPath::root().key("meta").key("tags")
+        );
+        viewer.select_previous_container().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("meta"));
+    }
+}
@@ -0,0 +1,427 @@
+//! Modification of the displayed value using JSON Patch (RFC 6902) operations.
+use std::collections::BTreeMap;
+
+use super::displayvalue::*;
+use super::members::{KeyPool, MemberId};
+use super::path::{JsonPath, JsonPathSegment};
+use crate::{Value, ValueVariant};
+
+/// A single operation of a JSON Patch (RFC 6902). Paths refer to the value *before* the
+/// operation is applied. The `test` and `copy` operations are not supported.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PatchOp<V> {
+    /// Set the object member at `path` or insert an array element at `path`, shifting all
+    /// subsequent elements. An index equal to the length of the array appends the element.
+    Add { path: JsonPath, value: V },
+    /// Remove the object member or array element at `path`.
+    Remove { path: JsonPath },
+    /// Replace the (existing) node at `path`.
+    Replace { path: JsonPath, value: V },
+    /// Remove the node at `from` and add it at `path`.
+    Move { from: JsonPath, path: JsonPath },
+}
+
+fn split_last(path: &JsonPath) -> Option<(JsonPath, &JsonPathSegment)> {
+    Some((path.parent()?, path.segments().last()?))
+}
+
+fn insert(value: &mut DisplayValue, path: &JsonPath, mut node: DisplayValue) -> Result<(), ()> {
+    node.set_changed(true);
+    let (parent, last) = if let Some(split) = split_last(path) {
+        split
+    } else {
+        *value = node;
+        return Ok(());
+    };
+    match (value.get_mut(&parent).ok_or(())?, last) {
+        (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => obj.members.insert(id, node),
+        (DisplayValue::Array(array), &JsonPathSegment::Index(i)) if i <= array.values.len() => {
+            let tail_start = array.values.len() - array.num_tail;
+            array.values.insert(i, node);
+            array.length_changed = true;
+            array.update_order();
+            // Make sure that the new element is visible
+            let position = array.sorted_position(i);
+            if array.page_size.is_some() {
+                array.jump_to_index(i);
+            } else if position <= array.num_extended {
+                array.num_extended += 1;
+            } else if position > tail_start {
+                array.num_tail += 1;
+            }
+            Ok(())
+        }
+        _ => Err(()),
+    }
+}
+
+fn remove(value: &mut DisplayValue, path: &JsonPath) -> Result<DisplayValue, ()> {
+    let (parent, last) = split_last(path).ok_or(())?;
+    match (value.get_mut(&parent).ok_or(())?, last) {
+        (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => obj.members.remove(id).ok_or(()),
+        (DisplayValue::Array(array), &JsonPathSegment::Index(i)) if i < array.values.len() => {
+            array.length_changed = true;
+            let position = array.sorted_position(i);
+            if position < array.num_extended {
+                array.num_extended -= 1;
+            } else if position >= array.values.len() - array.num_tail {
+                array.num_tail -= 1;
+            }
+            let removed = array.values.remove(i);
+            array.update_order();
+            array.fix_page();
+            Ok(removed)
+        }
+        _ => Err(()),
+    }
+}
+
+/// Sort the arrays containing the node at `path` again, as they may be sorted by its content.
+fn update_orders(value: &mut DisplayValue, path: &JsonPath) {
+    let mut ancestor = path.parent();
+    while let Some(path) = ancestor {
+        if let Some(DisplayValue::Array(array)) = value.get_mut(&path) {
+            array.update_order();
+        }
+        ancestor = path.parent();
+    }
+}
+
+fn apply_op<V: Value>(value: &mut DisplayValue, op: &PatchOp<V>, keys: &KeyPool) -> Result<(), ()> {
+    match op {
+        PatchOp::Add { path, value: new } => {
+            insert(value, path, DisplayValue::new_with_keys(new.clone(), keys))?;
+            update_orders(value, path);
+            Ok(())
+        }
+        PatchOp::Remove { path } => {
+            remove(value, path)?;
+            update_orders(value, path);
+            Ok(())
+        }
+        PatchOp::Replace { path, value: new } => {
+            let node = value.get_mut(path).ok_or(())?;
+            *node = node.update_with(
+                new.clone(),
+                UpdateOptions::default(),
+                &mut path.clone(),
+                keys,
+            );
+            update_orders(value, path);
+            Ok(())
+        }
+        PatchOp::Move { from, path } => {
+            if from == path {
+                return value.get(from).map(|_| ()).ok_or(());
+            }
+            // A node cannot be moved into one of its own children
+            if path.segments().starts_with(from.segments()) {
+                return Err(());
+            }
+            let node = remove(value, from)?;
+            update_orders(value, from);
+            insert(value, path, node)?;
+            update_orders(value, path);
+            Ok(())
+        }
+    }
+}
+
+/// Apply all operations in `ops` to a copy of `value`, taking the keys of new object members from
+/// `keys`. Only the nodes affected by the patch are marked as changed.
+pub fn apply_patch<V: Value>(
+    value: &DisplayValue,
+    ops: &[PatchOp<V>],
+    keys: &KeyPool,
+) -> Result<DisplayValue, ()> {
+    let mut result = value.clone();
+    result.set_changed(false);
+    for op in ops {
+        apply_op(&mut result, op, keys)?;
+    }
+    Ok(result)
+}
+
+/// Compute a patch that transforms `value` into `other`. Moved nodes are not detected, i.e., the
+/// patch only consists of `Add`, `Remove` and `Replace` operations.
+pub fn diff_to_patch<V: Value>(value: &DisplayValue, other: V) -> Vec<PatchOp<V>> {
+    let mut ops = Vec::new();
+    diff_at(value, other, &mut JsonPath::root(), &mut ops);
+    ops
+}
+
+fn diff_at<V: Value>(
+    value: &DisplayValue,
+    other: V,
+    path: &mut JsonPath,
+    ops: &mut Vec<PatchOp<V>>,
+) {
+    let kind = other.scalar_kind();
+    match (value, other.clone().visit()) {
+        (DisplayValue::Scalar(scalar), ValueVariant::Scalar(d, s)) => {
+            if scalar.value != s || scalar.kind != kind || scalar.description != d {
+                ops.push(PatchOp::Replace {
+                    path: path.clone(),
+                    value: other,
+                });
+            }
+        }
+        (DisplayValue::Object(obj), ValueVariant::Map(d, members)) if obj.description == d => {
+            // Members with duplicate keys are matched in order of their occurrence
+            let mut occurrences = BTreeMap::new();
+            let members = members
+                .map(|(key, new)| {
+                    let occurrence = occurrences.entry(key.clone()).or_insert(0);
+                    *occurrence += 1;
+                    let id = MemberId {
+                        key,
+                        occurrence: *occurrence - 1,
+                    };
+                    (id, new)
+                })
+                .collect::<Vec<_>>();
+            // Remove from the back so that the occurrences of the remaining members stay valid
+            for member in obj.members.iter().rev() {
+                let id = member.id();
+                if occurrences.get(&id.key).is_none_or(|&n| id.occurrence >= n) {
+                    ops.push(PatchOp::Remove {
+                        path: path.clone().member(id),
+                    });
+                }
+            }
+            for (id, new) in members {
+                path.push(JsonPathSegment::Key(id.clone()));
+                if let Some(old) = obj.members.get_by_id(&id) {
+                    diff_at(old, new, path, ops);
+                } else {
+                    ops.push(PatchOp::Add {
+                        path: path.clone(),
+                        value: new,
+                    });
+                }
+                path.pop();
+            }
+        }
+        (DisplayValue::Array(array), ValueVariant::Array(d, values)) if array.description == d => {
+            let mut len = 0;
+            for (i, new) in values.enumerate() {
+                path.push(JsonPathSegment::Index(i));
+                if let Some(old) = array.values.get(i) {
+                    diff_at(old, new, path, ops);
+                } else {
+                    ops.push(PatchOp::Add {
+                        path: path.clone(),
+                        value: new,
+                    });
+                }
+                path.pop();
+                len = i + 1;
+            }
+            // Remove from the back so that the indices of the remaining elements stay valid
+            for i in (len..array.values.len()).rev() {
+                ops.push(PatchOp::Remove {
+                    path: path.clone().index(i),
+                });
+            }
+        }
+        _ => ops.push(PatchOp::Replace {
+            path: path.clone(),
+            value: other,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Key;
+    use json::JsonValue;
+
+    #[test]
+    fn test_apply_patch() {
+        let value = DisplayValue::new(&object! {
+            "a" => 1,
+            "b" => array! { 1, 2, 3, 4 },
+            "c" => object! { "x" => "y" }
+        });
+        let one = JsonValue::from(1);
+        let five = JsonValue::from(5);
+
+        let ops = vec![
+            PatchOp::Replace {
+                path: JsonPath::root().key("a"),
+                value: &five,
+            },
+            PatchOp::Add {
+                path: JsonPath::root().key("b").index(0),
+                value: &one,
+            },
+            PatchOp::Remove {
+                path: JsonPath::root().key("b").index(4),
+            },
+            PatchOp::Move {
+                from: JsonPath::root().key("c").key("x"),
+                path: JsonPath::root().key("d"),
+            },
+        ];
+        let patched = apply_patch(&value, &ops, &KeyPool::new()).unwrap();
+        let expected = object! {
+            "a" => 5,
+            "b" => array! { 1, 1, 2, 3 },
+            "c" => object! {},
+            "d" => "y"
+        };
+        assert!(diff_to_patch(&patched, &expected).is_empty());
+
+        assert!(
+            patched
+                .get(&JsonPath::root().key("a"))
+                .unwrap()
+                .unwrap_scalar_ref()
+                .changed
+        );
+        let b = patched
+            .get(&JsonPath::root().key("b"))
+            .unwrap()
+            .unwrap_array_ref();
+        assert!(b.length_changed);
+        assert_eq!(b.num_extended, 4);
+        assert!(b.values[0].unwrap_scalar_ref().changed);
+        assert!(!b.values[1].unwrap_scalar_ref().changed);
+        assert!(
+            patched
+                .get(&JsonPath::root().key("d"))
+                .unwrap()
+                .unwrap_scalar_ref()
+                .changed
+        );
+
+        // Failing patches do not have any effect
+        assert!(apply_patch(
+            &value,
+            &[PatchOp::Remove::<&JsonValue> {
+                path: JsonPath::root()
+            }],
+            &KeyPool::new()
+        )
+        .is_err());
+        assert!(apply_patch(
+            &value,
+            &[
+                PatchOp::Replace {
+                    path: JsonPath::root().key("a"),
+                    value: &five,
+                },
+                PatchOp::Remove {
+                    path: JsonPath::root().key("nope"),
+                },
+            ],
+            &KeyPool::new()
+        )
+        .is_err());
+        assert!(apply_patch(
+            &value,
+            &[PatchOp::Move::<&JsonValue> {
+                from: JsonPath::root().key("c"),
+                path: JsonPath::root().key("c").key("z"),
+            }],
+            &KeyPool::new()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_patch_shares_keys() {
+        let keys = KeyPool::new();
+        let value = DisplayValue::new_with_keys(&array! { object! { "name" => 1 } }, &keys);
+        let added = object! { "name" => 2 };
+        let replaced = object! { "name" => 3 };
+        let patched = apply_patch(
+            &value,
+            &[
+                PatchOp::Add {
+                    path: JsonPath::root().index(1),
+                    value: &added,
+                },
+                PatchOp::Replace {
+                    path: JsonPath::root().index(0),
+                    value: &replaced,
+                },
+            ],
+            &keys,
+        )
+        .unwrap();
+        let key = |i: usize| match &patched
+            .get(&JsonPath::root().index(i))
+            .unwrap()
+            .unwrap_object_ref()
+            .members
+            .iter()
+            .next()
+            .unwrap()
+            .key
+        {
+            Key::String(s) => s.clone(),
+            _ => panic!("Not a string key"),
+        };
+        assert!(::std::sync::Arc::ptr_eq(&key(0), &key(1)));
+    }
+
+    #[test]
+    fn test_diff_to_patch() {
+        let value = DisplayValue::new(&object! {
+            "a" => 1,
+            "b" => array! { 1, 2, 3 },
+            "c" => "foo"
+        });
+        let other = object! {
+            "a" => 1,
+            "b" => array! { 1, 7 },
+            "c" => array! {},
+            "d" => true
+        };
+        let ops = diff_to_patch(&value, &other);
+        assert_eq!(
+            ops,
+            vec![
+                PatchOp::Replace {
+                    path: JsonPath::root().key("b").index(1),
+                    value: &other["b"][1],
+                },
+                PatchOp::Remove {
+                    path: JsonPath::root().key("b").index(2),
+                },
+                PatchOp::Replace {
+                    path: JsonPath::root().key("c"),
+                    value: &other["c"],
+                },
+                PatchOp::Add {
+                    path: JsonPath::root().key("d"),
+                    value: &other["d"],
+                },
+            ]
+        );
+        let patched = apply_patch(&value, &ops, &KeyPool::new()).unwrap();
+        assert!(diff_to_patch(&patched, &other).is_empty());
+
+        // Scalars of different types differ even if they are displayed the same way.
+        let value = DisplayValue::new(&array! { 1, "true", JsonValue::Null });
+        let other = array! { "1", true, "null" };
+        let ops = diff_to_patch(&value, &other);
+        assert_eq!(
+            ops,
+            (0..3)
+                .map(|i| PatchOp::Replace {
+                    path: JsonPath::root().index(i),
+                    value: &other[i],
+                })
+                .collect::<Vec<_>>()
+        );
+        let patched = apply_patch(&value, &ops, &KeyPool::new()).unwrap();
+        assert!(diff_to_patch(&patched, &other).is_empty());
+        assert_eq!(
+            diff_to_patch(&value, &array! { 1, "true", JsonValue::Null }),
+            vec![]
+        );
+    }
+}
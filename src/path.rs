@@ -1,4 +1,266 @@
 use super::displayvalue::*;
+use super::members::MemberId;
+use crate::{ActivatedNode, Key, ToggleResult};
+
+use std::cmp::Ordering;
+use std::fmt;
+
+/// A single step of a `JsonPath`: Either a member of an object (identified by its typed key and
+/// occurrence, see `MemberId`) or the index of an array element.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+pub enum JsonPathSegment {
+    Key(MemberId),
+    Index(usize),
+}
+
+/// The location of a node within the displayed value, e.g., `.foo[3].bar`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Default)]
+pub struct JsonPath {
+    segments: Vec<JsonPathSegment>,
+}
+
+impl JsonPath {
+    /// The path of the top level value.
+    pub fn root() -> Self {
+        JsonPath {
+            segments: Vec::new(),
+        }
+    }
+
+    /// Descend into the (first) member `key` of an object.
+    pub fn key<K: Into<Key>>(self, key: K) -> Self {
+        self.member(MemberId::new(key))
+    }
+
+    /// Descend into the member `id` of an object, e.g., a later one of several members with the
+    /// same key.
+    pub fn member(mut self, id: MemberId) -> Self {
+        self.segments.push(JsonPathSegment::Key(id));
+        self
+    }
+
+    /// Descend into the element `index` of an array.
+    pub fn index(mut self, index: usize) -> Self {
+        self.segments.push(JsonPathSegment::Index(index));
+        self
+    }
+
+    pub fn segments(&self) -> &[JsonPathSegment] {
+        &self.segments
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// The path of the object or array containing this node, or None for the root.
+    pub fn parent(&self) -> Option<JsonPath> {
+        let mut segments = self.segments.clone();
+        segments.pop().map(|_| JsonPath { segments })
+    }
+
+    /// Format the path as a JSON Pointer (RFC 6901), e.g., `/foo/3/bar`. Members are referred to
+    /// by their displayed key, so the type and occurrence of keys are lost.
+    pub fn to_pointer(&self) -> String {
+        let mut result = String::new();
+        for segment in &self.segments {
+            result.push('/');
+            match segment {
+                JsonPathSegment::Key(id) => {
+                    result.push_str(&id.key.to_string().replace('~', "~0").replace('/', "~1"))
+                }
+                JsonPathSegment::Index(i) => result.push_str(&i.to_string()),
+            }
+        }
+        result
+    }
+
+    pub(crate) fn push(&mut self, segment: JsonPathSegment) {
+        self.segments.push(segment);
+    }
+
+    pub(crate) fn pop(&mut self) {
+        self.segments.pop();
+    }
+}
+
+/// Split a JSON Pointer (RFC 6901) into its unescaped reference tokens.
+fn pointer_tokens(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(Vec::new());
+    }
+    if !pointer.starts_with('/') {
+        return None;
+    }
+    pointer[1..]
+        .split('/')
+        .map(|token| {
+            let mut result = String::new();
+            let mut chars = token.chars();
+            while let Some(c) = chars.next() {
+                if c == '~' {
+                    match chars.next() {
+                        Some('0') => result.push('~'),
+                        Some('1') => result.push('/'),
+                        _ => return None,
+                    }
+                } else {
+                    result.push(c);
+                }
+            }
+            Some(result)
+        })
+        .collect()
+}
+
+/// Resolve a JSON Pointer against `value`. Whether a token refers to an object member or an array
+/// element depends on the value, so this fails for pointers to nonexistent nodes.
+pub fn json_path_from_pointer(pointer: &str, value: &DisplayValue) -> Option<JsonPath> {
+    let mut result = JsonPath::root();
+    let mut current = value;
+    for token in pointer_tokens(pointer)? {
+        current = match current {
+            DisplayValue::Object(obj) => {
+                let id = obj.members.id_of(&token)?;
+                let next = obj.members.get_by_id(&id)?;
+                result.push(JsonPathSegment::Key(id));
+                next
+            }
+            DisplayValue::Array(array) => {
+                // RFC 6901 does not allow leading zeros or signs
+                if token.len() > 1 && token.starts_with('0')
+                    || !token.chars().all(|c| c.is_ascii_digit())
+                {
+                    return None;
+                }
+                let i = token.parse::<usize>().ok()?;
+                let next = array.values.get(i)?;
+                result.push(JsonPathSegment::Index(i));
+                next
+            }
+            DisplayValue::Scalar(_) => return None,
+        };
+    }
+    Some(result)
+}
+
+/// Compare the nodes at `a` and `b` by the order in which they are drawn in `value`, i.e., parents
+/// before their children and siblings in the order of the members or elements. The nodes do not
+/// have to exist as long as their common ancestor does.
+pub fn cmp_draw_order(value: &DisplayValue, a: &JsonPath, b: &JsonPath) -> Ordering {
+    let mut current = Some(value);
+    for (a, b) in a.segments.iter().zip(&b.segments) {
+        if a != b {
+            return match (current, a, b) {
+                (
+                    Some(DisplayValue::Object(obj)),
+                    JsonPathSegment::Key(a),
+                    JsonPathSegment::Key(b),
+                ) => obj.members.position(a).cmp(&obj.members.position(b)),
+                _ => a.cmp(b),
+            };
+        }
+        current = match (current, a) {
+            (Some(DisplayValue::Object(obj)), JsonPathSegment::Key(id)) => {
+                obj.members.get_by_id(id)
+            }
+            (Some(DisplayValue::Array(array)), &JsonPathSegment::Index(i)) => array.values.get(i),
+            _ => None,
+        };
+    }
+    a.segments.len().cmp(&b.segments.len())
+}
+
+/// The object containing the node at `path` (the innermost one if there are several on the
+/// path) and its location.
+pub fn enclosing_object<'a>(
+    value: &'a DisplayValue,
+    path: &JsonPath,
+) -> Option<(JsonPath, &'a DisplayObject)> {
+    let mut parent = path.parent()?;
+    loop {
+        if let Some(DisplayValue::Object(obj)) = value.get(&parent) {
+            return Some((parent, obj));
+        }
+        parent = parent.parent()?;
+    }
+}
+
+/// The location and the member keys of the object containing the node at `path` (see
+/// `enclosing_object`), i.e., the keys of the node and its siblings if it is an object member.
+pub fn sibling_keys<'a>(
+    value: &'a DisplayValue,
+    path: &JsonPath,
+) -> Option<(JsonPath, impl Iterator<Item = &'a Key>)> {
+    enclosing_object(value, path).map(|(parent, obj)| (parent, obj.members.keys()))
+}
+
+/// Unfold all objects and arrays on the way to the node at `segments` (growing arrays as
+/// required) and return the path to the node's first interaction point. If there is no such node,
+/// `value` is not modified.
+pub fn reveal_path(segments: &[JsonPathSegment], value: &mut DisplayValue) -> Option<Path> {
+    let mut node = &*value;
+    for segment in segments {
+        node = match (node, segment) {
+            (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => obj.members.get_by_id(id)?,
+            (DisplayValue::Array(array), &JsonPathSegment::Index(i)) => array.values.get(i)?,
+            _ => return None,
+        };
+    }
+    let mut node = value;
+    for segment in segments {
+        node.invalidate_extent();
+        node = match (node, segment) {
+            (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => {
+                obj.extended = true;
+                obj.members.get_by_id_mut(id)?
+            }
+            (DisplayValue::Array(array), &JsonPathSegment::Index(i)) => {
+                array.extended = true;
+                array.reveal(i);
+                array.values.get_mut(i)?
+            }
+            _ => return None,
+        };
+    }
+    node.invalidate_extent();
+    Some(Path::join(segments.to_vec(), first_path_in(node)))
+}
+
+fn is_identifier(key: &str) -> bool {
+    let mut chars = key.chars();
+    match chars.next() {
+        Some(c) if c.is_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+impl fmt::Display for JsonPath {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.segments.is_empty() {
+            return write!(f, ".");
+        }
+        for segment in &self.segments {
+            match segment {
+                JsonPathSegment::Key(id) => {
+                    let key = id.key.to_string();
+                    if is_identifier(&key) {
+                        write!(f, ".{}", key)?;
+                    } else {
+                        write!(f, "[{:?}]", key)?;
+                    }
+                    // Later occurrences of duplicate keys are numbered, e.g., `.foo#1`.
+                    if id.occurrence > 0 {
+                        write!(f, "#{}", id.occurrence)?;
+                    }
+                }
+                JsonPathSegment::Index(i) => write!(f, "[{}]", i)?,
+            }
+        }
+        Ok(())
+    }
+}
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum ArrayPath {
@@ -9,31 +271,142 @@ pub enum ArrayPath {
 }
 #[derive(Clone, PartialEq, Debug)]
 pub enum ObjectPath {
-    Item(String, Box<Path>),
+    Item(MemberId, Box<Path>),
+    Key(MemberId),
     Toggle,
 }
-#[derive(Clone, PartialEq, Debug)]
+/// Determines which nodes of the displayed value can be selected.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum SelectionMode {
+    /// Only interaction points (toggles, grow/shrink knobs, scalars) are selectable.
+    InteractionPoints,
+    /// Additionally, the keys of the members of expanded objects are selectable.
+    AllNodes,
+}
+
+#[derive(Debug)]
 pub enum Path {
     Array(ArrayPath),
     Object(ObjectPath),
     Scalar,
 }
 
+impl Drop for Path {
+    fn drop(&mut self) {
+        // Drop the subpaths one by one rather than recursively so that paths into arbitrarily deep
+        // values do not overflow the stack.
+        let mut subpath = self.take_subpath();
+        while let Some(mut path) = subpath {
+            subpath = path.take_subpath();
+        }
+    }
+}
+
+impl Clone for Path {
+    fn clone(&self) -> Self {
+        let (segments, innermost) = self.split_ref();
+        let innermost = match innermost {
+            Path::Array(path) => Path::Array(path.clone()),
+            Path::Object(path) => Path::Object(path.clone()),
+            Path::Scalar => Path::Scalar,
+        };
+        Path::join(segments, innermost)
+    }
+}
+
+impl PartialEq for Path {
+    fn eq(&self, other: &Path) -> bool {
+        let (mut a, mut b) = (self, other);
+        loop {
+            match (a, b) {
+                (
+                    Path::Array(ArrayPath::Item(i, subpath_a)),
+                    Path::Array(ArrayPath::Item(j, subpath_b)),
+                ) if i == j => {
+                    a = subpath_a;
+                    b = subpath_b;
+                }
+                (
+                    Path::Object(ObjectPath::Item(id_a, subpath_a)),
+                    Path::Object(ObjectPath::Item(id_b, subpath_b)),
+                ) if id_a == id_b => {
+                    a = subpath_a;
+                    b = subpath_b;
+                }
+                (Path::Array(ArrayPath::Item(..)), _)
+                | (Path::Object(ObjectPath::Item(..)), _)
+                | (_, Path::Array(ArrayPath::Item(..)))
+                | (_, Path::Object(ObjectPath::Item(..))) => return false,
+                (Path::Array(a), Path::Array(b)) => return a == b,
+                (Path::Object(a), Path::Object(b)) => return a == b,
+                (Path::Scalar, Path::Scalar) => return true,
+                _ => return false,
+            }
+        }
+    }
+}
+
 impl Path {
-    fn unwrap_array(self) -> ArrayPath {
-        if let Path::Array(arr) = self {
-            arr
-        } else {
-            panic!("Tried to unwrap array from other path");
+    /// The segment of the child this path points into, if any.
+    fn segment(&self) -> Option<JsonPathSegment> {
+        match self {
+            Path::Array(ArrayPath::Item(i, _)) => Some(JsonPathSegment::Index(*i)),
+            Path::Object(ObjectPath::Item(id, _)) => Some(JsonPathSegment::Key(id.clone())),
+            _ => None,
+        }
+    }
+
+    /// Take the path within the child this path points into (if any), leaving a trivial one.
+    fn take_subpath(&mut self) -> Option<Path> {
+        match self {
+            Path::Array(ArrayPath::Item(_, subpath))
+            | Path::Object(ObjectPath::Item(_, subpath)) => {
+                Some(::std::mem::replace(&mut **subpath, Path::Scalar))
+            }
+            _ => None,
         }
     }
-    fn unwrap_object(self) -> ObjectPath {
-        if let Path::Object(bo) = self {
-            bo
-        } else {
-            panic!("Tried to unwrap obj from other path");
+
+    /// Split the path into the segments of the children it points into and the path within the
+    /// innermost one, which does not point into a child.
+    fn split(mut self) -> (Vec<JsonPathSegment>, Path) {
+        let mut segments = Vec::new();
+        while let Some(segment) = self.segment() {
+            segments.push(segment);
+            self = self.take_subpath().expect("path into a child");
+        }
+        (segments, self)
+    }
+
+    /// Like `split`, but without taking the path apart.
+    fn split_ref(&self) -> (Vec<JsonPathSegment>, &Path) {
+        let mut segments = Vec::new();
+        let mut path = self;
+        loop {
+            path = match path {
+                Path::Array(ArrayPath::Item(i, subpath)) => {
+                    segments.push(JsonPathSegment::Index(*i));
+                    subpath
+                }
+                Path::Object(ObjectPath::Item(id, subpath)) => {
+                    segments.push(JsonPathSegment::Key(id.clone()));
+                    subpath
+                }
+                _ => return (segments, path),
+            };
         }
     }
+
+    /// The path to `path` within the child at `segments`, i.e., the inverse of `split`.
+    fn join(segments: Vec<JsonPathSegment>, mut path: Path) -> Path {
+        for segment in segments.into_iter().rev() {
+            path = match segment {
+                JsonPathSegment::Index(i) => Path::Array(ArrayPath::Item(i, Box::new(path))),
+                JsonPathSegment::Key(id) => Path::Object(ObjectPath::Item(id, Box::new(path))),
+            };
+        }
+        path
+    }
 }
 
 //Easily create paths (for debug purposes, as this is not exposed)
@@ -45,8 +418,11 @@ impl Path {
     fn object_toggle() -> Self {
         Path::Object(ObjectPath::Toggle)
     }
-    fn object<S: Into<String>>(self, key: S) -> Self {
-        Path::Object(ObjectPath::Item(key.into(), Box::new(self)))
+    fn object<K: Into<crate::Key>>(self, key: K) -> Self {
+        Path::Object(ObjectPath::Item(MemberId::new(key), Box::new(self)))
+    }
+    fn object_key<K: Into<crate::Key>>(key: K) -> Self {
+        Path::Object(ObjectPath::Key(MemberId::new(key)))
     }
     fn array_grow() -> Self {
         Path::Array(ArrayPath::Grow)
@@ -62,319 +438,544 @@ impl Path {
     }
 }
 
-fn first_path_in(value: &DisplayValue) -> Path {
+/// All paths that can be selected in `value` in the order in which `find_next_path` visits them.
+#[cfg(test)]
+pub fn all_paths(value: &DisplayValue, mode: SelectionMode) -> Vec<Path> {
     match value {
-        &DisplayValue::Array(_) => Path::Array(ArrayPath::Toggle),
-        &DisplayValue::Object(_) => Path::Object(ObjectPath::Toggle),
-        &DisplayValue::Scalar(_) => Path::Scalar,
+        DisplayValue::Scalar(_) => vec![Path::Scalar],
+        DisplayValue::Array(array) => {
+            let mut paths = vec![Path::Array(ArrayPath::Toggle)];
+            if array.extended {
+                for i in array.shown_indices() {
+                    let item = |p| Path::Array(ArrayPath::Item(i, Box::new(p)));
+                    paths.extend(all_paths(&array.values[i], mode).into_iter().map(item));
+                }
+                if array.can_shrink() {
+                    paths.push(Path::Array(ArrayPath::Shrink));
+                }
+                if array.can_grow() {
+                    paths.push(Path::Array(ArrayPath::Grow));
+                }
+            }
+            paths
+        }
+        DisplayValue::Object(obj) => {
+            let mut paths = vec![Path::Object(ObjectPath::Toggle)];
+            if obj.extended {
+                for (i, member) in obj.members.iter().enumerate() {
+                    let id = obj.members.id_at(i).unwrap();
+                    if mode == SelectionMode::AllNodes {
+                        paths.push(Path::Object(ObjectPath::Key(id.clone())));
+                    }
+                    let item = |p| Path::Object(ObjectPath::Item(id.clone(), Box::new(p)));
+                    paths.extend(all_paths(&member.value, mode).into_iter().map(item));
+                }
+            }
+            paths
+        }
     }
 }
-fn last_path_in(value: &DisplayValue) -> Path {
+
+pub(crate) fn first_path_in(value: &DisplayValue) -> Path {
     match value {
-        &DisplayValue::Array(ref array) => {
-            if !array.extended {
-                Path::Array(ArrayPath::Toggle)
-            } else if array.can_grow() {
-                Path::Array(ArrayPath::Grow)
-            } else if array.can_shrink() {
-                Path::Array(ArrayPath::Shrink)
-            } else {
-                debug_assert!(
-                    array.values.is_empty(),
-                    "Non-empty array has to be able to shrink OR grow"
+        DisplayValue::Array(_) => Path::Array(ArrayPath::Toggle),
+        DisplayValue::Object(_) => Path::Object(ObjectPath::Toggle),
+        DisplayValue::Scalar(_) => Path::Scalar,
+    }
+}
+fn first_path_in_member(
+    obj: &DisplayObject,
+    index: usize,
+    mode: SelectionMode,
+) -> Option<ObjectPath> {
+    let id = obj.members.id_at(index)?;
+    Some(match mode {
+        SelectionMode::InteractionPoints => {
+            let value = obj.members.get_by_id(&id)?;
+            ObjectPath::Item(id, Box::new(first_path_in(value)))
+        }
+        SelectionMode::AllNodes => ObjectPath::Key(id),
+    })
+}
+fn last_path_in_member(obj: &DisplayObject, index: usize) -> Option<ObjectPath> {
+    let id = obj.members.id_at(index)?;
+    let value = obj.members.get_by_id(&id)?;
+    Some(ObjectPath::Item(id.clone(), Box::new(last_path_in(value))))
+}
+fn last_path_before_member(obj: &DisplayObject, id: &MemberId) -> ObjectPath {
+    obj.members
+        .position(id)
+        .and_then(|i| i.checked_sub(1))
+        .and_then(|i| last_path_in_member(obj, i))
+        .unwrap_or(ObjectPath::Toggle)
+}
+pub(crate) fn last_path_in(value: &DisplayValue) -> Path {
+    // The last interaction point of an object is the last one of its last member.
+    let mut segments = Vec::new();
+    let mut node = value;
+    loop {
+        let last = match node {
+            DisplayValue::Array(array) => {
+                return Path::join(
+                    segments,
+                    Path::Array(if !array.extended {
+                        ArrayPath::Toggle
+                    } else if array.can_grow() {
+                        ArrayPath::Grow
+                    } else if array.can_shrink() {
+                        ArrayPath::Shrink
+                    } else {
+                        debug_assert!(
+                            array.values.is_empty(),
+                            "Non-empty array has to be able to shrink OR grow"
+                        );
+                        ArrayPath::Toggle
+                    }),
                 );
-                Path::Array(ArrayPath::Toggle)
             }
-        }
-        &DisplayValue::Object(ref obj) => {
-            if let (true, Some((key, value))) = (obj.extended, obj.members.iter().last()) {
-                Path::Object(ObjectPath::Item(
-                    key.to_string(),
-                    Box::new(last_path_in(value)),
-                ))
-            } else {
-                Path::Object(ObjectPath::Toggle)
+            DisplayValue::Object(obj) if obj.extended => {
+                obj.members.len().checked_sub(1).and_then(|i| {
+                    let id = obj.members.id_at(i)?;
+                    let value = obj.members.get_by_id(&id)?;
+                    Some((id, value))
+                })
             }
+            &DisplayValue::Object(_) => None,
+            &DisplayValue::Scalar(_) => return Path::join(segments, Path::Scalar),
+        };
+        match last {
+            Some((id, value)) => {
+                segments.push(JsonPathSegment::Key(id));
+                node = value;
+            }
+            None => return Path::join(segments, Path::Object(ObjectPath::Toggle)),
         }
-        &DisplayValue::Scalar(_) => Path::Scalar,
     }
 }
 
 impl Path {
-    pub fn find_next_path(self, value: &DisplayValue) -> Option<Self> {
-        match value {
-            &DisplayValue::Array(ref array) => match (self.unwrap_array(), array.extended) {
-                (ArrayPath::Toggle, false) => None,
-                (ArrayPath::Toggle, true) => {
-                    if let (true, Some(first)) = (array.num_extended > 0, array.values.first()) {
-                        Some(Path::Array(ArrayPath::Item(
-                            0,
-                            Box::new(first_path_in(&*first)),
-                        )))
-                    } else if array.can_grow() {
-                        Some(Path::Array(ArrayPath::Grow))
-                    } else {
-                        None
-                    }
+    /// The location of the node this path points at. Interaction points of containers (e.g.,
+    /// toggles) belong to the container itself.
+    pub fn to_json_path(&self) -> JsonPath {
+        let mut result = JsonPath::root();
+        let mut current = self;
+        loop {
+            current = match current {
+                Path::Array(ArrayPath::Item(i, subpath)) => {
+                    result.push(JsonPathSegment::Index(*i));
+                    subpath
                 }
-                (ArrayPath::Item(i, subpath), true) => {
-                    if let Some(new_sub_path) = subpath.find_next_path(&array.values[i]) {
-                        Some(Path::Array(ArrayPath::Item(i, Box::new(new_sub_path))))
-                    } else {
-                        let potential_new_i = i + 1;
-                        if let (true, Some(next)) = (
-                            potential_new_i < array.num_extended,
-                            array.values.get(potential_new_i),
-                        ) {
-                            Some(Path::Array(ArrayPath::Item(
-                                potential_new_i,
-                                Box::new(first_path_in(&*next)),
-                            )))
-                        } else {
-                            if array.can_shrink() {
-                                Some(Path::Array(ArrayPath::Shrink))
-                            } else if array.can_grow() {
-                                Some(Path::Array(ArrayPath::Grow))
-                            } else {
-                                None
-                            }
-                        }
-                    }
-                }
-                (ArrayPath::Shrink, true) => {
-                    if array.can_grow() {
-                        Some(Path::Array(ArrayPath::Grow))
-                    } else {
-                        None
-                    }
+                Path::Object(ObjectPath::Item(id, subpath)) => {
+                    result.push(JsonPathSegment::Key(id.clone()));
+                    subpath
                 }
-                (ArrayPath::Grow, true) => None,
-                (_, false) => {
-                    panic!("Invalid path for non-extended array");
+                Path::Object(ObjectPath::Key(id)) => {
+                    result.push(JsonPathSegment::Key(id.clone()));
+                    return result;
                 }
-            },
+                _ => return result,
+            }
+        }
+    }
 
-            &DisplayValue::Object(ref obj) => match self.unwrap_object() {
-                ObjectPath::Item(key, subpath) => {
-                    assert!(obj.extended, "Item path on non-extended object");
-                    if let Some(new_sub_path) = subpath.find_next_path(&obj.members[&key]) {
-                        Some(Path::Object(ObjectPath::Item(key, Box::new(new_sub_path))))
-                    } else {
-                        if let Some((first_key, first_val)) = obj
-                            .members
-                            .iter()
-                            .skip_while(|&(k, _)| *k != key)
-                            .skip(1)
-                            .next()
-                        {
-                            Some(Path::Object(ObjectPath::Item(
-                                first_key.to_string(),
-                                Box::new(first_path_in(first_val)),
-                            )))
-                        } else {
-                            None
-                        }
-                    }
+    /// Whether the innermost node on the path is selected via its main interaction point, i.e., it
+    /// is a scalar or the path points at its toggle (rather than at a key or a grow/shrink knob).
+    pub fn ends_at_main_point(&self) -> bool {
+        let mut current = self;
+        loop {
+            current = match current {
+                Path::Array(ArrayPath::Item(_, subpath))
+                | Path::Object(ObjectPath::Item(_, subpath)) => subpath,
+                Path::Scalar
+                | Path::Array(ArrayPath::Toggle)
+                | Path::Object(ObjectPath::Toggle) => return true,
+                _ => return false,
+            };
+        }
+    }
+
+    /// Whether the path points at the node itself rather than into one of its members.
+    pub fn is_on_node(&self) -> bool {
+        !matches!(
+            self,
+            Path::Array(ArrayPath::Item(_, _))
+                | Path::Object(ObjectPath::Item(_, _))
+                | Path::Object(ObjectPath::Key(_))
+        )
+    }
+
+    /// The node that can be activated at this path, i.e., a scalar or the key of a scalar member.
+    pub fn activated_node<'a>(&'a self, value: &'a DisplayValue) -> Option<ActivatedNode<'a>> {
+        let (mut path, mut value) = (self, value);
+        loop {
+            match (value, path) {
+                (DisplayValue::Scalar(scalar), Path::Scalar) => {
+                    return Some(match scalar.link() {
+                        Some(link) => ActivatedNode::Link(link),
+                        None => ActivatedNode::Scalar(scalar.text()),
+                    });
                 }
-                ObjectPath::Toggle => {
-                    if let (true, Some((first_key, first_val))) =
-                        (obj.extended, obj.members.iter().next())
-                    {
-                        Some(Path::Object(ObjectPath::Item(
-                            first_key.to_string(),
-                            Box::new(first_path_in(first_val)),
-                        )))
-                    } else {
-                        None
-                    }
+                (DisplayValue::Array(array), Path::Array(ArrayPath::Item(i, subpath))) => {
+                    value = &array.values[*i];
+                    path = subpath;
                 }
-            },
+                (DisplayValue::Object(obj), Path::Object(ObjectPath::Item(id, subpath))) => {
+                    value = obj.members.get_by_id(id)?;
+                    path = subpath;
+                }
+                (DisplayValue::Object(obj), Path::Object(ObjectPath::Key(id))) => {
+                    return match obj.members.get_by_id(id) {
+                        Some(DisplayValue::Scalar(_)) => Some(ActivatedNode::Key(&id.key)),
+                        _ => None,
+                    };
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    pub fn find_next_path(self, value: &DisplayValue, mode: SelectionMode) -> Option<Self> {
+        let (mut segments, innermost) = self.split();
+        let nodes = nodes_on(value, &segments);
+        if let Some(next) = next_in_node(nodes[segments.len()], &innermost, mode) {
+            return Some(Path::join(segments, next));
+        }
+        // Continue after the innermost child that is followed by another interaction point.
+        while let Some(segment) = segments.pop() {
+            if let Some(next) = next_after_child(nodes[segments.len()], &segment, mode) {
+                return Some(Path::join(segments, next));
+            }
+        }
+        None
+    }
 
-            &DisplayValue::Scalar(_) => None,
+    pub fn find_previous_path(self, value: &DisplayValue, mode: SelectionMode) -> Option<Self> {
+        let (mut segments, innermost) = self.split();
+        let nodes = nodes_on(value, &segments);
+        if let Some(previous) = previous_in_node(nodes[segments.len()], &innermost) {
+            return Some(Path::join(segments, previous));
         }
+        let segment = segments.pop()?;
+        let previous = previous_before_child(nodes[segments.len()], segment, mode);
+        Some(Path::join(segments, previous))
     }
 
-    pub fn find_previous_path(self, value: &DisplayValue) -> Option<Self> {
-        match value {
-            &DisplayValue::Array(ref array) => match (self.unwrap_array(), array.extended) {
-                (ArrayPath::Toggle, _) => None,
-                (ArrayPath::Item(i, subpath), true) => {
-                    if let Some(new_sub_path) = subpath.find_previous_path(&array.values[i]) {
-                        Some(Path::Array(ArrayPath::Item(i, Box::new(new_sub_path))))
-                    } else {
-                        if let Some(next) =
-                            i.checked_sub(1).and_then(|new_i| array.values.get(new_i))
-                        {
-                            Some(Path::Array(ArrayPath::Item(
-                                i - 1,
-                                Box::new(last_path_in(&*next)),
-                            )))
+    pub fn fix_path_for_value(self, value: &DisplayValue, mode: SelectionMode) -> Self {
+        let (segments, innermost) = self.split();
+        let mut fixed = Vec::new();
+        let mut node = value;
+        for segment in segments {
+            node = match (node, segment) {
+                (DisplayValue::Array(arr), _) if !arr.extended => {
+                    return Path::join(fixed, Path::Array(ArrayPath::Toggle));
+                }
+                (DisplayValue::Array(arr), JsonPathSegment::Index(i)) => {
+                    if arr.shown_position(i).is_none() {
+                        let path = if i < arr.values.len() {
+                            ArrayPath::Grow
                         } else {
-                            Some(Path::Array(ArrayPath::Toggle))
+                            ArrayPath::Toggle
+                        };
+                        return Path::join(fixed, Path::Array(path));
+                    }
+                    fixed.push(JsonPathSegment::Index(i));
+                    &arr.values[i]
+                }
+                (DisplayValue::Array(_), _) => {
+                    return Path::join(fixed, Path::Array(ArrayPath::Toggle));
+                }
+                (DisplayValue::Object(obj), _) if !obj.extended => {
+                    return Path::join(fixed, Path::Object(ObjectPath::Toggle));
+                }
+                (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => {
+                    match obj.members.get_by_id(&id) {
+                        Some(val) => {
+                            fixed.push(JsonPathSegment::Key(id));
+                            val
                         }
+                        None => return Path::join(fixed, Path::Object(ObjectPath::Toggle)),
                     }
                 }
-                (ArrayPath::Shrink, true) => Some(Path::Array(
-                    if let (true, Some(first)) = (
-                        array.num_extended > 0,
-                        array.values.iter().nth(array.num_extended - 1),
-                    ) {
-                        ArrayPath::Item(array.num_extended - 1, Box::new(last_path_in(&*first)))
-                    } else {
-                        ArrayPath::Toggle
-                    },
-                )),
-                (ArrayPath::Grow, true) => Some(Path::Array(if array.can_shrink() {
+                (DisplayValue::Object(_), _) => {
+                    return Path::join(fixed, Path::Object(ObjectPath::Toggle));
+                }
+                (DisplayValue::Scalar(_), _) => return Path::join(fixed, Path::Scalar),
+            };
+        }
+        let innermost = match (node, &innermost) {
+            (DisplayValue::Array(arr), Path::Array(ArrayPath::Shrink)) if arr.extended => {
+                Path::Array(if arr.can_shrink() {
+                    ArrayPath::Shrink
+                } else if arr.can_grow() {
+                    ArrayPath::Grow
+                } else {
+                    ArrayPath::Toggle
+                })
+            }
+            (DisplayValue::Array(arr), Path::Array(ArrayPath::Grow)) if arr.extended => {
+                Path::Array(if arr.can_grow() {
+                    ArrayPath::Grow
+                } else if arr.can_shrink() {
                     ArrayPath::Shrink
                 } else {
                     ArrayPath::Toggle
-                })),
-                (_, false) => {
-                    panic!("Invalid path for non-extended array");
+                })
+            }
+            (DisplayValue::Array(_), _) => Path::Array(ArrayPath::Toggle),
+            (DisplayValue::Object(obj), Path::Object(ObjectPath::Key(id))) if obj.extended => {
+                match (obj.members.get_by_id(id), mode) {
+                    (Some(_), SelectionMode::AllNodes) => Path::Object(ObjectPath::Key(id.clone())),
+                    (Some(val), SelectionMode::InteractionPoints) => {
+                        Path::Object(ObjectPath::Item(id.clone(), Box::new(first_path_in(val))))
+                    }
+                    (None, _) => Path::Object(ObjectPath::Toggle),
                 }
-            },
+            }
+            (DisplayValue::Object(_), _) => Path::Object(ObjectPath::Toggle),
+            (DisplayValue::Scalar(_), _) => Path::Scalar,
+        };
+        Path::join(fixed, innermost)
+    }
 
-            &DisplayValue::Object(ref obj) => match self.unwrap_object() {
-                ObjectPath::Item(key, subpath) => {
-                    if let Some(new_sub_path) = subpath.find_previous_path(&obj.members[&key]) {
-                        Some(Path::Object(ObjectPath::Item(key, Box::new(new_sub_path))))
-                    } else {
-                        if let Some((last_key, last_val)) = obj
-                            .members
-                            .iter()
-                            .rev()
-                            .skip_while(|&(k, _)| *k != key)
-                            .skip(1)
-                            .next()
-                        {
-                            Some(Path::Object(ObjectPath::Item(
-                                last_key.to_string(),
-                                Box::new(last_path_in(last_val)),
-                            )))
-                        } else {
-                            Some(Path::Object(ObjectPath::Toggle))
+    pub fn find_and_act_on_element(&self, value: &mut DisplayValue) -> Result<ToggleResult, ()> {
+        fn toggled(extended: bool) -> ToggleResult {
+            if extended {
+                ToggleResult::Expanded
+            } else {
+                ToggleResult::Collapsed
+            }
+        }
+        let (mut path, mut value) = (self, value);
+        loop {
+            value.invalidate_extent();
+            match (value, path) {
+                (&mut DisplayValue::Array(ref mut array), &Path::Array(ArrayPath::Shrink)) => {
+                    array.shrink();
+                    return Ok(ToggleResult::Shrank(array.num_shown()));
+                }
+                (&mut DisplayValue::Array(ref mut array), &Path::Array(ArrayPath::Toggle)) => {
+                    array.toggle_visibility();
+                    return Ok(toggled(array.extended));
+                }
+                (
+                    &mut DisplayValue::Array(ref mut array),
+                    &Path::Array(ArrayPath::Item(i, ref subpath)),
+                ) => {
+                    value = &mut array.values[i];
+                    path = subpath;
+                }
+                (&mut DisplayValue::Array(ref mut array), &Path::Array(ArrayPath::Grow)) => {
+                    array.grow();
+                    return Ok(ToggleResult::Grew(array.num_shown()));
+                }
+
+                (
+                    &mut DisplayValue::Object(ref mut obj),
+                    &Path::Object(ObjectPath::Item(ref id, ref subpath)),
+                ) => {
+                    value = obj.members.get_by_id_mut(id).unwrap();
+                    path = subpath;
+                }
+                (
+                    &mut DisplayValue::Object(ref mut obj),
+                    &Path::Object(ObjectPath::Key(ref id)),
+                ) => {
+                    // Keys act on the container they belong to. Scalars do not have anything to
+                    // act on.
+                    return match obj.members.get_by_id_mut(id).unwrap() {
+                        DisplayValue::Object(obj) => {
+                            obj.toggle_visibility();
+                            Ok(toggled(obj.extended))
                         }
-                    }
+                        DisplayValue::Array(array) => {
+                            array.toggle_visibility();
+                            Ok(toggled(array.extended))
+                        }
+                        DisplayValue::Scalar(_) => Err(()),
+                    };
+                }
+                (&mut DisplayValue::Object(ref mut obj), &Path::Object(ObjectPath::Toggle)) => {
+                    obj.toggle_visibility();
+                    return Ok(toggled(obj.extended));
                 }
-                ObjectPath::Toggle => None,
-            },
 
-            &DisplayValue::Scalar(_) => None,
+                (&mut DisplayValue::Scalar(ref mut scalar), &Path::Scalar) => {
+                    // Only binary data can be expanded, we do not do anything with other scalars.
+                    return scalar.toggle_binary().map(toggled);
+                }
+                _ => {
+                    panic!("Path does not match value");
+                }
+            }
         }
     }
+}
 
-    pub fn fix_path_for_value(self, value: &DisplayValue) -> Self {
-        match value {
-            // "E0009: cannot bind by-move and by-ref in the same pattern" is really annoying...
-            &DisplayValue::Array(ref arr) => {
-                if !arr.extended {
-                    Path::Array(ArrayPath::Toggle)
-                } else {
-                    match self {
-                        Path::Array(array_path) => Path::Array(match array_path {
-                            ArrayPath::Toggle => ArrayPath::Toggle,
-                            ArrayPath::Item(i, subpath) => {
-                                if i < arr.num_extended {
-                                    let new_sub_path = subpath.fix_path_for_value(&arr.values[i]);
-                                    ArrayPath::Item(i, Box::new(new_sub_path))
-                                } else {
-                                    if i < arr.values.len() {
-                                        ArrayPath::Grow
-                                    } else {
-                                        ArrayPath::Toggle
-                                    }
-                                }
-                            }
-                            ArrayPath::Shrink => {
-                                if arr.can_shrink() {
-                                    ArrayPath::Shrink
-                                } else if arr.can_grow() {
-                                    ArrayPath::Grow
-                                } else {
-                                    ArrayPath::Toggle
-                                }
-                            }
-                            ArrayPath::Grow => {
-                                if arr.can_grow() {
-                                    ArrayPath::Grow
-                                } else if arr.can_shrink() {
-                                    ArrayPath::Shrink
-                                } else {
-                                    ArrayPath::Toggle
-                                }
-                            }
-                        }),
-                        _ => Path::Array(ArrayPath::Toggle),
-                    }
-                }
+/// The nodes on the way to the node at `segments` (starting with `value` itself).
+fn nodes_on<'a>(value: &'a DisplayValue, segments: &[JsonPathSegment]) -> Vec<&'a DisplayValue> {
+    let mut nodes = vec![value];
+    for segment in segments {
+        let node = match (nodes[nodes.len() - 1], segment) {
+            (DisplayValue::Array(array), &JsonPathSegment::Index(i)) => {
+                assert!(array.extended, "Invalid path for non-extended array");
+                &array.values[i]
             }
-            &DisplayValue::Object(ref obj) => {
-                if !obj.extended {
-                    Path::Object(ObjectPath::Toggle)
-                } else {
-                    match self {
-                        Path::Object(obj_path) => Path::Object(match obj_path {
-                            ObjectPath::Item(key, subpath) => {
-                                if let Some(val) = obj.members.get(&key) {
-                                    let new_sub_path = subpath.fix_path_for_value(val);
-                                    ObjectPath::Item(key, Box::new(new_sub_path))
-                                } else {
-                                    ObjectPath::Toggle
-                                }
-                            }
-                            ObjectPath::Toggle => ObjectPath::Toggle,
-                        }),
-                        _ => Path::Object(ObjectPath::Toggle),
-                    }
-                }
+            (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => {
+                assert!(obj.extended, "Item path on non-extended object");
+                obj.members.get_by_id(id).expect("Invalid member path")
             }
-            &DisplayValue::Scalar(_) => Path::Scalar,
-        }
+            _ => panic!("Path does not match value"),
+        };
+        nodes.push(node);
     }
+    nodes
+}
 
-    pub fn find_and_act_on_element(&self, value: &mut DisplayValue) -> Result<(), ()> {
-        match (value, self) {
-            (&mut DisplayValue::Array(ref mut array), &Path::Array(ArrayPath::Shrink)) => {
-                array.shrink();
-                Ok(())
+/// The interaction point following `path` within `value` if `path` does not point into a child.
+fn next_in_node(value: &DisplayValue, path: &Path, mode: SelectionMode) -> Option<Path> {
+    match (value, path) {
+        (DisplayValue::Array(array), Path::Array(path)) if !array.extended => match path {
+            ArrayPath::Toggle => None,
+            _ => panic!("Invalid path for non-extended array"),
+        },
+        (DisplayValue::Array(array), Path::Array(ArrayPath::Toggle)) => {
+            if array.num_shown() > 0 {
+                let first = array.shown_index(0);
+                Some(Path::Array(ArrayPath::Item(
+                    first,
+                    Box::new(first_path_in(&array.values[first])),
+                )))
+            } else if array.can_grow() {
+                Some(Path::Array(ArrayPath::Grow))
+            } else {
+                None
             }
-            (&mut DisplayValue::Array(ref mut array), &Path::Array(ArrayPath::Toggle)) => {
-                array.toggle_visibility();
-                Ok(())
+        }
+        (DisplayValue::Array(array), Path::Array(ArrayPath::Shrink)) => {
+            if array.can_grow() {
+                Some(Path::Array(ArrayPath::Grow))
+            } else {
+                None
             }
-            (
-                &mut DisplayValue::Array(ref mut array),
-                &Path::Array(ArrayPath::Item(i, ref subpath)),
-            ) => subpath.find_and_act_on_element(&mut array.values[i]),
-            (&mut DisplayValue::Array(ref mut array), &Path::Array(ArrayPath::Grow)) => {
-                array.grow();
-                Ok(())
+        }
+        (DisplayValue::Array(_), Path::Array(ArrayPath::Grow)) => None,
+        (DisplayValue::Object(obj), Path::Object(ObjectPath::Key(id))) => {
+            assert!(obj.extended, "Key path on non-extended object");
+            let first = first_path_in(obj.members.get_by_id(id).expect("Invalid key path"));
+            Some(Path::Object(ObjectPath::Item(id.clone(), Box::new(first))))
+        }
+        (DisplayValue::Object(obj), Path::Object(ObjectPath::Toggle)) => {
+            if obj.extended {
+                first_path_in_member(obj, 0, mode).map(Path::Object)
+            } else {
+                None
             }
+        }
+        (DisplayValue::Scalar(_), _) => None,
+        _ => panic!("Path does not match value"),
+    }
+}
 
-            (
-                &mut DisplayValue::Object(ref mut obj),
-                &Path::Object(ObjectPath::Item(ref key, ref subpath)),
-            ) => subpath.find_and_act_on_element(obj.members.get_mut(key).unwrap()),
-            (&mut DisplayValue::Object(ref mut obj), &Path::Object(ObjectPath::Toggle)) => {
-                obj.toggle_visibility();
-                Ok(())
+/// The interaction point of `value` following the child at `segment` and all of its interaction
+/// points.
+fn next_after_child(
+    value: &DisplayValue,
+    segment: &JsonPathSegment,
+    mode: SelectionMode,
+) -> Option<Path> {
+    match (value, segment) {
+        (DisplayValue::Array(array), &JsonPathSegment::Index(i)) => {
+            let next_position = array.shown_position(i).map(|p| p + 1);
+            if let Some(next_position) = next_position.filter(|&p| p < array.num_shown()) {
+                let next = array.shown_index(next_position);
+                Some(Path::Array(ArrayPath::Item(
+                    next,
+                    Box::new(first_path_in(&array.values[next])),
+                )))
+            } else if array.can_shrink() {
+                Some(Path::Array(ArrayPath::Shrink))
+            } else if array.can_grow() {
+                Some(Path::Array(ArrayPath::Grow))
+            } else {
+                None
             }
+        }
+        (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => {
+            let index = obj.members.position(id).expect("Invalid member path");
+            first_path_in_member(obj, index + 1, mode).map(Path::Object)
+        }
+        _ => panic!("Path does not match value"),
+    }
+}
 
-            (&mut DisplayValue::Scalar(_), &Path::Scalar) => {
-                // We do not do anything with scalars.
-                Err(())
-            }
-            _ => {
-                panic!("Path does not match value");
+/// The interaction point preceding `path` within `value` if `path` does not point into a child.
+fn previous_in_node(value: &DisplayValue, path: &Path) -> Option<Path> {
+    match (value, path) {
+        (DisplayValue::Array(_), Path::Array(ArrayPath::Toggle)) => None,
+        (DisplayValue::Array(array), Path::Array(_)) if !array.extended => {
+            panic!("Invalid path for non-extended array");
+        }
+        (DisplayValue::Array(array), Path::Array(ArrayPath::Shrink)) => {
+            Some(Path::Array(if array.num_shown() > 0 {
+                let last = array.shown_index(array.num_shown() - 1);
+                ArrayPath::Item(last, Box::new(last_path_in(&array.values[last])))
+            } else {
+                ArrayPath::Toggle
+            }))
+        }
+        (DisplayValue::Array(array), Path::Array(ArrayPath::Grow)) => {
+            Some(Path::Array(if array.can_shrink() {
+                ArrayPath::Shrink
+            } else {
+                ArrayPath::Toggle
+            }))
+        }
+        (DisplayValue::Object(obj), Path::Object(ObjectPath::Key(id))) => {
+            Some(Path::Object(last_path_before_member(obj, id)))
+        }
+        (DisplayValue::Object(_), Path::Object(ObjectPath::Toggle)) => None,
+        (DisplayValue::Scalar(_), _) => None,
+        _ => panic!("Path does not match value"),
+    }
+}
+
+/// The interaction point of `value` preceding the child at `segment`.
+fn previous_before_child(
+    value: &DisplayValue,
+    segment: JsonPathSegment,
+    mode: SelectionMode,
+) -> Path {
+    match (value, segment) {
+        (DisplayValue::Array(array), JsonPathSegment::Index(i)) => {
+            let position = array.shown_position(i).and_then(|p| p.checked_sub(1));
+            if let Some(position) = position {
+                let previous = array.shown_index(position);
+                Path::Array(ArrayPath::Item(
+                    previous,
+                    Box::new(last_path_in(&array.values[previous])),
+                ))
+            } else {
+                Path::Array(ArrayPath::Toggle)
             }
         }
+        (DisplayValue::Object(_), JsonPathSegment::Key(id)) if mode == SelectionMode::AllNodes => {
+            Path::Object(ObjectPath::Key(id))
+        }
+        (DisplayValue::Object(obj), JsonPathSegment::Key(id)) => {
+            Path::Object(last_path_before_member(obj, &id))
+        }
+        _ => panic!("Path does not match value"),
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::test_util::arbitrary_json;
+    use crate::{Value, ValueVariant};
     use json::JsonValue;
+    use proptest::prelude::*;
+    use std::collections::BTreeMap;
 
     fn aeq_first_path_in(val: JsonValue, expected: Path) {
         let val = DisplayValue::new(&val);
@@ -421,7 +1022,7 @@ mod test {
         let expected_after = expected_after.into();
         let mut val = DisplayValue::new(&val);
         setup(&mut val);
-        let real_after = before.find_next_path(&val);
+        let real_after = before.find_next_path(&val, SelectionMode::InteractionPoints);
         assert_eq!(real_after, expected_after);
     }
 
@@ -429,6 +1030,12 @@ mod test {
         aeq_next_path_setup(val, |_| {}, before, expected_after);
     }
 
+    fn aeq_next_node<P: Into<Option<Path>>>(val: JsonValue, before: Path, expected_after: P) {
+        let val = DisplayValue::new(&val);
+        let real_after = before.find_next_path(&val, SelectionMode::AllNodes);
+        assert_eq!(real_after, expected_after.into());
+    }
+
     #[test]
     fn test_find_next_path() {
         aeq_next_path(JsonValue::String("foo".to_string()), Path::scalar(), None);
@@ -533,7 +1140,7 @@ mod test {
         let expected_after = expected_after.into();
         let mut val = DisplayValue::new(&val);
         setup(&mut val);
-        let real_after = before.find_previous_path(&val);
+        let real_after = before.find_previous_path(&val, SelectionMode::InteractionPoints);
         assert_eq!(real_after, expected_after);
     }
 
@@ -541,6 +1148,12 @@ mod test {
         aeq_previous_path_setup(val, |_| {}, before, expected_after);
     }
 
+    fn aeq_previous_node<P: Into<Option<Path>>>(val: JsonValue, before: Path, expected_after: P) {
+        let val = DisplayValue::new(&val);
+        let real_after = before.find_previous_path(&val, SelectionMode::AllNodes);
+        assert_eq!(real_after, expected_after.into());
+    }
+
     #[test]
     fn test_find_previous_path() {
         aeq_previous_path(JsonValue::String("foo".to_string()), Path::scalar(), None);
@@ -715,6 +1328,28 @@ mod test {
             false,
         );
 
+        aeq_find_and_act_on_element(
+            object! { "bar" => array!{0, 1, 2, 3, 4}, "foo" => "f"},
+            Path::object_key("bar"),
+            |v| {
+                !v.unwrap_object_ref().members["bar"]
+                    .unwrap_array_ref()
+                    .extended
+            },
+            false,
+        );
+        aeq_find_and_act_on_element(
+            object! { "bar" => array!{0, 1, 2, 3, 4}, "foo" => "f"},
+            Path::object_key("foo"),
+            |v| {
+                v.unwrap_object_ref().members["foo"]
+                    .unwrap_scalar_ref()
+                    .value
+                    == "f"
+            },
+            true,
+        );
+
         aeq_find_and_act_on_element(
             array! { object!{ "bar" => "b", "foo" => "f"}, 1, 2, 3},
             Path::object_toggle().array(0),
@@ -735,7 +1370,7 @@ mod test {
     ) {
         let mut val = DisplayValue::new(&val);
         setup(&mut val);
-        let real_after = before.fix_path_for_value(&val);
+        let real_after = before.fix_path_for_value(&val, SelectionMode::InteractionPoints);
         assert_eq!(real_after, expected_after);
     }
 
@@ -879,4 +1514,336 @@ mod test {
             Path::object_toggle(),
         );
     }
+
+    #[test]
+    fn test_navigate_all_nodes() {
+        let val = || object! { "bar" => array!{ 1 }, "foo" => "f"};
+
+        aeq_next_node(val(), Path::object_toggle(), Path::object_key("bar"));
+        aeq_next_node(
+            val(),
+            Path::object_key("bar"),
+            Path::array_toggle().object("bar"),
+        );
+        aeq_next_node(
+            val(),
+            Path::array_shrink().object("bar"),
+            Path::object_key("foo"),
+        );
+        aeq_next_node(val(), Path::object_key("foo"), Path::scalar().object("foo"));
+        aeq_next_node(val(), Path::scalar().object("foo"), None);
+
+        aeq_previous_node(val(), Path::scalar().object("foo"), Path::object_key("foo"));
+        aeq_previous_node(
+            val(),
+            Path::object_key("foo"),
+            Path::array_shrink().object("bar"),
+        );
+        aeq_previous_node(
+            val(),
+            Path::array_toggle().object("bar"),
+            Path::object_key("bar"),
+        );
+        aeq_previous_node(val(), Path::object_key("bar"), Path::object_toggle());
+
+        let val = DisplayValue::new(val());
+        assert_eq!(
+            Path::object_key("foo").fix_path_for_value(&val, SelectionMode::InteractionPoints),
+            Path::scalar().object("foo")
+        );
+        assert_eq!(
+            Path::object_key("foo").fix_path_for_value(&val, SelectionMode::AllNodes),
+            Path::object_key("foo")
+        );
+        assert_eq!(
+            Path::object_key("nope").fix_path_for_value(&val, SelectionMode::AllNodes),
+            Path::object_toggle()
+        );
+    }
+
+    #[derive(Clone)]
+    enum DuplicateValue {
+        Scalar(&'static str),
+        Map(Vec<(&'static str, DuplicateValue)>),
+    }
+
+    impl Value for DuplicateValue {
+        fn visit<'s>(self) -> ValueVariant<'s, Self> {
+            match self {
+                DuplicateValue::Scalar(s) => ValueVariant::Scalar(None, s.into()),
+                DuplicateValue::Map(members) => ValueVariant::Map(
+                    None,
+                    Box::new(members.into_iter().map(|(k, v)| (k.into(), v))),
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_navigate_duplicate_keys() {
+        let val = DisplayValue::new(DuplicateValue::Map(vec![
+            ("foo", DuplicateValue::Scalar("1")),
+            ("bar", DuplicateValue::Scalar("2")),
+            ("foo", DuplicateValue::Scalar("3")),
+        ]));
+        let second_foo = |subpath: Path| {
+            Path::Object(ObjectPath::Item(
+                MemberId {
+                    key: "foo".into(),
+                    occurrence: 1,
+                },
+                Box::new(subpath),
+            ))
+        };
+        let mode = SelectionMode::InteractionPoints;
+
+        assert_eq!(
+            Path::scalar().object("bar").find_next_path(&val, mode),
+            Some(second_foo(Path::scalar()))
+        );
+        assert_eq!(second_foo(Path::scalar()).find_next_path(&val, mode), None);
+        assert_eq!(
+            second_foo(Path::scalar()).find_previous_path(&val, mode),
+            Some(Path::scalar().object("bar"))
+        );
+        assert_eq!(last_path_in(&val), second_foo(Path::scalar()));
+        assert_eq!(
+            second_foo(Path::scalar()).activated_node(&val),
+            Some(ActivatedNode::Scalar("3"))
+        );
+        let path = second_foo(Path::scalar()).to_json_path();
+        assert_eq!(
+            path,
+            JsonPath::root().member(MemberId {
+                key: "foo".into(),
+                occurrence: 1,
+            })
+        );
+        assert_eq!(path.to_string(), ".foo#1");
+        assert_eq!(path.to_pointer(), "/foo");
+        assert_eq!(val.get(&path).unwrap().unwrap_scalar_ref().value, "3");
+        assert_eq!(
+            reveal_path(path.segments(), &mut val.clone()),
+            Some(second_foo(Path::scalar()))
+        );
+    }
+
+    /// An object with an integer key and a string key that are displayed the same way.
+    #[derive(Clone)]
+    struct TypedKeys(Option<&'static str>);
+
+    impl Value for TypedKeys {
+        fn visit<'s>(self) -> ValueVariant<'s, Self> {
+            match self.0 {
+                Some(value) => ValueVariant::Scalar(None, value.into()),
+                None => ValueVariant::Map(
+                    None,
+                    Box::new(
+                        vec![
+                            (Key::Integer(1), TypedKeys(Some("integer"))),
+                            (Key::from("1"), TypedKeys(Some("string"))),
+                        ]
+                        .into_iter(),
+                    ),
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_typed_keys() {
+        let mut val = DisplayValue::new(TypedKeys(None));
+        let integer = JsonPath::root().key(Key::Integer(1));
+        let string = JsonPath::root().key("1");
+        assert_ne!(integer, string);
+        let text =
+            |val: &DisplayValue, path| val.get(path).unwrap().unwrap_scalar_ref().text().to_owned();
+        assert_eq!(text(&val, &integer), "integer");
+        assert_eq!(text(&val, &string), "string");
+        assert_eq!(
+            reveal_path(string.segments(), &mut val)
+                .unwrap()
+                .to_json_path(),
+            string
+        );
+        // Pointers only refer to the displayed key
+        assert_eq!(json_path_from_pointer("/1", &val), Some(integer));
+    }
+
+    #[test]
+    fn test_folds_of_duplicate_keys() {
+        let object = |value| DuplicateValue::Map(vec![("x", DuplicateValue::Scalar(value))]);
+        let value = || DuplicateValue::Map(vec![("a", object("1")), ("a", object("2"))]);
+        let first = JsonPath::root().key("a");
+        let second = JsonPath::root().member(MemberId {
+            key: "a".into(),
+            occurrence: 1,
+        });
+        let mut val = DisplayValue::new(value());
+        val.get_mut(&second)
+            .unwrap()
+            .unwrap_object_ref_mut()
+            .toggle_visibility();
+        let mut folds = BTreeMap::new();
+        val.folds(&mut JsonPath::root(), &mut folds);
+
+        let mut restored = DisplayValue::new(value());
+        restored.restore_folds(&mut JsonPath::root(), &folds);
+        assert_eq!(restored.get(&first).unwrap().extended(), Some(true));
+        assert_eq!(restored.get(&second).unwrap().extended(), Some(false));
+        let updated = val.update(value());
+        assert_eq!(updated.get(&first).unwrap().extended(), Some(true));
+        assert_eq!(updated.get(&second).unwrap().extended(), Some(false));
+    }
+
+    #[test]
+    fn test_to_json_path() {
+        assert_eq!(Path::scalar().to_json_path(), JsonPath::root());
+        assert_eq!(
+            Path::array_grow().object("foo").to_json_path(),
+            JsonPath::root().key("foo")
+        );
+        assert_eq!(
+            Path::scalar()
+                .object("bar")
+                .array(3)
+                .object("foo")
+                .to_json_path(),
+            JsonPath::root().key("foo").index(3).key("bar")
+        );
+        assert_eq!(
+            Path::object_key("bar").array(1).to_json_path(),
+            JsonPath::root().index(1).key("bar")
+        );
+    }
+
+    #[test]
+    fn test_json_path_display() {
+        assert_eq!(JsonPath::root().to_string(), ".");
+        assert_eq!(
+            JsonPath::root().key("foo").index(3).key("bar").to_string(),
+            ".foo[3].bar"
+        );
+        assert_eq!(
+            JsonPath::root().key("with space").key("").to_string(),
+            "[\"with space\"][\"\"]"
+        );
+    }
+
+    #[test]
+    fn test_pointer() {
+        let val = DisplayValue::new(&object! {
+            "foo" => array!{ 0, object!{ "bar" => 1 } },
+            "a/b~" => "c",
+            "3" => "d"
+        });
+        let from_pointer = |p| json_path_from_pointer(p, &val);
+
+        assert_eq!(from_pointer(""), Some(JsonPath::root()));
+        assert_eq!(from_pointer("/foo"), Some(JsonPath::root().key("foo")));
+        assert_eq!(
+            from_pointer("/foo/1/bar"),
+            Some(JsonPath::root().key("foo").index(1).key("bar"))
+        );
+        assert_eq!(from_pointer("/a~1b~0"), Some(JsonPath::root().key("a/b~")));
+        assert_eq!(from_pointer("/3"), Some(JsonPath::root().key("3")));
+
+        assert_eq!(from_pointer("foo"), None);
+        assert_eq!(from_pointer("/nope"), None);
+        assert_eq!(from_pointer("/foo/2"), None);
+        assert_eq!(from_pointer("/foo/01"), None);
+        assert_eq!(from_pointer("/foo/-"), None);
+        assert_eq!(from_pointer("/foo/0/bar"), None);
+        assert_eq!(from_pointer("/a~2b"), None);
+
+        assert_eq!(JsonPath::root().to_pointer(), "");
+        assert_eq!(
+            JsonPath::root()
+                .key("foo")
+                .index(1)
+                .key("a/b~")
+                .to_pointer(),
+            "/foo/1/a~1b~0"
+        );
+    }
+
+    #[test]
+    fn test_reveal_path() {
+        let mut val = DisplayValue::new(&object! {
+            "foo" => array!{ 0, 1, 2, 3, object!{ "bar" => 1 } }
+        });
+        val.unwrap_object_ref_mut().extended = false;
+
+        let path = JsonPath::root().key("foo").index(4).key("nope");
+        assert_eq!(reveal_path(path.segments(), &mut val), None);
+        assert!(!val.unwrap_object_ref().extended);
+
+        let path = JsonPath::root().key("foo").index(4).key("bar");
+        assert_eq!(
+            reveal_path(path.segments(), &mut val),
+            Some(Path::scalar().object("bar").array(4).object("foo"))
+        );
+        assert!(val.unwrap_object_ref().extended);
+        assert_eq!(
+            val.unwrap_object_ref().members["foo"]
+                .unwrap_array_ref()
+                .num_extended,
+            5
+        );
+    }
+
+    /// The value with the objects and arrays at the selectable paths chosen by `toggles` folded,
+    /// unfolded, grown or shrunk.
+    fn toggled(value: &JsonValue, toggles: &[usize]) -> DisplayValue {
+        let mut value = DisplayValue::new(value);
+        for toggle in toggles {
+            let paths = all_paths(&value, SelectionMode::InteractionPoints);
+            let _ = paths[toggle % paths.len()].find_and_act_on_element(&mut value);
+        }
+        value
+    }
+
+    const MODES: [SelectionMode; 2] = [SelectionMode::InteractionPoints, SelectionMode::AllNodes];
+
+    proptest! {
+        #[test]
+        fn prop_next_and_previous_path_are_inverse(
+            value in arbitrary_json(),
+            toggles in prop::collection::vec(any::<usize>(), 0..8),
+        ) {
+            let value = toggled(&value, &toggles);
+            for &mode in MODES.iter() {
+                let paths = all_paths(&value, mode);
+                prop_assert_eq!(&first_path_in(&value), paths.first().unwrap());
+                prop_assert_eq!(&last_path_in(&value), paths.last().unwrap());
+                for (path, next) in paths.iter().zip(paths.iter().skip(1)) {
+                    prop_assert_eq!(path.clone().find_next_path(&value, mode), Some(next.clone()));
+                    prop_assert_eq!(next.clone().find_previous_path(&value, mode), Some(path.clone()));
+                }
+                prop_assert_eq!(paths.last().unwrap().clone().find_next_path(&value, mode), None);
+                prop_assert_eq!(paths[0].clone().find_previous_path(&value, mode), None);
+            }
+        }
+
+        #[test]
+        fn prop_fixed_path_is_valid(
+            old in arbitrary_json(),
+            new in arbitrary_json(),
+            old_toggles in prop::collection::vec(any::<usize>(), 0..8),
+            new_toggles in prop::collection::vec(any::<usize>(), 0..8),
+        ) {
+            let old = toggled(&old, &old_toggles);
+            let new = toggled(&new, &new_toggles);
+            for &mode in MODES.iter() {
+                let valid = all_paths(&new, mode);
+                for path in all_paths(&new, mode) {
+                    prop_assert_eq!(path.clone().fix_path_for_value(&new, mode), path);
+                }
+                for path in all_paths(&old, mode) {
+                    let fixed = path.fix_path_for_value(&new, mode);
+                    prop_assert!(valid.contains(&fixed), "{:?} is not valid", fixed);
+                }
+            }
+        }
+    }
 }
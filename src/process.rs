@@ -0,0 +1,133 @@
+//! The environment and the command-line arguments of the process, e.g., for debugging overlays.
+use std::sync::Arc;
+
+use super::{Value, ValueVariant};
+
+/// Environment variables, sorted by name:
+///
+/// ```
+/// # extern crate unsegen_jsonviewer;
+/// # use unsegen_jsonviewer::{Environment, JsonViewer};
+/// # fn main() {
+/// let viewer = JsonViewer::new(Environment::capture());
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Environment {
+    vars: Arc<Vec<(String, String)>>,
+    // The variable shown by this node, or `None` for the map of all of them.
+    entry: Option<usize>,
+}
+
+impl Environment {
+    pub fn new<K: Into<String>, V: Into<String>>(vars: impl IntoIterator<Item = (K, V)>) -> Self {
+        let mut vars = vars
+            .into_iter()
+            .map(|(name, value)| (name.into(), value.into()))
+            .collect::<Vec<_>>();
+        vars.sort();
+        Environment {
+            vars: Arc::new(vars),
+            entry: None,
+        }
+    }
+
+    /// The environment of the process. Names and values that are not valid unicode are shown
+    /// with replacement characters.
+    pub fn capture() -> Self {
+        Self::new(std::env::vars_os().map(|(name, value)| {
+            (
+                name.to_string_lossy().into_owned(),
+                value.to_string_lossy().into_owned(),
+            )
+        }))
+    }
+}
+
+impl Value for Environment {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        if let Some(entry) = self.entry {
+            return ValueVariant::Scalar(None, self.vars[entry].1.as_str().into());
+        }
+        let vars = self.vars.clone();
+        ValueVariant::Map(
+            None,
+            Box::new((0..vars.len()).map(move |entry| {
+                let var = Environment {
+                    vars: vars.clone(),
+                    entry: Some(entry),
+                };
+                (vars[entry].0.as_str().into(), var)
+            })),
+        )
+    }
+}
+
+/// Command-line arguments (starting with the program), shown as an array.
+#[derive(Clone, Debug)]
+pub struct Arguments {
+    args: Arc<Vec<String>>,
+    // The argument shown by this node, or `None` for the array of all of them.
+    entry: Option<usize>,
+}
+
+impl Arguments {
+    pub fn new<A: Into<String>>(args: impl IntoIterator<Item = A>) -> Self {
+        Arguments {
+            args: Arc::new(args.into_iter().map(Into::into).collect()),
+            entry: None,
+        }
+    }
+
+    /// The arguments of the process. Arguments that are not valid unicode are shown with
+    /// replacement characters.
+    pub fn capture() -> Self {
+        Self::new(std::env::args_os().map(|arg| arg.to_string_lossy().into_owned()))
+    }
+}
+
+impl Value for Arguments {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        if let Some(entry) = self.entry {
+            return ValueVariant::Scalar(None, self.args[entry].as_str().into());
+        }
+        let args = self.args.clone();
+        ValueVariant::Array(
+            None,
+            Box::new((0..args.len()).map(move |entry| Arguments {
+                args: args.clone(),
+                entry: Some(entry),
+            })),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{JsonPath, JsonPathSegment, JsonViewer, MemberId};
+
+    #[test]
+    fn test_process_values() {
+        let viewer = JsonViewer::new(Environment::new(vec![("PATH", "/bin"), ("HOME", "/")]));
+        let root = viewer.node(&JsonPath::root()).unwrap();
+        let names = root
+            .children()
+            .map(|(segment, _)| segment)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                JsonPathSegment::Key(MemberId::new("HOME")),
+                JsonPathSegment::Key(MemberId::new("PATH"))
+            ]
+        );
+        let path = JsonPath::root().key("PATH");
+        assert_eq!(viewer.node(&path).unwrap().scalar(), Some("/bin"));
+
+        let viewer = JsonViewer::new(Arguments::new(vec!["prog", "--verbose"]));
+        let path = JsonPath::root().index(1);
+        assert_eq!(viewer.node(&path).unwrap().scalar(), Some("--verbose"));
+        assert!(!Arguments::capture().args.is_empty());
+    }
+}
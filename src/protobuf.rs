@@ -0,0 +1,230 @@
+//! Protobuf messages decoded using their descriptors (e.g., for gRPC debugging tools), shown with
+//! the message type as description, repeated fields as arrays and map fields as objects. Enum
+//! values are shown by name, bytes and unknown fields (keyed by their number) as hex strings.
+//!
+//! ```
+//! # extern crate unsegen_jsonviewer;
+//! # use unsegen_jsonviewer::protobuf::ProtoMessage;
+//! # use unsegen_jsonviewer::JsonViewer;
+//! # fn main() {}
+//! # fn show(descriptor_set: &[u8], bytes: &[u8]) -> Result<JsonViewer, String> {
+//! let message = ProtoMessage::decode(descriptor_set, "shop.Order", bytes)?;
+//! Ok(JsonViewer::new(message.value()))
+//! # }
+//! ```
+use prost_reflect::prost::encoding::encoded_len_varint;
+use prost_reflect::{
+    DescriptorPool, DynamicMessage, Kind, MapKey, ReflectMessage, UnknownField, Value as FieldValue,
+};
+
+use super::{Key, Value, ValueVariant};
+
+/// A decoded protobuf message.
+#[derive(Clone, Debug)]
+pub struct ProtoMessage {
+    message: DynamicMessage,
+}
+
+impl ProtoMessage {
+    pub fn new(message: DynamicMessage) -> Self {
+        ProtoMessage { message }
+    }
+
+    /// Decode `bytes` as the message `message_name` (e.g., `shop.Order`) described by an encoded
+    /// `FileDescriptorSet` (as written by `protoc --descriptor_set_out`).
+    pub fn decode(descriptor_set: &[u8], message_name: &str, bytes: &[u8]) -> Result<Self, String> {
+        let pool = DescriptorPool::decode(descriptor_set).map_err(|e| e.to_string())?;
+        let descriptor = pool
+            .get_message_by_name(message_name)
+            .ok_or_else(|| format!("unknown message type {}", message_name))?;
+        let message = DynamicMessage::decode(descriptor, bytes).map_err(|e| e.to_string())?;
+        Ok(ProtoMessage { message })
+    }
+
+    pub fn message(&self) -> &DynamicMessage {
+        &self.message
+    }
+
+    pub fn value(&self) -> ProtoValue<'_> {
+        ProtoValue(Repr::Message(&self.message))
+    }
+}
+
+/// A message (see `ProtoMessage::value`) or the value of one of its fields.
+#[derive(Clone, Debug)]
+pub struct ProtoValue<'a>(Repr<'a>);
+
+#[derive(Clone, Debug)]
+enum Repr<'a> {
+    Message(&'a DynamicMessage),
+    // The value of a field (or an element of a repeated field) of kind `Kind`.
+    Field(&'a FieldValue, Kind),
+    Text(String),
+}
+
+impl<'a> Value for ProtoValue<'a> {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        let (value, kind) = match self.0 {
+            Repr::Message(message) => return visit_message(message),
+            Repr::Field(value, kind) => (value, kind),
+            Repr::Text(text) => return ValueVariant::Scalar(None, text.into()),
+        };
+        let text = match value {
+            FieldValue::Message(message) => return visit_message(message),
+            FieldValue::List(values) => {
+                return ValueVariant::Array(
+                    None,
+                    Box::new(
+                        values
+                            .iter()
+                            .map(move |value| ProtoValue(Repr::Field(value, kind.clone()))),
+                    ),
+                )
+            }
+            FieldValue::Map(entries) => {
+                let value_kind = kind
+                    .as_message()
+                    .map(|entry| entry.map_entry_value_field().kind())
+                    .unwrap_or(kind);
+                let mut entries = entries.iter().collect::<Vec<_>>();
+                entries.sort_by_key(|(key, _)| *key);
+                return ValueVariant::Map(
+                    None,
+                    Box::new(entries.into_iter().map(move |(key, value)| {
+                        let value = ProtoValue(Repr::Field(value, value_kind.clone()));
+                        (map_key(key), value)
+                    })),
+                );
+            }
+            FieldValue::EnumNumber(number) => kind
+                .as_enum()
+                .and_then(|descriptor| descriptor.get_value(*number))
+                .map_or_else(|| number.to_string(), |value| value.name().to_owned()),
+            FieldValue::Bytes(bytes) => hex(bytes),
+            FieldValue::String(text) => text.clone(),
+            FieldValue::Bool(value) => value.to_string(),
+            FieldValue::I32(value) => value.to_string(),
+            FieldValue::I64(value) => value.to_string(),
+            FieldValue::U32(value) => value.to_string(),
+            FieldValue::U64(value) => value.to_string(),
+            FieldValue::F32(value) => value.to_string(),
+            FieldValue::F64(value) => value.to_string(),
+        };
+        ValueVariant::Scalar(None, text.into())
+    }
+}
+
+fn visit_message<'s>(message: &'s DynamicMessage) -> ValueVariant<'s, ProtoValue<'s>> {
+    let fields = message.fields().map(|(field, value)| {
+        let name = Key::from(field.name());
+        (name, ProtoValue(Repr::Field(value, field.kind())))
+    });
+    let unknown = message.unknown_fields().map(|field| {
+        let number = Key::Integer(i64::from(field.number()));
+        (number, ProtoValue(Repr::Text(unknown_field_value(field))))
+    });
+    let description = message.descriptor().name().to_owned();
+    ValueVariant::Map(Some(description.into()), Box::new(fields.chain(unknown)))
+}
+
+fn map_key(key: &MapKey) -> Key {
+    match key {
+        MapKey::Bool(value) => Key::Other(value.to_string().into()),
+        MapKey::I32(value) => Key::Integer(i64::from(*value)),
+        MapKey::I64(value) => Key::Integer(*value),
+        MapKey::U32(value) => Key::Integer(i64::from(*value)),
+        MapKey::U64(value) => Key::Other(value.to_string().into()),
+        MapKey::String(value) => Key::from(value.as_str()),
+    }
+}
+
+// The encoded value of an unknown field (i.e., without its tag) as hex.
+fn unknown_field_value(field: &UnknownField) -> String {
+    let mut encoded = Vec::new();
+    field.encode(&mut encoded);
+    let tag = u64::from(field.number()) << 3 | field.wire_type() as u64;
+    hex(&encoded[encoded_len_varint(tag)..])
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{JsonPath, JsonViewer};
+    use prost_reflect::prost::Message;
+    use prost_reflect::prost_types::field_descriptor_proto::{Label, Type};
+    use prost_reflect::prost_types::{
+        DescriptorProto, EnumDescriptorProto, EnumValueDescriptorProto, FieldDescriptorProto,
+        FileDescriptorProto, FileDescriptorSet,
+    };
+
+    fn field(name: &str, number: i32, label: Label, kind: Type) -> FieldDescriptorProto {
+        FieldDescriptorProto {
+            name: Some(name.to_owned()),
+            number: Some(number),
+            label: Some(label as i32),
+            r#type: Some(kind as i32),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut status = field("status", 3, Label::Optional, Type::Enum);
+        status.type_name = Some(".shop.Status".to_owned());
+        let file = FileDescriptorProto {
+            name: Some("shop.proto".to_owned()),
+            package: Some("shop".to_owned()),
+            syntax: Some("proto3".to_owned()),
+            message_type: vec![DescriptorProto {
+                name: Some("Order".to_owned()),
+                field: vec![
+                    field("id", 1, Label::Optional, Type::String),
+                    field("items", 2, Label::Repeated, Type::Int32),
+                    status,
+                    field("token", 4, Label::Optional, Type::Bytes),
+                ],
+                ..Default::default()
+            }],
+            enum_type: vec![EnumDescriptorProto {
+                name: Some("Status".to_owned()),
+                value: ["UNKNOWN", "PAID"]
+                    .iter()
+                    .enumerate()
+                    .map(|(number, name)| EnumValueDescriptorProto {
+                        name: Some(name.to_string()),
+                        number: Some(number as i32),
+                        ..Default::default()
+                    })
+                    .collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let descriptor_set = FileDescriptorSet { file: vec![file] }.encode_to_vec();
+        // id: "a1", items: [5, 6], status: PAID, token: 0xbeef, unknown field 9: 150
+        let bytes = [
+            0x0a, 0x02, b'a', b'1', 0x12, 0x02, 0x05, 0x06, 0x18, 0x01, 0x22, 0x02, 0xbe, 0xef,
+            0x48, 0x96, 0x01,
+        ];
+        let message = ProtoMessage::decode(&descriptor_set, "shop.Order", &bytes).unwrap();
+        let viewer = JsonViewer::new(message.value());
+        let scalar = |path: JsonPath| viewer.node(&path).unwrap().scalar().map(str::to_owned);
+        assert_eq!(scalar(JsonPath::root().key("id")), Some("a1".into()));
+        assert_eq!(
+            scalar(JsonPath::root().key("items").index(1)),
+            Some("6".into())
+        );
+        assert_eq!(scalar(JsonPath::root().key("status")), Some("PAID".into()));
+        assert_eq!(scalar(JsonPath::root().key("token")), Some("beef".into()));
+        assert_eq!(
+            scalar(JsonPath::root().key(Key::Integer(9))),
+            Some("9601".into())
+        );
+
+        assert!(ProtoMessage::decode(&descriptor_set, "shop.Missing", &bytes).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+//! Protection against `Value` impls that recurse infinitely.
+use std::sync::Arc;
+
+use super::{ScalarKind, Value, ValueVariant};
+
+/// Shown instead of a node that would recurse infinitely (or is too deep).
+const RECURSIVE: &str = "<recursive>";
+
+/// Computes the identity of a node, e.g., the address of the vertex of a graph it represents.
+pub type NodeIdentity<V> = dyn Fn(&V) -> Option<usize> + Send + Sync;
+
+// The identities of the ancestors of a node, innermost first.
+struct Ancestor {
+    identity: usize,
+    parent: Option<Arc<Ancestor>>,
+}
+
+/// Wraps a `Value` (e.g., a custom impl over a graph) so that nodes that would recurse infinitely
+/// are shown as `<recursive>` instead of overflowing the stack when the value is displayed or
+/// updated:
+///
+/// ```
+/// # extern crate unsegen_jsonviewer;
+/// # use unsegen_jsonviewer::{JsonViewer, RecursionGuard, Value};
+/// # fn main() {}
+/// # fn show<G: Value + 'static>(viewer: &mut JsonViewer, graph: G, id: fn(&G) -> usize) {
+/// viewer.update(
+///     RecursionGuard::new(graph)
+///         .identity(move |node| Some(id(node)))
+///         .max_depth(100),
+/// );
+/// # }
+/// ```
+///
+/// Without `identity` or `max_depth`, the value is shown unchanged.
+#[derive(Clone)]
+pub struct RecursionGuard<V> {
+    value: V,
+    identity: Option<Arc<NodeIdentity<V>>>,
+    ancestors: Option<Arc<Ancestor>>,
+    depth: usize,
+    max_depth: Option<usize>,
+}
+
+impl<V: Value> RecursionGuard<V> {
+    pub fn new(value: V) -> Self {
+        RecursionGuard {
+            value,
+            identity: None,
+            ancestors: None,
+            depth: 0,
+            max_depth: None,
+        }
+    }
+
+    /// Show nodes with the same identity as one of their ancestors as `<recursive>`. Nodes for
+    /// which `identity` returns `None` are never considered recursive.
+    pub fn identity(
+        mut self,
+        identity: impl Fn(&V) -> Option<usize> + Send + Sync + 'static,
+    ) -> Self {
+        self.identity = Some(Arc::new(identity));
+        self
+    }
+
+    /// Show nodes more than `depth` levels below the root as `<recursive>`.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+
+    fn is_recursive(&self, identity: Option<usize>) -> bool {
+        if matches!(self.max_depth, Some(max) if self.depth > max) {
+            return true;
+        }
+        let identity = match identity {
+            Some(identity) => identity,
+            None => return false,
+        };
+        let mut ancestor = self.ancestors.as_ref();
+        while let Some(current) = ancestor {
+            if current.identity == identity {
+                return true;
+            }
+            ancestor = current.parent.as_ref();
+        }
+        false
+    }
+}
+
+impl<V: Value> Value for RecursionGuard<V> {
+    fn scalar_kind(&self) -> ScalarKind {
+        self.value.scalar_kind()
+    }
+
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        let identity = self
+            .identity
+            .as_ref()
+            .and_then(|identity| identity(&self.value));
+        if self.is_recursive(identity) {
+            return ValueVariant::Scalar(None, RECURSIVE.into());
+        }
+        let ancestors = match identity {
+            Some(identity) => Some(Arc::new(Ancestor {
+                identity,
+                parent: self.ancestors.clone(),
+            })),
+            None => self.ancestors.clone(),
+        };
+        let (node_identity, depth, max_depth) = (self.identity, self.depth + 1, self.max_depth);
+        let child = move |value| RecursionGuard {
+            value,
+            identity: node_identity.clone(),
+            ancestors: ancestors.clone(),
+            depth,
+            max_depth,
+        };
+        match self.value.visit() {
+            ValueVariant::Scalar(d, s) => ValueVariant::Scalar(d, s),
+            ValueVariant::Array(d, values) => ValueVariant::Array(d, Box::new(values.map(child))),
+            ValueVariant::Map(d, members) => ValueVariant::Map(
+                d,
+                Box::new(members.map(move |(key, value)| (key, child(value)))),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use crate::JsonViewer;
+    use unsegen::base::{Height, Width, WindowBuffer};
+    use unsegen::widget::{RenderingHints, Widget};
+
+    /// A vertex of a directed graph, shown as the object of its successors.
+    #[derive(Clone)]
+    struct Vertex {
+        edges: Arc<Vec<Vec<usize>>>,
+        index: usize,
+    }
+
+    impl Value for Vertex {
+        fn visit<'s>(self) -> ValueVariant<'s, Self> {
+            let edges = self.edges.clone();
+            let successors = self.edges[self.index].clone();
+            ValueVariant::Map(
+                None,
+                Box::new(successors.into_iter().map(move |index| {
+                    let vertex = Vertex {
+                        edges: edges.clone(),
+                        index,
+                    };
+                    (index.to_string().into(), vertex)
+                })),
+            )
+        }
+    }
+
+    fn draw(viewer: &mut JsonViewer) -> Vec<String> {
+        viewer.expand_all();
+        let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(8).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        lines(&mut buffer)
+    }
+
+    #[test]
+    fn test_recursion_guard() {
+        // 0 -> 1 -> 0, 1 -> 2
+        let graph = Vertex {
+            edges: Arc::new(vec![vec![1], vec![0, 2], vec![]]),
+            index: 0,
+        };
+        let mut viewer =
+            JsonViewer::new(RecursionGuard::new(graph.clone()).identity(|v| Some(v.index)));
+        assert_eq!(
+            draw(&mut viewer),
+            vec![
+                "{ [-]",
+                "  1: { [-]",
+                "    0: <recursive>,",
+                "    2: { [-]",
+                "    },",
+                "  },",
+                "}",
+                "",
+            ]
+        );
+
+        viewer.update(RecursionGuard::new(graph).max_depth(1));
+        assert_eq!(
+            draw(&mut viewer)[..6],
+            [
+                "{ [-]",
+                "  1: { [-]",
+                "    0: <recursive>,",
+                "    2: <recursive>,",
+                "  },",
+                "}",
+            ]
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! One-shot rendering of values without a stateful `JsonViewer`.
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, BTreeSet};
+
+use unsegen::base::basic_types::*;
+use unsegen::base::{Color, Cursor, StyleModifier, Window};
+use unsegen::widget::RenderingHints;
+
+use super::displayvalue::{DisplayValue, FocusStyles, RenderingInfo};
+use super::layers::StylePrecedence;
+use super::layout::{ClosingBrackets, Separators};
+use super::members::DuplicateKeys;
+use super::path::JsonPath;
+use super::renderer::NodeRenderer;
+use crate::Value;
+
+/// How `render_value` draws a value.
+#[derive(Clone, Copy)]
+pub struct RenderOptions<'a> {
+    indentation: Width,
+    link_style: StyleModifier,
+    duplicate_key_style: StyleModifier,
+    duplicate_keys: DuplicateKeys,
+    compact_width: Option<usize>,
+    inline_width: Option<usize>,
+    separators: Separators,
+    closing_brackets: ClosingBrackets,
+    renderer: Option<&'a dyn NodeRenderer>,
+}
+
+impl<'a> Default for RenderOptions<'a> {
+    fn default() -> Self {
+        RenderOptions {
+            indentation: Width::new(2).unwrap(),
+            link_style: StyleModifier::new().underline(true),
+            duplicate_key_style: StyleModifier::new().fg_color(Color::Yellow),
+            duplicate_keys: DuplicateKeys::All,
+            compact_width: None,
+            inline_width: None,
+            separators: Separators::default(),
+            closing_brackets: ClosingBrackets::default(),
+            renderer: None,
+        }
+    }
+}
+
+impl<'a> RenderOptions<'a> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn indentation(mut self, w: Width) -> Self {
+        self.indentation = w;
+        self
+    }
+    pub fn link(mut self, style: StyleModifier) -> Self {
+        self.link_style = style;
+        self
+    }
+    /// Style of keys that occur multiple times in the same object.
+    pub fn duplicate_key(mut self, style: StyleModifier) -> Self {
+        self.duplicate_key_style = style;
+        self
+    }
+    /// Choose which members of objects with duplicate keys are shown (see
+    /// `JsonViewer::set_duplicate_keys`).
+    pub fn duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = policy;
+        self
+    }
+    /// See `JsonViewerWidget::compact_width`.
+    pub fn compact_width(mut self, max_width: Width) -> Self {
+        self.compact_width = Some(max_width.raw_value() as usize);
+        self
+    }
+    /// See `JsonViewerWidget::inline_scalar_arrays`.
+    pub fn inline_scalar_arrays(mut self, max_width: Width) -> Self {
+        self.inline_width = Some(max_width.raw_value() as usize);
+        self
+    }
+    /// See `JsonViewerWidget::separators`.
+    pub fn separators(mut self, separators: Separators) -> Self {
+        self.separators = separators;
+        self
+    }
+    /// See `JsonViewerWidget::closing_brackets`.
+    pub fn closing_brackets(mut self, closing_brackets: ClosingBrackets) -> Self {
+        self.closing_brackets = closing_brackets;
+        self
+    }
+    /// Draw (some) nodes using a custom renderer instead of the default representation.
+    pub fn renderer(mut self, renderer: &'a dyn NodeRenderer) -> Self {
+        self.renderer = Some(renderer);
+        self
+    }
+}
+
+/// Draw `value` into `window` the same way a freshly created `JsonViewer` would show it, but
+/// without any selected node.
+pub fn render_value(value: impl Value, options: &RenderOptions, mut window: Window) {
+    let mut value = DisplayValue::new(value);
+    value.resolve_duplicate_keys(options.duplicate_keys);
+    let errors = BTreeMap::new();
+    let annotations = BTreeMap::new();
+    let hidden = BTreeSet::new();
+    let cut = BTreeSet::new();
+    let duplicates = BTreeSet::new();
+    let info = RenderingInfo {
+        hints: RenderingHints::default(),
+        active_focused_style: FocusStyles::uniform(StyleModifier::new()),
+        inactive_focused_style: FocusStyles::uniform(StyleModifier::new()),
+        precedence: StylePrecedence::default(),
+        item_changed_style: StyleModifier::new(),
+        link_style: options.link_style,
+        error_style: StyleModifier::new(),
+        duplicate_key_style: options.duplicate_key_style,
+        duplicate_style: StyleModifier::new(),
+        compact_width: options.compact_width,
+        inline_width: options.inline_width,
+        separators: options.separators,
+        closing_brackets: options.closing_brackets,
+        errors: &errors,
+        annotations: &annotations,
+        hidden: &hidden,
+        duplicates: &duplicates,
+        cut: &cut,
+        timestamp_style: StyleModifier::new(),
+        history_style: StyleModifier::new(),
+        renderer: options.renderer,
+        overridden: None,
+        highlight: None,
+        highlight_style: StyleModifier::new(),
+        selection: None,
+        selected_style: StyleModifier::new(),
+        node_style: None,
+        badge: None,
+        indentation_guide: None,
+        rainbow: &[],
+        summary: None,
+        summary_style: StyleModifier::new(),
+        layout: None,
+        active_row: Cell::new(None),
+        member_rows: RefCell::new(Vec::new()),
+        wrap_values: false,
+        index_label: None,
+    };
+    let mut cursor = Cursor::new(&mut window);
+    value.draw(
+        &mut cursor,
+        None,
+        &info,
+        options.indentation,
+        &mut JsonPath::root(),
+    );
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use unsegen::base::WindowBuffer;
+
+    #[test]
+    fn test_render_value() {
+        let value = object! { "a" => 1, "b" => array! { true } };
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(5).unwrap());
+        render_value(&value, &RenderOptions::new(), buffer.as_window());
+        assert_eq!(
+            lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  a: 1,",
+                "  b: [ [-]",
+                "    true,",
+                "  ] <-1/1 >,"
+            ]
+        );
+
+        let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(1).unwrap());
+        let options = RenderOptions::new().compact_width(Width::new(40).unwrap());
+        render_value(&value, &options, buffer.as_window());
+        assert_eq!(
+            lines(&mut buffer)[0],
+            "{ [-] a: 1, b: [ [-] true ] <-1/1 > }"
+        );
+
+        // The indentation of nested nodes counts against the compact width.
+        let nested = object! { "x" => object! { "yy" => 1, "z" => 2 } };
+        let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(3).unwrap());
+        let options = RenderOptions::new().compact_width(Width::new(20).unwrap());
+        render_value(&nested, &options, buffer.as_window());
+        assert_eq!(
+            lines(&mut buffer),
+            vec!["{ [-]", "  x: { [-]", "    yy: 1,"]
+        );
+        let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(3).unwrap());
+        let options = RenderOptions::new().compact_width(Width::new(21).unwrap());
+        render_value(&nested, &options, buffer.as_window());
+        assert_eq!(
+            lines(&mut buffer),
+            vec!["{ [-]", "  x: { [-] yy: 1, z: 2 },", "}"]
+        );
+
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(6).unwrap());
+        let options = RenderOptions::new().separators(Separators::Json);
+        render_value(&value, &options, buffer.as_window());
+        assert_eq!(
+            lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  a: 1,",
+                "  b: [ [-]",
+                "    true",
+                "  ] <-1/1 >",
+                "}"
+            ]
+        );
+
+        let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(4).unwrap());
+        let options = RenderOptions::new().closing_brackets(ClosingBrackets::Hanging);
+        render_value(&value, &options, buffer.as_window());
+        assert_eq!(
+            lines(&mut buffer),
+            vec!["{ [-]", "  a: 1,", "  b: [ [-]", "    true, ] <-1/1 >, }"]
+        );
+    }
+
+    #[test]
+    fn test_inline_scalar_arrays() {
+        let value = object! {
+            "n" => json::JsonValue::Array((0..120).map(Into::into).collect()),
+            "o" => array! { object! {} }
+        };
+        let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(6).unwrap());
+        let options = RenderOptions::new().inline_scalar_arrays(Width::new(30).unwrap());
+        render_value(&value, &options, buffer.as_window());
+        assert_eq!(
+            lines(&mut buffer),
+            vec![
+                "{ [-]",
+                "  n: [ [-] 0, 1, 2, … ] <-3/120+>,",
+                "  o: [ [-]",
+                "    { [-]",
+                "    },",
+                "  ] <-1/1 >,"
+            ]
+        );
+
+        // Too wide for a single line
+        let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(2).unwrap());
+        let options = RenderOptions::new().inline_scalar_arrays(Width::new(20).unwrap());
+        render_value(&value, &options, buffer.as_window());
+        assert_eq!(lines(&mut buffer)[1], "  n: [ [-]");
+    }
+}
@@ -0,0 +1,154 @@
+use unsegen::base::{Cursor, CursorTarget, StyleModifier};
+
+use super::node::Node;
+use super::path::JsonPath;
+
+/// The part of a `Cursor` that is available to a `NodeRenderer`.
+pub trait NodeCursor {
+    /// Write `text` in the current style.
+    fn write(&mut self, text: &str);
+    /// Write `text` with `style` applied on top of the current style.
+    fn write_styled(&mut self, text: &str, style: StyleModifier);
+    /// Continue at the start of the next line (respecting the indentation of the node).
+    fn wrap_line(&mut self);
+}
+
+impl<'c, 'g: 'c, T: 'c + CursorTarget> NodeCursor for Cursor<'c, 'g, T> {
+    fn write(&mut self, text: &str) {
+        Cursor::write(self, text);
+    }
+    fn write_styled(&mut self, text: &str, style: StyleModifier) {
+        let mut cursor = self.save().style_modifier();
+        cursor.apply_style_modifier(style);
+        cursor.write(text);
+    }
+    fn wrap_line(&mut self) {
+        Cursor::wrap_line(self);
+    }
+}
+
+/// Overrides how specific nodes are drawn, e.g., to show a progress bar or a hex dump for some
+/// fields. Register a renderer using `JsonViewerWidget::renderer`.
+pub trait NodeRenderer {
+    /// Draw `node` (located at `path`) and return true, or return false without drawing anything
+    /// to fall back to the default rendering.
+    ///
+    /// If `active` is set, the node (or a node within it) is selected and the focus style has
+    /// already been applied to `cursor`. Once drawn, an overridden object or array is selected as
+    /// a whole, i.e., moving the selection skips the interaction points within it. Its output is
+    /// also measured to decide whether the enclosing containers fit on a single line (see
+    /// `JsonViewerWidget::compact_width`), so it should not depend on `active`.
+    fn draw(&self, path: &JsonPath, node: Node, active: bool, cursor: &mut dyn NodeCursor) -> bool;
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use crate::JsonViewer;
+    use unsegen::base::{Height, Width, WindowBuffer};
+    use unsegen::widget::{RenderingHints, Widget};
+
+    /// Draws `.bar` and `.o.bar` as a bar of its length, `.blob` as a single word and `.multi` on two lines.
+    struct TestRenderer;
+
+    impl NodeRenderer for TestRenderer {
+        fn draw(
+            &self,
+            path: &JsonPath,
+            node: Node,
+            active: bool,
+            cursor: &mut dyn NodeCursor,
+        ) -> bool {
+            match path.to_string().as_str() {
+                ".bar" | ".o.bar" => {
+                    let len = node.scalar().and_then(|s| s.parse().ok()).unwrap_or(0);
+                    cursor.write(&"#".repeat(len));
+                }
+                ".blob" if active => cursor.write("<BLOB>"),
+                ".blob" => cursor.write("<blob>"),
+                ".multi" => {
+                    cursor.write("line 1");
+                    cursor.wrap_line();
+                    cursor.write("line 2");
+                }
+                _ => return false,
+            }
+            true
+        }
+    }
+
+    fn draw(viewer: &JsonViewer, compact_width: usize) -> Vec<String> {
+        let mut buffer = WindowBuffer::new(Width::new(40).unwrap(), Height::new(6).unwrap());
+        viewer
+            .as_widget()
+            .renderer(&TestRenderer)
+            .compact_width(Width::new(compact_width as i32).unwrap())
+            .draw(buffer.as_window(), RenderingHints::default());
+        lines(&mut buffer)
+    }
+
+    #[test]
+    fn test_draw() {
+        let mut viewer = JsonViewer::new(&object! {
+            "bar" => 3,
+            "blob" => object! { "x" => 1, "y" => array! { 1, 2 } },
+            "c" => 2
+        });
+        assert_eq!(
+            draw(&viewer, 1),
+            vec![
+                "{ [-]",
+                "  bar: ###,",
+                "  blob: <blob>,",
+                "  c: 2,",
+                "}",
+                ""
+            ]
+        );
+        // Selecting a node within an overridden one activates the latter.
+        viewer
+            .select_path(&JsonPath::root().key("blob").key("y").index(1))
+            .unwrap();
+        assert_eq!(draw(&viewer, 1)[2], "  blob: <BLOB>,");
+    }
+
+    #[test]
+    fn test_selection() {
+        let mut viewer = JsonViewer::new(&object! {
+            "a" => 1,
+            "blob" => object! { "x" => 1, "y" => array! { 1, 2 } },
+            "c" => 2
+        });
+        let blob = JsonPath::root().key("blob");
+        // Nodes are only known to be overridden once they are drawn.
+        draw(&viewer, 1);
+        viewer.select_path(&JsonPath::root().key("a")).unwrap();
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.active_path(), blob);
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.active_path(), JsonPath::root().key("c"));
+        viewer.select_previous().unwrap();
+        assert_eq!(viewer.active_path(), blob);
+
+        // The nodes may be rendered differently after a change of the content.
+        viewer.update(&object! { "a" => 1, "blob" => object! { "x" => 2 }, "c" => 2 });
+        viewer.select_next().unwrap();
+        assert_eq!(viewer.active_path(), blob.key("x"));
+    }
+
+    #[test]
+    fn test_compact_width() {
+        let viewer = JsonViewer::new(&object! { "o" => object! { "bar" => 10, "z" => 1 } });
+        // `o: { [-] bar: ##########, z: 1 }` is 29 characters wide at an indentation of two.
+        assert_eq!(draw(&viewer, 31)[1], "  o: { [-] bar: ##########, z: 1 },");
+        assert_eq!(draw(&viewer, 30)[2], "    bar: ##########,");
+
+        // Nodes drawn on multiple lines keep their container from being drawn on a single line.
+        let viewer = JsonViewer::new(&object! { "multi" => 1 });
+        assert_eq!(
+            draw(&viewer, 40),
+            vec!["{ [-]", "  multi: line 1", "  line 2,", "}", "", ""]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! Validation of `json` values against a JSON Schema.
+//!
+//! Only a subset of the specification is supported: `type`, `enum`, `const`, `properties`,
+//! `required`, `additionalProperties`, `items`, `minItems`, `maxItems`, `minimum`, `maximum`,
+//! `minLength`, `maxLength`, `allOf`, `anyOf` and `not`. Other keywords (including `$ref`) are
+//! ignored.
+use json::JsonValue;
+
+use super::members::MemberId;
+use super::path::{JsonPath, JsonPathSegment};
+
+/// A violation of a schema by the node at `path`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ValidationError {
+    pub path: JsonPath,
+    pub message: String,
+}
+
+/// Check `document` against `schema` and return all violations found.
+pub fn validate(document: &JsonValue, schema: &JsonValue) -> Vec<ValidationError> {
+    let mut errors = Vec::new();
+    validate_at(document, schema, &mut JsonPath::root(), &mut errors);
+    errors
+}
+
+fn is_valid(document: &JsonValue, schema: &JsonValue) -> bool {
+    let mut errors = Vec::new();
+    validate_at(document, schema, &mut JsonPath::root(), &mut errors);
+    errors.is_empty()
+}
+
+fn type_matches(value: &JsonValue, type_name: &str) -> bool {
+    match type_name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        _ => false,
+    }
+}
+
+fn validate_at(
+    document: &JsonValue,
+    schema: &JsonValue,
+    path: &mut JsonPath,
+    errors: &mut Vec<ValidationError>,
+) {
+    let mut error = |message: String| {
+        errors.push(ValidationError {
+            path: path.clone(),
+            message,
+        })
+    };
+
+    match schema {
+        JsonValue::Boolean(true) => return,
+        JsonValue::Boolean(false) => {
+            error("no value allowed".to_owned());
+            return;
+        }
+        _ => {}
+    }
+
+    match &schema["type"] {
+        JsonValue::Null => {}
+        JsonValue::Array(types) => {
+            if !types
+                .iter()
+                .filter_map(|t| t.as_str())
+                .any(|t| type_matches(document, t))
+            {
+                let types = types.iter().map(|t| t.dump()).collect::<Vec<_>>();
+                error(format!("expected one of types {}", types.join(", ")));
+            }
+        }
+        type_name => {
+            if let Some(type_name) = type_name.as_str() {
+                if !type_matches(document, type_name) {
+                    error(format!("expected type {}", type_name));
+                }
+            }
+        }
+    }
+
+    if let JsonValue::Array(values) = &schema["enum"] {
+        if !values.contains(document) {
+            let values = values.iter().map(|v| v.dump()).collect::<Vec<_>>();
+            error(format!("expected one of {}", values.join(", ")));
+        }
+    }
+    if schema.has_key("const") && schema["const"] != *document {
+        error(format!("expected {}", schema["const"].dump()));
+    }
+
+    if let Some(n) = document.as_f64() {
+        if let Some(min) = schema["minimum"].as_f64() {
+            if n < min {
+                error(format!("must be at least {}", min));
+            }
+        }
+        if let Some(max) = schema["maximum"].as_f64() {
+            if n > max {
+                error(format!("must be at most {}", max));
+            }
+        }
+    }
+
+    if let Some(s) = document.as_str() {
+        let len = s.chars().count();
+        if let Some(min) = schema["minLength"].as_usize() {
+            if len < min {
+                error(format!("must be at least {} characters long", min));
+            }
+        }
+        if let Some(max) = schema["maxLength"].as_usize() {
+            if len > max {
+                error(format!("must be at most {} characters long", max));
+            }
+        }
+    }
+
+    if let JsonValue::Array(subschemas) = &schema["allOf"] {
+        if !subschemas.iter().all(|s| is_valid(document, s)) {
+            error("does not match all schemas of allOf".to_owned());
+        }
+    }
+    if let JsonValue::Array(subschemas) = &schema["anyOf"] {
+        if !subschemas.iter().any(|s| is_valid(document, s)) {
+            error("does not match any schema of anyOf".to_owned());
+        }
+    }
+    if schema.has_key("not") && is_valid(document, &schema["not"]) {
+        error("must not match schema of not".to_owned());
+    }
+
+    match document {
+        JsonValue::Object(obj) => {
+            if let JsonValue::Array(required) = &schema["required"] {
+                for key in required.iter().filter_map(|k| k.as_str()) {
+                    if obj.get(key).is_none() {
+                        error(format!("missing required member \"{}\"", key));
+                    }
+                }
+            }
+            for (key, value) in obj.iter() {
+                let subschema = if schema["properties"].has_key(key) {
+                    &schema["properties"][key]
+                } else if schema.has_key("additionalProperties") {
+                    &schema["additionalProperties"]
+                } else {
+                    continue;
+                };
+                path.push(JsonPathSegment::Key(MemberId::new(key)));
+                validate_at(value, subschema, path, errors);
+                path.pop();
+            }
+        }
+        JsonValue::Array(values) => {
+            if let Some(min) = schema["minItems"].as_usize() {
+                if values.len() < min {
+                    error(format!("must have at least {} elements", min));
+                }
+            }
+            if let Some(max) = schema["maxItems"].as_usize() {
+                if values.len() > max {
+                    error(format!("must have at most {} elements", max));
+                }
+            }
+            if schema.has_key("items") {
+                for (i, value) in values.iter().enumerate() {
+                    path.push(JsonPathSegment::Index(i));
+                    validate_at(value, &schema["items"], path, errors);
+                    path.pop();
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn messages(document: JsonValue, schema: JsonValue) -> Vec<(String, String)> {
+        validate(&document, &schema)
+            .into_iter()
+            .map(|e| (e.path.to_string(), e.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = object! {
+            "type" => "object",
+            "required" => array!{ "name", "id" },
+            "properties" => object!{
+                "name" => object!{ "type" => "string", "maxLength" => 3 },
+                "id" => object!{ "type" => "integer", "minimum" => 0 },
+                "tags" => object!{ "type" => "array", "items" => object!{ "enum" => array!{ "a", "b" } } }
+            },
+            "additionalProperties" => false
+        };
+
+        assert_eq!(
+            messages(
+                object! { "name" => "foo", "id" => 3, "tags" => array!{ "a" } },
+                schema.clone()
+            ),
+            vec![]
+        );
+        assert_eq!(
+            messages(
+                object! { "name" => "food", "tags" => array!{ "a", "c" }, "extra" => 1 },
+                schema.clone()
+            ),
+            vec![
+                (".".to_owned(), "missing required member \"id\"".to_owned()),
+                (
+                    ".name".to_owned(),
+                    "must be at most 3 characters long".to_owned()
+                ),
+                (
+                    ".tags[1]".to_owned(),
+                    "expected one of \"a\", \"b\"".to_owned()
+                ),
+                (".extra".to_owned(), "no value allowed".to_owned()),
+            ]
+        );
+        assert_eq!(
+            messages(
+                JsonValue::Number((-1.5).into()),
+                object! { "type" => "integer", "minimum" => 0 }
+            ),
+            vec![
+                (".".to_owned(), "expected type integer".to_owned()),
+                (".".to_owned(), "must be at least 0".to_owned()),
+            ]
+        );
+    }
+}
@@ -0,0 +1,268 @@
+//! Searching the keys and values of the displayed value.
+use super::displayvalue::DisplayValue;
+use super::path::{cmp_draw_order, JsonPath};
+
+use std::cmp::Ordering;
+use std::ops::Range;
+
+#[cfg(feature = "regex")]
+use regex::{Regex, RegexBuilder};
+
+/// How and where `JsonViewer::search` looks for a pattern.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SearchOptions {
+    /// Ignore the case of letters.
+    pub case_insensitive: bool,
+    /// Only match whole words, i.e., matches must not be preceded or followed by an alphanumeric
+    /// character or `_`.
+    pub whole_word: bool,
+    /// Search the keys of object members.
+    pub keys: bool,
+    /// Search the (formatted) scalar values.
+    pub values: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        SearchOptions {
+            case_insensitive: false,
+            whole_word: false,
+            keys: true,
+            values: true,
+        }
+    }
+}
+
+/// What to search for (see `JsonViewer::search`).
+#[derive(Clone, Debug)]
+pub enum Pattern {
+    /// A substring.
+    Text(String),
+    /// A regular expression that matches (part of) the text.
+    #[cfg(feature = "regex")]
+    Regex(Regex),
+}
+
+impl Pattern {
+    pub fn is_match(&self, text: &str) -> bool {
+        match self {
+            Pattern::Text(pattern) => text.contains(pattern.as_str()),
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => regex.is_match(text),
+        }
+    }
+
+    /// The byte ranges of all (non-empty, non-overlapping) matches in `text`.
+    pub fn find_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        self.find(text, &SearchOptions::default())
+    }
+
+    /// Like `find_ranges`, but respecting `options`. Regular expressions have to be made case
+    /// insensitive beforehand (see `Search::new`).
+    pub(crate) fn find(&self, text: &str, options: &SearchOptions) -> Vec<Range<usize>> {
+        let ranges = match self {
+            Pattern::Text(pattern) => find_text(text, pattern, options.case_insensitive),
+            #[cfg(feature = "regex")]
+            Pattern::Regex(regex) => regex
+                .find_iter(text)
+                .filter(|m| !m.as_str().is_empty())
+                .map(|m| m.start()..m.end())
+                .collect(),
+        };
+        if options.whole_word {
+            ranges
+                .into_iter()
+                .filter(|range| is_whole_word(text, range))
+                .collect()
+        } else {
+            ranges
+        }
+    }
+
+    #[cfg(feature = "regex")]
+    fn case_insensitive(self) -> Self {
+        match self {
+            Pattern::Regex(regex) => {
+                let insensitive = RegexBuilder::new(regex.as_str())
+                    .case_insensitive(true)
+                    .build();
+                Pattern::Regex(insensitive.unwrap_or(regex))
+            }
+            text => text,
+        }
+    }
+}
+
+fn find_text(text: &str, pattern: &str, case_insensitive: bool) -> Vec<Range<usize>> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    if !case_insensitive {
+        return text
+            .match_indices(pattern)
+            .map(|(i, m)| i..i + m.len())
+            .collect();
+    }
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    while let Some(c) = text[start..].chars().next() {
+        match prefix_ignoring_case(&text[start..], pattern) {
+            Some(len) => {
+                ranges.push(start..start + len);
+                start += len;
+            }
+            None => start += c.len_utf8(),
+        }
+    }
+    ranges
+}
+
+/// The length of the prefix of `text` that equals `pattern` when ignoring case.
+fn prefix_ignoring_case(text: &str, pattern: &str) -> Option<usize> {
+    let mut chars = text.char_indices();
+    for p in pattern.chars() {
+        let (_, c) = chars.next()?;
+        if !c.to_lowercase().eq(p.to_lowercase()) {
+            return None;
+        }
+    }
+    Some(chars.next().map(|(i, _)| i).unwrap_or(text.len()))
+}
+
+fn is_whole_word(text: &str, range: &Range<usize>) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+    let before = text[..range.start].chars().next_back();
+    let after = text[range.end..].chars().next();
+    !matches!(before, Some(c) if is_word_char(c)) && !matches!(after, Some(c) if is_word_char(c))
+}
+
+impl<'a> From<&'a str> for Pattern {
+    fn from(pattern: &'a str) -> Self {
+        Pattern::Text(pattern.to_owned())
+    }
+}
+
+impl From<String> for Pattern {
+    fn from(pattern: String) -> Self {
+        Pattern::Text(pattern)
+    }
+}
+
+#[cfg(feature = "regex")]
+impl From<Regex> for Pattern {
+    fn from(regex: Regex) -> Self {
+        Pattern::Regex(regex)
+    }
+}
+
+/// An active search and its matches in document order.
+#[derive(Clone)]
+pub struct Search {
+    pub pattern: Pattern,
+    pub options: SearchOptions,
+    pub matches: Vec<JsonPath>,
+    pub current: Option<usize>,
+}
+
+impl Search {
+    pub fn new(pattern: Pattern, options: SearchOptions, value: &DisplayValue) -> Self {
+        #[cfg(feature = "regex")]
+        let pattern = if options.case_insensitive {
+            pattern.case_insensitive()
+        } else {
+            pattern
+        };
+        let mut search = Search {
+            pattern,
+            options,
+            matches: Vec::new(),
+            current: None,
+        };
+        search.refresh(value);
+        search
+    }
+
+    /// Find the matches in (the modified) `value` again.
+    pub fn refresh(&mut self, value: &DisplayValue) {
+        let matches = self.find_matches(value, &JsonPath::root());
+        self.replace_matches(matches);
+    }
+
+    /// Like `refresh`, if only the subtree at `location` of `value` was modified. The matches
+    /// outside of it are kept.
+    pub fn refresh_at(&mut self, value: &DisplayValue, location: &JsonPath) {
+        let found = value
+            .get(location)
+            .map_or_else(Vec::new, |node| self.find_matches(node, location));
+        let start = self
+            .matches
+            .partition_point(|m| cmp_draw_order(value, m, location) == Ordering::Less);
+        let len = self.matches[start..]
+            .iter()
+            .take_while(|m| m.segments().starts_with(location.segments()))
+            .count();
+        self.modify_matches(|matches| {
+            matches.splice(start..start + len, found);
+        });
+    }
+
+    /// Replace the matches by the ones found in a modified value, keeping the current match if
+    /// it is still one.
+    pub fn replace_matches(&mut self, matches: Vec<JsonPath>) {
+        self.modify_matches(|old| *old = matches);
+    }
+
+    fn modify_matches(&mut self, f: impl FnOnce(&mut Vec<JsonPath>)) {
+        let current = self.current.map(|i| self.matches[i].clone());
+        f(&mut self.matches);
+        self.current = current.and_then(|current| self.matches.iter().position(|m| *m == current));
+    }
+
+    fn is_match(&self, text: &str) -> bool {
+        !self.pattern.find(text, &self.options).is_empty()
+    }
+
+    /// The matches in `node`, which is at `location`.
+    fn find_matches(&self, node: &DisplayValue, location: &JsonPath) -> Vec<JsonPath> {
+        let SearchOptions { keys, values, .. } = self.options;
+        let mut matches = Vec::new();
+        node.walk(&mut location.clone(), &mut |node, path, key| {
+            if keys && key.is_some_and(|key| self.is_match(&key.to_string())) {
+                matches.push(path.clone());
+            }
+            if let DisplayValue::Scalar(scalar) = node {
+                // The value of a member whose key matches is the same match
+                if values && matches.last() != Some(path) && self.is_match(scalar.text()) {
+                    matches.push(path.clone());
+                }
+            }
+        });
+        matches
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_find_with_options() {
+        let pattern = Pattern::from("foo");
+        let text = "Foo food foo_ FOO";
+        let options = SearchOptions::default();
+        assert_eq!(pattern.find(text, &options), vec![4..7, 9..12]);
+        let options = SearchOptions {
+            case_insensitive: true,
+            ..options
+        };
+        assert_eq!(
+            pattern.find(text, &options),
+            vec![0..3, 4..7, 9..12, 14..17]
+        );
+        let options = SearchOptions {
+            whole_word: true,
+            ..options
+        };
+        assert_eq!(pattern.find(text, &options), vec![0..3, 14..17]);
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeSet;
+
+use super::node::Node;
+use super::path::JsonPath;
+
+/// A range of consecutive siblings selected using `JsonViewer::extend_selection_next` or
+/// `extend_selection_previous`, e.g., to copy several members of an object at once.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Selection {
+    anchor: JsonPath,
+    end: JsonPath,
+    // The selected nodes in document order and for lookup while drawing.
+    paths: Vec<JsonPath>,
+    lookup: BTreeSet<JsonPath>,
+}
+
+impl Selection {
+    /// The siblings from `anchor` to `end` (in either order) below `parent`. None if they are not
+    /// both children of `parent`.
+    pub(crate) fn between(parent: Node, anchor: JsonPath, end: JsonPath) -> Option<Self> {
+        let parent_path = anchor
+            .parent()
+            .filter(|p| Some(p) == end.parent().as_ref())?;
+        let segments = parent.children().map(|(s, _)| s).collect::<Vec<_>>();
+        let position = |path: &JsonPath| {
+            segments
+                .iter()
+                .position(|s| path.segments().last() == Some(s))
+        };
+        let (a, b) = (position(&anchor)?, position(&end)?);
+        let paths = segments[a.min(b)..=a.max(b)]
+            .iter()
+            .map(|segment| {
+                let mut path = parent_path.clone();
+                path.push(segment.clone());
+                path
+            })
+            .collect::<Vec<_>>();
+        let lookup = paths.iter().cloned().collect();
+        Some(Selection {
+            anchor,
+            end,
+            paths,
+            lookup,
+        })
+    }
+
+    /// The node where the selection was started.
+    pub fn anchor(&self) -> &JsonPath {
+        &self.anchor
+    }
+
+    /// The other end of the range, i.e., the active node.
+    pub fn end(&self) -> &JsonPath {
+        &self.end
+    }
+
+    /// The object or array containing the selected nodes.
+    pub fn parent(&self) -> JsonPath {
+        self.end.parent().expect("selected nodes have a parent")
+    }
+
+    /// The selected nodes in document order.
+    pub fn paths(&self) -> &[JsonPath] {
+        &self.paths
+    }
+
+    pub fn contains(&self, path: &JsonPath) -> bool {
+        self.lookup.contains(path)
+    }
+}
@@ -0,0 +1,277 @@
+//! Multiple named documents that are shown one at a time, with a tab bar to switch between them.
+use unsegen::base::basic_types::*;
+use unsegen::base::{BoolModifyMode, Cursor, StyleModifier, Window};
+use unsegen::input::{OperationResult, Scrollable};
+use unsegen::widget::{Demand, Demand2D, RenderingHints, Widget};
+
+use super::layout::Extent;
+use super::{JsonViewer, JsonViewerWidget};
+
+const TAB_SEPARATOR: &str = " | ";
+
+/// Manages several named documents, each with its own `JsonViewer` (and thus its own fold state
+/// and selection). One document is active at a time and is shown below a tab bar.
+pub struct JsonViewerSet {
+    documents: Vec<(String, JsonViewer)>,
+    active: usize,
+}
+
+impl Default for JsonViewerSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonViewerSet {
+    /// Create a set without any documents.
+    pub fn new() -> Self {
+        JsonViewerSet {
+            documents: Vec::new(),
+            active: 0,
+        }
+    }
+
+    /// Add a document at the end and return its index. The active document does not change.
+    pub fn add_document(&mut self, name: impl Into<String>, viewer: JsonViewer) -> usize {
+        self.documents.push((name.into(), viewer));
+        self.documents.len() - 1
+    }
+
+    /// Remove the document at `index`. If it was active, the following document (or the last one)
+    /// becomes active.
+    pub fn remove_document(&mut self, index: usize) -> Option<(String, JsonViewer)> {
+        if index >= self.documents.len() {
+            return None;
+        }
+        let document = self.documents.remove(index);
+        if index < self.active || self.active == self.documents.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        Some(document)
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// The names of all documents in tab order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.documents.iter().map(|(name, _)| name.as_str())
+    }
+
+    /// The index of the document with `name`, if any.
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.documents.iter().position(|(n, _)| n == name)
+    }
+
+    pub fn document(&self, index: usize) -> Option<&JsonViewer> {
+        self.documents.get(index).map(|(_, viewer)| viewer)
+    }
+
+    pub fn document_mut(&mut self, index: usize) -> Option<&mut JsonViewer> {
+        self.documents.get_mut(index).map(|(_, viewer)| viewer)
+    }
+
+    /// The index of the active document (0 if there are no documents).
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn active_document(&self) -> Option<&JsonViewer> {
+        self.document(self.active)
+    }
+
+    pub fn active_document_mut(&mut self) -> Option<&mut JsonViewer> {
+        self.document_mut(self.active)
+    }
+
+    /// Make the document at `index` the active one.
+    pub fn select_document(&mut self, index: usize) -> Result<(), ()> {
+        if index < self.documents.len() {
+            self.active = index;
+            Ok(())
+        } else {
+            Err(())
+        }
+    }
+
+    /// Activate the document to the right of the active one.
+    pub fn next_document(&mut self) -> Result<(), ()> {
+        self.select_document(self.active + 1)
+    }
+
+    /// Activate the document to the left of the active one.
+    pub fn previous_document(&mut self) -> Result<(), ()> {
+        if self.active > 0 {
+            self.select_document(self.active - 1)
+        } else {
+            Err(())
+        }
+    }
+
+    pub fn as_widget<'a>(&'a self) -> JsonViewerSetWidget<'a> {
+        JsonViewerSetWidget {
+            inner: self,
+            active_tab_style: StyleModifier::new()
+                .invert(BoolModifyMode::Toggle)
+                .bold(true),
+            inactive_tab_style: StyleModifier::new(),
+            theme: Box::new(|widget| widget),
+        }
+    }
+}
+
+/// Forwards to the active document.
+impl Scrollable for JsonViewerSet {
+    fn scroll_forwards(&mut self) -> OperationResult {
+        self.active_document_mut().ok_or(())?.select_next()
+    }
+    fn scroll_backwards(&mut self) -> OperationResult {
+        self.active_document_mut().ok_or(())?.select_previous()
+    }
+    fn scroll_to_beginning(&mut self) -> OperationResult {
+        self.active_document_mut().ok_or(())?.scroll_to_beginning()
+    }
+    fn scroll_to_end(&mut self) -> OperationResult {
+        self.active_document_mut().ok_or(())?.scroll_to_end()
+    }
+}
+
+type Theme<'a> = Box<dyn Fn(JsonViewerWidget<'a>) -> JsonViewerWidget<'a> + 'a>;
+
+pub struct JsonViewerSetWidget<'a> {
+    inner: &'a JsonViewerSet,
+    active_tab_style: StyleModifier,
+    inactive_tab_style: StyleModifier,
+    theme: Theme<'a>,
+}
+
+impl<'a> JsonViewerSetWidget<'a> {
+    pub fn active_tab(mut self, style: StyleModifier) -> Self {
+        self.active_tab_style = style;
+        self
+    }
+    pub fn inactive_tab(mut self, style: StyleModifier) -> Self {
+        self.inactive_tab_style = style;
+        self
+    }
+    /// Configure the widget of every document in the same way, e.g.,
+    /// `.theme(|w| w.indentation(Width::new(4).unwrap()))`.
+    pub fn theme(
+        mut self,
+        theme: impl Fn(JsonViewerWidget<'a>) -> JsonViewerWidget<'a> + 'a,
+    ) -> Self {
+        self.theme = Box::new(theme);
+        self
+    }
+
+    fn active_widget(&self) -> Option<JsonViewerWidget<'a>> {
+        self.inner
+            .active_document()
+            .map(|viewer| (self.theme)(viewer.as_widget()))
+    }
+
+    fn tab_bar_width(&self) -> usize {
+        let names = self.inner.names().map(|name| Extent::text(name).first);
+        let separators = self.inner.len().saturating_sub(1) * TAB_SEPARATOR.len();
+        names.sum::<usize>() + separators
+    }
+}
+
+impl<'a> Widget for JsonViewerSetWidget<'a> {
+    fn space_demand(&self) -> Demand2D {
+        let tab_bar = Demand2D {
+            width: Demand::at_least(Width::new(self.tab_bar_width() as i32).unwrap()),
+            height: Demand::exact(Height::new(1).unwrap()),
+        };
+        match self.active_widget() {
+            Some(widget) => {
+                let document = widget.space_demand();
+                Demand2D {
+                    width: tab_bar.width.max(document.width),
+                    height: tab_bar.height + document.height,
+                }
+            }
+            None => tab_bar,
+        }
+    }
+    fn draw(&self, window: Window, hints: RenderingHints) {
+        let (mut tab_bar, document) = match window.split(RowIndex::new(1)) {
+            Ok(split) => split,
+            Err(_) => return,
+        };
+        {
+            let mut cursor = Cursor::new(&mut tab_bar);
+            for (i, name) in self.inner.names().enumerate() {
+                if i > 0 {
+                    cursor.write(TAB_SEPARATOR);
+                }
+                let mut cursor = cursor.save().style_modifier();
+                cursor.apply_style_modifier(if i == self.inner.active {
+                    self.active_tab_style
+                } else {
+                    self.inactive_tab_style
+                });
+                cursor.write(name);
+            }
+        }
+        if let Some(widget) = self.active_widget() {
+            widget.draw(document, hints);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use unsegen::base::WindowBuffer;
+
+    #[test]
+    fn test_viewer_set() {
+        let mut set = JsonViewerSet::new();
+        assert!(set.next_document().is_err());
+        set.add_document("request", JsonViewer::new(&object! { "a" => 1 }));
+        set.add_document("response", JsonViewer::new(&array! { 1, 2 }));
+        assert_eq!(set.active_index(), 0);
+        set.next_document().unwrap();
+        assert!(set.next_document().is_err());
+        assert_eq!(set.active_index(), 1);
+
+        let mut buffer = WindowBuffer::new(Width::new(20).unwrap(), Height::new(5).unwrap());
+        set.as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            lines(&mut buffer),
+            vec!["request | response", "[ [-]", "  1,", "  2,", "] <-2/2 >"]
+        );
+        assert_eq!(
+            set.as_widget().space_demand().height,
+            Demand::exact(Height::new(5).unwrap())
+        );
+
+        // Every document keeps its own view state
+        set.active_document_mut()
+            .unwrap()
+            .toggle_active_element()
+            .unwrap();
+        set.previous_document().unwrap();
+        assert_eq!(
+            set.as_widget().space_demand().height,
+            Demand::exact(Height::new(4).unwrap())
+        );
+        set.next_document().unwrap();
+        assert_eq!(
+            set.as_widget().space_demand().height,
+            Demand::exact(Height::new(2).unwrap())
+        );
+
+        set.remove_document(1).unwrap();
+        assert_eq!(set.active_index(), 0);
+        assert_eq!(set.names().collect::<Vec<_>>(), vec!["request"]);
+    }
+}
@@ -0,0 +1,100 @@
+//! View-only ordering of the elements of arrays (see `JsonViewer::set_array_sort`).
+use std::cmp::Ordering;
+
+use super::displayvalue::DisplayValue;
+
+/// What the elements of an array are sorted by.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum SortKey {
+    /// The elements themselves (if they are scalars).
+    Value,
+    /// The member with this key of the elements (if they are objects and the member is a scalar).
+    Field(String),
+}
+
+/// The order of the shown elements of an array. Numbers are compared by their value, other
+/// scalars by their text, and numbers precede other scalars. Elements without a (scalar) key come
+/// last in either direction, and equal elements keep their order.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct ArraySort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+impl ArraySort {
+    pub fn new(key: SortKey, descending: bool) -> Self {
+        ArraySort { key, descending }
+    }
+
+    /// The text shown next to the length indicator of sorted arrays, e.g., `sorted by id ↓`.
+    pub(crate) fn indicator(&self) -> String {
+        let arrow = if self.descending { "↓" } else { "↑" };
+        match &self.key {
+            SortKey::Value => format!("sorted {}", arrow),
+            SortKey::Field(field) => format!("sorted by {} {}", field, arrow),
+        }
+    }
+
+    fn key<'a>(&self, value: &'a DisplayValue) -> Option<&'a str> {
+        let value = match (&self.key, value) {
+            (SortKey::Value, value) => value,
+            (SortKey::Field(field), DisplayValue::Object(obj)) => obj.members.get(field)?,
+            (SortKey::Field(_), _) => return None,
+        };
+        match value {
+            DisplayValue::Scalar(scalar) => Some(scalar.text()),
+            _ => None,
+        }
+    }
+
+    /// The indices of `values` in sorted order.
+    pub(crate) fn order(&self, values: &[DisplayValue]) -> Vec<usize> {
+        let keys = values
+            .iter()
+            .map(|value| self.key(value))
+            .collect::<Vec<_>>();
+        let mut order = (0..values.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| match (keys[a], keys[b]) {
+            (Some(a), Some(b)) if self.descending => compare(b, a),
+            (Some(a), Some(b)) => compare(a, b),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        });
+        order
+    }
+}
+
+fn compare(a: &str, b: &str) -> Ordering {
+    match (a.parse::<f64>(), b.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        (Err(_), Err(_)) => a.cmp(b),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_order() {
+        let values = DisplayValue::new(&array! {
+            object! { "id" => 10, "name" => "b" },
+            object! { "name" => "c" },
+            object! { "id" => 9, "name" => "a" },
+            object! { "id" => "x" },
+            object! { "id" => 10 }
+        });
+        let values = &values.unwrap_array_ref().values;
+        let by_id = ArraySort::new(SortKey::Field("id".to_owned()), false);
+        assert_eq!(by_id.order(values), vec![2, 0, 4, 3, 1]);
+        let by_id = ArraySort::new(SortKey::Field("id".to_owned()), true);
+        assert_eq!(by_id.order(values), vec![3, 0, 4, 2, 1]);
+        assert_eq!(by_id.indicator(), "sorted by id ↓");
+        let by_name = ArraySort::new(SortKey::Field("name".to_owned()), false);
+        assert_eq!(by_name.order(values), vec![2, 0, 1, 3, 4]);
+        // Objects have no scalar value.
+        let by_value = ArraySort::new(SortKey::Value, false);
+        assert_eq!(by_value.order(values), vec![0, 1, 2, 3, 4]);
+    }
+}
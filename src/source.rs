@@ -0,0 +1,64 @@
+//! Sources of documents that a `JsonViewer` polls for new values.
+use std::sync::mpsc::Receiver;
+
+use super::Value;
+
+/// A source of documents that is checked for new values without blocking (e.g., once per frame)
+/// using `JsonViewer::poll`. Producing the documents is up to the source, e.g., a background
+/// thread or an async task sending them through a channel.
+pub trait DocumentSource {
+    type Value: Value;
+
+    /// The next document, or the reason why it could not be obtained, if one is available. Must
+    /// eventually return `None` when polled repeatedly.
+    fn poll(&mut self) -> Option<Result<Self::Value, String>>;
+}
+
+/// Receives documents sent by another thread. A disconnected sender is treated as a source that
+/// never has new documents.
+impl<V: Value> DocumentSource for Receiver<V> {
+    type Value = V;
+
+    fn poll(&mut self) -> Option<Result<V, String>> {
+        self.try_recv().ok().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{JsonPath, JsonViewer};
+    use std::sync::mpsc::channel;
+
+    #[test]
+    fn test_poll() {
+        struct Failing(Vec<Result<&'static str, String>>);
+        impl DocumentSource for Failing {
+            type Value = &'static str;
+            fn poll(&mut self) -> Option<Result<&'static str, String>> {
+                self.0.pop()
+            }
+        }
+
+        let mut viewer = JsonViewer::new("a");
+        let (sender, mut receiver) = channel();
+        assert!(!viewer.poll(&mut receiver));
+        sender.send("b").unwrap();
+        sender.send("c").unwrap();
+        assert!(viewer.poll(&mut receiver));
+        assert!(viewer.has_new_data());
+        assert_eq!(viewer.node(&JsonPath::root()).unwrap().scalar(), Some("c"));
+        assert!(viewer.take_new_data());
+        assert!(!viewer.has_new_data());
+
+        drop(sender);
+        assert!(!viewer.poll(&mut receiver));
+        assert!(!viewer.has_new_data());
+
+        // An error received after the last document is shown in its place.
+        let mut source = Failing(vec![Err("connection lost".to_owned()), Ok("d")]);
+        assert!(viewer.poll(&mut source));
+        assert_eq!(viewer.error(), Some("connection lost"));
+        assert_eq!(viewer.node(&JsonPath::root()).unwrap().scalar(), Some("d"));
+    }
+}
@@ -0,0 +1,187 @@
+//! Statistics about the subtrees of the displayed value.
+use super::node::NodeKind;
+
+use super::layout::lock;
+
+use std::cmp::max;
+use std::fmt;
+use std::sync::{Mutex, PoisonError};
+
+/// The size of a node including all of its (transitive) children.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct SubtreeStats {
+    /// The number of nodes, including the node itself.
+    pub nodes: usize,
+    pub objects: usize,
+    pub arrays: usize,
+    pub scalars: usize,
+    /// The number of levels, i.e., 1 for a scalar or an empty object or array.
+    pub depth: usize,
+    /// The total length (in bytes) of all keys and formatted scalars.
+    pub bytes: usize,
+}
+
+impl SubtreeStats {
+    /// The statistics of a node without its children.
+    pub fn single(kind: NodeKind, bytes: usize) -> Self {
+        SubtreeStats {
+            nodes: 1,
+            objects: (kind == NodeKind::Object) as usize,
+            arrays: (kind == NodeKind::Array) as usize,
+            scalars: (kind == NodeKind::Scalar) as usize,
+            depth: 1,
+            bytes,
+        }
+    }
+
+    /// Include the subtree of a child (one level below this node).
+    pub fn add_child(&mut self, child: SubtreeStats) {
+        self.nodes += child.nodes;
+        self.objects += child.objects;
+        self.arrays += child.arrays;
+        self.scalars += child.scalars;
+        self.depth = max(self.depth, child.depth + 1);
+        self.bytes += child.bytes;
+    }
+}
+
+/// Statistics of the whole value displayed by a `JsonViewer` (see `JsonViewer::stats`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DocumentStats {
+    pub objects: usize,
+    pub arrays: usize,
+    pub scalars: usize,
+    /// The number of levels of the value, i.e., 1 for a scalar.
+    pub max_depth: usize,
+    /// The number of lines of the value in its current fold state (with the default widget
+    /// settings).
+    pub rendered_lines: usize,
+}
+
+/// The count, minimum, maximum and mean of the numbers of an array or of a member of the elements
+/// of an array (see `JsonViewer::aggregates`).
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Aggregates {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+}
+
+impl Aggregates {
+    /// The aggregates of `numbers` or None if there are none.
+    pub fn of(numbers: impl IntoIterator<Item = f64>) -> Option<Self> {
+        let mut aggregates: Option<Aggregates> = None;
+        let mut sum = 0.0;
+        for number in numbers {
+            sum += number;
+            aggregates = Some(match aggregates {
+                Some(a) => Aggregates {
+                    count: a.count + 1,
+                    min: a.min.min(number),
+                    max: a.max.max(number),
+                    mean: 0.0,
+                },
+                None => Aggregates {
+                    count: 1,
+                    min: number,
+                    max: number,
+                    mean: 0.0,
+                },
+            });
+        }
+        aggregates.map(|a| Aggregates {
+            mean: sum / a.count as f64,
+            ..a
+        })
+    }
+}
+
+impl fmt::Display for Aggregates {
+    /// The mean is rounded to three decimals.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mean = format!("{:.3}", self.mean);
+        let mean = mean.trim_end_matches('0').trim_end_matches('.');
+        write!(
+            f,
+            "count {}, min {}, max {}, mean {}",
+            self.count, self.min, self.max, mean
+        )
+    }
+}
+
+/// The statistics of a node, which only change if the node or any of its children is modified
+/// (i.e., not if it is folded or unfolded), and the aggregates of the numbers of arrays computed
+/// last (for the member they were computed for). Synchronized like `ExtentCache`.
+#[derive(Default)]
+pub struct StatsCache {
+    stats: Mutex<Option<SubtreeStats>>,
+    aggregates: Mutex<Option<(Option<String>, Option<Aggregates>)>>,
+}
+
+impl Clone for StatsCache {
+    fn clone(&self) -> Self {
+        StatsCache {
+            stats: Mutex::new(*lock(&self.stats)),
+            aggregates: Mutex::new(lock(&self.aggregates).clone()),
+        }
+    }
+}
+
+impl StatsCache {
+    pub fn get_or_compute(&self, compute: impl FnOnce() -> SubtreeStats) -> SubtreeStats {
+        let cached = *lock(&self.stats);
+        match cached {
+            Some(stats) => stats,
+            None => {
+                let stats = compute();
+                *lock(&self.stats) = Some(stats);
+                stats
+            }
+        }
+    }
+
+    pub fn is_cached(&self) -> bool {
+        lock(&self.stats).is_some()
+    }
+
+    pub fn aggregates_or_compute(
+        &self,
+        field: Option<&str>,
+        compute: impl FnOnce() -> Option<Aggregates>,
+    ) -> Option<Aggregates> {
+        if let Some((cached_field, aggregates)) = &*lock(&self.aggregates) {
+            if cached_field.as_deref() == field {
+                return *aggregates;
+            }
+        }
+        let aggregates = compute();
+        *lock(&self.aggregates) = Some((field.map(str::to_owned), aggregates));
+        aggregates
+    }
+
+    pub fn invalidate(&mut self) {
+        *self.stats.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+        *self
+            .aggregates
+            .get_mut()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_aggregates() {
+        let aggregates = Aggregates::of(vec![4.0, -1.5, 9.0, 0.5]).unwrap();
+        assert_eq!(aggregates.count, 4);
+        assert_eq!(aggregates.min, -1.5);
+        assert_eq!(aggregates.max, 9.0);
+        assert_eq!(aggregates.to_string(), "count 4, min -1.5, max 9, mean 3");
+        let aggregates = Aggregates::of(vec![1.0, 2.0, 2.0]).unwrap();
+        assert_eq!(aggregates.to_string(), "count 3, min 1, max 2, mean 1.667");
+        assert_eq!(Aggregates::of(Vec::new()), None);
+    }
+}
@@ -0,0 +1,168 @@
+//! Streams of newline-delimited JSON records (NDJSON, JSON Lines), e.g., structured logs.
+use std::io::{self, Read};
+use std::sync::mpsc::Receiver;
+
+use super::json_ext::JsonValue;
+use super::{JsonPath, JsonViewer, JsonViewerWidget};
+
+/// Shows the records of a stream of newline-delimited JSON as a top-level array that grows as
+/// records arrive. Records that have been shown keep their fold state, and the selection follows
+/// the last record if it is selected (see `JsonViewer::set_follow_tail`).
+///
+/// Lines that are not valid JSON are shown as strings. Empty lines are skipped.
+pub struct JsonStreamViewer {
+    viewer: JsonViewer,
+    // The bytes after the last newline of the data pushed so far.
+    partial: Vec<u8>,
+}
+
+impl Default for JsonStreamViewer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JsonStreamViewer {
+    /// Create a viewer without any records.
+    pub fn new() -> Self {
+        let mut viewer = JsonViewer::new(JsonValue::new_array());
+        viewer.set_follow_tail(true);
+        JsonStreamViewer {
+            viewer,
+            partial: Vec::new(),
+        }
+    }
+
+    pub fn viewer(&self) -> &JsonViewer {
+        &self.viewer
+    }
+
+    /// The viewer showing the records, e.g., to handle input using `JsonViewerBehavior`.
+    pub fn viewer_mut(&mut self) -> &mut JsonViewer {
+        &mut self.viewer
+    }
+
+    /// The number of records shown.
+    pub fn len(&self) -> usize {
+        self.viewer
+            .node(&JsonPath::root())
+            .map_or(0, |node| node.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Append the records of all complete lines in `data`. The last line is kept until it is
+    /// completed by the next call (or `finish`), so `data` may be split anywhere.
+    pub fn push(&mut self, data: &[u8]) {
+        self.partial.extend_from_slice(data);
+        let end = match self.partial.iter().rposition(|&b| b == b'\n') {
+            Some(end) => end,
+            None => return,
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        self.append(complete.split(|&b| b == b'\n'));
+    }
+
+    /// Append the record of a line that was received separately.
+    pub fn push_line(&mut self, line: &str) {
+        self.append(Some(line.as_bytes()));
+    }
+
+    /// Append the last line pushed using `push`, even though it is not terminated by a newline,
+    /// e.g., at the end of the stream.
+    pub fn finish(&mut self) {
+        let line = std::mem::take(&mut self.partial);
+        self.append(Some(&line[..]));
+    }
+
+    /// Read from `reader` once and append the records of all lines completed by the data read.
+    /// Returns the number of bytes read, i.e., `0` at the end of the stream (in which case
+    /// `finish` is called).
+    pub fn read_from(&mut self, reader: &mut impl Read) -> io::Result<usize> {
+        let mut buffer = [0; 8192];
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            self.finish();
+        } else {
+            self.push(&buffer[..n]);
+        }
+        Ok(n)
+    }
+
+    /// Append the records of all lines that are available from `lines` without blocking.
+    /// Returns whether any lines were received.
+    pub fn poll(&mut self, lines: &Receiver<String>) -> bool {
+        let lines = lines.try_iter().collect::<Vec<_>>();
+        self.append(lines.iter().map(|line| line.as_bytes()));
+        !lines.is_empty()
+    }
+
+    fn append<'l>(&mut self, lines: impl IntoIterator<Item = &'l [u8]>) {
+        let records = lines
+            .into_iter()
+            .map(|line| String::from_utf8_lossy(line))
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| json::parse(&line).unwrap_or_else(|_| line.into_owned().into()))
+            .collect::<Vec<JsonValue>>();
+        if !records.is_empty() {
+            self.viewer
+                .append_array_elements(&JsonPath::root(), records.iter())
+                .expect("root is an array");
+        }
+    }
+
+    pub fn as_widget(&self) -> JsonViewerWidget<'_> {
+        self.viewer.as_widget()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::mpsc::channel;
+
+    fn scalar(viewer: &JsonStreamViewer, path: JsonPath) -> Option<&str> {
+        viewer.viewer().node(&path)?.scalar()
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut viewer = JsonStreamViewer::new();
+        viewer.push(b"{\"a\": 1}\n{\"a\"");
+        assert_eq!(viewer.len(), 1);
+        viewer.push(b": 2}\n\nnot json\n[1,");
+        assert_eq!(viewer.len(), 3);
+        assert_eq!(
+            scalar(&viewer, JsonPath::root().index(1).key("a")),
+            Some("2")
+        );
+        assert_eq!(scalar(&viewer, JsonPath::root().index(2)), Some("not json"));
+
+        let mut reader = &b" 2]"[..];
+        assert_eq!(viewer.read_from(&mut reader).unwrap(), 3);
+        assert_eq!(viewer.len(), 3);
+        assert_eq!(viewer.read_from(&mut reader).unwrap(), 0);
+        assert_eq!(
+            scalar(&viewer, JsonPath::root().index(3).index(1)),
+            Some("2")
+        );
+
+        // The selection follows the last record and earlier records keep their fold state.
+        let first = JsonPath::root().index(0);
+        viewer.viewer_mut().set_expanded(&first, true).unwrap();
+        viewer
+            .viewer_mut()
+            .select_path(&JsonPath::root().index(3))
+            .unwrap();
+        let (sender, receiver) = channel();
+        assert!(!viewer.poll(&receiver));
+        sender.send("true".to_owned()).unwrap();
+        assert!(viewer.poll(&receiver));
+        assert_eq!(viewer.len(), 5);
+        assert_eq!(viewer.viewer().active_path(), JsonPath::root().index(4));
+        assert_eq!(viewer.viewer().node(&first).unwrap().expanded(), Some(true));
+    }
+}
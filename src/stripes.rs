@@ -0,0 +1,43 @@
+//! Alternating background shading of the rows of the value (see `JsonViewerWidget::stripes`).
+use unsegen::base::basic_types::*;
+
+/// Which rows share a stripe.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Stripes {
+    /// Every drawn line is a stripe of its own.
+    Lines,
+    /// All lines of a member (or element) of the top-level object (or array) form a stripe. The
+    /// brackets of the top-level value are not shaded.
+    Members,
+}
+
+/// The index of the top-level member drawn in `row`, given the rows in which members start
+/// (with their index) and the row of the closing bracket of the top-level value (None) in the
+/// order they were drawn.
+pub fn member_at(starts: &[(RowIndex, Option<usize>)], row: RowIndex) -> Option<usize> {
+    starts
+        .iter()
+        .rev()
+        .find(|&&(start, _)| start <= row)
+        .and_then(|&(_, member)| member)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_member_at() {
+        let row = RowIndex::new;
+        let starts = [(row(1), Some(0)), (row(3), Some(1)), (row(4), None)];
+        let members = (0..6)
+            .map(|y| member_at(&starts, row(y)))
+            .collect::<Vec<_>>();
+        assert_eq!(members, vec![None, Some(0), Some(0), Some(1), None, None]);
+        // Scrolled into the middle of the third member.
+        let starts = [(row(-2), Some(2)), (row(2), Some(3))];
+        assert_eq!(member_at(&starts, row(0)), Some(2));
+        assert_eq!(member_at(&starts, row(2)), Some(3));
+        assert_eq!(member_at(&[], row(0)), None);
+    }
+}
@@ -0,0 +1,155 @@
+use unsegen::base::{Cursor, CursorTarget, StyleModifier};
+
+use std::fmt;
+use std::ops::Range;
+
+/// Text that consists of spans with different styles, e.g., to color-code the type of a scalar.
+///
+/// Plain strings can be converted using `From`/`Into`.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct StyledString {
+    text: String,
+    // The end (byte offset into `text`) and style of each span
+    spans: Vec<(usize, StyleModifier)>,
+}
+
+impl StyledString {
+    pub fn new() -> Self {
+        StyledString {
+            text: String::new(),
+            spans: Vec::new(),
+        }
+    }
+
+    /// Append `text` in the specified style.
+    pub fn push<S: AsRef<str>>(&mut self, text: S, style: StyleModifier) {
+        self.text.push_str(text.as_ref());
+        self.spans.push((self.text.len(), style));
+    }
+
+    /// Builder style variant of `push`.
+    pub fn with<S: AsRef<str>>(mut self, text: S, style: StyleModifier) -> Self {
+        self.push(text, style);
+        self
+    }
+
+    /// The text without any styling.
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The spans of text and their styles.
+    pub fn spans(&self) -> impl Iterator<Item = (&str, StyleModifier)> {
+        let mut begin = 0;
+        self.spans.iter().map(move |&(end, style)| {
+            let span = &self.text[begin..end];
+            begin = end;
+            (span, style)
+        })
+    }
+
+    pub(crate) fn draw<T: CursorTarget>(&self, cursor: &mut Cursor<T>) {
+        for (text, style) in self.spans() {
+            let mut cursor = cursor.save().style_modifier();
+            cursor.apply_style_modifier(style);
+            cursor.write(text);
+        }
+    }
+
+    /// Like `draw`, but apply `highlight` to the byte `ranges` of the text and `style` to the
+    /// rest, both on top of the styles of the spans.
+    pub(crate) fn draw_highlighted<T: CursorTarget>(
+        &self,
+        cursor: &mut Cursor<T>,
+        ranges: &[Range<usize>],
+        style: StyleModifier,
+        highlight: StyleModifier,
+    ) {
+        let mut offset = 0;
+        for (text, span_style) in self.spans() {
+            let mut cursor = cursor.save().style_modifier();
+            cursor.apply_style_modifier(span_style);
+            write_highlighted(&mut cursor, text, offset, ranges, style, highlight);
+            offset += text.len();
+        }
+    }
+}
+
+/// Write `text`, which starts at byte `offset` of a larger text, and apply `highlight` to the
+/// parts within the (sorted, non-overlapping) byte `ranges` of the larger text and `style` to the
+/// rest.
+pub(crate) fn write_highlighted<T: CursorTarget>(
+    cursor: &mut Cursor<T>,
+    text: &str,
+    offset: usize,
+    ranges: &[Range<usize>],
+    style: StyleModifier,
+    highlight: StyleModifier,
+) {
+    let end = offset + text.len();
+    let mut pos = offset;
+    while pos < end {
+        let (next, highlighted) = match ranges.iter().find(|r| r.end > pos) {
+            Some(r) if r.start <= pos => (r.end.min(end), true),
+            Some(r) => (r.start.min(end), false),
+            None => (end, false),
+        };
+        let part = &text[pos - offset..next - offset];
+        let mut cursor = cursor.save().style_modifier();
+        cursor.apply_style_modifier(if highlighted { highlight } else { style });
+        cursor.write(part);
+        pos = next;
+    }
+}
+
+impl From<String> for StyledString {
+    fn from(text: String) -> Self {
+        let end = text.len();
+        StyledString {
+            text,
+            spans: vec![(end, StyleModifier::new())],
+        }
+    }
+}
+
+impl<'a> From<&'a str> for StyledString {
+    fn from(text: &'a str) -> Self {
+        text.to_owned().into()
+    }
+}
+
+impl<'a> PartialEq<&'a str> for StyledString {
+    fn eq(&self, other: &&'a str) -> bool {
+        self.text == *other
+    }
+}
+
+impl fmt::Display for StyledString {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.text)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use unsegen::base::Color;
+
+    #[test]
+    fn test_spans() {
+        let red = StyleModifier::new().fg_color(Color::Red);
+        let s = StyledString::new()
+            .with("len=", StyleModifier::new())
+            .with("5", red);
+        assert_eq!(s.as_str(), "len=5");
+        assert_eq!(
+            s.spans().collect::<Vec<_>>(),
+            vec![("len=", StyleModifier::new()), ("5", red)]
+        );
+        assert_eq!(StyledString::from("foo"), "foo");
+        assert_eq!(
+            StyledString::from("foo"),
+            StyledString::new().with("foo", StyleModifier::new())
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Helpers shared by the tests of multiple modules.
+use crate::{Value, ValueVariant};
+use json::object::Object;
+use json::JsonValue;
+use proptest::prelude::*;
+use unsegen::base::{ColIndex, CursorTarget, RowIndex, TextFormat, WindowBuffer};
+
+/// The text of every line of `buffer` without trailing whitespace.
+pub fn lines(buffer: &mut WindowBuffer) -> Vec<String> {
+    let window = buffer.as_window();
+    (0..window.get_height().raw_value())
+        .map(|y| {
+            let line = (0..window.get_width().raw_value())
+                .map(|x| {
+                    let cell = CursorTarget::get_cell(&window, x.into(), y.into()).unwrap();
+                    cell.grapheme_cluster.as_str().to_owned()
+                })
+                .collect::<String>();
+            line.trim_end().to_owned()
+        })
+        .collect()
+}
+
+/// The text format of the cell of `buffer` in column `x` and row `y`.
+pub fn format_at(buffer: &mut WindowBuffer, x: i32, y: i32) -> TextFormat {
+    let window = buffer.as_window();
+    let cell = CursorTarget::get_cell(&window, ColIndex::new(x), RowIndex::new(y));
+    cell.expect("cell within the buffer").style.format()
+}
+
+/// Whether the cell of `buffer` in column `x` and row `y` is bold.
+pub fn bold_at(buffer: &mut WindowBuffer, x: i32, y: i32) -> bool {
+    format_at(buffer, x, y).bold
+}
+
+/// A scalar nested in the specified number of arrays.
+#[derive(Clone)]
+pub struct Nested(pub usize, pub &'static str);
+
+impl Value for Nested {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        match self.0 {
+            0 => ValueVariant::Scalar(None, self.1.into()),
+            depth => {
+                ValueVariant::Array(None, Box::new(Some(Nested(depth - 1, self.1)).into_iter()))
+            }
+        }
+    }
+}
+
+/// Small arbitrary JSON values with (possibly empty) nested objects and arrays.
+pub fn arbitrary_json() -> impl Strategy<Value = JsonValue> {
+    let leaf = prop_oneof![
+        Just(JsonValue::Null),
+        any::<bool>().prop_map(JsonValue::Boolean),
+        any::<i32>().prop_map(JsonValue::from),
+        "[a-z]{0,3}".prop_map(JsonValue::from),
+    ];
+    leaf.prop_recursive(4, 32, 5, |inner| {
+        prop_oneof![
+            prop::collection::vec(inner.clone(), 0..6).prop_map(JsonValue::Array),
+            prop::collection::vec(("[a-d]", inner), 0..4).prop_map(|members| {
+                let mut object = Object::new();
+                for (key, value) in members {
+                    object.insert(&key, value);
+                }
+                JsonValue::Object(object)
+            }),
+        ]
+    })
+}
@@ -0,0 +1,84 @@
+//! Detection of scalars that look like points in time and their humanized representation.
+use std::ops::Range;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+
+/// Which scalars `JsonViewer::set_humanize_timestamps` considers to be timestamps.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct TimestampOptions {
+    /// ISO-8601 date-times such as `2023-11-14T22:13:20+01:00` (which are shown in UTC).
+    pub iso8601: bool,
+    /// Integers in this range are UNIX timestamps in seconds.
+    pub epoch_seconds: Option<Range<i64>>,
+    /// Integers in this range are UNIX timestamps in milliseconds.
+    pub epoch_millis: Option<Range<i64>>,
+}
+
+impl Default for TimestampOptions {
+    /// ISO-8601 strings and UNIX timestamps (in seconds or milliseconds) between 2000 and 2100.
+    fn default() -> Self {
+        const Y2000: i64 = 946_684_800;
+        const Y2100: i64 = 4_102_444_800;
+        TimestampOptions {
+            iso8601: true,
+            epoch_seconds: Some(Y2000..Y2100),
+            epoch_millis: Some(Y2000 * 1000..Y2100 * 1000),
+        }
+    }
+}
+
+fn format(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+/// The point in time `text` represents according to `options` in a human readable form, e.g.,
+/// `2023-11-14 22:13 UTC`.
+pub fn humanize(text: &str, options: &TimestampOptions) -> Option<String> {
+    if let Ok(number) = text.parse::<i64>() {
+        let in_range = |range: &Option<Range<i64>>| match range {
+            Some(range) => range.contains(&number),
+            None => false,
+        };
+        return if in_range(&options.epoch_seconds) {
+            DateTime::from_timestamp(number, 0).map(format)
+        } else if in_range(&options.epoch_millis) {
+            DateTime::from_timestamp_millis(number).map(format)
+        } else {
+            None
+        };
+    }
+    if !options.iso8601 {
+        return None;
+    }
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Some(format(time.with_timezone(&Utc)));
+    }
+    // Date-times without an offset are assumed to be in UTC.
+    NaiveDateTime::parse_from_str(text, "%Y-%m-%dT%H:%M:%S%.f")
+        .ok()
+        .map(|time| format(time.and_utc()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_humanize() {
+        let options = TimestampOptions::default();
+        let humanized = Some("2023-11-14 22:13 UTC".to_owned());
+        assert_eq!(humanize("1700000000", &options), humanized);
+        assert_eq!(humanize("1700000000123", &options), humanized);
+        assert_eq!(humanize("2023-11-14T23:13:20+01:00", &options), humanized);
+        assert_eq!(humanize("2023-11-14T22:13:20.5", &options), humanized);
+        assert_eq!(humanize("42", &options), None);
+        assert_eq!(humanize("hello", &options), None);
+        let options = TimestampOptions {
+            iso8601: false,
+            epoch_seconds: None,
+            ..options
+        };
+        assert_eq!(humanize("1700000000", &options), None);
+        assert_eq!(humanize("2023-11-14T22:13:20Z", &options), None);
+    }
+}
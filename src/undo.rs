@@ -0,0 +1,63 @@
+use std::collections::{BTreeMap, VecDeque};
+
+use super::displayvalue::Fold;
+use super::path::JsonPath;
+
+/// The maximum number of view changes that can be undone.
+const MAX_CHANGES: usize = 100;
+
+/// The fold state of the objects and arrays (and binary data) affected by a change and the active
+/// node at some point.
+#[derive(Clone, Debug)]
+pub struct ViewState {
+    /// The fold state of the nodes affected by the change, or of all nodes if `complete`.
+    pub folds: BTreeMap<JsonPath, Fold>,
+    /// Whether `folds` contains all nodes, e.g., for `JsonViewer::collapse_all`.
+    pub complete: bool,
+    pub active: JsonPath,
+}
+
+/// The view states before the changes that can be undone and redone (see
+/// `JsonViewer::undo_view_change`), most recent last.
+#[derive(Clone, Debug, Default)]
+pub struct ViewHistory {
+    undo: VecDeque<ViewState>,
+    redo: Vec<ViewState>,
+}
+
+impl ViewHistory {
+    /// Record the state before a new change. Changes that were undone cannot be redone anymore.
+    pub fn push(&mut self, before: ViewState) {
+        if self.undo.len() == MAX_CHANGES {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(before);
+        self.redo.clear();
+    }
+
+    /// The state that `undo` restores, if any.
+    pub fn next_undo(&self) -> Option<&ViewState> {
+        self.undo.back()
+    }
+
+    /// The state that `redo` restores, if any.
+    pub fn next_redo(&self) -> Option<&ViewState> {
+        self.redo.last()
+    }
+
+    /// The state to restore to undo the last change, which can be redone by restoring `current`
+    /// (the current state of the nodes in `next_undo`).
+    pub fn undo(&mut self, current: ViewState) -> Option<ViewState> {
+        let state = self.undo.pop_back()?;
+        self.redo.push(current);
+        Some(state)
+    }
+
+    /// The state to restore to redo the last undone change, which can be undone again by
+    /// restoring `current` (the current state of the nodes in `next_redo`).
+    pub fn redo(&mut self, current: ViewState) -> Option<ViewState> {
+        let state = self.redo.pop()?;
+        self.undo.push_back(current);
+        Some(state)
+    }
+}
@@ -0,0 +1,80 @@
+use std::ops::Range;
+
+use super::displayvalue::common_subsequence_by;
+
+/// The maximum number of (differing) words of the old and new text that are compared, which bounds
+/// the size of the table of the diff.
+const MAX_WORDS: usize = 200;
+
+/// The byte ranges of `new` that differ from `old` word by word, e.g., to highlight only the
+/// changed parts of a long string. None if the texts do not have most of their content in common,
+/// in which case the whole text is considered changed.
+pub fn changed_ranges(old: &str, new: &str) -> Option<Vec<Range<usize>>> {
+    let old_words = words(old);
+    let new_words = words(new);
+    if old_words.len() * new_words.len() > MAX_WORDS * MAX_WORDS {
+        return None;
+    }
+    let same = |o: &Range<usize>, n: &Range<usize>| old[o.clone()] == new[n.clone()];
+    let common = common_subsequence_by(&old_words, &new_words, same);
+    let unchanged = common
+        .iter()
+        .map(|&(_, n)| new_words[n].len())
+        .sum::<usize>();
+    if unchanged * 2 < new.len() {
+        return None;
+    }
+    let mut ranges: Vec<Range<usize>> = Vec::new();
+    let mut common = common.iter().map(|&(_, n)| n).peekable();
+    for (i, word) in new_words.iter().enumerate() {
+        if common.peek() == Some(&i) {
+            common.next();
+            continue;
+        }
+        match ranges.last_mut() {
+            Some(last) if last.end == word.start => last.end = word.end,
+            _ => ranges.push(word.clone()),
+        }
+    }
+    Some(ranges)
+}
+
+/// The byte ranges of the words of `text`, i.e., runs of alphanumeric characters and all other
+/// characters individually.
+fn words(text: &str) -> Vec<Range<usize>> {
+    let mut words: Vec<Range<usize>> = Vec::new();
+    let mut in_word = false;
+    for (i, c) in text.char_indices() {
+        let end = i + c.len_utf8();
+        match words.last_mut() {
+            Some(last) if in_word && c.is_alphanumeric() => last.end = end,
+            _ => words.push(i..end),
+        }
+        in_word = c.is_alphanumeric();
+    }
+    words
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_changed_ranges() {
+        let old = "https://example.com/api/v1/users?page=2";
+        let new = "https://example.com/api/v2/users?page=12";
+        let ranges = changed_ranges(old, new).unwrap();
+        assert_eq!(
+            ranges.iter().map(|r| &new[r.clone()]).collect::<Vec<_>>(),
+            vec!["v2", "12"]
+        );
+        let new = "connection timed out";
+        let ranges = changed_ranges("connection refused", new).unwrap();
+        assert_eq!(
+            ranges.iter().map(|r| &new[r.clone()]).collect::<Vec<_>>(),
+            vec!["timed out"]
+        );
+        assert_eq!(changed_ranges("42", "47"), None);
+        assert_eq!(changed_ranges("a b", "x y z"), None);
+    }
+}
@@ -0,0 +1,475 @@
+//! XML documents: Elements are shown as objects described by their name, with their attributes
+//! as members with an `@` prefix (e.g., `@id`), followed by the list of their children (elements
+//! and text) as `#children`. Elements without attributes that contain nothing but text are shown
+//! as strings.
+//!
+//! ```
+//! # extern crate unsegen_jsonviewer;
+//! # use unsegen_jsonviewer::xml::XmlElement;
+//! # use unsegen_jsonviewer::JsonViewer;
+//! # fn main() {
+//! let root = XmlElement::parse(r#"<book id="1"><title>Dune</title></book>"#).unwrap();
+//! // book { @id: 1, #children: [ title Dune ] }
+//! let viewer = JsonViewer::new(root.value());
+//! # }
+//! ```
+//!
+//! The parser is not validating. Processing instructions, comments and the document type
+//! declaration are skipped, and only the predefined and numeric entities are expanded.
+use std::fmt;
+use std::iter::once;
+
+use super::{Value, ValueVariant};
+
+/// The child of an element.
+#[derive(Clone, PartialEq, Debug)]
+pub enum XmlNode {
+    Element(XmlElement),
+    /// Text or CDATA (with surrounding whitespace removed).
+    Text(String),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct XmlElement {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<XmlNode>,
+}
+
+/// Why a document could not be parsed.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ParseError {
+    /// The byte offset in the document at which the error was detected.
+    pub position: usize,
+    /// The line (starting at 1) of `position`.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl XmlElement {
+    /// Parse a document and return its root element.
+    pub fn parse(text: &str) -> Result<Self, ParseError> {
+        let mut parser = Parser { text, rest: text };
+        parser.skip_misc()?;
+        let root = parser.element()?;
+        parser.skip_misc()?;
+        if parser.rest.is_empty() {
+            Ok(root)
+        } else {
+            parser.error("expected the end of the document")
+        }
+    }
+
+    /// This element, e.g., `book { @id: 1, #children: [ title Dune ] }`.
+    pub fn value(&self) -> XmlValue<'_> {
+        XmlValue(Repr::Element(self))
+    }
+}
+
+impl Drop for XmlElement {
+    fn drop(&mut self) {
+        // Drop the descendants one by one rather than recursively so that arbitrarily deep
+        // documents do not overflow the stack.
+        let mut descendants = ::std::mem::take(&mut self.children);
+        while let Some(node) = descendants.pop() {
+            if let XmlNode::Element(mut element) = node {
+                descendants.append(&mut element.children);
+            }
+        }
+    }
+}
+
+/// An XML element (see `XmlElement::value`), its list of children, an attribute or a text node.
+#[derive(Clone, Debug)]
+pub struct XmlValue<'a>(Repr<'a>);
+
+#[derive(Clone, Debug)]
+enum Repr<'a> {
+    Element(&'a XmlElement),
+    Children(&'a [XmlNode]),
+    Text(&'a str),
+}
+
+impl<'a> Value for XmlValue<'a> {
+    fn visit<'s>(self) -> ValueVariant<'s, Self> {
+        let element = match self.0 {
+            Repr::Element(element) => element,
+            Repr::Children(children) => {
+                return ValueVariant::Array(
+                    None,
+                    Box::new(children.iter().map(|child| match child {
+                        XmlNode::Element(child) => XmlValue(Repr::Element(child)),
+                        XmlNode::Text(text) => XmlValue(Repr::Text(text.as_str())),
+                    })),
+                )
+            }
+            Repr::Text(text) => return ValueVariant::Scalar(None, text.into()),
+        };
+        let name = Some(element.name.as_str().into());
+        match (&element.attributes[..], &element.children[..]) {
+            ([], []) => return ValueVariant::Scalar(name, "".into()),
+            ([], [XmlNode::Text(text)]) => return ValueVariant::Scalar(name, text.as_str().into()),
+            _ => {}
+        }
+        let attributes = element.attributes.iter().map(|(name, value)| {
+            (
+                format!("@{}", name).into(),
+                XmlValue(Repr::Text(value.as_str())),
+            )
+        });
+        let children = once(&element.children)
+            .filter(|children| !children.is_empty())
+            .map(|children| ("#children".into(), XmlValue(Repr::Children(children))));
+        ValueVariant::Map(name, Box::new(attributes.chain(children)))
+    }
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    rest: &'t str,
+}
+
+impl<'t> Parser<'t> {
+    fn position(&self) -> usize {
+        self.text.len() - self.rest.len()
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, ParseError> {
+        self.error_at(self.position(), message)
+    }
+
+    fn error_at<T>(&self, position: usize, message: &str) -> Result<T, ParseError> {
+        Err(ParseError {
+            position,
+            line: self.text[..position].matches('\n').count() + 1,
+            message: message.to_owned(),
+        })
+    }
+
+    fn eat(&mut self, prefix: &str) -> bool {
+        if self.rest.starts_with(prefix) {
+            self.rest = &self.rest[prefix.len()..];
+            true
+        } else {
+            false
+        }
+    }
+
+    // Everything up to `end` (which is skipped as well).
+    fn until(&mut self, end: &str) -> Result<&'t str, ParseError> {
+        let pos = match self.rest.find(end) {
+            Some(pos) => pos,
+            None => return self.error(&format!("expected `{}`", end)),
+        };
+        let taken = &self.rest[..pos];
+        self.rest = &self.rest[pos + end.len()..];
+        Ok(taken)
+    }
+
+    fn skip_whitespace(&mut self) {
+        self.rest = self.rest.trim_start();
+    }
+
+    fn name(&mut self) -> Result<&'t str, ParseError> {
+        let end = self
+            .rest
+            .find(|c: char| c.is_whitespace() || "/>=".contains(c))
+            .unwrap_or(self.rest.len());
+        let (name, rest) = self.rest.split_at(end);
+        if name.is_empty() {
+            return self.error("expected a name");
+        }
+        self.rest = rest;
+        Ok(name)
+    }
+
+    // Whitespace, comments, processing instructions and the document type declaration.
+    fn skip_misc(&mut self) -> Result<(), ParseError> {
+        loop {
+            self.skip_whitespace();
+            if self.eat("<!--") {
+                self.until("-->")?;
+            } else if self.eat("<?") {
+                self.until("?>")?;
+            } else if self.eat("<!DOCTYPE") {
+                self.doctype()?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    // The rest of a document type declaration, which may contain an internal subset.
+    fn doctype(&mut self) -> Result<(), ParseError> {
+        let end = match self.rest.find('>') {
+            Some(end) => end,
+            None => return self.error("expected `>`"),
+        };
+        let subset = self.rest.find('[').filter(|&start| start < end);
+        if subset.is_some() {
+            self.until("]")?;
+        }
+        self.until(">").map(|_| ())
+    }
+
+    // `text` (a part of the document) with the entities expanded.
+    fn unescape(&self, text: &'t str) -> Result<String, ParseError> {
+        let start = text.as_ptr() as usize - self.text.as_ptr() as usize;
+        unescape(text).or_else(|offset| self.error_at(start + offset, "invalid entity"))
+    }
+
+    // A start tag (or empty-element tag), returning the element without children and whether it
+    // is empty, i.e., has no content and end tag.
+    fn start_tag(&mut self) -> Result<(XmlElement, bool), ParseError> {
+        if !self.eat("<") {
+            return self.error("expected an element");
+        }
+        let name = self.name()?.to_owned();
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let empty = self.eat("/>");
+            if empty || self.eat(">") {
+                let element = XmlElement {
+                    name,
+                    attributes,
+                    children: Vec::new(),
+                };
+                return Ok((element, empty));
+            }
+            let attribute = self.name()?.to_owned();
+            self.skip_whitespace();
+            if !self.eat("=") {
+                return self.error("expected `=`");
+            }
+            self.skip_whitespace();
+            let quote = if self.eat("\"") {
+                "\""
+            } else if self.eat("'") {
+                "'"
+            } else {
+                return self.error("expected a quoted value");
+            };
+            let value = self.until(quote)?;
+            attributes.push((attribute, self.unescape(value)?));
+        }
+    }
+
+    fn element(&mut self) -> Result<XmlElement, ParseError> {
+        let (mut element, empty) = self.start_tag()?;
+        if empty {
+            return Ok(element);
+        }
+        // The ancestors of `element` that have not been closed yet, innermost last. Keeping them
+        // on an explicit stack (rather than recursing) keeps deeply nested documents from
+        // overflowing the stack.
+        let mut open = Vec::new();
+        loop {
+            let text = match self.rest.find('<') {
+                Some(text) => text,
+                None => return self.error(&format!("expected `</{}>`", element.name)),
+            };
+            let (text, rest) = self.rest.split_at(text);
+            self.rest = rest;
+            push_text(&mut element.children, self.unescape(text)?);
+            if self.eat("</") {
+                let position = self.position();
+                if self.name()? != element.name {
+                    return self.error_at(position, &format!("expected `</{}>`", element.name));
+                }
+                self.skip_whitespace();
+                if !self.eat(">") {
+                    return self.error("expected `>`");
+                }
+                match open.pop() {
+                    Some(parent) => {
+                        let closed = ::std::mem::replace(&mut element, parent);
+                        element.children.push(XmlNode::Element(closed));
+                    }
+                    None => return Ok(element),
+                }
+            } else if self.eat("<![CDATA[") {
+                push_text(&mut element.children, self.until("]]>")?.to_owned());
+            } else if self.eat("<!--") {
+                self.until("-->")?;
+            } else if self.eat("<?") {
+                self.until("?>")?;
+            } else {
+                let (child, empty) = self.start_tag()?;
+                if empty {
+                    element.children.push(XmlNode::Element(child));
+                } else {
+                    open.push(::std::mem::replace(&mut element, child));
+                }
+            }
+        }
+    }
+}
+
+// Append text to the children of an element, merging it with preceding text (e.g., text before a
+// comment) and dropping whitespace.
+fn push_text(children: &mut Vec<XmlNode>, text: String) {
+    let text = text.trim();
+    if text.is_empty() {
+        return;
+    }
+    if let Some(XmlNode::Text(previous)) = children.last_mut() {
+        previous.push(' ');
+        previous.push_str(text);
+    } else {
+        children.push(XmlNode::Text(text.to_owned()));
+    }
+}
+
+// Expand the entities of `text`, or return the offset of the first invalid one.
+fn unescape(text: &str) -> Result<String, usize> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        let offset = text.len() - rest.len() + start;
+        result.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+        let end = rest.find(';').ok_or(offset)?;
+        let c = match &rest[..end] {
+            "lt" => '<',
+            "gt" => '>',
+            "amp" => '&',
+            "quot" => '"',
+            "apos" => '\'',
+            entity => {
+                let code = if let Some(hex) = entity.strip_prefix("#x") {
+                    u32::from_str_radix(hex, 16)
+                } else if let Some(decimal) = entity.strip_prefix('#') {
+                    decimal.parse()
+                } else {
+                    return Err(offset);
+                };
+                code.ok().and_then(std::char::from_u32).ok_or(offset)?
+            }
+        };
+        result.push(c);
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_util::lines;
+    use crate::{JsonPath, JsonViewer};
+    use unsegen::base::{Height, Width, WindowBuffer};
+    use unsegen::widget::{RenderingHints, Widget};
+
+    #[test]
+    fn test_parse() {
+        let root = XmlElement::parse(
+            "<?xml version=\"1.0\"?>\n<!DOCTYPE a [<!ENTITY x \"y\">]>\n\
+             <a k='1 &lt; 2'>x<!-- c -->y<b/><![CDATA[<z>]]></a>\n",
+        )
+        .unwrap();
+        assert_eq!(
+            root,
+            XmlElement {
+                name: "a".to_owned(),
+                attributes: vec![("k".to_owned(), "1 < 2".to_owned())],
+                children: vec![
+                    XmlNode::Text("x y".to_owned()),
+                    XmlNode::Element(XmlElement {
+                        name: "b".to_owned(),
+                        attributes: vec![],
+                        children: vec![],
+                    }),
+                    XmlNode::Text("<z>".to_owned()),
+                ],
+            }
+        );
+        let error = |position, line, message: &str| {
+            Err(ParseError {
+                position,
+                line,
+                message: message.to_owned(),
+            })
+        };
+        assert_eq!(
+            XmlElement::parse("<a>\n</b>"),
+            error(6, 2, "expected `</a>`")
+        );
+        assert_eq!(
+            XmlElement::parse("<a>&#x41;&unknown;</a>"),
+            error(9, 1, "invalid entity")
+        );
+        assert_eq!(
+            XmlElement::parse("<a k='&'/>"),
+            error(6, 1, "invalid entity")
+        );
+        assert_eq!(
+            XmlElement::parse("<a/>\n<b/>"),
+            error(5, 2, "expected the end of the document")
+        );
+        assert_eq!(
+            XmlElement::parse("<a><!-- x").unwrap_err().to_string(),
+            "line 1: expected `-->`"
+        );
+    }
+
+    #[test]
+    fn test_xml_value() {
+        let root = XmlElement::parse(
+            r#"<library><book id="1"><title>Dune</title></book><book id="2"/></library>"#,
+        )
+        .unwrap();
+        let mut viewer = JsonViewer::new(root.value());
+        viewer.expand_all();
+        let mut buffer = WindowBuffer::new(Width::new(30).unwrap(), Height::new(14).unwrap());
+        viewer
+            .as_widget()
+            .draw(buffer.as_window(), RenderingHints::default());
+        assert_eq!(
+            lines(&mut buffer)[..13],
+            [
+                "library { [-]",
+                "  #children: [ [-]",
+                "    book { [-]",
+                "      @id: 1,",
+                "      #children: [ [-]",
+                "        title Dune,",
+                "      ] <-1/1 >,",
+                "    },",
+                "    book { [-]",
+                "      @id: 2,",
+                "    },",
+                "  ] <-2/2 >,",
+                "}",
+            ]
+        );
+        // Elements with the same name can be told apart by their position.
+        let second = JsonPath::root().key("#children").index(1).key("@id");
+        assert_eq!(viewer.node(&second).unwrap().scalar(), Some("2"));
+    }
+
+    #[test]
+    fn test_deep_nesting() {
+        const DEPTH: usize = 100_000;
+        let document = "<a>".repeat(DEPTH) + "x" + &"</a>".repeat(DEPTH);
+        let root = XmlElement::parse(&document).unwrap();
+        let mut depth = 1;
+        let mut element = &root;
+        while let [XmlNode::Element(child)] = &element.children[..] {
+            element = child;
+            depth += 1;
+        }
+        assert_eq!(depth, DEPTH);
+        assert_eq!(element.children, vec![XmlNode::Text("x".to_owned())]);
+        // All elements but the innermost one are objects with a `#children` array.
+        let viewer = JsonViewer::new(root.value());
+        assert_eq!(viewer.stats().max_depth, 2 * DEPTH - 1);
+    }
+}